@@ -1,5 +1,6 @@
 // FICHIER : src-tauri/src/ai/nlp/preprocessing.rs
 use std::collections::HashSet;
+use std::sync::OnceLock;
 
 /// Normalise le texte :
 /// 1. Minuscule & Sans accents.
@@ -25,7 +26,7 @@ pub fn normalize(text: &str) -> String {
 
 /// Supprime les mots vides (Stop Words) français.
 pub fn remove_stopwords(text: &str) -> String {
-    let stopwords = get_french_stopwords();
+    let stopwords = french_stopwords();
     text.split_whitespace()
         .filter(|word| !stopwords.contains(*word))
         .collect::<Vec<&str>>()
@@ -48,18 +49,36 @@ fn remove_accents(s: &str) -> String {
         .collect()
 }
 
-fn get_french_stopwords() -> HashSet<&'static str> {
-    let mut set = HashSet::new();
-    let list = [
-        "le", "la", "les", "l", "un", "une", "des", "du", "de", "d", "ce", "cet", "cette", "ces",
-        "mon", "ton", "son", "et", "ou", "mais", "donc", "car", "ni", "à", "en", "dans", "par",
-        "pour", "sur", "avec", "sans", "qui", "que", "quoi", "dont", "où", "est", "sont", "avoir",
-        "être", "je", "tu", "il", "nous", "vous", "veut", "voudrais",
-    ];
-    for word in list {
-        set.insert(word);
-    }
-    set
+/// Mots vides français. Les formes accentuées ont leur équivalent normalisé
+/// (`à` / `a`) : la liste s'applique avant comme après `normalize`.
+pub fn french_stopwords() -> &'static HashSet<&'static str> {
+    static SET: OnceLock<HashSet<&'static str>> = OnceLock::new();
+    SET.get_or_init(|| {
+        [
+            "le", "la", "les", "l", "un", "une", "des", "du", "de", "d", "ce", "cet", "cette",
+            "ces", "mon", "ton", "son", "et", "ou", "mais", "donc", "car", "ni", "à", "a", "en",
+            "dans", "par", "pour", "sur", "avec", "sans", "qui", "que", "qu", "quoi", "dont", "où",
+            "est", "sont", "avoir", "être", "etre", "je", "j", "tu", "il", "nous", "vous", "veut",
+            "voudrais", "n", "s", "c",
+        ]
+        .into_iter()
+        .collect()
+    })
+}
+
+/// Mots vides anglais (texte normalisé)
+pub fn english_stopwords() -> &'static HashSet<&'static str> {
+    static SET: OnceLock<HashSet<&'static str>> = OnceLock::new();
+    SET.get_or_init(|| {
+        [
+            "a", "an", "the", "and", "or", "but", "if", "of", "to", "in", "on", "at", "by", "for",
+            "with", "from", "as", "is", "are", "was", "were", "be", "been", "it", "its", "this",
+            "that", "these", "those", "shall", "should", "will", "would", "can", "could", "not",
+            "no", "s",
+        ]
+        .into_iter()
+        .collect()
+    })
 }
 
 #[cfg(test)]
@@ -76,4 +95,9 @@ mod tests {
         // Test du cas qui a fait échouer votre pipeline
         assert_eq!(normalize("l'architecture"), "l architecture");
     }
+
+    #[test]
+    fn test_stopwords_after_normalize() {
+        assert_eq!(remove_stopwords(&normalize("Où être à l'heure")), "heure");
+    }
 }
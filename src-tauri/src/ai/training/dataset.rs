@@ -33,7 +33,10 @@ pub fn export_logic(
         space, db_name
     );
 
-    let manager = CollectionsManager::new(storage, &space, &db_name);
+    // Snapshot : l'export voit un état cohérent même si des transactions sont validées pendant
+    let manager = CollectionsManager::new(storage, &space, &db_name)
+        .snapshot()
+        .map_err(|e| format!("Erreur ouverture snapshot : {}", e))?;
 
     // Récupération de la variable d'env (Mockable via std::env::set_var dans les tests)
     let env_path_str =
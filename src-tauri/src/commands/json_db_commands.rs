@@ -1,11 +1,22 @@
 // FICHIER : src-tauri/src/commands/json_db_commands.rs
 
+use crate::json_db::backup::{self, BackupManifest, RestoreOptions, RestoreReport};
+use crate::json_db::changes::{self, ChangeEvent};
 use crate::json_db::collections::manager::{self, CollectionsManager};
 use crate::json_db::query::{Query, QueryEngine, QueryResult};
-use crate::json_db::schema::SchemaRegistry; // <--- AJOUTÉ
-use crate::json_db::storage::{file_storage, StorageEngine};
+use crate::json_db::replication::{
+    self, ConflictChoice, MergeStrategy, ReplicationReport, SyncOptions, SyncReport,
+};
+use crate::json_db::schema::{SchemaRegistry, ValidationReport};
+use crate::json_db::storage::{cache::CacheStats, file_storage, StorageEngine};
+use futures::StreamExt;
+use serde::Serialize;
 use serde_json::{json, Value};
-use tauri::{command, State};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::async_runtime::JoinHandle;
+use tauri::{command, AppHandle, Emitter, State};
 
 // Helper pour instancier le manager rapidement
 fn mgr<'a>(
@@ -89,12 +100,14 @@ pub async fn jsondb_create_index(
     space: String,
     db: String,
     collection: String,
-    field: String,
-    kind: String, // "hash", "btree", "text"
+    field: String, // "layer,name" pour un index composite
+    kind: String,  // "hash", "btree", "text" (ou "text:fr", "text:en", "text:simple"), "unique"
+    unique: Option<bool>,
 ) -> Result<(), String> {
     let manager = mgr(&storage, &space, &db)?;
+    let fields: Vec<&str> = field.split(',').map(str::trim).collect();
     manager
-        .create_index(&collection, &field, &kind)
+        .create_index_on(&collection, &fields, &kind, unique.unwrap_or(false))
         .map_err(|e| e.to_string())
 }
 
@@ -169,6 +182,22 @@ pub async fn jsondb_evaluate_draft(
     Ok(doc)
 }
 
+/// Valide un brouillon contre le schéma de la collection (règles métier appliquées)
+/// et renvoie toutes les violations, localisées, pour l'affichage champ par champ.
+#[command]
+pub async fn jsondb_validate_document(
+    storage: State<'_, StorageEngine>,
+    space: String,
+    db: String,
+    collection: String,
+    document: Value,
+) -> Result<ValidationReport, String> {
+    let manager = mgr(&storage, &space, &db)?;
+    manager
+        .validate_document(&collection, document)
+        .map_err(|e| e.to_string())
+}
+
 // --- CRUD DOCUMENTS ---
 
 #[command]
@@ -263,10 +292,235 @@ pub async fn jsondb_execute_sql(
     sql: String,
 ) -> Result<QueryResult, String> {
     let manager = mgr(&storage, &space, &db)?;
-    let query = crate::json_db::query::sql::parse_sql(&sql)
+    let statement = crate::json_db::query::sql::parse_sql_statement(&sql)
         .map_err(|e| format!("SQL Parse Error: {}", e))?;
-    let engine = QueryEngine::new(&manager);
-    engine.execute_query(query).await.map_err(|e| e.to_string())
+    statement.execute(&manager).await.map_err(|e| e.to_string())
+}
+
+// --- FLUX DE CHANGEMENTS ---
+
+/// Événement Tauri émis pour chaque changement d'un abonnement
+pub const CHANGE_EVENT: &str = "jsondb:change";
+
+/// Charge utile de l'événement `jsondb:change`
+#[derive(Clone, Serialize)]
+struct ChangeNotification<'a> {
+    subscription: &'a str,
+    space: &'a str,
+    db: &'a str,
+    event: &'a ChangeEvent,
+}
+
+/// Abonnements actifs : tâche de diffusion de chacun
+fn subscriptions() -> &'static Mutex<HashMap<String, JoinHandle<()>>> {
+    static SUBSCRIPTIONS: OnceLock<Mutex<HashMap<String, JoinHandle<()>>>> = OnceLock::new();
+    SUBSCRIPTIONS.get_or_init(Default::default)
+}
+
+/// Changements postérieurs au jeton `after` (rattrapage ou consultation ponctuelle)
+#[command]
+pub async fn jsondb_read_changes(
+    storage: State<'_, StorageEngine>,
+    space: String,
+    db: String,
+    after: Option<u64>,
+    limit: Option<usize>,
+) -> Result<Vec<ChangeEvent>, String> {
+    changes::read_since(
+        &storage.config,
+        &space,
+        &db,
+        after.unwrap_or(0),
+        limit.unwrap_or(1000),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Diffuse les changements postérieurs au jeton `after` (par défaut : ceux à
+/// venir) en événements `jsondb:change`. Retourne l'identifiant de
+/// l'abonnement, à passer à `jsondb_unsubscribe_changes`.
+#[command]
+pub async fn jsondb_subscribe_changes(
+    app: AppHandle,
+    storage: State<'_, StorageEngine>,
+    space: String,
+    db: String,
+    after: Option<u64>,
+) -> Result<String, String> {
+    let after = match after {
+        Some(seq) => seq,
+        None => changes::last_seq(&storage.config, &space, &db).map_err(|e| e.to_string())?,
+    };
+    let mut stream = changes::subscribe(&storage.config, &space, &db, after);
+    let subscription = uuid::Uuid::new_v4().to_string();
+
+    let id = subscription.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        while let Some(item) = stream.next().await {
+            match item {
+                Ok(event) => {
+                    let payload = ChangeNotification {
+                        subscription: &id,
+                        space: &space,
+                        db: &db,
+                        event: &event,
+                    };
+                    if app.emit(CHANGE_EVENT, payload).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("⚠️ Flux de changements {}/{} : {}", space, db, e);
+                    tokio::time::sleep(changes::POLL_INTERVAL).await;
+                }
+            }
+        }
+    });
+    subscriptions()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(subscription.clone(), task);
+    Ok(subscription)
+}
+
+/// Arrête un abonnement (`false` s'il n'existe pas)
+#[command]
+pub async fn jsondb_unsubscribe_changes(subscription: String) -> Result<bool, String> {
+    let task = subscriptions()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&subscription);
+    Ok(task.map(|task| task.abort()).is_some())
+}
+
+// --- SAUVEGARDE ---
+
+/// Sauvegarde à chaud de la base dans l'archive `path` (.tar.zst)
+#[command]
+pub async fn jsondb_backup_db(
+    storage: State<'_, StorageEngine>,
+    space: String,
+    db: String,
+    path: String,
+) -> Result<BackupManifest, String> {
+    backup::backup(&storage.config, &space, &db, Path::new(&path)).map_err(|e| e.to_string())
+}
+
+/// Restaure l'archive `path` dans une nouvelle base `space/db`. Avec un journal
+/// des changements archivé (`changes`), rejoue les écritures postérieures à la
+/// sauvegarde jusqu'à `until` (ms depuis l'epoch, incluse).
+#[command]
+pub async fn jsondb_restore_db(
+    storage: State<'_, StorageEngine>,
+    path: String,
+    space: String,
+    db: String,
+    changes: Option<String>,
+    until: Option<i64>,
+) -> Result<RestoreReport, String> {
+    let options = RestoreOptions {
+        changes: changes.map(PathBuf::from),
+        until,
+    };
+    backup::restore(&storage.config, Path::new(&path), &space, &db, &options)
+        .map_err(|e| e.to_string())
+}
+
+// --- RÉPLICATION ---
+
+fn sync_options(
+    strategy: Option<String>,
+    collections: Option<HashMap<String, String>>,
+) -> Result<SyncOptions, String> {
+    let parse = |s: &str| MergeStrategy::parse(s).map_err(|e| e.to_string());
+    let mut options = SyncOptions::new().with_strategy(parse(strategy.as_deref().unwrap_or(""))?);
+    for (collection, strategy) in collections.unwrap_or_default() {
+        options = options.with_collection_strategy(&collection, parse(&strategy)?);
+    }
+    Ok(options)
+}
+
+/// Synchronise `space/db` avec la même base sous la racine `remote_root`
+/// (dans les deux sens). `strategy` : "lww" (défaut), "three-way" ou "manual",
+/// `collections` : stratégie propre à certaines collections.
+#[command]
+pub async fn jsondb_sync_db(
+    storage: State<'_, StorageEngine>,
+    space: String,
+    db: String,
+    remote_root: String,
+    strategy: Option<String>,
+    collections: Option<HashMap<String, String>>,
+) -> Result<ReplicationReport, String> {
+    let options = sync_options(strategy, collections)?;
+    let mut remote = storage.config.clone();
+    remote.data_root = PathBuf::from(remote_root);
+    replication::sync(&storage.config, &remote, &space, &db, &options).map_err(|e| e.to_string())
+}
+
+/// Exporte l'état de la base dans le lot `path`. Retourne le nombre de documents.
+#[command]
+pub async fn jsondb_export_bundle(
+    storage: State<'_, StorageEngine>,
+    space: String,
+    db: String,
+    path: String,
+) -> Result<usize, String> {
+    let bundle =
+        replication::export_bundle(&storage.config, &space, &db).map_err(|e| e.to_string())?;
+    replication::write_bundle(&bundle, Path::new(&path)).map_err(|e| e.to_string())?;
+    Ok(bundle.documents.len())
+}
+
+/// Fusionne le lot `path` dans la base
+#[command]
+pub async fn jsondb_import_bundle(
+    storage: State<'_, StorageEngine>,
+    space: String,
+    db: String,
+    path: String,
+    strategy: Option<String>,
+    collections: Option<HashMap<String, String>>,
+) -> Result<SyncReport, String> {
+    let options = sync_options(strategy, collections)?;
+    let bundle = replication::read_bundle(Path::new(&path)).map_err(|e| e.to_string())?;
+    replication::merge_bundle(&storage.config, &space, &db, &bundle, &options)
+        .map_err(|e| e.to_string())
+}
+
+/// Règle un conflit ouvert : `choice` vaut "local", "remote" ou "custom"
+/// (contenu `document`, ou suppression s'il est absent)
+#[command]
+pub async fn jsondb_resolve_conflict(
+    storage: State<'_, StorageEngine>,
+    space: String,
+    db: String,
+    conflict_id: String,
+    choice: String,
+    document: Option<Value>,
+) -> Result<Option<Value>, String> {
+    let choice = match choice.as_str() {
+        "custom" => ConflictChoice::Document(document),
+        other => ConflictChoice::parse(other).map_err(|e| e.to_string())?,
+    };
+    replication::resolve_conflict(&storage.config, &space, &db, &conflict_id, choice)
+        .map_err(|e| e.to_string())
+}
+
+// --- CACHE ---
+
+/// Compteurs du cache de documents (hits, misses, évictions, octets utilisés)
+#[command]
+pub async fn jsondb_cache_stats(storage: State<'_, StorageEngine>) -> Result<CacheStats, String> {
+    Ok(storage.cache_stats())
+}
+
+/// Vide le cache de documents et remet ses compteurs à zéro
+#[command]
+pub async fn jsondb_clear_cache(storage: State<'_, StorageEngine>) -> Result<(), String> {
+    storage.cache.clear();
+    storage.cache.reset_stats();
+    Ok(())
 }
 
 // --- UTILITAIRE DE DÉMO ---
@@ -0,0 +1,642 @@
+// FICHIER : src-tauri/src/json_db/backup/mod.rs
+
+//! Sauvegarde à chaud, restauration et restauration à une date (PITR).
+//!
+//! Une sauvegarde est une archive `.tar.zst` unique :
+//! - `db/...` : l'arborescence de la base (documents, `_meta.json`, index,
+//!   `_system.json`, WAL en attente, journal des changements) ;
+//! - `schemas/...` : les schémas de l'espace (`<space>/_system/schemas`) ;
+//! - `manifest.json`, en dernier : origine, date, jeton du flux de changements
+//!   et empreinte SHA-256 de chaque fichier.
+//!
+//! Cohérence : toutes les collections sont verrouillées en exclusif (ordre trié,
+//! comme les transactions) le temps de la copie. Les lectures continuent ; les
+//! écritures attendent la fin de la copie. Les fichiers de coordination propres
+//! à l'instance (`_locks`, `_snapshots`, `_history`) ne sont pas sauvegardés.
+//!
+//! Restauration : vers une base inexistante, éventuellement sous un autre nom
+//! (les URIs `db://` des `_meta.json` et de `_system.json` suivent). L'archive
+//! est extraite et vérifiée dans un dossier temporaire avant sa mise en place,
+//! puis la récupération traite les transactions restées dans le WAL.
+//!
+//! Restauration à une date : le journal des changements, archivé au fil de
+//! l'eau par `changes::archive`, tient lieu d'archive du WAL. Ses événements
+//! postérieurs à la sauvegarde sont rejoués jusqu'à la date demandée
+//! (documents, index et `_system.json`), puis repris dans le journal de la base
+//! restaurée avec leurs numéros d'origine.
+
+use crate::json_db::changes::{self, ChangeEvent};
+use crate::json_db::storage::{file_storage, JsonDbConfig};
+use crate::json_db::transactions::lock_manager::{FileLock, LockManager, LockMode};
+use crate::json_db::transactions::manager::TransactionManager;
+use crate::json_db::transactions::{recovery, DocumentImage};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Version du format d'archive produit
+pub const BACKUP_FORMAT: u32 = 1;
+
+const MANIFEST: &str = "manifest.json";
+const DB_PREFIX: &str = "db";
+const SCHEMAS_PREFIX: &str = "schemas";
+
+/// Dossiers propres à l'instance en cours, jamais sauvegardés
+const EXCLUDED_DIRS: [&str; 3] = ["_locks", "_snapshots", "_history"];
+
+/// Fichier de l'archive, avec son empreinte
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupEntry {
+    /// Chemin dans l'archive (`db/...` ou `schemas/...`)
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format: u32,
+    pub space: String,
+    pub db: String,
+    /// Date de la sauvegarde (ms depuis l'epoch)
+    pub created_at: i64,
+    /// Dernier événement du flux de changements contenu dans la sauvegarde
+    pub change_seq: u64,
+    pub files: Vec<BackupEntry>,
+}
+
+/// Options de restauration
+#[derive(Debug, Clone, Default)]
+pub struct RestoreOptions {
+    /// Journal des changements à rejouer après la sauvegarde
+    pub changes: Option<PathBuf>,
+    /// Date limite incluse du rejeu (ms). Sans date : journal complet
+    pub until: Option<i64>,
+}
+
+impl RestoreOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_changes(mut self, journal: impl Into<PathBuf>) -> Self {
+        self.changes = Some(journal.into());
+        self
+    }
+
+    pub fn with_until(mut self, until: i64) -> Self {
+        self.until = Some(until);
+        self
+    }
+}
+
+/// Bilan d'une restauration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RestoreReport {
+    pub space: String,
+    pub db: String,
+    /// Fichiers extraits et vérifiés
+    pub files: usize,
+    /// Événements rejoués après la sauvegarde
+    pub replayed: usize,
+    /// Jeton du flux de changements de la base restaurée
+    pub change_seq: u64,
+}
+
+// --- SAUVEGARDE ---
+
+/// Sauvegarde cohérente de la base dans l'archive `dest` (écrite atomiquement)
+pub fn backup(config: &JsonDbConfig, space: &str, db: &str, dest: &Path) -> Result<BackupManifest> {
+    let db_root = config.db_root(space, db);
+    if !db_root.exists() {
+        bail!("Base introuvable : {}/{}", space, db);
+    }
+
+    let _guards = lock_collections(config, space, db)?;
+    let change_seq = changes::last_seq(config, space, db)?;
+    let created_at = chrono::Utc::now().timestamp_millis();
+
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = dest.with_extension("tmp");
+    let mut archive = tar::Builder::new(zstd::Encoder::new(File::create(&tmp)?, 0)?);
+    let mut files = Vec::new();
+
+    append_tree(&mut archive, &db_root, DB_PREFIX, &mut files)?;
+    // La base système contient déjà les schémas de l'espace
+    let schemas_root = config.db_schemas_root(space, db);
+    if !schemas_root.starts_with(&db_root) {
+        append_tree(&mut archive, &schemas_root, SCHEMAS_PREFIX, &mut files)?;
+    }
+
+    let manifest = BackupManifest {
+        format: BACKUP_FORMAT,
+        space: space.to_string(),
+        db: db.to_string(),
+        created_at,
+        change_seq,
+        files,
+    };
+    append_file(
+        &mut archive,
+        MANIFEST,
+        &serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    archive.into_inner()?.finish()?.sync_all()?;
+    fs::rename(&tmp, dest)?;
+    Ok(manifest)
+}
+
+/// Verrous exclusifs de toutes les collections, dans l'ordre des transactions
+fn lock_collections(config: &JsonDbConfig, space: &str, db: &str) -> Result<Vec<FileLock>> {
+    let collections_root = config.db_root(space, db).join("collections");
+    let mut collections = Vec::new();
+    if collections_root.exists() {
+        for entry in fs::read_dir(&collections_root)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                collections.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    collections.sort();
+
+    let lm = LockManager::new();
+    collections
+        .iter()
+        .map(|c| lm.lock_collection(config, space, db, c, LockMode::Exclusive))
+        .collect()
+}
+
+fn append_tree<W: Write>(
+    archive: &mut tar::Builder<W>,
+    root: &Path,
+    prefix: &str,
+    files: &mut Vec<BackupEntry>,
+) -> Result<()> {
+    if !root.exists() {
+        return Ok(());
+    }
+    let walker = WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            !(e.depth() == 1
+                && e.file_type().is_dir()
+                && EXCLUDED_DIRS.iter().any(|d| e.file_name() == *d))
+        });
+    for entry in walker {
+        let entry = entry?;
+        let rel = entry.path().strip_prefix(root)?;
+        // Fichiers temporaires d'une écriture atomique interrompue
+        if !entry.file_type().is_file() || rel.extension().is_some_and(|e| e == "tmp") {
+            continue;
+        }
+        let data = fs::read(entry.path())?;
+        let path = format!("{}/{}", prefix, rel.to_string_lossy().replace('\\', "/"));
+        append_file(archive, &path, &data)?;
+        files.push(BackupEntry {
+            path,
+            size: data.len() as u64,
+            sha256: sha256_hex(&data),
+        });
+    }
+    Ok(())
+}
+
+fn append_file<W: Write>(archive: &mut tar::Builder<W>, path: &str, data: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    archive.append_data(&mut header, path, data)?;
+    Ok(())
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+// --- RESTAURATION ---
+
+/// Lit le manifeste d'une archive (sans l'extraire ni la vérifier)
+pub fn read_manifest(archive: &Path) -> Result<BackupManifest> {
+    let mut tar = open_archive(archive)?;
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.as_ref() == Path::new(MANIFEST) {
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            return Ok(serde_json::from_slice(&data)?);
+        }
+    }
+    Err(anyhow!("Archive sans manifeste : {:?}", archive))
+}
+
+/// Restaure l'archive dans la base `space/db`, qui ne doit pas exister.
+/// Avec un journal des changements, rejoue ensuite les écritures postérieures
+/// à la sauvegarde (jusqu'à `options.until`).
+pub fn restore(
+    config: &JsonDbConfig,
+    archive: &Path,
+    space: &str,
+    db: &str,
+    options: &RestoreOptions,
+) -> Result<RestoreReport> {
+    let target = config.db_root(space, db);
+    if target.exists() {
+        bail!(
+            "La base {}/{} existe déjà : restaurer sous un autre nom",
+            space,
+            db
+        );
+    }
+
+    // Extraction et vérification à côté de la cible, puis mise en place
+    let staging =
+        config
+            .data_root
+            .join(space)
+            .join(format!(".{}.restore-{}", db, uuid::Uuid::new_v4()));
+    let installed = extract(archive, &staging).and_then(|manifest| {
+        if let Some(until) = options.until {
+            if until < manifest.created_at {
+                bail!(
+                    "Sauvegarde postérieure à la date demandée : utiliser une sauvegarde plus ancienne"
+                );
+            }
+        }
+        let staged_db = staging.join(DB_PREFIX);
+        rewrite_schema_uris(&staged_db, &manifest, space, db)?;
+        fs::rename(&staged_db, &target)?;
+        install_schemas(
+            &staging.join(SCHEMAS_PREFIX),
+            &config.db_schemas_root(space, db),
+        )?;
+        Ok(manifest)
+    });
+    let _ = fs::remove_dir_all(&staging);
+    let manifest = installed?;
+
+    // Transactions validées au moment de la sauvegarde mais pas encore appliquées
+    recovery::recover(config, space, db)?;
+    let replayed = match &options.changes {
+        Some(journal) => replay(config, space, db, &manifest, journal, options.until)?,
+        None => 0,
+    };
+
+    Ok(RestoreReport {
+        space: space.to_string(),
+        db: db.to_string(),
+        files: manifest.files.len(),
+        replayed,
+        change_seq: changes::last_seq(config, space, db)?,
+    })
+}
+
+fn open_archive(
+    archive: &Path,
+) -> Result<tar::Archive<zstd::Decoder<'static, std::io::BufReader<File>>>> {
+    let file = File::open(archive).with_context(|| format!("Archive illisible : {:?}", archive))?;
+    Ok(tar::Archive::new(zstd::Decoder::new(file)?))
+}
+
+/// Extrait l'archive dans `staging` et vérifie chaque fichier contre le manifeste
+fn extract(archive: &Path, staging: &Path) -> Result<BackupManifest> {
+    let mut tar = open_archive(archive)?;
+    let mut manifest: Option<BackupManifest> = None;
+    let mut extracted: HashMap<String, (u64, String)> = HashMap::new();
+
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().replace('\\', "/");
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        if path == MANIFEST {
+            manifest = Some(serde_json::from_slice(&data)?);
+            continue;
+        }
+        let dest = staging.join(checked_path(&path)?);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&dest, &data)?;
+        extracted.insert(path, (data.len() as u64, sha256_hex(&data)));
+    }
+
+    let manifest = manifest.ok_or_else(|| anyhow!("Archive sans manifeste : {:?}", archive))?;
+    if manifest.format > BACKUP_FORMAT {
+        bail!(
+            "Format de sauvegarde {} non supporté (maximum {})",
+            manifest.format,
+            BACKUP_FORMAT
+        );
+    }
+    for file in &manifest.files {
+        match extracted.remove(&file.path) {
+            Some((size, sha256)) if size == file.size && sha256 == file.sha256 => {}
+            Some(_) => bail!("Empreinte invalide : {}", file.path),
+            None => bail!("Fichier absent de l'archive : {}", file.path),
+        }
+    }
+    if let Some(path) = extracted.keys().next() {
+        bail!("Fichier absent du manifeste : {}", path);
+    }
+    Ok(manifest)
+}
+
+/// Chemin relatif sûr (ni absolu, ni `..`) sous `db/` ou `schemas/`
+fn checked_path(path: &str) -> Result<&Path> {
+    let rel = Path::new(path);
+    let normal = rel.components().all(|c| matches!(c, Component::Normal(_)));
+    if !normal || !(rel.starts_with(DB_PREFIX) || rel.starts_with(SCHEMAS_PREFIX)) {
+        bail!("Chemin invalide dans l'archive : {}", path);
+    }
+    Ok(rel)
+}
+
+/// Restauration sous un autre nom : les métadonnées désignent la nouvelle base.
+/// Les documents sont restaurés à l'identique (leur `$schema` éventuel garde
+/// l'URI d'origine).
+fn rewrite_schema_uris(
+    db_root: &Path,
+    manifest: &BackupManifest,
+    space: &str,
+    db: &str,
+) -> Result<()> {
+    if manifest.space == space && manifest.db == db {
+        return Ok(());
+    }
+    let from = format!("db://{}/{}/", manifest.space, manifest.db);
+    let to = format!("db://{}/{}/", space, db);
+
+    for entry in WalkDir::new(db_root) {
+        let entry = entry?;
+        let is_system = entry.depth() == 1 && entry.file_name() == "_system.json";
+        if !entry.file_type().is_file() || !(is_system || entry.file_name() == "_meta.json") {
+            continue;
+        }
+        let content = fs::read_to_string(entry.path())?.replace(&from, &to);
+        let mut doc: Value = serde_json::from_str(&content)?;
+        if is_system {
+            if let Some(obj) = doc.as_object_mut() {
+                if obj.contains_key("space") {
+                    obj.insert("space".to_string(), Value::from(space));
+                }
+                if obj.contains_key("database") {
+                    obj.insert("database".to_string(), Value::from(db));
+                }
+            }
+        }
+        file_storage::atomic_write(entry.path(), serde_json::to_string_pretty(&doc)?)?;
+    }
+    Ok(())
+}
+
+/// Ajoute les schémas sauvegardés absents de l'espace cible (les schémas déjà
+/// présents sont conservés)
+fn install_schemas(staged: &Path, dest: &Path) -> Result<()> {
+    if !staged.exists() {
+        return Ok(());
+    }
+    for entry in WalkDir::new(staged) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let target = dest.join(entry.path().strip_prefix(staged)?);
+        if !target.exists() {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Rejoue les événements du journal qui suivent la sauvegarde, jusqu'à `until`.
+/// Retourne le nombre d'événements rejoués.
+fn replay(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    manifest: &BackupManifest,
+    journal: &Path,
+    until: Option<i64>,
+) -> Result<usize> {
+    let events: Vec<ChangeEvent> = changes::read_journal(journal)?
+        .into_iter()
+        .filter(|e| e.seq > manifest.change_seq)
+        .take_while(|e| until.is_none_or(|until| e.timestamp <= until))
+        .collect();
+
+    // Le journal doit prolonger la sauvegarde sans trou
+    for (expected, event) in (manifest.change_seq + 1..).zip(&events) {
+        if event.seq != expected {
+            bail!(
+                "Journal incomplet : événement {} attendu, {} trouvé",
+                expected,
+                event.seq
+            );
+        }
+    }
+    if events.is_empty() {
+        return Ok(0);
+    }
+
+    // Dernier état de chaque document, appliqué en une passe
+    let mut order: Vec<(String, String)> = Vec::new();
+    let mut latest: HashMap<(String, String), Option<Value>> = HashMap::new();
+    for event in &events {
+        let key = (event.collection.clone(), event.id.clone());
+        if latest.insert(key.clone(), event.after.clone()).is_none() {
+            order.push(key);
+        }
+    }
+    let mut from = Vec::with_capacity(order.len());
+    let mut to = Vec::with_capacity(order.len());
+    for key in order {
+        let (collection, id) = &key;
+        from.push(DocumentImage {
+            collection: collection.clone(),
+            id: id.clone(),
+            document: file_storage::read_document(config, space, db, collection, id)?,
+        });
+        to.push(DocumentImage {
+            document: latest.remove(&key).flatten(),
+            collection: key.0,
+            id: key.1,
+        });
+    }
+    TransactionManager::new(config, space, db).apply_images(&from, &to)?;
+    changes::import(config, space, db, &events)?;
+    Ok(events.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_db::test_utils::init_test_env;
+    use serde_json::json;
+
+    fn write(cfg: &JsonDbConfig, space: &str, db: &str, id: &str, doc: Option<Value>) {
+        let before = file_storage::read_document(cfg, space, db, "users", id).unwrap();
+        match &doc {
+            Some(d) => file_storage::write_document(cfg, space, db, "users", id, d).unwrap(),
+            None => file_storage::delete_document(cfg, space, db, "users", id).unwrap(),
+        }
+        changes::record_write(cfg, space, db, "users", id, before.as_ref(), doc.as_ref()).unwrap();
+    }
+
+    #[test]
+    fn test_backup_and_restore_under_new_name() {
+        let env = init_test_env();
+        let (cfg, space, db) = (&env.cfg, env.space.as_str(), env.db.as_str());
+        write(
+            cfg,
+            space,
+            db,
+            "u1",
+            Some(json!({ "id": "u1", "name": "Alice" })),
+        );
+        let meta = json!({ "schema": format!("db://{}/{}/schemas/v1/user.json", space, db) });
+        fs::write(
+            cfg.db_collection_path(space, db, "users")
+                .join("_meta.json"),
+            meta.to_string(),
+        )
+        .unwrap();
+        fs::create_dir_all(cfg.db_root(space, db).join("_locks")).unwrap();
+
+        let archive = env.tmp_dir.path().join("backups").join("d.tar.zst");
+        let manifest = backup(cfg, space, db, &archive).unwrap();
+        assert_eq!(manifest.change_seq, 1);
+        assert!(manifest.files.iter().all(|f| !f.path.contains("_locks")));
+        assert_eq!(read_manifest(&archive).unwrap(), manifest);
+
+        let report = restore(cfg, &archive, space, "copy", &RestoreOptions::new()).unwrap();
+        assert_eq!(report.files, manifest.files.len());
+        assert_eq!(report.change_seq, 1);
+        let doc = file_storage::read_document(cfg, space, "copy", "users", "u1").unwrap();
+        assert_eq!(doc.unwrap()["name"], "Alice");
+        let meta: Value = serde_json::from_str(
+            &fs::read_to_string(
+                cfg.db_collection_path(space, "copy", "users")
+                    .join("_meta.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            meta["schema"],
+            format!("db://{}/copy/schemas/v1/user.json", space)
+        );
+
+        // Cible existante refusée, sans dossier temporaire résiduel
+        assert!(restore(cfg, &archive, space, "copy", &RestoreOptions::new()).is_err());
+        let leftovers = fs::read_dir(cfg.data_root.join(space))
+            .unwrap()
+            .filter(|e| {
+                e.as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with('.')
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    fn test_restore_rejects_tampered_archive() {
+        let env = init_test_env();
+        let (cfg, space, db) = (&env.cfg, env.space.as_str(), env.db.as_str());
+        write(cfg, space, db, "u1", Some(json!({ "id": "u1" })));
+        let archive = env.tmp_dir.path().join("d.tar.zst");
+        let mut manifest = backup(cfg, space, db, &archive).unwrap();
+
+        // Même contenu, empreinte déclarée différente
+        manifest.files[0].sha256 = sha256_hex(b"autre contenu");
+        let tampered = env.tmp_dir.path().join("tampered.tar.zst");
+        let mut out =
+            tar::Builder::new(zstd::Encoder::new(File::create(&tampered).unwrap(), 0).unwrap());
+        let mut tar = open_archive(&archive).unwrap();
+        for entry in tar.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_string_lossy().to_string();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).unwrap();
+            if path == MANIFEST {
+                data = serde_json::to_vec(&manifest).unwrap();
+            }
+            append_file(&mut out, &path, &data).unwrap();
+        }
+        out.into_inner().unwrap().finish().unwrap();
+
+        let err = restore(cfg, &tampered, space, "copy", &RestoreOptions::new()).unwrap_err();
+        assert!(err.to_string().contains("Empreinte invalide"));
+        assert!(!cfg.db_root(space, "copy").exists());
+    }
+
+    #[test]
+    fn test_point_in_time_restore_replays_journal_until_timestamp() {
+        let env = init_test_env();
+        let (cfg, space, db) = (&env.cfg, env.space.as_str(), env.db.as_str());
+        write(cfg, space, db, "u1", Some(json!({ "id": "u1", "v": 1 })));
+        let archive = env.tmp_dir.path().join("d.tar.zst");
+        backup(cfg, space, db, &archive).unwrap();
+
+        write(cfg, space, db, "u1", Some(json!({ "id": "u1", "v": 2 })));
+        write(cfg, space, db, "u2", Some(json!({ "id": "u2" })));
+        write(cfg, space, db, "u1", None);
+        let journal = env.tmp_dir.path().join("wal-archive").join("changes.jsonl");
+        changes::archive(cfg, space, db, &journal).unwrap();
+        let events = changes::read_journal(&journal).unwrap();
+
+        // Jusqu'au deuxième événement postérieur à la sauvegarde
+        let options = RestoreOptions::new()
+            .with_changes(&journal)
+            .with_until(events[2].timestamp);
+        let report = restore(cfg, &archive, space, "pitr", &options).unwrap();
+        let expected = events
+            .iter()
+            .filter(|e| e.seq > 1 && e.timestamp <= events[2].timestamp);
+        assert_eq!(report.replayed, expected.count());
+        assert_eq!(report.change_seq, 1 + report.replayed as u64);
+
+        // Journal complet : état final de la base d'origine
+        let report = restore(
+            cfg,
+            &archive,
+            space,
+            "latest",
+            &RestoreOptions::new().with_changes(&journal),
+        )
+        .unwrap();
+        assert_eq!(report.replayed, 3);
+        assert_eq!(report.change_seq, 4);
+        let read =
+            |id: &str| file_storage::read_document(cfg, space, "latest", "users", id).unwrap();
+        assert!(read("u1").is_none());
+        assert!(read("u2").is_some());
+        let sys: Value = serde_json::from_str(
+            &fs::read_to_string(cfg.db_root(space, "latest").join("_system.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            sys["collections"]["users"]["items"],
+            json!([{ "file": "u2.json" }])
+        );
+    }
+}
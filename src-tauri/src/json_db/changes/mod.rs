@@ -0,0 +1,853 @@
+// FICHIER : src-tauri/src/json_db/changes/mod.rs
+
+//! Flux de changements (change feed) d'une base.
+//!
+//! Chaque écriture produit un `ChangeEvent` (insertion, mise à jour ou
+//! suppression, avec l'état du document avant et après), ajouté au journal
+//! `<db>/_changes/changes.jsonl` sous un numéro de séquence croissant propre à
+//! la base. Ce numéro sert de jeton de reprise : un consommateur qui a traité
+//! l'événement `seq` reprend avec `read_since(.., seq, ..)` ou
+//! `subscribe(.., seq)`, y compris après un redémarrage.
+//!
+//! - Transactions : les événements sont écrits à la validation, avant le
+//!   retrait du WAL. Une validation rejouée par la récupération n'est pas
+//!   dupliquée (les dernières transactions publiées sont mémorisées).
+//! - Écritures directes du `CollectionsManager` : journalisées dans le WAL
+//!   comme une transaction d'une opération, l'événement (sans transaction
+//!   d'origine) est écrit avant l'acquittement ; un échec fait échouer
+//!   l'écriture, que la récupération publie ensuite.
+//!
+//! Les abonnés du processus sont réveillés à chaque ajout ; les écritures des
+//! autres processus (CLI, agents) sont vues par relecture périodique du journal.
+//!
+//! L'état du journal (`_state.json`) relève régulièrement la position d'un
+//! événement (point de reprise) : une reprise lit à partir du dernier point qui
+//! précède son jeton, pas depuis le début. `purge` retire les événements déjà
+//! archivés ; un consommateur dont le jeton précède la purge reçoit
+//! `ChangesPurged`.
+
+use crate::json_db::storage::{file_storage, JsonDbConfig};
+use crate::json_db::transactions::lock_manager::{FileLock, LockMode, DEFAULT_LOCK_TIMEOUT};
+use crate::json_db::transactions::TransactionLog;
+use anyhow::Result;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Intervalle de relecture du journal par un abonné inactif
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Événements lus par passe dans le journal
+const BATCH_SIZE: usize = 256;
+
+/// Journaux WAL publiés mémorisés contre les doublons de la récupération
+const RECENT_TRANSACTIONS: usize = 64;
+
+/// Écart minimal (octets) entre deux points de reprise du journal
+const CHECKPOINT_SPACING: u64 = 1024 * 1024;
+
+/// Jeton de reprise antérieur à la rétention : des événements qui le suivent
+/// ont été purgés du journal. Récupérable via `err.downcast_ref::<ChangesPurged>()`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "Reprise impossible après l'événement {after} : journal purgé jusqu'à l'événement {purged}"
+)]
+pub struct ChangesPurged {
+    pub after: u64,
+    pub purged: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl ChangeKind {
+    /// Nature du changement d'après la présence du document avant / après
+    pub fn of(before: Option<&Value>, after: Option<&Value>) -> Option<Self> {
+        match (before, after) {
+            (None, Some(_)) => Some(Self::Insert),
+            (Some(_), Some(_)) => Some(Self::Update),
+            (Some(_), None) => Some(Self::Delete),
+            (None, None) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// Numéro de séquence dans la base (jeton de reprise)
+    pub seq: u64,
+    /// Horodatage de l'écriture (millisecondes UTC)
+    pub timestamp: i64,
+    pub collection: String,
+    pub id: String,
+    pub kind: ChangeKind,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    /// Transaction d'origine (`None` pour une écriture directe)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<String>,
+}
+
+/// Écriture à publier : (collection, id, avant, après)
+type Change<'a> = (&'a str, &'a str, Option<&'a Value>, Option<&'a Value>);
+
+/// État du journal, réécrit après chaque ajout
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FeedState {
+    seq: u64,
+    /// Taille du journal après le dernier ajout complet
+    len: u64,
+    recent: VecDeque<String>,
+    /// Dernier événement purgé (0 si aucun)
+    #[serde(default)]
+    purged: u64,
+    /// Points de reprise : (numéro d'un événement, position de sa ligne)
+    #[serde(default)]
+    checkpoints: Vec<(u64, u64)>,
+}
+
+impl FeedState {
+    /// Mémorise un journal publié (une seule fois par journal)
+    fn remember(&mut self, tx: &str) {
+        if self.recent.back().map(String::as_str) != Some(tx) {
+            self.recent.push_back(tx.to_string());
+        }
+        if self.recent.len() > RECENT_TRANSACTIONS {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Relève la ligne de l'événement `seq` comme point de reprise si elle est
+    /// assez loin du précédent
+    fn checkpoint(&mut self, seq: u64, offset: u64) {
+        let last = self.checkpoints.last().map(|&(_, o)| o);
+        if last.map_or(offset >= CHECKPOINT_SPACING, |o| {
+            offset >= o + CHECKPOINT_SPACING
+        }) {
+            self.checkpoints.push((seq, offset));
+        }
+    }
+}
+
+/// État enregistré du journal (réécrit en entier : lisible sans verrou)
+fn read_state(path: &Path) -> FeedState {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn changes_dir(config: &JsonDbConfig, space: &str, db: &str) -> PathBuf {
+    config.db_root(space, db).join("_changes")
+}
+
+fn log_path(config: &JsonDbConfig, space: &str, db: &str) -> PathBuf {
+    changes_dir(config, space, db).join("changes.jsonl")
+}
+
+fn state_path(config: &JsonDbConfig, space: &str, db: &str) -> PathBuf {
+    changes_dir(config, space, db).join("_state.json")
+}
+
+fn feed_lock(config: &JsonDbConfig, space: &str, db: &str) -> Result<FileLock> {
+    let path = config
+        .db_root(space, db)
+        .join("_locks")
+        .join("_changes.lock");
+    FileLock::acquire(&path, LockMode::Exclusive, DEFAULT_LOCK_TIMEOUT)
+}
+
+/// Publie les documents modifiés par une transaction validée (sans effet si
+/// elle l'a déjà été, cas d'une validation rejouée après un crash)
+pub(crate) fn record_transaction(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    log: &TransactionLog,
+) -> Result<()> {
+    let changes: Vec<Change> = log
+        .after_images
+        .iter()
+        .map(|after| {
+            let before = log
+                .before_images
+                .iter()
+                .find(|b| b.collection == after.collection && b.id == after.id)
+                .and_then(|b| b.document.as_ref());
+            (
+                after.collection.as_str(),
+                after.id.as_str(),
+                before,
+                after.document.as_ref(),
+            )
+        })
+        .collect();
+    // Écriture directe : dédoublonnée par l'ID de son journal, publiée sans transaction
+    let transaction = (!log.direct).then_some(log.id.as_str());
+    append(config, space, db, &log.id, transaction, &changes)
+}
+
+/// Publie une écriture hors WAL (tests)
+#[cfg(test)]
+pub(crate) fn record_write(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    collection: &str,
+    id: &str,
+    before: Option<&Value>,
+    after: Option<&Value>,
+) -> Result<()> {
+    let key = uuid::Uuid::new_v4().to_string();
+    append(
+        config,
+        space,
+        db,
+        &key,
+        None,
+        &[(collection, id, before, after)],
+    )
+}
+
+/// Ajoute les événements d'un journal WAL `key` (ignoré s'il a déjà été publié)
+fn append(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    key: &str,
+    transaction: Option<&str>,
+    changes: &[Change],
+) -> Result<()> {
+    let _guard = feed_lock(config, space, db)?;
+    let path = log_path(config, space, db);
+    let mut state = load_state(config, space, db, &path)?;
+    if state.recent.iter().any(|t| t == key) {
+        return Ok(());
+    }
+
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let mut events = Vec::with_capacity(changes.len());
+    let mut seq = state.seq;
+    for &(collection, id, before, after) in changes {
+        let Some(kind) = ChangeKind::of(before, after) else {
+            continue;
+        };
+        seq += 1;
+        events.push(ChangeEvent {
+            seq,
+            timestamp,
+            collection: collection.to_string(),
+            id: id.to_string(),
+            kind,
+            before: before.cloned(),
+            after: after.cloned(),
+            transaction: transaction.map(String::from),
+        });
+    }
+    if !events.is_empty() {
+        state.remember(key);
+    }
+    write_events(config, space, db, &path, state, &events)
+}
+
+/// Reprend des événements d'un autre journal tels quels (numéros et dates
+/// conservés), à la suite du journal de la base. Sert au rejeu d'une
+/// restauration : les jetons de reprise des consommateurs restent valides.
+pub(crate) fn import(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    events: &[ChangeEvent],
+) -> Result<()> {
+    let _guard = feed_lock(config, space, db)?;
+    let path = log_path(config, space, db);
+    let state = load_state(config, space, db, &path)?;
+    let events: Vec<ChangeEvent> = events
+        .iter()
+        .filter(|e| e.seq > state.seq)
+        .cloned()
+        .collect();
+    write_events(config, space, db, &path, state, &events)
+}
+
+/// Ajoute les événements au journal (appel sous le verrou du flux), puis
+/// enregistre l'état et réveille les abonnés
+fn write_events(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    path: &Path,
+    mut state: FeedState,
+    events: &[ChangeEvent],
+) -> Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    let mut lines = String::new();
+    for event in events {
+        state.checkpoint(event.seq, state.len + lines.len() as u64);
+        lines.push_str(&serde_json::to_string(event)?);
+        lines.push('\n');
+        state.seq = state.seq.max(event.seq);
+        if let Some(tx) = &event.transaction {
+            state.remember(tx);
+        }
+    }
+
+    fs::create_dir_all(changes_dir(config, space, db))?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())?;
+    file.sync_data()?;
+    state.len = file.metadata()?.len();
+    file_storage::atomic_write(
+        state_path(config, space, db),
+        serde_json::to_string(&state)?,
+    )?;
+
+    notifier(config, space, db).send_replace(state.seq);
+    Ok(())
+}
+
+/// État du journal, resynchronisé si un ajout a été interrompu avant la mise
+/// à jour de l'état (événements complets repris, ligne tronquée retirée)
+fn load_state(config: &JsonDbConfig, space: &str, db: &str, path: &Path) -> Result<FeedState> {
+    let mut state = read_state(&state_path(config, space, db));
+    let len = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if len == state.len {
+        return Ok(state);
+    }
+
+    // Journal recréé ou réduit (purge interrompue) : relu en entier, les
+    // événements absents sont tenus pour purgés
+    let from = if len < state.len { 0 } else { state.len };
+    if from == 0 {
+        state.checkpoints.clear();
+        if let Some((_, first)) = read_from(path, 0, 1)?.0.first() {
+            state.purged = state.purged.max(first.seq.saturating_sub(1));
+        }
+    }
+    let mut offset = from;
+    loop {
+        let (events, end) = read_from(path, offset, BATCH_SIZE)?;
+        for (line, event) in events {
+            state.checkpoint(event.seq, line);
+            state.seq = state.seq.max(event.seq);
+            if let Some(tx) = &event.transaction {
+                state.remember(tx);
+            }
+        }
+        if end == offset {
+            break;
+        }
+        offset = end;
+    }
+    if offset < len {
+        OpenOptions::new().write(true).open(path)?.set_len(offset)?;
+    }
+    state.len = offset;
+    Ok(state)
+}
+
+/// Lit au plus `limit` événements complets à partir de l'octet `offset`, avec
+/// la position de leur ligne. Retourne aussi la position après le dernier
+/// événement lu (une ligne en cours d'écriture, sans retour à la ligne final,
+/// n'est pas consommée).
+fn read_from(path: &Path, offset: u64, limit: usize) -> Result<(Vec<(u64, ChangeEvent)>, u64)> {
+    let Ok(mut file) = File::open(path) else {
+        return Ok((Vec::new(), offset));
+    };
+    file.seek(SeekFrom::Start(offset))?;
+    let mut reader = BufReader::new(file);
+    let mut events = Vec::new();
+    let mut end = offset;
+    let mut line = Vec::new();
+    while events.len() < limit {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 || line.last() != Some(&b'\n') {
+            break;
+        }
+        events.push((end, serde_json::from_slice(&line)?));
+        end += read as u64;
+    }
+    Ok((events, end))
+}
+
+/// Position de lecture dans le journal : octet de départ et, sauf au début du
+/// journal, numéro de l'événement déjà lu dont la ligne commence à cet octet
+type Position = (u64, Option<u64>);
+
+/// Position de lecture d'un consommateur dans le journal : chaque lot reprend
+/// à la ligne du dernier événement lu. Cette ligne est relue pour vérifier la
+/// position : si le journal a été réécrit par une purge entre-temps, le
+/// curseur se replace d'après les points de reprise.
+#[derive(Clone)]
+pub(crate) struct ChangeCursor {
+    path: PathBuf,
+    state_path: PathBuf,
+    position: Option<Position>,
+    after: u64,
+}
+
+impl ChangeCursor {
+    pub(crate) fn new(config: &JsonDbConfig, space: &str, db: &str, after: u64) -> Self {
+        Self {
+            path: log_path(config, space, db),
+            state_path: state_path(config, space, db),
+            position: None,
+            after,
+        }
+    }
+
+    /// Position de départ : dernier point de reprise qui précède le jeton, ou
+    /// début du journal (`from_start`, ou aucun point utilisable)
+    fn seek(&self, from_start: bool) -> Result<Position> {
+        let state = read_state(&self.state_path);
+        if self.after > 0 && self.after < state.purged {
+            return Err(ChangesPurged {
+                after: self.after,
+                purged: state.purged,
+            }
+            .into());
+        }
+        let checkpoint = state
+            .checkpoints
+            .iter()
+            .rev()
+            .find(|&&(seq, _)| seq <= self.after)
+            .filter(|_| !from_start);
+        Ok(checkpoint.map_or((0, None), |&(seq, offset)| (offset, Some(seq))))
+    }
+
+    /// Prochains événements postérieurs au jeton (vide en fin de journal)
+    pub(crate) fn next_batch(&mut self) -> Result<Vec<ChangeEvent>> {
+        let mut misplaced = 0;
+        loop {
+            let (offset, anchor) = match self.position {
+                Some(position) => position,
+                None => self.seek(misplaced > 1)?,
+            };
+            let read = read_from(&self.path, offset, BATCH_SIZE);
+            if anchor.is_some() {
+                let found = read.as_ref().ok().and_then(|(events, _)| events.first());
+                if found.map(|(_, e)| e.seq) != anchor {
+                    // Journal réécrit depuis la dernière lecture
+                    misplaced += 1;
+                    self.position = None;
+                    continue;
+                }
+            }
+            let (events, _) = read?;
+            let consumed = if anchor.is_some() { 1 } else { 0 };
+            let progressed = events.len() > consumed;
+            if let Some(&(line, ref last)) = events.last() {
+                self.position = Some((line, Some(last.seq)));
+            } else {
+                self.position = Some((offset, anchor));
+            }
+            let events: Vec<ChangeEvent> = events
+                .into_iter()
+                .map(|(_, e)| e)
+                .filter(|e| e.seq > self.after)
+                .collect();
+            if let Some(last) = events.last() {
+                self.after = last.seq;
+            }
+            if !events.is_empty() || !progressed {
+                return Ok(events);
+            }
+        }
+    }
+}
+
+/// Événements postérieurs au jeton `after` (0 : depuis le début), au plus `limit`
+pub fn read_since(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    after: u64,
+    limit: usize,
+) -> Result<Vec<ChangeEvent>> {
+    let mut cursor = ChangeCursor::new(config, space, db, after);
+    let mut events = Vec::new();
+    while events.len() < limit {
+        let batch = cursor.next_batch()?;
+        if batch.is_empty() {
+            break;
+        }
+        events.extend(batch);
+    }
+    events.truncate(limit);
+    Ok(events)
+}
+
+/// Numéro du dernier événement publié (0 si aucun)
+pub fn last_seq(config: &JsonDbConfig, space: &str, db: &str) -> Result<u64> {
+    let _guard = feed_lock(config, space, db)?;
+    Ok(load_state(config, space, db, &log_path(config, space, db))?.seq)
+}
+
+/// Événements complets d'un fichier journal quelconque (journal d'une base,
+/// copie archivée). Un fichier absent est un journal vide.
+pub fn read_journal(path: &Path) -> Result<Vec<ChangeEvent>> {
+    let (events, _) = read_from(path, 0, usize::MAX)?;
+    Ok(events.into_iter().map(|(_, e)| e).collect())
+}
+
+/// Archivage continu : copie dans `dest` les événements de la base qui n'y
+/// sont pas encore (reprise après le dernier événement archivé). Lancé
+/// périodiquement vers un autre support, il permet de restaurer une
+/// sauvegarde jusqu'à une date donnée. Retourne le nombre d'événements copiés.
+pub fn archive(config: &JsonDbConfig, space: &str, db: &str, dest: &Path) -> Result<usize> {
+    let (archived, end) = read_from(dest, 0, usize::MAX)?;
+    let last = archived.last().map_or(0, |(_, e)| e.seq);
+    let mut cursor = ChangeCursor::new(config, space, db, last);
+
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(dest)?;
+    // Une copie interrompue peut laisser une ligne tronquée : retirée
+    if file.metadata()?.len() > end {
+        file.set_len(end)?;
+    }
+    let mut copied = 0;
+    loop {
+        let batch = cursor.next_batch()?;
+        if batch.is_empty() {
+            break;
+        }
+        let mut lines = String::new();
+        for event in &batch {
+            lines.push_str(&serde_json::to_string(event)?);
+            lines.push('\n');
+        }
+        file.write_all(lines.as_bytes())?;
+        copied += batch.len();
+    }
+    file.sync_data()?;
+    Ok(copied)
+}
+
+/// Rétention : retire du journal les événements jusqu'au numéro `upto` inclus
+/// (en général le dernier événement archivé par `archive`). Le reste est
+/// recopié dans un nouveau journal qui remplace l'ancien ; les numéros ne
+/// changent pas. Retourne le nombre d'événements retirés.
+pub fn purge(config: &JsonDbConfig, space: &str, db: &str, upto: u64) -> Result<usize> {
+    let _guard = feed_lock(config, space, db)?;
+    let path = log_path(config, space, db);
+    let mut state = load_state(config, space, db, &path)?;
+
+    // Position du premier événement conservé
+    let (mut cut, mut purged, mut removed) = (0, state.purged, 0);
+    'scan: loop {
+        let (events, end) = read_from(&path, cut, BATCH_SIZE)?;
+        if events.is_empty() {
+            break;
+        }
+        for (line, event) in events {
+            if event.seq > upto {
+                cut = line;
+                break 'scan;
+            }
+            purged = event.seq;
+            removed += 1;
+        }
+        cut = end;
+    }
+    if removed == 0 {
+        return Ok(0);
+    }
+
+    let tmp = path.with_extension("jsonl.tmp");
+    let mut source = File::open(&path)?;
+    source.seek(SeekFrom::Start(cut))?;
+    let mut kept = File::create(&tmp)?;
+    io::copy(&mut source, &mut kept)?;
+    kept.sync_data()?;
+    // Journal d'abord : interrompue ici, la purge est constatée par `load_state`
+    fs::rename(&tmp, &path)?;
+
+    state.len -= cut;
+    state.purged = purged;
+    state.checkpoints = state
+        .checkpoints
+        .iter()
+        .filter(|&&(seq, _)| seq > purged)
+        .map(|&(seq, offset)| (seq, offset - cut))
+        .collect();
+    file_storage::atomic_write(
+        state_path(config, space, db),
+        serde_json::to_string(&state)?,
+    )?;
+    Ok(removed)
+}
+
+/// Flux continu des événements postérieurs au jeton `after` : l'historique
+/// d'abord, puis les nouveaux événements au fil de l'eau. Ne se termine jamais ;
+/// une erreur de lecture est transmise et la lecture reprend au même point.
+pub fn subscribe(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    after: u64,
+) -> BoxStream<'static, Result<ChangeEvent>> {
+    struct Subscription {
+        cursor: ChangeCursor,
+        pending: VecDeque<ChangeEvent>,
+        wake: watch::Receiver<u64>,
+    }
+
+    let subscription = Subscription {
+        cursor: ChangeCursor::new(config, space, db, after),
+        pending: VecDeque::new(),
+        wake: notifier(config, space, db).subscribe(),
+    };
+    stream::unfold(subscription, |mut sub| async move {
+        loop {
+            if let Some(event) = sub.pending.pop_front() {
+                return Some((Ok(event), sub));
+            }
+            // Lecture bloquante hors du runtime ; en cas d'échec, le curseur
+            // reste à sa position
+            let mut cursor = sub.cursor.clone();
+            let read = tokio::task::spawn_blocking(move || {
+                cursor.next_batch().map(|events| (cursor, events))
+            })
+            .await;
+            match read {
+                Ok(Ok((cursor, events))) => {
+                    sub.cursor = cursor;
+                    if !events.is_empty() {
+                        sub.pending.extend(events);
+                        continue;
+                    }
+                    // Réveil par un ajout du processus, ou relecture périodique
+                    let _ = tokio::time::timeout(POLL_INTERVAL, sub.wake.changed()).await;
+                }
+                Ok(Err(e)) => return Some((Err(e), sub)),
+                Err(e) => return Some((Err(e.into()), sub)),
+            }
+        }
+    })
+    .boxed()
+}
+
+// --- NOTIFICATION DES ABONNÉS DU PROCESSUS ---
+// Un canal par base, partagé par tous les écrivains et abonnés du processus.
+
+fn notifier(config: &JsonDbConfig, space: &str, db: &str) -> watch::Sender<u64> {
+    static REGISTRY: OnceLock<Mutex<HashMap<PathBuf, watch::Sender<u64>>>> = OnceLock::new();
+    let mut registry = REGISTRY
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    registry
+        .entry(config.db_root(space, db))
+        .or_insert_with(|| watch::channel(0).0)
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_db::test_utils::init_test_env;
+    use serde_json::json;
+
+    #[test]
+    fn test_append_and_resume() {
+        let env = init_test_env();
+        let (cfg, space, db) = (&env.cfg, env.space.as_str(), env.db.as_str());
+        let v1 = json!({ "id": "a", "n": 1 });
+        let v2 = json!({ "id": "a", "n": 2 });
+
+        record_write(cfg, space, db, "c", "a", None, Some(&v1)).unwrap();
+        record_write(cfg, space, db, "c", "a", Some(&v1), Some(&v2)).unwrap();
+        record_write(cfg, space, db, "c", "a", Some(&v2), None).unwrap();
+
+        let events = read_since(cfg, space, db, 0, 10).unwrap();
+        let kinds: Vec<ChangeKind> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![ChangeKind::Insert, ChangeKind::Update, ChangeKind::Delete]
+        );
+        assert_eq!(events[1].before, Some(v1));
+        assert_eq!(events[1].after, Some(v2));
+
+        // Reprise après le jeton 1, limite respectée
+        let resumed = read_since(cfg, space, db, 1, 1).unwrap();
+        assert_eq!(resumed.len(), 1);
+        assert_eq!(resumed[0].seq, 2);
+        assert_eq!(last_seq(cfg, space, db).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_interrupted_append_is_repaired() {
+        let env = init_test_env();
+        let (cfg, space, db) = (&env.cfg, env.space.as_str(), env.db.as_str());
+        let doc = json!({ "id": "a" });
+        record_write(cfg, space, db, "c", "a", None, Some(&doc)).unwrap();
+
+        // Crash simulé : un événement complet non reporté dans l'état, puis une
+        // ligne tronquée
+        let path = log_path(cfg, space, db);
+        let mut event = read_since(cfg, space, db, 0, 1).unwrap().remove(0);
+        event.seq = 2;
+        event.transaction = Some("tx-1".to_string());
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "{}", serde_json::to_string(&event).unwrap()).unwrap();
+        write!(file, "{{\"seq\": 3, \"trunc").unwrap();
+
+        assert_eq!(last_seq(cfg, space, db).unwrap(), 2);
+        // La transaction déjà publiée n'est pas dupliquée
+        let log_doc = json!({
+            "id": "tx-1", "status": "Committed", "operations": [], "timestamp": 0,
+            "after_images": [{ "collection": "c", "id": "b", "document": {} }]
+        });
+        let log: TransactionLog = serde_json::from_value(log_doc).unwrap();
+        record_transaction(cfg, space, db, &log).unwrap();
+        let events = read_since(cfg, space, db, 0, 10).unwrap();
+        assert_eq!(events.len(), 2);
+
+        record_write(cfg, space, db, "c", "b", None, Some(&doc)).unwrap();
+        let events = read_since(cfg, space, db, 2, 10).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].seq, 3);
+    }
+
+    #[test]
+    fn test_archive_is_incremental_and_import_keeps_sequence() {
+        let env = init_test_env();
+        let (cfg, space, db) = (&env.cfg, env.space.as_str(), env.db.as_str());
+        let dest = env.tmp_dir.path().join("archive").join("changes.jsonl");
+        let doc = json!({ "id": "a" });
+
+        record_write(cfg, space, db, "c", "a", None, Some(&doc)).unwrap();
+        assert_eq!(archive(cfg, space, db, &dest).unwrap(), 1);
+        record_write(cfg, space, db, "c", "a", Some(&doc), None).unwrap();
+        assert_eq!(archive(cfg, space, db, &dest).unwrap(), 1);
+        assert_eq!(archive(cfg, space, db, &dest).unwrap(), 0);
+
+        let archived = read_journal(&dest).unwrap();
+        assert_eq!(archived.iter().map(|e| e.seq).collect::<Vec<_>>(), [1, 2]);
+
+        // Import dans une autre base : numéros conservés, doublons ignorés
+        import(cfg, space, "copy", &archived[..1]).unwrap();
+        import(cfg, space, "copy", &archived).unwrap();
+        let copied = read_since(cfg, space, "copy", 0, 10).unwrap();
+        assert_eq!(copied.iter().map(|e| e.seq).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(last_seq(cfg, space, "copy").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_resume_seeks_from_checkpoint() {
+        let env = init_test_env();
+        let (cfg, space, db) = (&env.cfg, env.space.as_str(), env.db.as_str());
+        let doc = json!({ "id": "a", "text": "x".repeat(64 * 1024) });
+        for _ in 0..40 {
+            record_write(cfg, space, db, "c", "a", None, Some(&doc)).unwrap();
+        }
+        let state = read_state(&state_path(cfg, space, db));
+        assert!(!state.checkpoints.is_empty());
+
+        // Début du journal illisible : une reprise tardive ne le relit pas
+        let path = log_path(cfg, space, db);
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.write_all(b"####").unwrap();
+        let events = read_since(cfg, space, db, 35, 10).unwrap();
+        assert_eq!(
+            events.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            [36, 37, 38, 39, 40]
+        );
+        assert!(read_since(cfg, space, db, 0, 10).is_err());
+    }
+
+    #[test]
+    fn test_purge_relocates_cursors_and_refuses_purged_tokens() {
+        let env = init_test_env();
+        let (cfg, space, db) = (&env.cfg, env.space.as_str(), env.db.as_str());
+        let doc = json!({ "id": "a" });
+        for _ in 0..5 {
+            record_write(cfg, space, db, "c", "a", None, Some(&doc)).unwrap();
+        }
+        let mut cursor = ChangeCursor::new(cfg, space, db, 0);
+        assert_eq!(cursor.next_batch().unwrap().len(), 5);
+        let mut late = ChangeCursor::new(cfg, space, db, 1);
+        assert_eq!(late.next_batch().unwrap().len(), 4);
+
+        assert_eq!(purge(cfg, space, db, 3).unwrap(), 3);
+        assert_eq!(purge(cfg, space, db, 3).unwrap(), 0);
+        let seqs = |events: Vec<ChangeEvent>| events.iter().map(|e| e.seq).collect::<Vec<_>>();
+        assert_eq!(seqs(read_since(cfg, space, db, 0, 10).unwrap()), [4, 5]);
+        assert_eq!(seqs(read_since(cfg, space, db, 3, 10).unwrap()), [4, 5]);
+        let err = read_since(cfg, space, db, 1, 10).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ChangesPurged>(),
+            Some(&ChangesPurged {
+                after: 1,
+                purged: 3
+            })
+        );
+
+        // Les curseurs ouverts avant la purge se replacent dans le nouveau journal
+        record_write(cfg, space, db, "c", "a", Some(&doc), None).unwrap();
+        assert_eq!(seqs(cursor.next_batch().unwrap()), [6]);
+        assert_eq!(seqs(late.next_batch().unwrap()), [6]);
+        assert_eq!(last_seq(cfg, space, db).unwrap(), 6);
+    }
+
+    #[test]
+    fn test_interrupted_purge_is_detected() {
+        let env = init_test_env();
+        let (cfg, space, db) = (&env.cfg, env.space.as_str(), env.db.as_str());
+        let doc = json!({ "id": "a" });
+        for _ in 0..3 {
+            record_write(cfg, space, db, "c", "a", None, Some(&doc)).unwrap();
+        }
+        // Crash simulé : journal remplacé, état non mis à jour
+        let saved = fs::read(state_path(cfg, space, db)).unwrap();
+        purge(cfg, space, db, 2).unwrap();
+        fs::write(state_path(cfg, space, db), saved).unwrap();
+
+        assert_eq!(last_seq(cfg, space, db).unwrap(), 3);
+        record_write(cfg, space, db, "c", "a", None, Some(&doc)).unwrap();
+        assert!(read_since(cfg, space, db, 1, 10).is_err());
+        let events = read_since(cfg, space, db, 2, 10).unwrap();
+        assert_eq!(events.iter().map(|e| e.seq).collect::<Vec<_>>(), [3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_streams_history_then_live_events() {
+        let env = init_test_env();
+        let (cfg, space, db) = (env.cfg.clone(), env.space.clone(), env.db.clone());
+        let doc = json!({ "id": "a" });
+        record_write(&cfg, &space, &db, "c", "a", None, Some(&doc)).unwrap();
+
+        let mut stream = subscribe(&cfg, &space, &db, 0);
+        assert_eq!(stream.next().await.unwrap().unwrap().seq, 1);
+
+        let writer = tokio::spawn(async move {
+            record_write(&cfg, &space, &db, "c", "a", Some(&doc), None).unwrap();
+        });
+        let event = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("événement attendu")
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.seq, 2);
+        assert_eq!(event.kind, ChangeKind::Delete);
+        writer.await.unwrap();
+    }
+}
@@ -6,9 +6,8 @@ use serde_json::Value;
 use std::fs;
 use std::path::PathBuf;
 
-// On utilise atomic_write depuis file_storage
-use crate::json_db::storage::file_storage::atomic_write;
-use crate::json_db::storage::JsonDbConfig;
+// Les I/O documents passent par file_storage (codec + lecture mixte)
+use crate::json_db::storage::{file_storage, JsonDbConfig};
 
 /// Racine des collections : {db_root}/collections/{collection}
 pub fn collection_root(cfg: &JsonDbConfig, space: &str, db: &str, collection: &str) -> PathBuf {
@@ -16,11 +15,6 @@ pub fn collection_root(cfg: &JsonDbConfig, space: &str, db: &str, collection: &s
     cfg.db_collection_path(space, db, collection)
 }
 
-/// Fichier d’un document : {collection_root}/{id}.json
-fn doc_path(cfg: &JsonDbConfig, space: &str, db: &str, collection: &str, id: &str) -> PathBuf {
-    collection_root(cfg, space, db, collection).join(format!("{id}.json"))
-}
-
 /// S’assure que la collection existe (création récursive).
 pub fn create_collection_if_missing(
     cfg: &JsonDbConfig,
@@ -43,14 +37,8 @@ pub fn read_document(
     collection: &str,
     id: &str,
 ) -> Result<Value> {
-    let path = doc_path(cfg, space, db, collection, id);
-    let content = fs::read_to_string(&path)
-        .with_context(|| format!("Document introuvable : {}/{}", collection, id))?;
-
-    let doc: Value = serde_json::from_str(&content)
-        .with_context(|| format!("JSON invalide : {}", path.display()))?;
-
-    Ok(doc)
+    file_storage::read_document(cfg, space, db, collection, id)?
+        .with_context(|| format!("Document introuvable : {}/{}", collection, id))
 }
 
 // --- FONCTIONS CRUD ---
@@ -64,10 +52,7 @@ pub fn create_document(
     document: &Value,
 ) -> Result<()> {
     create_collection_if_missing(cfg, space, db, collection)?;
-    let path = doc_path(cfg, space, db, collection, id);
-    let content = serde_json::to_string_pretty(document)?;
-    atomic_write(path, content.as_bytes())?;
-    Ok(())
+    file_storage::write_document(cfg, space, db, collection, id, document)
 }
 
 pub fn update_document(
@@ -88,11 +73,8 @@ pub fn delete_document(
     collection: &str,
     id: &str,
 ) -> Result<()> {
-    let path = doc_path(cfg, space, db, collection, id);
-    if path.exists() {
-        fs::remove_file(&path).with_context(|| format!("Suppression {}", path.display()))?;
-    }
-    Ok(())
+    file_storage::delete_document(cfg, space, db, collection, id)
+        .with_context(|| format!("Suppression {}/{}", collection, id))
 }
 
 // --- AJOUT : Suppression de collection ---
@@ -113,27 +95,8 @@ pub fn list_document_ids(
     db: &str,
     collection: &str,
 ) -> Result<Vec<String>> {
-    let root = collection_root(cfg, space, db, collection);
-    let mut out = Vec::new();
-
-    if !root.exists() {
-        return Ok(out);
-    }
-
-    for e in fs::read_dir(&root)? {
-        let e = e?;
-        let p = e.path();
-        if p.is_file() && p.extension().and_then(|s| s.to_str()) == Some("json") {
-            // On ignore les fichiers spéciaux comme _meta.json
-            if let Some(stem) = p.file_stem().and_then(|s| s.to_str()) {
-                if !stem.starts_with('_') {
-                    out.push(stem.to_string());
-                }
-            }
-        }
-    }
-    out.sort();
-    Ok(out)
+    // Fichiers individuels et segment (hors fichiers spéciaux comme _meta.json)
+    file_storage::list_document_ids(cfg, space, db, collection)
 }
 
 pub fn list_documents(
@@ -142,14 +105,7 @@ pub fn list_documents(
     db: &str,
     collection: &str,
 ) -> Result<Vec<Value>> {
-    let ids = list_document_ids(cfg, space, db, collection)?;
-    let mut docs = Vec::with_capacity(ids.len());
-    for id in ids {
-        if let Ok(doc) = read_document(cfg, space, db, collection, &id) {
-            docs.push(doc);
-        }
-    }
-    Ok(docs)
+    file_storage::list_documents(cfg, space, db, collection)
 }
 
 pub fn list_collection_names_fs(cfg: &JsonDbConfig, space: &str, db: &str) -> Result<Vec<String>> {
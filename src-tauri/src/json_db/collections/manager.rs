@@ -2,8 +2,12 @@
 
 use crate::json_db::indexes::IndexManager;
 use crate::json_db::jsonld::{JsonLdProcessor, VocabularyRegistry};
-use crate::json_db::schema::{SchemaRegistry, SchemaValidator};
+use crate::json_db::schema::{SchemaRegistry, SchemaValidator, ValidationReport};
 use crate::json_db::storage::{file_storage, JsonDbConfig, StorageEngine};
+use crate::json_db::transactions::lock_manager::{FileLock, LockManager, LockMode};
+use crate::json_db::transactions::manager::TransactionManager;
+use crate::json_db::transactions::snapshot::Snapshot;
+use crate::json_db::transactions::{recovery, revision, TransactionLog};
 use crate::rules_engine::{DataProvider, EvalError, Evaluator, Rule, RuleStore};
 
 use anyhow::{anyhow, Context, Result};
@@ -11,6 +15,7 @@ use chrono::Utc;
 use serde_json::{json, Value};
 use std::collections::HashSet;
 use std::fs;
+use std::sync::Arc;
 use uuid::Uuid;
 
 use super::collection;
@@ -20,6 +25,8 @@ pub struct CollectionsManager<'a> {
     pub storage: &'a StorageEngine,
     pub space: String,
     pub db: String,
+    /// Vue figée utilisée par les lectures (`get_document`, `list_all`)
+    snapshot: Option<Arc<Snapshot>>,
 }
 
 impl<'a> CollectionsManager<'a> {
@@ -28,15 +35,38 @@ impl<'a> CollectionsManager<'a> {
             storage,
             space: space.to_string(),
             db: db.to_string(),
+            snapshot: None,
         }
     }
 
+    /// Lectures à travers le snapshot donné (partageable entre managers)
+    pub fn with_snapshot(mut self, snapshot: Arc<Snapshot>) -> Self {
+        self.snapshot = Some(snapshot);
+        self
+    }
+
+    /// Snapshot à travers lequel ce manager lit, s'il en a un
+    pub fn active_snapshot(&self) -> Option<&Arc<Snapshot>> {
+        self.snapshot.as_ref()
+    }
+
+    /// Manager en lecture cohérente : toutes ses lectures voient la base telle
+    /// qu'elle est à cet instant, même si des transactions sont validées ensuite.
+    /// À réserver aux lectures longues (exports, chargement du modèle).
+    pub fn snapshot(&self) -> Result<CollectionsManager<'a>> {
+        let snapshot = Snapshot::begin(&self.storage.config, &self.space, &self.db)?;
+        Ok(CollectionsManager::new(self.storage, &self.space, &self.db)
+            .with_snapshot(Arc::new(snapshot)))
+    }
+
     pub fn init_db(&self) -> Result<()> {
         file_storage::create_db(&self.storage.config, &self.space, &self.db)?;
+        recovery::recover(&self.storage.config, &self.space, &self.db)?;
         self.ensure_system_index()
     }
 
     pub fn ensure_system_index(&self) -> Result<()> {
+        let _system = self.lock_system()?;
         let sys_path = self
             .storage
             .config
@@ -69,22 +99,11 @@ impl<'a> CollectionsManager<'a> {
             self.space, self.db
         );
 
+        // id / createdAt / updatedAt : calculés par les `x_compute` du schéma
         if let Some(obj) = doc.as_object_mut() {
             if !obj.contains_key("$schema") {
                 obj.insert("$schema".to_string(), Value::String(expected_uri.clone()));
             }
-            // Injection de l'ID si manquant (requis par le schéma strict)
-            if !obj.contains_key("id") {
-                obj.insert("id".to_string(), Value::String(Uuid::new_v4().to_string()));
-            }
-            // Injection des dates (requises par le schéma strict)
-            let now = Utc::now().to_rfc3339();
-            if !obj.contains_key("createdAt") {
-                obj.insert("createdAt".to_string(), Value::String(now.clone()));
-            }
-            if !obj.contains_key("updatedAt") {
-                obj.insert("updatedAt".to_string(), Value::String(now));
-            }
         }
 
         let reg = SchemaRegistry::from_db(&self.storage.config, &self.space, &self.db)?;
@@ -119,8 +138,7 @@ impl<'a> CollectionsManager<'a> {
             panic!("{}", msg);
         }
 
-        fs::write(&sys_path, serde_json::to_string_pretty(doc)?)?;
-        Ok(())
+        file_storage::atomic_write(&sys_path, serde_json::to_string_pretty(doc)?)
     }
 
     pub fn create_collection(&self, name: &str, schema_uri: Option<String>) -> Result<()> {
@@ -153,6 +171,14 @@ impl<'a> CollectionsManager<'a> {
     }
 
     pub fn drop_collection(&self, name: &str) -> Result<()> {
+        // Attend la fin des écritures en cours sur la collection
+        let _guard = LockManager::new().lock_collection(
+            &self.storage.config,
+            &self.space,
+            &self.db,
+            name,
+            LockMode::Exclusive,
+        )?;
         collection::drop_collection(&self.storage.config, &self.space, &self.db, name)?;
         self.remove_collection_from_system_index(name)?;
         Ok(())
@@ -163,6 +189,28 @@ impl<'a> CollectionsManager<'a> {
         idx_mgr.create_index(collection, field, kind)
     }
 
+    /// Index composite et/ou unique (cf. `IndexManager::create_index_on`)
+    pub fn create_index_on(
+        &self,
+        collection: &str,
+        fields: &[&str],
+        kind: &str,
+        unique: bool,
+    ) -> Result<()> {
+        let _unique = if unique {
+            Some(LockManager::new().lock_unique(
+                &self.storage.config,
+                &self.space,
+                &self.db,
+                collection,
+            )?)
+        } else {
+            None
+        };
+        let mut idx_mgr = IndexManager::new(self.storage, &self.space, &self.db);
+        idx_mgr.create_index_on(collection, fields, kind, unique)
+    }
+
     pub fn drop_index(&self, collection: &str, field: &str) -> Result<()> {
         let mut idx_mgr = IndexManager::new(self.storage, &self.space, &self.db);
         idx_mgr.drop_index(collection, field)
@@ -184,6 +232,10 @@ impl<'a> CollectionsManager<'a> {
             .pointer(&ptr)
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow!("Collection '{}' inconnue", col_name))?;
+        // Collection sans schéma : pas d'URI à reconstruire
+        if raw_path.is_empty() {
+            return Ok(String::new());
+        }
         let relative_path = if let Some(idx) = raw_path.find("/schemas/v1/") {
             &raw_path[idx + "/schemas/v1/".len()..]
         } else {
@@ -196,6 +248,7 @@ impl<'a> CollectionsManager<'a> {
     }
 
     fn update_system_index_collection(&self, col_name: &str, schema_uri: &str) -> Result<()> {
+        let _system = self.lock_system()?;
         let sys_path = self
             .storage
             .config
@@ -226,6 +279,7 @@ impl<'a> CollectionsManager<'a> {
     }
 
     fn remove_collection_from_system_index(&self, col_name: &str) -> Result<()> {
+        let _system = self.lock_system()?;
         let sys_path = self
             .storage
             .config
@@ -252,6 +306,7 @@ impl<'a> CollectionsManager<'a> {
     }
 
     fn add_item_to_index(&self, col_name: &str, id: &str) -> Result<()> {
+        let _system = self.lock_system()?;
         let sys_path = self
             .storage
             .config
@@ -320,28 +375,11 @@ impl<'a> CollectionsManager<'a> {
     }
 
     pub fn list_all(&self, collection: &str) -> Result<Vec<Value>> {
-        let col_path = self
-            .storage
-            .config
-            .db_collection_path(&self.space, &self.db, collection);
-        let mut docs = Vec::new();
-        if !col_path.exists() {
-            return Ok(docs);
-        }
-        for entry in fs::read_dir(&col_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.extension().is_some_and(|e| e == "json") {
-                if path.file_name().unwrap() == "_meta.json" {
-                    continue;
-                }
-                let content = fs::read_to_string(&path)?;
-                if let Ok(doc) = serde_json::from_str::<Value>(&content) {
-                    docs.push(doc);
-                }
-            }
+        if let Some(snapshot) = &self.snapshot {
+            return snapshot.list_documents(collection);
         }
-        Ok(docs)
+        // Tous formats confondus : fichiers (clairs ou compressés) et segment
+        file_storage::list_documents(&self.storage.config, &self.space, &self.db, collection)
     }
 
     pub fn insert_raw(&self, collection: &str, doc: &Value) -> Result<()> {
@@ -349,6 +387,7 @@ impl<'a> CollectionsManager<'a> {
             .get("id")
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow!("ID manquant"))?;
+        let _guards = self.lock_document(collection, id)?;
         let meta_path = self
             .storage
             .config
@@ -361,24 +400,36 @@ impl<'a> CollectionsManager<'a> {
                 .map(|s| s.to_string());
             self.create_collection(collection, schema_hint)?;
         }
-        self.storage
-            .write_document(&self.space, &self.db, collection, id, doc)?;
-        self.add_item_to_index(collection, id)?;
+        // Contraintes d'unicité vérifiées avant toute écriture
         let mut idx_mgr = IndexManager::new(self.storage, &self.space, &self.db);
-        if let Err(_e) = idx_mgr.index_document(collection, doc) {
-            #[cfg(debug_assertions)]
-            eprintln!("⚠️ Indexation secondaire échouée: {}", _e);
-        }
-        Ok(())
+        let _unique = self.lock_unique(&idx_mgr, collection)?;
+        idx_mgr.check_unique(collection, doc)?;
+        let before = self
+            .storage
+            .read_document(&self.space, &self.db, collection, id)?;
+        self.write_logged(collection, id, before, Some(doc.clone()), || {
+            self.storage
+                .write_document(&self.space, &self.db, collection, id, doc)?;
+            self.add_item_to_index(collection, id)?;
+            if let Err(_e) = idx_mgr.index_document(collection, doc) {
+                #[cfg(debug_assertions)]
+                eprintln!("⚠️ Indexation secondaire échouée: {}", _e);
+            }
+            Ok(())
+        })
     }
 
     pub fn insert_with_schema(&self, collection: &str, mut doc: Value) -> Result<Value> {
         self.prepare_document(collection, &mut doc)?;
+        revision::stamp(&mut doc, None);
         self.insert_raw(collection, &doc)?;
         Ok(doc)
     }
 
     pub fn get_document(&self, collection: &str, id: &str) -> Result<Option<Value>> {
+        if let Some(snapshot) = &self.snapshot {
+            return snapshot.read_document(collection, id);
+        }
         self.storage
             .read_document(&self.space, &self.db, collection, id)
     }
@@ -386,52 +437,118 @@ impl<'a> CollectionsManager<'a> {
         self.get_document(collection, id)
     }
 
+    /// Remplace un document. Si `doc` porte un `_rev`, la mise à jour n'a lieu
+    /// que si le document stocké a toujours cette révision (`RevisionConflict` sinon).
     pub fn update_document(&self, collection: &str, id: &str, mut doc: Value) -> Result<Value> {
+        let _guards = self.lock_document(collection, id)?;
         let old_doc = self.get_document(collection, id)?;
         if old_doc.is_none() {
             return Err(anyhow!("Document introuvable"));
         }
+        revision::check(collection, id, old_doc.as_ref(), &doc)?;
         if let Some(obj) = doc.as_object_mut() {
             obj.insert("id".to_string(), Value::String(id.to_string()));
         }
         self.prepare_document(collection, &mut doc)?;
-        self.storage
-            .write_document(&self.space, &self.db, collection, id, &doc)?;
+        revision::stamp(&mut doc, old_doc.as_ref());
         let mut idx_mgr = IndexManager::new(self.storage, &self.space, &self.db);
-        if let Some(old) = old_doc {
-            let _ = idx_mgr.remove_document(collection, &old);
-        }
-        let _ = idx_mgr.index_document(collection, &doc);
+        let _unique = self.lock_unique(&idx_mgr, collection)?;
+        idx_mgr.check_unique(collection, &doc)?;
+        self.write_logged(collection, id, old_doc.clone(), Some(doc.clone()), || {
+            self.storage
+                .write_document(&self.space, &self.db, collection, id, &doc)?;
+            if let Some(old) = &old_doc {
+                let _ = idx_mgr.remove_document(collection, old);
+            }
+            let _ = idx_mgr.index_document(collection, &doc);
+            Ok(())
+        })?;
         Ok(doc)
     }
 
     pub fn delete_document(&self, collection: &str, id: &str) -> Result<bool> {
+        let _guards = self.lock_document(collection, id)?;
         let old_doc = self.get_document(collection, id)?;
-        self.storage
-            .delete_document(&self.space, &self.db, collection, id)?;
-        if let Some(doc) = old_doc {
-            let mut idx_mgr = IndexManager::new(self.storage, &self.space, &self.db);
-            let _ = idx_mgr.remove_document(collection, &doc);
-        }
+        self.write_logged(collection, id, old_doc.clone(), None, || {
+            self.storage
+                .delete_document(&self.space, &self.db, collection, id)?;
+            if let Some(doc) = &old_doc {
+                let mut idx_mgr = IndexManager::new(self.storage, &self.space, &self.db);
+                let _ = idx_mgr.remove_document(collection, doc);
+            }
+            Ok(())
+        })?;
         Ok(true)
     }
 
+    /// Écriture directe journalisée dans le WAL : son événement est publié
+    /// dans le flux de changements avant qu'elle ne soit acquittée
+    fn write_logged<F>(
+        &self,
+        collection: &str,
+        id: &str,
+        before: Option<Value>,
+        after: Option<Value>,
+        write: F,
+    ) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        TransactionManager::new(&self.storage.config, &self.space, &self.db)
+            .write_direct(TransactionLog::direct(collection, id, before, after), write)
+    }
+
+    fn lock_document(&self, collection: &str, id: &str) -> Result<Vec<FileLock>> {
+        LockManager::new().lock_document(
+            &self.storage.config,
+            &self.space,
+            &self.db,
+            collection,
+            id,
+        )
+    }
+
+    /// Verrou de l'index système, tenu le temps d'une lecture-modification-écriture
+    fn lock_system(&self) -> Result<FileLock> {
+        LockManager::new().lock_system(&self.storage.config, &self.space, &self.db)
+    }
+
+    /// Verrou d'unicité, pris seulement si la collection a un index unique
+    fn lock_unique(&self, idx_mgr: &IndexManager, collection: &str) -> Result<Option<FileLock>> {
+        if !idx_mgr.has_unique(collection)? {
+            return Ok(None);
+        }
+        LockManager::new()
+            .lock_unique(&self.storage.config, &self.space, &self.db, collection)
+            .map(Some)
+    }
+
+    /// Valide un brouillon comme une insertion (champs calculés, règles métier, schéma)
+    /// sans rien écrire. Rapport vide si la collection n'a pas de schéma.
+    pub fn validate_document(&self, collection: &str, mut doc: Value) -> Result<ValidationReport> {
+        Ok(self
+            .prepare_and_report(collection, &mut doc)?
+            .unwrap_or_default())
+    }
+
     fn prepare_document(&self, collection: &str, doc: &mut Value) -> Result<()> {
-        // --- 1. INJECTION AUTOMATIQUE (ID / CreatedAt / UpdatedAt) ---
-        if let Some(obj) = doc.as_object_mut() {
-            if !obj.contains_key("id") {
-                obj.insert("id".to_string(), Value::String(Uuid::new_v4().to_string()));
-            }
-            let now = Utc::now().to_rfc3339();
-            if !obj.contains_key("createdAt") {
-                obj.insert("createdAt".to_string(), Value::String(now.clone()));
-            }
-            if !obj.contains_key("updatedAt") {
-                obj.insert("updatedAt".to_string(), Value::String(now));
+        if let Some(report) = self.prepare_and_report(collection, doc)? {
+            if !report.is_valid() {
+                return Err(report.into());
             }
         }
-        // -------------------------------------------------
+        self.apply_semantic_logic(doc)
+            .context("Validation sémantique")?;
+        Ok(())
+    }
 
+    /// Champs calculés (`x_compute`) et règles métier, puis rapport de validation
+    /// du schéma de la collection (`None` si elle n'en a pas)
+    fn prepare_and_report(
+        &self,
+        collection: &str,
+        doc: &mut Value,
+    ) -> Result<Option<ValidationReport>> {
         let meta_path = self
             .storage
             .config
@@ -447,36 +564,53 @@ impl<'a> CollectionsManager<'a> {
             None
         };
 
-        if let Some(uri) = schema_uri {
-            if !uri.is_empty() {
-                if let Some(obj) = doc.as_object_mut() {
-                    if !obj.contains_key("$schema") {
-                        obj.insert("$schema".to_string(), Value::String(uri.clone()));
-                    }
-                }
-                let reg = SchemaRegistry::from_db(&self.storage.config, &self.space, &self.db)?;
-
-                // MOTEUR DE RÈGLES
-                if let Err(e) = apply_business_rules(
-                    &self.storage.config,
-                    &self.space,
-                    &self.db,
-                    collection,
-                    doc,
-                    None,
-                    &reg,
-                    &uri,
-                ) {
-                    eprintln!("⚠️ Erreur règles métier (non bloquant): {}", e);
-                }
-
-                let validator = SchemaValidator::compile_with_registry(&uri, &reg)?;
-                validator.compute_then_validate(doc)?;
+        let Some(uri) = schema_uri.filter(|uri| !uri.is_empty()) else {
+            // Sans schéma : socle minimal (celui de base.schema.json)
+            if let Some(obj) = doc.as_object_mut() {
+                obj.entry("id")
+                    .or_insert_with(|| Value::String(Uuid::new_v4().to_string()));
+                let now = Value::String(Utc::now().to_rfc3339());
+                obj.entry("createdAt").or_insert_with(|| now.clone());
+                obj.entry("updatedAt").or_insert(now);
+            }
+            return Ok(None);
+        };
+        if let Some(obj) = doc.as_object_mut() {
+            if !obj.contains_key("$schema") {
+                obj.insert("$schema".to_string(), Value::String(uri.clone()));
             }
         }
-        self.apply_semantic_logic(doc)
-            .context("Validation sémantique")?;
-        Ok(())
+        let reg = SchemaRegistry::from_db(&self.storage.config, &self.space, &self.db)?;
+        let validator = SchemaValidator::compile_with_registry(&uri, &reg)?;
+
+        // CHAMPS CALCULÉS (x_compute : id, dates, défauts...)
+        let provider = DbDataProvider {
+            cfg: &self.storage.config,
+            space: &self.space,
+            db: &self.db,
+        };
+        validator.compute(doc, &provider)?;
+        // L'identifiant nomme le fichier : garanti même si le schéma ne le calcule pas
+        if let Some(obj) = doc.as_object_mut() {
+            obj.entry("id")
+                .or_insert_with(|| Value::String(Uuid::new_v4().to_string()));
+        }
+
+        // MOTEUR DE RÈGLES
+        if let Err(e) = apply_business_rules(
+            &self.storage.config,
+            &self.space,
+            &self.db,
+            collection,
+            doc,
+            None,
+            &reg,
+            &uri,
+        ) {
+            eprintln!("⚠️ Erreur règles métier (non bloquant): {}", e);
+        }
+
+        Ok(Some(validator.report(doc)))
     }
 
     fn apply_semantic_logic(&self, doc: &mut Value) -> Result<()> {
@@ -507,10 +641,11 @@ impl<'a> CollectionsManager<'a> {
     }
 }
 
-struct DbDataProvider<'a> {
-    cfg: &'a JsonDbConfig,
-    space: &'a str,
-    db: &'a str,
+/// Accès aux documents des autres collections pour les `lookup`
+pub(crate) struct DbDataProvider<'a> {
+    pub(crate) cfg: &'a JsonDbConfig,
+    pub(crate) space: &'a str,
+    pub(crate) db: &'a str,
 }
 
 impl<'a> DataProvider for DbDataProvider<'a> {
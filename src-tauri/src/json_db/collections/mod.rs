@@ -8,6 +8,7 @@ use serde_json::Value;
 use std::path::PathBuf;
 
 use crate::json_db::{
+    collections::manager::DbDataProvider,
     schema::{SchemaRegistry, SchemaValidator},
     storage::JsonDbConfig,
 };
@@ -50,6 +51,9 @@ pub fn insert_with_schema(
 
     let collection_name = collection_from_schema_rel(schema_rel);
 
+    // Champs calculés (x_compute) avant les règles, qui peuvent s'en servir
+    validator.compute(&mut doc, &DbDataProvider { cfg, space, db })?;
+
     // --- CORRECTION ICI : Ajout de cfg, space, db ---
     manager::apply_business_rules(
         cfg,              // 1. Config DB (Nouveau)
@@ -64,7 +68,7 @@ pub fn insert_with_schema(
     .context("Rules Engine")?;
     // -----------------------------------------------
 
-    validator.compute_then_validate(&mut doc)?;
+    validator.validate(&doc)?;
 
     let id = doc
         .get("id")
@@ -100,7 +104,8 @@ pub fn update_with_schema(
     let root_uri = reg.uri(schema_rel);
     let validator = SchemaValidator::compile_with_registry(&root_uri, &reg)?;
 
-    validator.compute_then_validate(&mut doc)?;
+    validator.compute(&mut doc, &DbDataProvider { cfg, space, db })?;
+    validator.validate(&doc)?;
 
     let collection_name = collection_from_schema_rel(schema_rel);
     let id = doc
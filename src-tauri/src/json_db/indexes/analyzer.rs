@@ -0,0 +1,180 @@
+// FICHIER : src-tauri/src/json_db/indexes/analyzer.rs
+
+//! Analyseurs linguistiques des index texte.
+//!
+//! Un analyseur transforme un texte en termes indexés, chacun avec sa position
+//! (rang du mot dans le texte). Les analyseurs français et anglais s'appuient
+//! sur `ai::nlp::preprocessing` (minuscules, accents, ponctuation, mots vides)
+//! puis réduisent chaque mot à sa racine par un stemmer léger : `exigences`,
+//! `exigence` et `exiger` donnent tous `exig`.
+//!
+//! Les mots vides sont retirés mais conservent leur position, pour que les
+//! requêtes de phrase (`"station de sol"`) respectent l'écart entre les mots.
+
+use crate::ai::nlp::preprocessing;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAnalyzer {
+    /// Minuscules et découpage sur les caractères non alphanumériques
+    /// (comportement historique des index texte)
+    #[default]
+    Simple,
+    French,
+    English,
+}
+
+impl TextAnalyzer {
+    /// Analyseur désigné par son code (`fr`, `en`, `simple`)
+    pub fn parse(code: &str) -> Option<Self> {
+        match code.to_lowercase().as_str() {
+            "simple" => Some(Self::Simple),
+            "fr" | "french" | "francais" => Some(Self::French),
+            "en" | "english" | "anglais" => Some(Self::English),
+            _ => None,
+        }
+    }
+
+    /// Mots du texte, normalisés mais ni filtrés ni réduits
+    pub fn words(&self, text: &str) -> Vec<String> {
+        match self {
+            Self::Simple => text
+                .to_lowercase()
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+                .collect(),
+            Self::French | Self::English => preprocessing::normalize(text)
+                .split_whitespace()
+                .map(|s| s.to_string())
+                .collect(),
+        }
+    }
+
+    /// Terme indexé pour un mot normalisé (`None` pour un mot vide)
+    pub fn term(&self, word: &str) -> Option<String> {
+        match self {
+            Self::Simple => Some(word.to_string()),
+            Self::French => {
+                (!preprocessing::french_stopwords().contains(word)).then(|| stem_french(word))
+            }
+            Self::English => {
+                (!preprocessing::english_stopwords().contains(word)).then(|| stem_english(word))
+            }
+        }
+    }
+
+    /// Termes du texte avec leur position
+    pub fn analyze(&self, text: &str) -> Vec<(String, u32)> {
+        self.words(text)
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, word)| self.term(word).map(|t| (t, pos as u32)))
+            .collect()
+    }
+}
+
+/// Longueur minimale (en caractères) d'une racine
+const MIN_STEM: usize = 3;
+
+/// Retire le premier suffixe de la liste qui laisse une racine assez longue
+fn strip_suffix(word: &str, suffixes: &[&str]) -> Option<String> {
+    suffixes.iter().find_map(|suffix| {
+        word.strip_suffix(suffix)
+            .filter(|stem| stem.chars().count() >= MIN_STEM)
+            .map(|stem| stem.to_string())
+    })
+}
+
+/// Stemmer français léger (texte déjà sans accents) : pluriel, puis suffixes
+/// dérivationnels et flexionnels les plus courants.
+fn stem_french(word: &str) -> String {
+    if word.chars().count() <= MIN_STEM || word.chars().any(|c| c.is_ascii_digit()) {
+        return word.to_string();
+    }
+    // Pluriel : chevaux -> cheval, exigences -> exigence
+    let singular = if let Some(stem) = strip_suffix(word, &["aux"]) {
+        format!("{}al", stem)
+    } else if (word.ends_with('s') || word.ends_with('x')) && !word.ends_with("ss") {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    };
+
+    const SUFFIXES: &[&str] = &[
+        "issement", "ement", "ation", "atrice", "ateur", "ance", "ence", "euse", "eur", "ite",
+        "ique", "isme", "iste", "able", "ible", "ee", "er", "ez", "e",
+    ];
+    strip_suffix(&singular, SUFFIXES).unwrap_or(singular)
+}
+
+/// Stemmer anglais léger : pluriel, suffixes courants puis `e` final
+fn stem_english(word: &str) -> String {
+    if word.chars().count() <= MIN_STEM || word.chars().any(|c| c.is_ascii_digit()) {
+        return word.to_string();
+    }
+    let singular = if let Some(stem) = strip_suffix(word, &["ies"]) {
+        format!("{}y", stem)
+    } else if word.ends_with("sses") {
+        word[..word.len() - 2].to_string()
+    } else if word.ends_with('s') && !(word.ends_with("ss") || word.ends_with("us")) {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    };
+
+    const SUFFIXES: &[&str] = &[
+        "ization", "ation", "ement", "ment", "ness", "ingly", "edly", "ing", "ed", "ly", "ity",
+        "ive", "ful", "er",
+    ];
+    let stem = strip_suffix(&singular, SUFFIXES).unwrap_or(singular);
+    match stem.strip_suffix('e') {
+        Some(s) if s.chars().count() > MIN_STEM => s.to_string(),
+        _ => stem,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_french_analyzer() {
+        let fr = TextAnalyzer::French;
+        assert_eq!(fr.term("exigences"), fr.term("exigence"));
+        assert_eq!(fr.term("exiger"), fr.term("exigence"));
+        assert_eq!(fr.term("chevaux").as_deref(), Some("cheval"));
+        assert_eq!(fr.term("la"), None);
+
+        // Accents, élisions et mots vides retirés, positions conservées
+        assert_eq!(
+            fr.analyze("L'Exigence de la Station"),
+            vec![("exig".to_string(), 1), ("station".to_string(), 4)]
+        );
+    }
+
+    #[test]
+    fn test_english_analyzer() {
+        let en = TextAnalyzer::English;
+        let stem = en.term("requirements");
+        assert_eq!(stem, en.term("required"));
+        assert_eq!(stem, en.term("requiring"));
+        assert_eq!(en.term("batteries").as_deref(), Some("battery"));
+        assert_eq!(en.term("the"), None);
+    }
+
+    #[test]
+    fn test_simple_analyzer_keeps_words() {
+        let simple = TextAnalyzer::Simple;
+        assert_eq!(
+            simple.analyze("Le Radar, été"),
+            vec![
+                ("le".to_string(), 0),
+                ("radar".to_string(), 1),
+                ("été".to_string(), 2)
+            ]
+        );
+        assert_eq!(TextAnalyzer::parse("FR"), Some(TextAnalyzer::French));
+    }
+}
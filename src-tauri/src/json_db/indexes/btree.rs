@@ -1,6 +1,5 @@
 use anyhow::Result;
 use serde_json::Value;
-use std::collections::BTreeMap;
 
 use super::{driver, paths, IndexDefinition};
 use crate::json_db::storage::JsonDbConfig;
@@ -18,6 +17,48 @@ pub fn update_btree_index(
     new_doc: Option<&Value>,
 ) -> Result<()> {
     let path = paths::index_path(cfg, space, db, collection, &def.name, def.index_type);
-    // On spécifie le type BTreeMap pour garantir l'ordre des clés
-    driver::update::<BTreeMap<String, Vec<String>>>(&path, def, doc_id, old_doc, new_doc)
+    let old_entries = old_doc.map(|d| def.entries_of(d)).unwrap_or_default();
+    let new_entries = new_doc.map(|d| def.entries_of(d)).unwrap_or_default();
+    driver::update_entries(&path, doc_id, &old_entries, &new_entries)
+}
+
+/// Parcours ordonné de l'index B-Tree : retourne les IDs dont la clé est acceptée.
+///
+/// Les clés sont stockées en JSON stringifié ("30", "\"abc\""), l'ordre lexical
+/// ne correspond donc pas à l'ordre numérique : chaque clé est décodée avant
+/// d'être soumise au prédicat. L'index est lu page par page et les documents
+/// ne sont jamais chargés.
+pub fn scan_btree_index<F>(
+    cfg: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    collection: &str,
+    def: &IndexDefinition,
+    accept: F,
+) -> Result<Vec<String>>
+where
+    F: Fn(&Value) -> bool,
+{
+    let path = paths::index_path(cfg, space, db, collection, &def.name, def.index_type);
+
+    let mut ids = Vec::new();
+    // Les entrées d'une même clé sont contiguës : une seule évaluation par clé
+    let mut current: Option<(String, bool)> = None;
+    for record in driver::scan(&path, None)? {
+        let record = record?;
+        let accepted = match &current {
+            Some((key, accepted)) if *key == record.key => *accepted,
+            _ => {
+                let accepted = serde_json::from_str::<Value>(&record.key)
+                    .map(|decoded| accept(&decoded))
+                    .unwrap_or(false);
+                current = Some((record.key, accepted));
+                accepted
+            }
+        };
+        if accepted {
+            ids.push(record.document_id);
+        }
+    }
+    Ok(ids)
 }
@@ -1,178 +1,955 @@
-use anyhow::{Context, Result};
-use serde::{de::DeserializeOwned, Serialize};
-use serde_json::Value;
-use std::collections::{BTreeMap, HashMap};
-use std::fs;
-use std::path::Path;
-
-use super::{IndexDefinition, IndexRecord};
-use crate::json_db::storage::file_storage::atomic_write_binary;
-
-/// Trait définissant le comportement d'une structure d'index en mémoire
-pub trait IndexMap: Default + Serialize + DeserializeOwned {
-    fn insert_record(&mut self, key: String, doc_id: String);
-    fn remove_record(&mut self, key: &str, doc_id: &str);
-    fn get_doc_ids(&self, key: &str) -> Option<&Vec<String>>;
-    fn from_records(records: Vec<IndexRecord>) -> Self;
-    fn to_records(&self) -> Vec<IndexRecord>;
-}
-
-// --- Implémentation pour Hash Index (HashMap) ---
-impl IndexMap for HashMap<String, Vec<String>> {
-    fn insert_record(&mut self, key: String, doc_id: String) {
-        self.entry(key).or_default().push(doc_id);
-    }
-
-    fn remove_record(&mut self, key: &str, doc_id: &str) {
-        if let Some(ids) = self.get_mut(key) {
-            ids.retain(|id| id != doc_id);
-            if ids.is_empty() {
-                self.remove(key);
-            }
+// FICHIER : src-tauri/src/json_db/indexes/driver.rs
+
+//! Stockage des index sur disque : arbre LSM à deux niveaux.
+//!
+//! Un index est un ensemble trié de couples `(clé, id de document)`, chacun
+//! portant une charge utile optionnelle (positions des termes d'un index texte) :
+//!
+//! - **Run** (`{nom}.{type}.idx`) : fichier immuable de pages triées, suivi d'un
+//!   répertoire (première clé + offset de chaque page). Une recherche ne lit que
+//!   le répertoire et les pages qui peuvent contenir la clé ; un parcours lit les
+//!   pages une à une, sans jamais charger l'index entier.
+//! - **Journal** (`{nom}.{type}.log`) : la memtable. Ajouts et retraits y sont
+//!   écrits en append-only : une écriture de document coûte un seul `write`.
+//! - **Verrou** (`{nom}.{type}.lock`) : exclusif, pris par chaque lecture,
+//!   écriture ou fusion, y compris depuis un autre processus.
+//! - **Fusion** : au-delà de `LOG_FLUSH_BYTES`, le journal est renommé (`.flush`)
+//!   puis fusionné avec le run dans un nouveau run (écriture séquentielle,
+//!   renommage atomique). Les opérations sont ensemblistes : une fusion
+//!   interrompue par un crash est simplement rejouée.
+//!
+//! Formats binaires :
+//! - run : `RIDX` `version:u8`, pages (`Vec<IndexRecord>` Bincode), répertoire
+//!   (`Vec<PageRef>` Bincode), puis `dir_offset:u64 LE` `dir_len:u32 LE` `RIDX`
+//! - journal : `RIDL` `version:u8` puis des enregistrements
+//!   `len:u32 LE` | `op:u8` | `key_len:u32 LE` | `key` | `id_len:u32 LE` | `doc_id`
+//!   | `positions` (`u32 LE` jusqu'à la fin de l'enregistrement)
+//!
+//! La version 1 (sans positions) reste lisible : ses runs sont réécrits en
+//! version 2 à la fusion suivante, un journal v1 est fusionné avant tout ajout.
+//! Un fichier `.idx` sans en-tête `RIDX` est un index de l'ancien format (liste
+//! Bincode complète) : il est converti en run à sa première lecture.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{btree_map, BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use super::IndexRecord;
+use crate::json_db::storage::file_storage;
+use crate::json_db::transactions::lock_manager::{FileLock, LockMode, DEFAULT_LOCK_TIMEOUT};
+
+const RUN_MAGIC: [u8; 4] = *b"RIDX";
+const LOG_MAGIC: [u8; 4] = *b"RIDL";
+const FORMAT_VERSION: u8 = 2;
+/// Format sans positions, lu mais plus écrit
+const FORMAT_V1: u8 = 1;
+/// Magic + version
+const HEADER_LEN: u64 = 5;
+/// `dir_offset` + `dir_len` + magic
+const TRAILER_LEN: u64 = 16;
+const OP_PUT: u8 = 1;
+const OP_DELETE: u8 = 0;
+
+/// Taille visée d'une page du run (octets de clés et d'IDs)
+pub const PAGE_BYTES: usize = 4096;
+/// Au-delà de cette taille, le journal est fusionné dans le run
+pub const LOG_FLUSH_BYTES: u64 = 64 * 1024;
+
+/// Entrée du répertoire de pages d'un run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PageRef {
+    first_key: String,
+    offset: u64,
+    len: u32,
+    records: u32,
+}
+
+/// Entrée du format v1 (avant les positions)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordV1 {
+    key: String,
+    document_id: String,
+}
+
+impl From<RecordV1> for IndexRecord {
+    fn from(r: RecordV1) -> Self {
+        IndexRecord {
+            key: r.key,
+            document_id: r.document_id,
+            positions: Vec::new(),
         }
     }
+}
 
-    fn get_doc_ids(&self, key: &str) -> Option<&Vec<String>> {
-        self.get(key)
-    }
+/// Mutation élémentaire d'un index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexOp {
+    Put {
+        key: String,
+        doc_id: String,
+        positions: Vec<u32>,
+    },
+    Delete {
+        key: String,
+        doc_id: String,
+    },
+}
+
+/// Occupation d'un index sur disque
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexStats {
+    pub pages: usize,
+    pub run_records: usize,
+    pub run_bytes: u64,
+    /// Journal en attente de fusion (y compris une fusion interrompue)
+    pub log_bytes: u64,
+}
+
+/// Journal (memtable) associé au run `path`
+pub fn log_path(path: &Path) -> PathBuf {
+    path.with_extension("log")
+}
+
+/// Journal en cours de fusion
+fn flush_path(path: &Path) -> PathBuf {
+    path.with_extension("flush")
+}
 
-    fn from_records(records: Vec<IndexRecord>) -> Self {
-        let mut map: HashMap<String, Vec<String>> = HashMap::new();
-        for r in records {
-            map.entry(r.key).or_default().push(r.document_id);
+// --- VERROUS PAR INDEX ---
+// Sérialise les accès à un même index : mutex dans le processus, puis verrou
+// fichier exclusif (`{nom}.{type}.lock`) entre processus (app, CLI...), comme
+// `data.lock` pour les segments. Un ajout au journal, sa troncature et la
+// rotation d'une fusion ne peuvent donc pas s'entrelacer.
+
+fn handle(path: &Path) -> Arc<Mutex<()>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>> = OnceLock::new();
+    let mut registry = REGISTRY
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    registry.entry(path.to_path_buf()).or_default().clone()
+}
+
+/// Fichier de verrou inter-processus de l'index `path`
+pub(crate) fn lock_path(path: &Path) -> PathBuf {
+    path.with_extension("lock")
+}
+
+fn locked<R>(path: &Path, f: impl FnOnce() -> Result<R>) -> Result<R> {
+    let handle = handle(path);
+    let _guard = handle
+        .lock()
+        .map_err(|_| anyhow!("Verrou d'index empoisonné"))?;
+    let _file_lock =
+        FileLock::acquire(&lock_path(path), LockMode::Exclusive, DEFAULT_LOCK_TIMEOUT)?;
+    f()
+}
+
+// --- RUN (pages triées immuables) ---
+
+struct Run {
+    file: File,
+    version: u8,
+    pages: Vec<PageRef>,
+}
+
+impl Run {
+    /// Ouvre le run (`None` s'il n'existe pas). Un index de l'ancien format est
+    /// converti au passage. À appeler sous `locked`.
+    fn open(path: &Path) -> Result<Option<Run>> {
+        let mut file = match File::open(path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Lecture index {}", path.display())),
+        };
+        let len = file.metadata()?.len();
+
+        let mut header = [0u8; HEADER_LEN as usize];
+        if len < HEADER_LEN || file.read_exact(&mut header).is_err() || header[..4] != RUN_MAGIC {
+            drop(file);
+            convert_legacy(path)?;
+            return Run::open(path);
+        }
+        let version = header[4];
+        if version != FORMAT_VERSION && version != FORMAT_V1 {
+            bail!(
+                "Version d'index inconnue ({}) : {}",
+                header[4],
+                path.display()
+            );
+        }
+        if len < HEADER_LEN + TRAILER_LEN {
+            bail!("Index tronqué : {}", path.display());
+        }
+
+        file.seek(SeekFrom::End(-(TRAILER_LEN as i64)))?;
+        let mut trailer = [0u8; TRAILER_LEN as usize];
+        file.read_exact(&mut trailer)?;
+        if trailer[12..] != RUN_MAGIC {
+            bail!("Index tronqué : {}", path.display());
         }
-        map
+        let dir_offset = u64::from_le_bytes(trailer[0..8].try_into().unwrap());
+        let dir_len = u32::from_le_bytes(trailer[8..12].try_into().unwrap()) as usize;
+
+        file.seek(SeekFrom::Start(dir_offset))?;
+        let mut dir = vec![0u8; dir_len];
+        file.read_exact(&mut dir)?;
+        let (pages, _): (Vec<PageRef>, usize) =
+            bincode::serde::decode_from_slice(&dir, bincode::config::standard())
+                .with_context(|| format!("Répertoire de pages illisible : {}", path.display()))?;
+
+        Ok(Some(Run {
+            file,
+            version,
+            pages,
+        }))
     }
 
-    fn to_records(&self) -> Vec<IndexRecord> {
-        let mut records = Vec::new();
-        for (k, ids) in self {
-            for id in ids {
-                records.push(IndexRecord {
-                    key: k.clone(),
-                    document_id: id.clone(),
-                });
-            }
+    fn read_page(&mut self, index: usize) -> Result<Vec<IndexRecord>> {
+        let page = &self.pages[index];
+        self.file.seek(SeekFrom::Start(page.offset))?;
+        let mut bytes = vec![0u8; page.len as usize];
+        self.file.read_exact(&mut bytes)?;
+        decode_records(&bytes, self.version).context("Page d'index illisible")
+    }
+
+    /// Première page pouvant contenir `key` : une clé peut commencer dans la
+    /// page précédant celle dont la première clé est >= `key`.
+    fn first_page_for(&self, key: &str) -> usize {
+        self.pages
+            .partition_point(|p| p.first_key.as_str() < key)
+            .saturating_sub(1)
+    }
+}
+
+fn decode_records(bytes: &[u8], version: u8) -> Result<Vec<IndexRecord>> {
+    let config = bincode::config::standard();
+    Ok(if version == FORMAT_V1 {
+        let (records, _): (Vec<RecordV1>, usize) =
+            bincode::serde::decode_from_slice(bytes, config)?;
+        records.into_iter().map(IndexRecord::from).collect()
+    } else {
+        bincode::serde::decode_from_slice(bytes, config)?.0
+    })
+}
+
+/// Écrit un run à partir de couples triés et sans doublon, puis le substitue
+/// atomiquement à l'ancien.
+fn write_run<I>(path: &Path, records: I) -> Result<()>
+where
+    I: Iterator<Item = Result<IndexRecord>>,
+{
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    let mut out = BufWriter::new(File::create(&tmp)?);
+    out.write_all(&RUN_MAGIC)?;
+    out.write_all(&[FORMAT_VERSION])?;
+
+    let mut offset = HEADER_LEN;
+    let mut pages = Vec::new();
+    let mut page = Vec::new();
+    let mut page_bytes = 0;
+    let mut write_page = |page: Vec<IndexRecord>, out: &mut BufWriter<File>| -> Result<()> {
+        let encoded = bincode::serde::encode_to_vec(&page, bincode::config::standard())?;
+        out.write_all(&encoded)?;
+        pages.push(PageRef {
+            first_key: page[0].key.clone(),
+            offset,
+            len: encoded.len() as u32,
+            records: page.len() as u32,
+        });
+        offset += encoded.len() as u64;
+        Ok(())
+    };
+
+    for record in records {
+        let record = record?;
+        page_bytes += record.key.len() + record.document_id.len() + 4 * record.positions.len();
+        page.push(record);
+        if page_bytes >= PAGE_BYTES {
+            write_page(std::mem::take(&mut page), &mut out)?;
+            page_bytes = 0;
         }
-        records
     }
+    if !page.is_empty() {
+        write_page(page, &mut out)?;
+    }
+
+    let dir = bincode::serde::encode_to_vec(&pages, bincode::config::standard())?;
+    out.write_all(&dir)?;
+    out.write_all(&offset.to_le_bytes())?;
+    out.write_all(&(dir.len() as u32).to_le_bytes())?;
+    out.write_all(&RUN_MAGIC)?;
+    let file = out.into_inner().map_err(|e| e.into_error())?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Convertit un index de l'ancien format (liste Bincode complète) en run
+fn convert_legacy(path: &Path) -> Result<()> {
+    let content = fs::read(path).with_context(|| format!("Lecture index {}", path.display()))?;
+    let mut records = decode_records(&content, FORMAT_V1).with_context(|| {
+        format!(
+            "Index illisible (ni run, ni ancien format) : {}",
+            path.display()
+        )
+    })?;
+    sort_records(&mut records);
+    write_run(path, records.into_iter().map(Ok))
+}
+
+fn sort_records(records: &mut Vec<IndexRecord>) {
+    records.sort_by(|a, b| (&a.key, &a.document_id).cmp(&(&b.key, &b.document_id)));
+    records.dedup_by(|a, b| a.key == b.key && a.document_id == b.document_id);
 }
 
-// --- Implémentation pour BTree Index (BTreeMap) ---
-impl IndexMap for BTreeMap<String, Vec<String>> {
-    fn insert_record(&mut self, key: String, doc_id: String) {
-        self.entry(key).or_default().push(doc_id);
+// --- JOURNAL (memtable) ---
+
+/// Dernier état connu de chaque couple (clé, id) : `Some(positions)` = présent
+type Memtable = BTreeMap<(String, String), Option<Vec<u32>>>;
+type MemtableIter = btree_map::IntoIter<(String, String), Option<Vec<u32>>>;
+
+/// Version d'un journal existant (`None` s'il est absent ou vide)
+fn log_version(path: &Path) -> Result<Option<u8>> {
+    let mut header = [0u8; HEADER_LEN as usize];
+    match File::open(path) {
+        Ok(mut f) => match f.read_exact(&mut header) {
+            Ok(()) => Ok(Some(header[4])),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e.into()),
+        },
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Lecture journal {}", path.display())),
     }
+}
 
-    fn remove_record(&mut self, key: &str, doc_id: &str) {
-        if let Some(ids) = self.get_mut(key) {
-            ids.retain(|id| id != doc_id);
-            if ids.is_empty() {
-                self.remove(key);
+/// Rejoue un journal dans la memtable. Un enregistrement tronqué (écriture en
+/// cours ou crash) est ignoré, puis coupé par le prochain `write_batch`.
+fn read_log(path: &Path, memtable: &mut Memtable) -> Result<()> {
+    let bytes = match fs::read(path) {
+        Ok(b) => b,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("Lecture journal {}", path.display())),
+    };
+    if bytes.len() < HEADER_LEN as usize {
+        return Ok(());
+    }
+    let version = bytes[4];
+    if bytes[..4] != LOG_MAGIC || (version != FORMAT_VERSION && version != FORMAT_V1) {
+        bail!("Journal d'index invalide : {}", path.display());
+    }
+
+    let mut pos = HEADER_LEN as usize;
+    while pos + 4 <= bytes.len() {
+        let body_len = u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+        let Some(body) = bytes.get(pos + 4..pos + 4 + body_len) else {
+            break;
+        };
+        let corrupted = || {
+            anyhow!(
+                "Journal d'index corrompu à l'offset {} : {}",
+                pos,
+                path.display()
+            )
+        };
+        if body.len() < 5 {
+            return Err(corrupted());
+        }
+        let key_len = u32::from_le_bytes(body[1..5].try_into().unwrap()) as usize;
+        let key = body.get(5..5 + key_len).ok_or_else(corrupted)?;
+        let rest = &body[5 + key_len..];
+        let (id, positions) = if version == FORMAT_V1 {
+            (rest, &[][..])
+        } else {
+            let id_len = rest
+                .get(..4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize)
+                .ok_or_else(corrupted)?;
+            let id = rest.get(4..4 + id_len).ok_or_else(corrupted)?;
+            let positions = &rest[4 + id_len..];
+            if positions.len() % 4 != 0 {
+                return Err(corrupted());
             }
+            (id, positions)
+        };
+        let key = String::from_utf8(key.to_vec()).context("Clé d'index non UTF-8")?;
+        let id = String::from_utf8(id.to_vec()).context("ID de document non UTF-8")?;
+        let state = (body[0] == OP_PUT).then(|| {
+            positions
+                .as_chunks::<4>()
+                .0
+                .iter()
+                .map(|b| u32::from_le_bytes(*b))
+                .collect()
+        });
+        memtable.insert((key, id), state);
+        pos += 4 + body_len;
+    }
+    Ok(())
+}
+
+/// Longueur du journal jusqu'au dernier enregistrement complet (0 si l'en-tête
+/// lui-même est incomplet). Seuls les préfixes de longueur sont lus.
+fn complete_log_len(bytes: &[u8]) -> u64 {
+    if bytes.len() < HEADER_LEN as usize {
+        return 0;
+    }
+    let mut pos = HEADER_LEN as usize;
+    while let Some(prefix) = bytes.get(pos..pos + 4) {
+        let end = pos + 4 + u32::from_le_bytes(prefix.try_into().unwrap()) as usize;
+        if end > bytes.len() {
+            break;
         }
+        pos = end;
     }
+    pos as u64
+}
+
+/// Journal en cours de fusion puis journal courant (le plus récent l'emporte)
+fn load_memtable(path: &Path) -> Result<Memtable> {
+    let mut memtable = Memtable::new();
+    read_log(&flush_path(path), &mut memtable)?;
+    read_log(&log_path(path), &mut memtable)?;
+    Ok(memtable)
+}
+
+fn file_len(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
 
-    fn get_doc_ids(&self, key: &str) -> Option<&Vec<String>> {
-        self.get(key)
+/// Fusionne le journal dans un nouveau run. À appeler sous `locked`.
+fn merge(path: &Path) -> Result<()> {
+    let log = log_path(path);
+    let flush = flush_path(path);
+    // Rotation : les écritures suivantes partent dans un nouveau journal.
+    // Un `.flush` déjà présent (fusion interrompue) est fusionné en premier,
+    // le journal courant attendra la fusion suivante.
+    if !flush.exists() {
+        if !log.exists() {
+            return Ok(());
+        }
+        fs::rename(&log, &flush)?;
     }
 
-    fn from_records(records: Vec<IndexRecord>) -> Self {
-        let mut map: BTreeMap<String, Vec<String>> = BTreeMap::new();
-        for r in records {
-            map.entry(r.key).or_default().push(r.document_id);
+    let mut memtable = Memtable::new();
+    read_log(&flush, &mut memtable)?;
+    let scan = Scan::new(Run::open(path)?, memtable, None)?;
+    write_run(path, scan)?;
+    file_storage::remove_if_exists(&flush)
+}
+
+// --- PARCOURS ---
+
+/// Parcours ordonné des couples (clé, id) présents : run et journal fusionnés.
+/// Le run est lu page par page.
+pub struct Scan {
+    run: Option<Run>,
+    next_page: usize,
+    page: VecDeque<IndexRecord>,
+    memtable: Peekable<MemtableIter>,
+}
+
+impl Scan {
+    fn new(run: Option<Run>, mut memtable: Memtable, from: Option<&str>) -> Result<Self> {
+        let next_page = match (&run, from) {
+            (Some(run), Some(key)) => run.first_page_for(key),
+            _ => 0,
+        };
+        if let Some(key) = from {
+            memtable = memtable.split_off(&(key.to_string(), String::new()));
+        }
+        let mut scan = Scan {
+            run,
+            next_page,
+            page: VecDeque::new(),
+            memtable: memtable.into_iter().peekable(),
+        };
+        if let Some(key) = from {
+            // Le début de la première page peut précéder la borne
+            loop {
+                scan.fill_page()?;
+                match scan.page.front() {
+                    Some(r) if r.key.as_str() < key => {
+                        scan.page.pop_front();
+                    }
+                    _ => break,
+                }
+            }
         }
-        map
+        Ok(scan)
     }
 
-    fn to_records(&self) -> Vec<IndexRecord> {
-        let mut records = Vec::new();
-        for (k, ids) in self {
-            for id in ids {
-                records.push(IndexRecord {
-                    key: k.clone(),
-                    document_id: id.clone(),
-                });
+    fn fill_page(&mut self) -> Result<()> {
+        while self.page.is_empty() {
+            let Some(run) = self.run.as_mut() else {
+                return Ok(());
+            };
+            if self.next_page >= run.pages.len() {
+                return Ok(());
             }
+            self.page = run.read_page(self.next_page)?.into();
+            self.next_page += 1;
         }
-        records
+        Ok(())
     }
 }
 
-// --- Logique I/O Générique (Version Bincode 2.0.0-rc.3) ---
+impl Iterator for Scan {
+    type Item = Result<IndexRecord>;
 
-/// Charge un index depuis le disque (Format Binaire Bincode)
-pub fn load<T: IndexMap>(path: &Path) -> Result<T> {
-    if !path.exists() {
-        return Ok(T::default());
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Err(e) = self.fill_page() {
+                return Some(Err(e));
+            }
+            let order = match (self.page.front(), self.memtable.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(r), Some(((key, id), _))) => {
+                    (r.key.as_str(), r.document_id.as_str()).cmp(&(key.as_str(), id.as_str()))
+                }
+            };
+            match order {
+                Ordering::Less => return self.page.pop_front().map(Ok),
+                // Le journal est plus récent que le run
+                Ordering::Equal => {
+                    self.page.pop_front();
+                }
+                Ordering::Greater => {}
+            }
+            let ((key, document_id), state) = self.memtable.next()?;
+            if let Some(positions) = state {
+                return Some(Ok(IndexRecord {
+                    key,
+                    document_id,
+                    positions,
+                }));
+            }
+        }
     }
+}
 
-    let content = fs::read(path).with_context(|| format!("Lecture index {}", path.display()))?;
+// --- API ---
 
-    // CORRECTION : Utilisation de bincode::serde::decode_from_slice (API v2)
-    // Retourne un tuple (valeur, taille_lue), on prend .0
-    let (records, _): (Vec<IndexRecord>, usize) =
-        bincode::serde::decode_from_slice(&content, bincode::config::standard())
-            .with_context(|| format!("Désérialisation Bincode index {}", path.display()))?;
+/// Parcours ordonné de l'index, à partir de la clé `from` si elle est fournie.
+/// L'état est figé à l'appel : les écritures suivantes ne sont pas vues.
+pub fn scan(path: &Path, from: Option<&str>) -> Result<Scan> {
+    locked(path, || {
+        Scan::new(Run::open(path)?, load_memtable(path)?, from)
+    })
+}
 
-    Ok(T::from_records(records))
+/// Recherche exacte : retourne les IDs associés à l'une des clés (sans doublons)
+pub fn lookup(path: &Path, keys: &[String]) -> Result<Vec<String>> {
+    let mut seen = HashSet::new();
+    Ok(records(path, keys)?
+        .into_iter()
+        .filter(|r| seen.insert(r.document_id.clone()))
+        .map(|r| r.document_id)
+        .collect())
 }
 
-/// Sauvegarde un index sur le disque (Format Binaire Bincode)
-pub fn save<T: IndexMap>(path: &Path, index: &T) -> Result<()> {
-    let records = index.to_records();
+/// Entrées complètes (avec positions) des clés demandées, par clé puis par ID
+pub fn records(path: &Path, keys: &[String]) -> Result<Vec<IndexRecord>> {
+    locked(path, || {
+        let mut run = Run::open(path)?;
+        let memtable = load_memtable(path)?;
+        let mut records = Vec::new();
 
-    // CORRECTION : Utilisation de bincode::serde::encode_to_vec (API v2)
-    let encoded: Vec<u8> = bincode::serde::encode_to_vec(&records, bincode::config::standard())?;
+        for key in keys {
+            let mut found = BTreeMap::new();
+            if let Some(run) = run.as_mut() {
+                let mut page = run.first_page_for(key);
+                while page < run.pages.len() && run.pages[page].first_key <= *key {
+                    found.extend(
+                        run.read_page(page)?
+                            .into_iter()
+                            .filter(|r| r.key == *key)
+                            .map(|r| (r.document_id, r.positions)),
+                    );
+                    page += 1;
+                }
+            }
+            let pending = memtable
+                .range((key.clone(), String::new())..)
+                .take_while(|((k, _), _)| k == key);
+            for ((_, id), state) in pending {
+                match state {
+                    Some(positions) => found.insert(id.clone(), positions.clone()),
+                    None => found.remove(id),
+                };
+            }
+            records.extend(
+                found
+                    .into_iter()
+                    .map(|(document_id, positions)| IndexRecord {
+                        key: key.clone(),
+                        document_id,
+                        positions,
+                    }),
+            );
+        }
+        Ok(records)
+    })
+}
+
+/// Applique des mutations : un seul ajout en fin de journal, puis fusion
+/// si le journal dépasse `LOG_FLUSH_BYTES`.
+pub fn write_batch(path: &Path, ops: &[IndexOp]) -> Result<()> {
+    if ops.is_empty() {
+        return Ok(());
+    }
+    locked(path, || {
+        let log = log_path(path);
+        if let Some(parent) = log.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Un journal v1 est fusionné plutôt que prolongé dans un autre format
+        if log_version(&log)? == Some(FORMAT_V1) {
+            merge(path)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&log)?;
+
+        // Fin tronquée (crash pendant un ajout) : coupée avant d'ajouter, sinon
+        // les enregistrements suivants seraient lus comme la suite du fragment
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        let complete = complete_log_len(&bytes);
+        if complete < bytes.len() as u64 {
+            file.set_len(complete)?;
+        }
 
-    atomic_write_binary(path, &encoded)
+        let mut buf = Vec::new();
+        if complete == 0 {
+            buf.extend_from_slice(&LOG_MAGIC);
+            buf.push(FORMAT_VERSION);
+        }
+        for op in ops {
+            let (code, key, doc_id, positions) = match op {
+                IndexOp::Put {
+                    key,
+                    doc_id,
+                    positions,
+                } => (OP_PUT, key, doc_id, positions.as_slice()),
+                IndexOp::Delete { key, doc_id } => (OP_DELETE, key, doc_id, &[][..]),
+            };
+            let body_len = u32::try_from(9 + key.len() + doc_id.len() + 4 * positions.len())
+                .map_err(|_| anyhow!("Clé d'index trop longue"))?;
+            buf.extend_from_slice(&body_len.to_le_bytes());
+            buf.push(code);
+            buf.extend_from_slice(&(key.len() as u32).to_le_bytes());
+            buf.extend_from_slice(key.as_bytes());
+            buf.extend_from_slice(&(doc_id.len() as u32).to_le_bytes());
+            buf.extend_from_slice(doc_id.as_bytes());
+            for p in positions {
+                buf.extend_from_slice(&p.to_le_bytes());
+            }
+        }
+        // Un seul write en mode append : les enregistrements ne sont jamais entrelacés
+        file.write_all(&buf)?;
+
+        if file.metadata()?.len() >= LOG_FLUSH_BYTES {
+            drop(file);
+            merge(path)?;
+        }
+        Ok(())
+    })
+}
+
+/// Met à jour les entrées d'un document : retire les clés disparues, ajoute
+/// les nouvelles. Rien n'est écrit si les clés n'ont pas changé.
+pub fn update(path: &Path, doc_id: &str, old_keys: &[String], new_keys: &[String]) -> Result<()> {
+    let entries = |keys: &[String]| -> Vec<(String, Vec<u32>)> {
+        keys.iter().map(|k| (k.clone(), Vec::new())).collect()
+    };
+    update_entries(path, doc_id, &entries(old_keys), &entries(new_keys))
 }
 
-/// Met à jour un index (charge -> modifie -> sauvegarde)
-pub fn update<T: IndexMap>(
+/// Variante de `update` avec positions : une clé dont les positions changent
+/// est réécrite.
+pub fn update_entries(
     path: &Path,
-    def: &IndexDefinition,
     doc_id: &str,
-    old_doc: Option<&Value>,
-    new_doc: Option<&Value>,
+    old: &[(String, Vec<u32>)],
+    new: &[(String, Vec<u32>)],
 ) -> Result<()> {
-    let mut index: T = load(path)?;
-    let mut changed = false;
-
-    // 1. Suppression de l'ancienne entrée
-    if let Some(doc) = old_doc {
-        if let Some(old_key) = doc.pointer(&def.field_path) {
-            index.remove_record(&old_key.to_string(), doc_id);
-            changed = true;
-        }
-    }
-
-    // 2. Ajout de la nouvelle entrée
-    if let Some(doc) = new_doc {
-        if let Some(new_key) = doc.pointer(&def.field_path) {
-            let key_str = new_key.to_string();
-
-            // Vérification unicité
-            if def.unique {
-                if let Some(ids) = index.get_doc_ids(&key_str) {
-                    if !ids.is_empty() && (ids.len() > 1 || ids[0] != doc_id) {
-                        anyhow::bail!(
-                            "Index unique constraint violation: {} = {}",
-                            def.name,
-                            key_str
-                        );
-                    }
-                }
-            }
+    let mut ops: Vec<IndexOp> = old
+        .iter()
+        .filter(|(k, _)| !new.iter().any(|(n, _)| n == k))
+        .map(|(key, _)| IndexOp::Delete {
+            key: key.clone(),
+            doc_id: doc_id.to_string(),
+        })
+        .collect();
+    ops.extend(
+        new.iter()
+            .filter(|entry| !old.contains(entry))
+            .map(|(key, positions)| IndexOp::Put {
+                key: key.clone(),
+                doc_id: doc_id.to_string(),
+                positions: positions.clone(),
+            }),
+    );
+    write_batch(path, &ops)
+}
 
-            index.insert_record(key_str, doc_id.to_string());
-            changed = true;
+/// Reconstruit l'index à partir de tous ses couples (backfill) : un run neuf,
+/// sans journal.
+pub fn rebuild(path: &Path, mut records: Vec<IndexRecord>) -> Result<()> {
+    sort_records(&mut records);
+    locked(path, || {
+        file_storage::remove_if_exists(&log_path(path))?;
+        file_storage::remove_if_exists(&flush_path(path))?;
+        write_run(path, records.into_iter().map(Ok))
+    })
+}
+
+/// Fusionne immédiatement le journal dans le run
+pub fn compact(path: &Path) -> Result<IndexStats> {
+    locked(path, || {
+        // Fusion interrompue éventuelle, puis journal courant
+        merge(path)?;
+        merge(path)?;
+        Ok(())
+    })?;
+    stats(path)
+}
+
+pub fn stats(path: &Path) -> Result<IndexStats> {
+    locked(path, || {
+        let log_bytes = file_len(&log_path(path)) + file_len(&flush_path(path));
+        Ok(match Run::open(path)? {
+            Some(run) => IndexStats {
+                pages: run.pages.len(),
+                run_records: run.pages.iter().map(|p| p.records as usize).sum(),
+                run_bytes: file_len(path),
+                log_bytes,
+            },
+            None => IndexStats {
+                log_bytes,
+                ..Default::default()
+            },
+        })
+    })
+}
+
+/// Supprime l'index et ses journaux
+pub fn remove(path: &Path) -> Result<()> {
+    locked(path, || {
+        file_storage::remove_if_exists(path)?;
+        file_storage::remove_if_exists(&log_path(path))?;
+        file_storage::remove_if_exists(&flush_path(path))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn put(key: &str, id: &str) -> IndexOp {
+        IndexOp::Put {
+            key: key.to_string(),
+            doc_id: id.to_string(),
+            positions: Vec::new(),
         }
     }
 
-    if changed {
-        save(path, &index)?;
+    fn keys(path: &Path) -> Vec<(String, String)> {
+        scan(path, None)
+            .unwrap()
+            .map(|r| r.map(|r| (r.key, r.document_id)).unwrap())
+            .collect()
     }
 
-    Ok(())
+    #[test]
+    fn test_log_then_merge() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("email.hash.idx");
+
+        write_batch(&path, &[put("\"a\"", "u1"), put("\"b\"", "u2")]).unwrap();
+        update(&path, "u1", &["\"a\"".into()], &["\"c\"".into()]).unwrap();
+        assert!(!path.exists(), "les écritures restent dans le journal");
+        assert_eq!(
+            lookup(&path, &["\"a\"".into()]).unwrap(),
+            Vec::<String>::new()
+        );
+        assert_eq!(lookup(&path, &["\"c\"".into()]).unwrap(), vec!["u1"]);
+
+        let stats = compact(&path).unwrap();
+        assert_eq!(stats.run_records, 2);
+        assert_eq!(stats.log_bytes, 0);
+        assert_eq!(lookup(&path, &["\"c\"".into()]).unwrap(), vec!["u1"]);
+
+        // Retrait après fusion : masqué par le journal
+        update(&path, "u2", &["\"b\"".into()], &[]).unwrap();
+        assert!(lookup(&path, &["\"b\"".into()]).unwrap().is_empty());
+        assert_eq!(keys(&path), vec![("\"c\"".to_string(), "u1".to_string())]);
+    }
+
+    #[test]
+    fn test_scan_spans_pages_in_order() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("level.btree.idx");
+
+        // Assez d'entrées pour plusieurs pages, une clé répartie sur plusieurs pages
+        let records: Vec<IndexRecord> = (0..2000)
+            .map(|i| IndexRecord {
+                key: format!("k{:04}", i % 500),
+                document_id: format!("doc-{:05}", i),
+                positions: Vec::new(),
+            })
+            .collect();
+        rebuild(&path, records).unwrap();
+        assert!(stats(&path).unwrap().pages > 1);
+        write_batch(&path, &[put("k0250", "doc-late"), put("a", "first")]).unwrap();
+
+        let all = keys(&path);
+        assert_eq!(all.len(), 2002);
+        assert!(all.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(all[0].1, "first");
+
+        let hits = lookup(&path, &["k0250".into()]).unwrap();
+        assert_eq!(hits.len(), 5);
+        assert!(hits.contains(&"doc-late".to_string()));
+
+        let from: Vec<_> = scan(&path, Some("k0499"))
+            .unwrap()
+            .map(|r| r.unwrap().key)
+            .collect();
+        assert_eq!(from, vec!["k0499"; 4]);
+    }
+
+    #[test]
+    fn test_truncated_log_record_is_ignored() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("name.hash.idx");
+        write_batch(&path, &[put("\"x\"", "d1")]).unwrap();
+
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(log_path(&path))
+            .unwrap();
+        log.write_all(&[40, 0, 0, 0, OP_PUT, 3]).unwrap();
+        drop(log);
+
+        assert_eq!(lookup(&path, &["\"x\"".into()]).unwrap(), vec!["d1"]);
+
+        // L'ajout suivant commence là où s'arrête le dernier enregistrement complet
+        let torn_len = file_len(&log_path(&path));
+        write_batch(&path, &[put("\"y\"", "d2")]).unwrap();
+        assert_eq!(lookup(&path, &["\"y\"".into()]).unwrap(), vec!["d2"]);
+        assert_eq!(lookup(&path, &["\"x\"".into()]).unwrap(), vec!["d1"]);
+        let bytes = fs::read(log_path(&path)).unwrap();
+        assert_eq!(complete_log_len(&bytes), bytes.len() as u64);
+        assert_eq!(bytes.len() as u64, torn_len - 6 + 4 + 9 + 3 + 2);
+    }
+
+    #[test]
+    fn test_writes_wait_for_the_file_lock() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("name.hash.idx");
+        write_batch(&path, &[put("\"x\"", "d1")]).unwrap();
+        let before = file_len(&log_path(&path));
+
+        // Verrou tenu par « un autre processus » (descripteur distinct)
+        let held = FileLock::acquire(&lock_path(&path), LockMode::Exclusive, DEFAULT_LOCK_TIMEOUT)
+            .unwrap();
+        let writer = {
+            let path = path.clone();
+            std::thread::spawn(move || write_batch(&path, &[put("\"y\"", "d2")]))
+        };
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert_eq!(file_len(&log_path(&path)), before);
+
+        drop(held);
+        writer.join().unwrap().unwrap();
+        assert_eq!(lookup(&path, &["\"y\"".into()]).unwrap(), vec!["d2"]);
+    }
+
+    #[test]
+    fn test_legacy_index_is_converted() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("email.hash.idx");
+        let legacy = vec![
+            RecordV1 {
+                key: "\"b\"".into(),
+                document_id: "u2".into(),
+            },
+            RecordV1 {
+                key: "\"a\"".into(),
+                document_id: "u1".into(),
+            },
+        ];
+        let bytes = bincode::serde::encode_to_vec(&legacy, bincode::config::standard()).unwrap();
+        fs::write(&path, bytes).unwrap();
+
+        assert_eq!(lookup(&path, &["\"b\"".into()]).unwrap(), vec!["u2"]);
+        assert_eq!(&fs::read(&path).unwrap()[..4], b"RIDX");
+        assert_eq!(stats(&path).unwrap().run_records, 2);
+    }
+
+    #[test]
+    fn test_positions_survive_merge() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("title.text.idx");
+        let entry = |key: &str, positions: &[u32]| (key.to_string(), positions.to_vec());
+
+        update_entries(&path, "r1", &[], &[entry("radar", &[0, 4])]).unwrap();
+        update_entries(
+            &path,
+            "r1",
+            &[entry("radar", &[0, 4])],
+            &[entry("radar", &[1])],
+        )
+        .unwrap();
+        assert_eq!(records(&path, &["radar".into()]).unwrap()[0].positions, [1]);
+
+        compact(&path).unwrap();
+        let stored = records(&path, &["radar".into()]).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].positions, [1]);
+    }
+
+    #[test]
+    fn test_v1_log_is_merged_before_append() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("email.hash.idx");
+        let mut log = LOG_MAGIC.to_vec();
+        log.push(FORMAT_V1);
+        let (key, id) = (b"\"a\"", b"u1");
+        log.extend_from_slice(&((5 + key.len() + id.len()) as u32).to_le_bytes());
+        log.push(OP_PUT);
+        log.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        log.extend_from_slice(key);
+        log.extend_from_slice(id);
+        fs::write(log_path(&path), log).unwrap();
+
+        assert_eq!(lookup(&path, &["\"a\"".into()]).unwrap(), vec!["u1"]);
+        write_batch(&path, &[put("\"b\"", "u2")]).unwrap();
+        assert_eq!(
+            keys(&path),
+            vec![
+                ("\"a\"".to_string(), "u1".to_string()),
+                ("\"b\"".to_string(), "u2".to_string())
+            ]
+        );
+        assert_eq!(stats(&path).unwrap().run_records, 1);
+    }
 }
@@ -1,6 +1,5 @@
 use anyhow::Result;
 use serde_json::Value;
-use std::collections::HashMap;
 
 use super::{driver, paths, IndexDefinition};
 use crate::json_db::storage::JsonDbConfig;
@@ -18,6 +17,7 @@ pub fn update_hash_index(
     new_doc: Option<&Value>,
 ) -> Result<()> {
     let path = paths::index_path(cfg, space, db, collection, &def.name, def.index_type);
-    // On spécifie le type HashMap pour utiliser l'implémentation IndexMap correspondante
-    driver::update::<HashMap<String, Vec<String>>>(&path, def, doc_id, old_doc, new_doc)
+    let old_entries = old_doc.map(|d| def.entries_of(d)).unwrap_or_default();
+    let new_entries = new_doc.map(|d| def.entries_of(d)).unwrap_or_default();
+    driver::update_entries(&path, doc_id, &old_entries, &new_entries)
 }
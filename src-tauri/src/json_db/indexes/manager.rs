@@ -1,10 +1,14 @@
 // FICHIER : src-tauri/src/json_db/indexes/manager.rs
 
-use super::{btree, hash, text, IndexDefinition, IndexType};
-use crate::json_db::storage::StorageEngine;
+use super::{
+    btree, driver, hash, paths, text, DuplicateKeyError, IndexDefinition, IndexRecord, IndexType,
+    TextAnalyzer,
+};
+use crate::json_db::storage::{file_storage, StorageEngine};
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 
@@ -34,32 +38,115 @@ impl<'a> IndexManager<'a> {
 
     /// Crée un nouvel index (Mise à jour de _meta.json + Backfill)
     pub fn create_index(&mut self, collection: &str, field: &str, kind_str: &str) -> Result<()> {
+        self.create_index_on(collection, &[field], kind_str, false)
+    }
+
+    /// Crée un index simple ou composite (plusieurs champs, ex: `["layer", "name"]`),
+    /// éventuellement unique. Le type `"unique"` est un raccourci pour un hash unique.
+    ///
+    /// Un index texte précise son analyseur après `:` (`"text:fr"`, `"text:en"`,
+    /// `"text:simple"`) ; `"text"` seul utilise l'analyseur français.
+    ///
+    /// Pour un index unique, les documents existants sont contrôlés avant toute
+    /// écriture : un doublon fait échouer la création avec un `DuplicateKeyError`.
+    pub fn create_index_on(
+        &mut self,
+        collection: &str,
+        fields: &[&str],
+        kind_str: &str,
+        unique: bool,
+    ) -> Result<()> {
         // 1. Validation du type
-        let kind = match kind_str.to_lowercase().as_str() {
-            "hash" => IndexType::Hash,
-            "btree" => IndexType::BTree,
-            "text" => IndexType::Text,
+        let kind_lower = kind_str.to_lowercase();
+        let (base, analyzer_code) = match kind_lower.split_once(':') {
+            Some((base, code)) => (base, Some(code)),
+            None => (kind_lower.as_str(), None),
+        };
+        let (kind, unique) = match base {
+            "hash" => (IndexType::Hash, unique),
+            "unique" => (IndexType::Hash, true),
+            "btree" => (IndexType::BTree, unique),
+            "text" => (IndexType::Text, unique),
             _ => return Err(anyhow!("Type d'index inconnu: {}", kind_str)),
         };
+        let analyzer = match (kind, analyzer_code) {
+            (IndexType::Text, None) => Some(TextAnalyzer::French),
+            (IndexType::Text, Some(code)) => Some(
+                TextAnalyzer::parse(code)
+                    .ok_or_else(|| anyhow!("Analyseur de texte inconnu: {}", code))?,
+            ),
+            (_, None) => None,
+            (_, Some(_)) => {
+                return Err(anyhow!(
+                    "Seul un index texte accepte un analyseur: {}",
+                    kind_str
+                ))
+            }
+        };
+        if fields.is_empty() {
+            return Err(anyhow!("Aucun champ fourni pour l'index"));
+        }
+        if kind == IndexType::Text && (fields.len() > 1 || unique) {
+            return Err(anyhow!(
+                "Un index texte ne peut être ni composite ni unique"
+            ));
+        }
 
         // 2. Construction de la définition
-        let field_path = if field.starts_with('/') {
-            field.to_string()
+        let pointers: Vec<String> = fields
+            .iter()
+            .map(|f| {
+                if f.starts_with('/') {
+                    f.to_string()
+                } else {
+                    format!("/{}", f)
+                }
+            })
+            .collect();
+        let name = if fields.len() == 1 {
+            fields[0].to_string()
         } else {
-            format!("/{}", field)
+            fields
+                .iter()
+                .map(|f| f.trim_start_matches('/'))
+                .collect::<Vec<_>>()
+                .join("_")
         };
 
         let def = IndexDefinition {
-            name: field.to_string(),
-            field_path,
+            name,
+            field_path: pointers[0].clone(),
+            fields: if pointers.len() > 1 {
+                pointers
+            } else {
+                Vec::new()
+            },
             index_type: kind,
-            unique: false,
+            unique,
+            analyzer,
         };
 
-        // 3. Mise à jour de _meta.json via la fonction statique (DRY)
+        // 3. Unicité : les données existantes doivent déjà respecter la contrainte
+        if def.unique {
+            let docs = file_storage::list_documents(
+                &self.storage.config,
+                &self.space,
+                &self.db,
+                collection,
+            )?;
+            let writes: Vec<(&str, Option<&Value>)> = docs
+                .iter()
+                .filter_map(|d| d.get("id").and_then(|v| v.as_str()).map(|id| (id, Some(d))))
+                .collect();
+            if let Some(err) = self.unique_violation(collection, &def, &writes)? {
+                return Err(err.into());
+            }
+        }
+
+        // 4. Mise à jour de _meta.json via la fonction statique (DRY)
         add_index_definition(self.storage, &self.space, &self.db, collection, def.clone())?;
 
-        // 4. Backfill (Reconstruction)
+        // 5. Backfill (Reconstruction)
         self.rebuild_index(collection, &def)?;
 
         Ok(())
@@ -80,23 +167,15 @@ impl<'a> IndexManager<'a> {
             // Sauvegarde Meta
             fs::write(&meta_path, serde_json::to_string_pretty(&meta)?)?;
 
-            // Suppression Physique
-            let index_filename = match removed.index_type {
-                IndexType::Hash => format!("{}.hash.idx", removed.name),
-                IndexType::BTree => format!("{}.btree.idx", removed.name),
-                IndexType::Text => format!("{}.text.idx", removed.name),
-            };
-
-            let index_path = self
-                .storage
-                .config
-                .db_collection_path(&self.space, &self.db, collection)
-                .join("_indexes")
-                .join(index_filename);
-
-            if index_path.exists() {
-                fs::remove_file(index_path)?;
-            }
+            // Suppression Physique (run et journaux)
+            driver::remove(&paths::index_path(
+                &self.storage.config,
+                &self.space,
+                &self.db,
+                collection,
+                &removed.name,
+                removed.index_type,
+            ))?;
         } else {
             return Err(anyhow!("Index introuvable pour le champ '{}'", field));
         }
@@ -104,41 +183,41 @@ impl<'a> IndexManager<'a> {
         Ok(())
     }
 
-    /// Reconstruit un index en parcourant tous les documents
+    /// Reconstruit un index en parcourant tous les documents : les entrées
+    /// sont triées en mémoire puis écrites d'un bloc dans un run neuf.
     fn rebuild_index(&self, collection: &str, def: &IndexDefinition) -> Result<()> {
-        let col_path = self
-            .storage
-            .config
-            .db_collection_path(&self.space, &self.db, collection);
-        let indexes_dir = col_path.join("_indexes");
-        if !indexes_dir.exists() {
-            fs::create_dir_all(&indexes_dir)?;
-        }
-
         println!(
             "🔄 Reconstruction de l'index {} sur {}...",
-            def.name, def.field_path
+            def.name,
+            def.pointers().join(", ")
         );
 
-        for entry in fs::read_dir(&col_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.extension().is_some_and(|e| e == "json") {
-                let filename = path.file_name().unwrap().to_str().unwrap();
-                if filename.starts_with('_') {
-                    continue;
-                }
-
-                let content = fs::read_to_string(&path)?;
-                if let Ok(doc) = serde_json::from_str::<Value>(&content) {
-                    let doc_id = doc.get("id").and_then(|v| v.as_str()).unwrap_or("");
-                    if !doc_id.is_empty() {
-                        self.dispatch_update(collection, def, doc_id, None, Some(&doc))?;
+        let docs =
+            file_storage::list_documents(&self.storage.config, &self.space, &self.db, collection)?;
+        let mut records = Vec::new();
+        for doc in &docs {
+            let doc_id = doc.get("id").and_then(|v| v.as_str()).unwrap_or("");
+            if !doc_id.is_empty() {
+                records.extend(def.entries_of(doc).into_iter().map(|(key, positions)| {
+                    IndexRecord {
+                        key,
+                        document_id: doc_id.to_string(),
+                        positions,
                     }
-                }
+                }));
             }
         }
-        Ok(())
+
+        let path = paths::index_path(
+            &self.storage.config,
+            &self.space,
+            &self.db,
+            collection,
+            &def.name,
+            def.index_type,
+        );
+        driver::rebuild(&path, records)
+            .with_context(|| format!("Erreur reconstruction index '{}'", def.name))
     }
 
     /// Indexe un document (ajout/mise à jour)
@@ -185,9 +264,167 @@ impl<'a> IndexManager<'a> {
         Ok(())
     }
 
-    // --- Helpers Privés ---
+    /// La collection a-t-elle au moins un index unique ?
+    pub fn has_unique(&self, collection: &str) -> Result<bool> {
+        Ok(self.load_indexes(collection)?.iter().any(|d| d.unique))
+    }
+
+    /// Vérifie les index uniques avant l'écriture de `doc`.
+    /// Renvoie un `DuplicateKeyError` si une clé est déjà portée par un autre document.
+    pub fn check_unique(&self, collection: &str, doc: &Value) -> Result<()> {
+        let doc_id = doc
+            .get("id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Document sans ID"))?;
+        self.check_unique_batch(collection, &[(doc_id, Some(doc))])
+    }
 
-    fn load_indexes(&self, collection: &str) -> Result<Vec<IndexDefinition>> {
+    /// Vérifie un lot d'écritures (id -> nouvel état, `None` = suppression).
+    /// Les documents du lot sont comparés entre eux ; leurs anciennes entrées
+    /// d'index sont ignorées puisqu'elles vont être remplacées.
+    pub fn check_unique_batch(
+        &self,
+        collection: &str,
+        writes: &[(&str, Option<&Value>)],
+    ) -> Result<()> {
+        for def in self.load_indexes(collection)?.iter().filter(|d| d.unique) {
+            if let Some(err) = self.unique_violation(collection, def, writes)? {
+                return Err(err.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Première violation de l'index unique `def` par le lot, s'il y en a une.
+    /// Une clé contenant `null` ne viole jamais l'unicité (comme en SQL).
+    fn unique_violation(
+        &self,
+        collection: &str,
+        def: &IndexDefinition,
+        writes: &[(&str, Option<&Value>)],
+    ) -> Result<Option<DuplicateKeyError>> {
+        let touched: HashSet<&str> = writes.iter().map(|(id, _)| *id).collect();
+        let mut seen: HashMap<String, &str> = HashMap::new();
+
+        for (id, doc) in writes {
+            let Some(value) = doc.and_then(|d| def.key_value(d)) else {
+                continue;
+            };
+            let has_null = match &value {
+                Value::Array(parts) if def.is_composite() => parts.iter().any(Value::is_null),
+                v => v.is_null(),
+            };
+            if has_null {
+                continue;
+            }
+            let key = value.to_string();
+
+            let existing = match seen.get(&key) {
+                Some(other) if other != id => Some(other.to_string()),
+                Some(_) => None,
+                None => self
+                    .lookup(collection, def, &[value])?
+                    .into_iter()
+                    .find(|other| other != id && !touched.contains(other.as_str())),
+            };
+            if let Some(existing_id) = existing {
+                return Ok(Some(DuplicateKeyError {
+                    collection: collection.to_string(),
+                    index: def.name.clone(),
+                    key,
+                    existing_id,
+                }));
+            }
+            seen.insert(key, id);
+        }
+        Ok(None)
+    }
+
+    /// Recherche exacte (Eq / In) : retourne les IDs des documents dont la clé
+    /// indexée vaut l'une des valeurs fournies. Pour un index composite, chaque
+    /// valeur est le tableau des valeurs des champs (ex: `["OA", "Radar"]`).
+    pub fn lookup(
+        &self,
+        collection: &str,
+        def: &IndexDefinition,
+        values: &[Value],
+    ) -> Result<Vec<String>> {
+        let path = paths::index_path(
+            &self.storage.config,
+            &self.space,
+            &self.db,
+            collection,
+            &def.name,
+            def.index_type,
+        );
+        // Les clés sont stockées sous forme de JSON stringifié (cf. driver::update),
+        // un tableau de valeurs pour un index composite
+        let keys: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+
+        match def.index_type {
+            IndexType::Hash | IndexType::BTree => driver::lookup(&path, &keys),
+            IndexType::Text => Err(anyhow!(
+                "L'index texte '{}' ne supporte pas la recherche exacte",
+                def.name
+            )),
+        }
+    }
+
+    /// Recherche plein texte classée sur un index texte : IDs et scores BM25,
+    /// par pertinence décroissante (cf. `text::TextQuery` pour la syntaxe).
+    pub fn search(
+        &self,
+        collection: &str,
+        def: &IndexDefinition,
+        query: &str,
+    ) -> Result<Vec<(String, f64)>> {
+        if def.index_type != IndexType::Text {
+            return Err(anyhow!(
+                "Recherche plein texte impossible sur l'index '{}' (type {:?})",
+                def.name,
+                def.index_type
+            ));
+        }
+        let path = paths::index_path(
+            &self.storage.config,
+            &self.space,
+            &self.db,
+            collection,
+            &def.name,
+            def.index_type,
+        );
+        text::search(&path, def.text_analyzer(), query)
+    }
+
+    /// Parcours de plage (Gt / Gte / Lt / Lte) sur un index B-Tree
+    pub fn scan_range<F>(
+        &self,
+        collection: &str,
+        def: &IndexDefinition,
+        accept: F,
+    ) -> Result<Vec<String>>
+    where
+        F: Fn(&Value) -> bool,
+    {
+        if def.index_type != IndexType::BTree {
+            return Err(anyhow!(
+                "Parcours de plage impossible sur l'index '{}' (type {:?})",
+                def.name,
+                def.index_type
+            ));
+        }
+        btree::scan_btree_index(
+            &self.storage.config,
+            &self.space,
+            &self.db,
+            collection,
+            def,
+            accept,
+        )
+    }
+
+    /// Liste les index déclarés dans le _meta.json de la collection
+    pub fn load_indexes(&self, collection: &str) -> Result<Vec<IndexDefinition>> {
         let meta_path = self.get_meta_path(collection);
         if !meta_path.exists() {
             return Ok(Vec::new());
@@ -196,6 +433,8 @@ impl<'a> IndexManager<'a> {
         Ok(meta.indexes)
     }
 
+    // --- Helpers Privés ---
+
     fn get_meta_path(&self, collection: &str) -> PathBuf {
         self.storage
             .config
@@ -1,24 +1,48 @@
+//! Index texte : index inversé positionnel et recherche classée (BM25).
+//!
+//! Chaque terme (cf. `analyzer`) est une clé de l'index, associée aux positions
+//! du terme dans le document. Une entrée supplémentaire par document, sous la
+//! clé `LENGTH_KEY`, porte sa longueur en termes : elle fournit les statistiques
+//! du corpus (nombre de documents, longueur moyenne) nécessaires au score BM25.
+
 use anyhow::Result;
 use serde_json::Value;
-use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
 
-// CORRECTION : On importe uniquement le module driver, le trait IndexMap n'est pas utilisé directement
-use super::driver;
-use super::{paths, IndexDefinition};
+use super::{driver, paths, IndexDefinition, TextAnalyzer};
 use crate::json_db::storage::JsonDbConfig;
 
-/// Tokenizer simple : minuscules, alphanumérique seulement
-fn tokenize(text: &str) -> HashSet<String> {
-    text.to_lowercase()
-        .split(|c: char| !c.is_alphanumeric())
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_string())
-        .collect()
+/// Clé des entrées de longueur : `\0` est trié avant tout terme et n'est jamais
+/// produit par un analyseur.
+pub(crate) const LENGTH_KEY: &str = "\u{0}";
+
+/// Paramètres BM25 usuels : saturation de la fréquence et normalisation de longueur
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// Entrées indexées d'un document (champ texte uniquement) : ses termes avec
+/// leurs positions, et sa longueur.
+pub(crate) fn document_entries(def: &IndexDefinition, doc: &Value) -> Vec<(String, Vec<u32>)> {
+    let Some(text) = doc.pointer(&def.field_path).and_then(|v| v.as_str()) else {
+        return Vec::new();
+    };
+    let terms = def.text_analyzer().analyze(text);
+    let mut entries = vec![(LENGTH_KEY.to_string(), vec![terms.len() as u32])];
+
+    let mut postings: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+    for (term, position) in terms {
+        postings.entry(term).or_default().push(position);
+    }
+    entries.extend(postings);
+    entries
 }
 
 /// Met à jour l'index Textuel (Index Inversé).
 ///
-/// Structure : Token -> [DocId1, DocId2, ...]
+/// Structure : Token -> [DocId1, DocId2, ...] ; seuls les tokens ajoutés,
+/// disparus ou déplacés sont écrits dans le journal de l'index.
 #[allow(clippy::too_many_arguments)]
 pub fn update_text_index(
     cfg: &JsonDbConfig,
@@ -31,48 +55,364 @@ pub fn update_text_index(
     new_doc: Option<&Value>,
 ) -> Result<()> {
     let path = paths::index_path(cfg, space, db, collection, &def.name, def.index_type);
+    let old_entries = old_doc
+        .map(|d| document_entries(def, d))
+        .unwrap_or_default();
+    let new_entries = new_doc
+        .map(|d| document_entries(def, d))
+        .unwrap_or_default();
+    driver::update_entries(&path, doc_id, &old_entries, &new_entries)
+}
+
+// --- REQUÊTES ---
 
-    // On charge l'index existant (HashMap<Token, Vec<DocId>>)
-    let mut index: HashMap<String, Vec<String>> = driver::load(&path)?;
-    let mut changed = false;
-
-    // 1. Suppression des anciens tokens
-    if let Some(doc) = old_doc {
-        if let Some(val) = doc.pointer(&def.field_path).and_then(|v| v.as_str()) {
-            let tokens = tokenize(val);
-            for token in tokens {
-                if let Some(ids) = index.get_mut(&token) {
-                    if let Some(pos) = ids.iter().position(|x| x == doc_id) {
-                        ids.swap_remove(pos); // Suppression rapide O(1)
-                        changed = true;
+/// Élément d'une requête plein texte. Tous les éléments doivent être satisfaits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextClause {
+    /// Terme analysé (racine)
+    Term(String),
+    /// `archi*` : tout terme indexé commençant par le préfixe
+    Prefix(String),
+    /// `"station de sol"` : termes avec leur écart relatif (le premier à 0)
+    Phrase(Vec<(String, u32)>),
+}
+
+/// Requête plein texte analysée.
+///
+/// Syntaxe : des mots (`exigence radar`), des phrases entre guillemets
+/// (`"station de sol"`) et des préfixes (`archi*`). Le texte de la requête
+/// passe par le même analyseur que les documents.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextQuery {
+    pub clauses: Vec<TextClause>,
+}
+
+impl TextQuery {
+    pub fn parse(query: &str, analyzer: TextAnalyzer) -> Self {
+        let mut clauses = Vec::new();
+        // Les segments de rang impair sont entre guillemets
+        for (i, segment) in query.split('"').enumerate() {
+            if i % 2 == 1 {
+                let terms = analyzer.analyze(segment);
+                match terms.len() {
+                    0 => {}
+                    1 => clauses.push(TextClause::Term(terms[0].0.clone())),
+                    _ => {
+                        let start = terms[0].1;
+                        clauses.push(TextClause::Phrase(
+                            terms.into_iter().map(|(t, p)| (t, p - start)).collect(),
+                        ));
                     }
                 }
-                // Nettoyage si vide
-                if index.get(&token).map(|ids| ids.is_empty()).unwrap_or(false) {
-                    index.remove(&token);
-                }
+                continue;
+            }
+            for token in segment.split_whitespace() {
+                let Some(prefix) = token.strip_suffix('*') else {
+                    clauses.extend(
+                        analyzer
+                            .analyze(token)
+                            .into_iter()
+                            .map(|(t, _)| TextClause::Term(t)),
+                    );
+                    continue;
+                };
+                let mut words = analyzer.words(prefix);
+                let Some(last) = words.pop() else {
+                    continue;
+                };
+                clauses.extend(
+                    words
+                        .iter()
+                        .filter_map(|w| analyzer.term(w))
+                        .map(TextClause::Term),
+                );
+                clauses.push(TextClause::Prefix(stem_prefix(analyzer, &last)));
             }
         }
+
+        let mut seen = Vec::new();
+        clauses.retain(|c| {
+            let new = !seen.contains(c);
+            if new {
+                seen.push(c.clone());
+            }
+            new
+        });
+        TextQuery { clauses }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// Évaluation sur un texte, sans index (filtre en mémoire). Une requête
+    /// vide (mots vides uniquement) ne retient rien.
+    pub fn matches(&self, text: &str, analyzer: TextAnalyzer) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let mut positions: HashMap<String, Vec<u32>> = HashMap::new();
+        for (term, position) in analyzer.analyze(text) {
+            positions.entry(term).or_default().push(position);
+        }
+        self.clauses.iter().all(|clause| match clause {
+            TextClause::Term(term) => positions.contains_key(term),
+            TextClause::Prefix(prefix) => positions.keys().any(|t| t.starts_with(prefix.as_str())),
+            TextClause::Phrase(terms) => {
+                phrase_matches(terms, |t| positions.get(t).map(Vec::as_slice))
+            }
+        })
     }
+}
+
+/// Préfixe ramené à la partie commune avec sa racine : les termes indexés sont
+/// des racines (`exigence*` doit retrouver `exig`).
+fn stem_prefix(analyzer: TextAnalyzer, word: &str) -> String {
+    let Some(stem) = analyzer.term(word) else {
+        return word.to_string();
+    };
+    word.chars()
+        .zip(stem.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a)
+        .collect()
+}
+
+/// Vrai si les termes de la phrase apparaissent avec leurs écarts relatifs
+fn phrase_matches<'a, F>(phrase: &[(String, u32)], positions_of: F) -> bool
+where
+    F: Fn(&str) -> Option<&'a [u32]>,
+{
+    let Some(((first, _), rest)) = phrase.split_first() else {
+        return false;
+    };
+    let Some(starts) = positions_of(first) else {
+        return false;
+    };
+    starts.iter().any(|start| {
+        rest.iter()
+            .all(|(term, offset)| positions_of(term).is_some_and(|p| p.contains(&(start + offset))))
+    })
+}
+
+/// Postings chargés : terme -> (id -> positions)
+type Postings = HashMap<String, HashMap<String, Vec<u32>>>;
 
-    // 2. Ajout des nouveaux tokens
-    if let Some(doc) = new_doc {
-        if let Some(val) = doc.pointer(&def.field_path).and_then(|v| v.as_str()) {
-            let tokens = tokenize(val);
-            for token in tokens {
-                let ids = index.entry(token).or_default();
-                if !ids.contains(&doc_id.to_string()) {
-                    ids.push(doc_id.to_string());
-                    changed = true;
+fn load_terms(path: &Path, terms: &[&String], postings: &mut Postings) -> Result<()> {
+    let missing: Vec<String> = terms
+        .iter()
+        .filter(|t| !postings.contains_key(t.as_str()))
+        .map(|t| t.to_string())
+        .collect();
+    for term in &missing {
+        postings.entry(term.clone()).or_default();
+    }
+    for record in driver::records(path, &missing)? {
+        postings
+            .entry(record.key)
+            .or_default()
+            .insert(record.document_id, record.positions);
+    }
+    Ok(())
+}
+
+/// Recherche classée sur l'index texte `path` : IDs des documents satisfaisant
+/// la requête, par score BM25 décroissant.
+///
+/// Les statistiques du corpus sont lues dans les entrées de longueur (un
+/// parcours de `LENGTH_KEY` par requête). Un index construit avant les
+/// positions reste interrogeable (fréquence 1, longueur moyenne), mais les
+/// phrases n'y trouvent rien tant qu'il n'est pas reconstruit.
+pub fn search(path: &Path, analyzer: TextAnalyzer, query: &str) -> Result<Vec<(String, f64)>> {
+    let query = TextQuery::parse(query, analyzer);
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let lengths: HashMap<String, u32> = driver::records(path, &[LENGTH_KEY.to_string()])?
+        .into_iter()
+        .map(|r| (r.document_id, r.positions.first().copied().unwrap_or(0)))
+        .collect();
+
+    let mut postings = Postings::new();
+    // Termes qui contribuent au score (ceux des préfixes sont développés)
+    let mut scored: Vec<String> = Vec::new();
+    let mut candidates: Option<HashSet<String>> = None;
+
+    for clause in &query.clauses {
+        let matched: HashSet<String> = match clause {
+            TextClause::Term(term) => {
+                load_terms(path, &[term], &mut postings)?;
+                scored.push(term.clone());
+                postings[term].keys().cloned().collect()
+            }
+            TextClause::Prefix(prefix) => {
+                let mut ids = HashSet::new();
+                for record in driver::scan(path, Some(prefix))? {
+                    let record = record?;
+                    if !record.key.starts_with(prefix.as_str()) {
+                        break;
+                    }
+                    ids.insert(record.document_id.clone());
+                    if !scored.contains(&record.key) {
+                        scored.push(record.key.clone());
+                    }
+                    postings
+                        .entry(record.key)
+                        .or_default()
+                        .insert(record.document_id, record.positions);
                 }
+                ids
+            }
+            TextClause::Phrase(terms) => {
+                let keys: Vec<&String> = terms.iter().map(|(t, _)| t).collect();
+                load_terms(path, &keys, &mut postings)?;
+                scored.extend(keys.into_iter().cloned());
+                postings[&terms[0].0]
+                    .keys()
+                    .filter(|id| {
+                        phrase_matches(terms, |t| {
+                            postings
+                                .get(t)
+                                .and_then(|docs| docs.get(*id))
+                                .map(Vec::as_slice)
+                        })
+                    })
+                    .cloned()
+                    .collect()
             }
+        };
+        candidates = Some(match candidates {
+            None => matched,
+            Some(previous) => previous.intersection(&matched).cloned().collect(),
+        });
+    }
+    scored.sort();
+    scored.dedup();
+
+    let avgdl = if lengths.is_empty() {
+        1.0
+    } else {
+        (lengths.values().map(|l| *l as f64).sum::<f64>() / lengths.len() as f64).max(1.0)
+    };
+    let mut hits: Vec<(String, f64)> = candidates
+        .unwrap_or_default()
+        .into_iter()
+        .map(|id| {
+            let dl = lengths.get(&id).map(|l| *l as f64).unwrap_or(avgdl);
+            let score = scored
+                .iter()
+                .filter_map(|term| {
+                    let docs = postings.get(term)?;
+                    let tf = docs.get(&id)?.len().max(1) as f64;
+                    let df = docs.len() as f64;
+                    let n = (lengths.len() as f64).max(df);
+                    let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+                    Some(idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * dl / avgdl)))
+                })
+                .sum();
+            (id, score)
+        })
+        .collect();
+
+    hits.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_db::indexes::IndexType;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn def() -> IndexDefinition {
+        IndexDefinition {
+            name: "title".to_string(),
+            field_path: "/title".to_string(),
+            fields: Vec::new(),
+            index_type: IndexType::Text,
+            unique: false,
+            analyzer: Some(TextAnalyzer::French),
         }
     }
 
-    // 3. Sauvegarde si modification
-    if changed {
-        driver::save(&path, &index)?;
+    fn index(path: &Path, docs: &[(&str, &str)]) {
+        let def = def();
+        for (id, title) in docs {
+            let entries = document_entries(&def, &json!({ "id": id, "title": title }));
+            driver::update_entries(path, id, &[], &entries).unwrap();
+        }
     }
 
-    Ok(())
+    #[test]
+    fn test_query_parsing() {
+        let q = TextQuery::parse(
+            "Exigences \"station de sol\" archi* le",
+            TextAnalyzer::French,
+        );
+        assert_eq!(
+            q.clauses,
+            vec![
+                TextClause::Term("exig".into()),
+                TextClause::Phrase(vec![("station".into(), 0), ("sol".into(), 2)]),
+                TextClause::Prefix("archi".into()),
+            ]
+        );
+        assert!(TextQuery::parse("la le", TextAnalyzer::French).is_empty());
+        assert_eq!(
+            TextQuery::parse("exigence*", TextAnalyzer::French).clauses,
+            vec![TextClause::Prefix("exig".into())]
+        );
+    }
+
+    #[test]
+    fn test_bm25_ranking_and_phrases() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("title.text.idx");
+        index(
+            &path,
+            &[
+                ("r1", "La station de sol reçoit la télémesure"),
+                ("r2", "Le sol de la station est isolé, la station est close"),
+                ("r3", "Exigence de performance du radar de veille aérienne"),
+                (
+                    "r4",
+                    "Exigences radar : le radar détecte, le radar suit les pistes",
+                ),
+            ],
+        );
+
+        // Phrase : l'écart des mots vides est respecté
+        let ids: Vec<String> = search(&path, TextAnalyzer::French, "\"station de sol\"")
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(ids, vec!["r1"]);
+
+        // Tous les termes sont requis ; la fréquence élevée classe r4 en tête
+        let hits = search(&path, TextAnalyzer::French, "exigence radar").unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].0, "r4");
+        assert!(hits[0].1 > hits[1].1);
+
+        // Préfixe sur les racines
+        let hits = search(&path, TextAnalyzer::French, "télé*").unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(search(&path, TextAnalyzer::French, "satellite")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_in_memory_match_agrees_with_index() {
+        let q = TextQuery::parse("\"station sol\" exigence*", TextAnalyzer::French);
+        assert!(q.matches("Exigences de la station sol", TextAnalyzer::French));
+        assert!(!q.matches("Exigences du sol de la station", TextAnalyzer::French));
+    }
 }
@@ -0,0 +1,70 @@
+// FICHIER : src-tauri/src/json_db/migrations/catalog.rs
+
+//! Migrations déclarées : celles de l'application (appliquées au démarrage et
+//! par défaut par `jsondb_cli migrate`) ou un dossier de fichiers JSON.
+
+use super::{Migration, MigrationStep};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Migrations de l'application, avec leurs étapes inverses
+pub fn app_migrations() -> Vec<Migration> {
+    let core = ["articles", "systems", "exchange_items"];
+    vec![
+        Migration {
+            id: "init_001_core_collections".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Création des collections de base Arcadia".to_string(),
+            up: core
+                .iter()
+                .map(|name| MigrationStep::CreateCollection {
+                    name: name.to_string(),
+                    schema: Value::Null,
+                })
+                .collect(),
+            down: core
+                .iter()
+                .map(|name| MigrationStep::DropCollection {
+                    name: name.to_string(),
+                })
+                .collect(),
+            applied_at: None,
+        },
+        Migration {
+            id: "idx_001_articles_title".to_string(),
+            version: "1.1.0".to_string(),
+            description: "Indexation des articles par titre".to_string(),
+            up: vec![MigrationStep::CreateIndex {
+                collection: "articles".to_string(),
+                fields: vec!["title".to_string()],
+                unique: false,
+            }],
+            down: vec![MigrationStep::DropIndex {
+                collection: "articles".to_string(),
+                name: "title".to_string(),
+            }],
+            applied_at: None,
+        },
+    ]
+}
+
+/// Charge les migrations d'un dossier (un fichier `*.json` par migration)
+pub fn load_dir(dir: &Path) -> Result<Vec<Migration>> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("Dossier de migrations illisible : {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            let content = fs::read_to_string(path)?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Migration invalide : {:?}", path))
+        })
+        .collect()
+}
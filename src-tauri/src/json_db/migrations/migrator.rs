@@ -1,13 +1,42 @@
+use super::plan::{MigrationPlan, Rewrite, Workspace};
 use super::version::MigrationVersion;
-use super::{Migration, MigrationStep};
+use super::{Direction, Migration, MigrationStep};
 use crate::json_db::collections::manager::CollectionsManager;
+use crate::json_db::indexes::manager::IndexManager;
 use crate::json_db::storage::StorageEngine;
+use crate::json_db::transactions::manager::TransactionManager;
+use crate::json_db::transactions::revision::RevisionConflict;
+use crate::json_db::transactions::Operation;
 
-// ✅ CORRECTION : On retire 'anyhow' qui n'était pas utilisé, on garde juste 'Result'
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use chrono::Utc;
+use serde::Serialize;
 use serde_json::{json, Value};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+
+const MIGRATIONS_COLLECTION: &str = "_migrations";
+
+/// État d'une migration par rapport à l'historique de la base
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Appliquée, mais modifiée depuis (checksum différent)
+    Modified,
+    /// Appliquée, mais plus déclarée
+    Missing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationStatus {
+    pub id: String,
+    pub version: String,
+    pub description: String,
+    pub state: MigrationState,
+    pub applied_at: Option<String>,
+}
 
 pub struct Migrator<'a> {
     manager: CollectionsManager<'a>,
@@ -22,163 +51,371 @@ impl<'a> Migrator<'a> {
 
     /// Initialise la table de suivi des migrations (_migrations)
     pub fn init(&self) -> Result<()> {
+        // Collection interne : absente de `list_collections`
         let exists = self
             .manager
-            .list_collections()?
-            .contains(&"_migrations".to_string());
+            .storage
+            .config
+            .db_collection_path(&self.manager.space, &self.manager.db, MIGRATIONS_COLLECTION)
+            .join("_meta.json")
+            .exists();
         if !exists {
-            println!("⚙️ Création de la table de suivi des migrations...");
-            self.manager.create_collection("_migrations", None)?;
+            tracing::info!("⚙️ Création de la table de suivi des migrations...");
+            self.manager
+                .create_collection(MIGRATIONS_COLLECTION, None)?;
         }
         Ok(())
     }
 
     /// Exécute les migrations en attente
     pub fn run_migrations(&self, declared_migrations: Vec<Migration>) -> Result<()> {
-        self.init()?;
+        self.migrate_up(&declared_migrations)?;
+        Ok(())
+    }
 
-        // 1. Récupérer les migrations déjà appliquées
-        let applied_docs = self.manager.list_all("_migrations")?;
-        let applied_ids: HashSet<String> = applied_docs
+    /// État de chaque migration déclarée ou enregistrée, par version
+    pub fn status(&self, declared: &[Migration]) -> Result<Vec<MigrationStatus>> {
+        let records = self.applied_records()?;
+        let mut statuses: Vec<MigrationStatus> = declared
             .iter()
-            .filter_map(|doc| {
-                doc.get("id")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
+            .map(|migration| {
+                let record = records.get(&migration.id);
+                let state = match record {
+                    None => MigrationState::Pending,
+                    // Enregistrement antérieur aux checksums : considéré comme conforme
+                    Some(r) => match r.get("checksum").and_then(|v| v.as_str()) {
+                        Some(sum) if sum != migration.checksum() => MigrationState::Modified,
+                        _ => MigrationState::Applied,
+                    },
+                };
+                MigrationStatus {
+                    id: migration.id.clone(),
+                    version: migration.version.clone(),
+                    description: migration.description.clone(),
+                    state,
+                    applied_at: record.and_then(applied_at),
+                }
             })
             .collect();
 
-        // 2. Trier les migrations déclarées par version
-        let mut sorted_migrations = declared_migrations;
-        sorted_migrations.sort_by(|a, b| {
-            let ver_a = MigrationVersion::parse(&a.version)
-                .unwrap_or(MigrationVersion::parse("0.0.0").unwrap());
-            let ver_b = MigrationVersion::parse(&b.version)
-                .unwrap_or(MigrationVersion::parse("0.0.0").unwrap());
-            ver_a.cmp(&ver_b)
-        });
+        let declared_ids: HashSet<&str> = declared.iter().map(|m| m.id.as_str()).collect();
+        for (id, record) in &records {
+            if declared_ids.contains(id.as_str()) {
+                continue;
+            }
+            let text = |field: &str| {
+                record
+                    .get(field)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            statuses.push(MigrationStatus {
+                id: id.clone(),
+                version: text("version"),
+                description: text("description"),
+                state: MigrationState::Missing,
+                applied_at: applied_at(record),
+            });
+        }
+        statuses.sort_by_key(|status| version_of(&status.version));
+        Ok(statuses)
+    }
+
+    /// Effet des migrations en attente, sans rien écrire
+    pub fn plan_up(&self, declared: &[Migration]) -> Result<Vec<MigrationPlan>> {
+        let pending = self.pending(declared)?;
+        let mut workspace = Workspace::new(&self.manager);
+        pending
+            .into_iter()
+            .map(|m| Ok(workspace.simulate(m, Direction::Up)?.0))
+            .collect()
+    }
+
+    /// Applique les migrations en attente, une transaction par migration
+    pub fn migrate_up(&self, declared: &[Migration]) -> Result<Vec<MigrationPlan>> {
+        self.init()?;
+        let mut plans = Vec::new();
+        for migration in self.pending(declared)? {
+            tracing::info!(
+                "🚀 Application de la migration {} ({})",
+                migration.version,
+                migration.description
+            );
+            plans.push(self.apply(migration, Direction::Up)?);
+        }
+        Ok(plans)
+    }
 
-        // 3. Appliquer celles qui manquent
-        for migration in sorted_migrations {
-            if !applied_ids.contains(&migration.id) {
-                println!(
-                    "🚀 Application de la migration {} ({})",
-                    migration.version, migration.description
+    /// Effet du retour à la version `target`, sans rien écrire
+    pub fn plan_down(&self, declared: &[Migration], target: &str) -> Result<Vec<MigrationPlan>> {
+        let rollback = self.rollback(declared, target)?;
+        let mut workspace = Workspace::new(&self.manager);
+        rollback
+            .into_iter()
+            .map(|m| Ok(workspace.simulate(m, Direction::Down)?.0))
+            .collect()
+    }
+
+    /// Annule, de la plus récente à la plus ancienne, les migrations appliquées
+    /// de version supérieure à `target`
+    pub fn migrate_down(&self, declared: &[Migration], target: &str) -> Result<Vec<MigrationPlan>> {
+        let mut plans = Vec::new();
+        for migration in self.rollback(declared, target)? {
+            tracing::info!(
+                "⏪ Annulation de la migration {} ({})",
+                migration.version,
+                migration.description
+            );
+            plans.push(self.apply(migration, Direction::Down)?);
+        }
+        Ok(plans)
+    }
+
+    /// Migrations déclarées non appliquées, par version. Refuse de continuer si
+    /// une migration appliquée a été modifiée depuis.
+    fn pending<'m>(&self, declared: &'m [Migration]) -> Result<Vec<&'m Migration>> {
+        self.check_declared(declared)?;
+        let statuses = self.status(declared)?;
+        let mut pending = Vec::new();
+        for status in &statuses {
+            match status.state {
+                MigrationState::Modified => bail!(
+                    "Migration {} modifiée depuis son application (checksum différent)",
+                    status.id
+                ),
+                MigrationState::Pending => {
+                    pending.extend(declared.iter().find(|m| m.id == status.id));
+                }
+                _ => {}
+            }
+        }
+        Ok(pending)
+    }
+
+    /// Migrations à annuler pour revenir à `target`, de la plus récente à la
+    /// plus ancienne. Toutes sont vérifiées avant la première annulation.
+    fn rollback<'m>(&self, declared: &'m [Migration], target: &str) -> Result<Vec<&'m Migration>> {
+        self.check_declared(declared)?;
+        let target = MigrationVersion::parse(target).map_err(|e| anyhow!(e))?;
+        let mut rollback = Vec::new();
+        for status in self.status(declared)?.iter().rev() {
+            if version_of(&status.version) <= target || status.state == MigrationState::Pending {
+                continue;
+            }
+            let migration = match status.state {
+                MigrationState::Missing => bail!(
+                    "Migration {} appliquée mais non déclarée : étapes d'annulation inconnues",
+                    status.id
+                ),
+                MigrationState::Modified => bail!(
+                    "Migration {} modifiée depuis son application (checksum différent)",
+                    status.id
+                ),
+                _ => declared
+                    .iter()
+                    .find(|m| m.id == status.id)
+                    .expect("migration déclarée"),
+            };
+            if migration.down.is_empty() && !migration.up.is_empty() {
+                bail!(
+                    "Migration {} irréversible (aucune étape down)",
+                    migration.id
                 );
-                self.apply_migration(&migration)?;
             }
+            rollback.push(migration);
         }
+        Ok(rollback)
+    }
 
+    fn check_declared(&self, declared: &[Migration]) -> Result<()> {
+        let mut ids = HashSet::new();
+        for migration in declared {
+            if !ids.insert(migration.id.as_str()) {
+                bail!("Migration déclarée deux fois : {}", migration.id);
+            }
+        }
         Ok(())
     }
 
-    fn apply_migration(&self, migration: &Migration) -> Result<()> {
-        // Exécution atomique des étapes (Up)
-        for step in &migration.up {
-            self.execute_step(step)?;
+    fn applied_records(&self) -> Result<HashMap<String, Value>> {
+        let path = self.manager.storage.config.db_collection_path(
+            &self.manager.space,
+            &self.manager.db,
+            MIGRATIONS_COLLECTION,
+        );
+        if !path.exists() {
+            return Ok(HashMap::new());
         }
+        Ok(self
+            .manager
+            .list_all(MIGRATIONS_COLLECTION)?
+            .into_iter()
+            .filter_map(|doc| {
+                let id = doc.get("id").and_then(|v| v.as_str())?.to_string();
+                Some((id, doc))
+            })
+            .collect())
+    }
 
-        // Enregistrement du succès
-        let record = json!({
-            "id": migration.id,
-            "version": migration.version,
-            "description": migration.description,
-            "appliedAt": Utc::now().to_rfc3339()
-        });
+    /// Applique une migration : étapes structurelles (collections, index), puis
+    /// documents réécrits et historique dans une seule transaction ; les
+    /// collections supprimées le sont une fois la transaction validée. En cas
+    /// d'échec, la migration n'est pas enregistrée et peut être rejouée.
+    fn apply(&self, migration: &Migration, direction: Direction) -> Result<MigrationPlan> {
+        let mut workspace = Workspace::new(&self.manager);
+        let (plan, writes) = workspace.simulate(migration, direction)?;
 
-        self.manager.insert_raw("_migrations", &record)?;
+        for step in migration.steps(direction) {
+            self.execute_structural(step, &workspace)?;
+        }
 
-        Ok(())
+        self.commit(migration, direction, writes)?;
+
+        for step in migration.steps(direction) {
+            if let MigrationStep::DropCollection { name } = step {
+                if workspace.is_dropped(name) && self.collection_exists(name) {
+                    self.manager.drop_collection(name)?;
+                    tracing::info!("   -> Collection supprimée : {}", name);
+                }
+            }
+        }
+        for diff in &plan.collections {
+            if !diff.documents.is_empty() {
+                tracing::info!(
+                    "   -> {} : {} document(s) réécrit(s)",
+                    diff.collection,
+                    diff.documents.len()
+                );
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Documents réécrits et entrée `_migrations`, en une transaction. Chaque
+    /// document est remplacé à la révision lue par la simulation : s'il a été
+    /// modifié depuis, la transaction échoue et la migration n'est pas enregistrée.
+    fn commit(
+        &self,
+        migration: &Migration,
+        direction: Direction,
+        writes: Vec<Rewrite>,
+    ) -> Result<()> {
+        let storage = self.manager.storage;
+        let tx_mgr =
+            TransactionManager::new(&storage.config, &self.manager.space, &self.manager.db);
+        tx_mgr
+            .execute(|tx| {
+                for (collection, id, revision, doc) in writes {
+                    tx.operations.push(Operation::Replace {
+                        collection,
+                        id,
+                        revision,
+                        document: Some(doc),
+                    });
+                }
+                match direction {
+                    Direction::Up => {
+                        let record = json!({
+                            "id": migration.id,
+                            "version": migration.version,
+                            "description": migration.description,
+                            "checksum": migration.checksum(),
+                            "appliedAt": Utc::now().to_rfc3339()
+                        });
+                        tx.add_insert(MIGRATIONS_COLLECTION, &migration.id, record);
+                    }
+                    Direction::Down => tx.operations.push(Operation::Delete {
+                        collection: MIGRATIONS_COLLECTION.to_string(),
+                        id: migration.id.clone(),
+                    }),
+                }
+                Ok(())
+            })
+            .map_err(|e| {
+                if e.is::<RevisionConflict>() {
+                    e.context(format!(
+                        "Migration {} : document modifié, à relancer",
+                        migration.id
+                    ))
+                } else {
+                    e
+                }
+            })
     }
 
-    fn execute_step(&self, step: &MigrationStep) -> Result<()> {
+    /// Étapes hors documents, rejouables sans effet
+    fn execute_structural(&self, step: &MigrationStep, workspace: &Workspace) -> Result<()> {
         match step {
             MigrationStep::CreateCollection { name, schema } => {
-                let schema_str = schema.as_str().map(|s| s.to_string());
-                self.manager.create_collection(name, schema_str)?;
-                println!("   -> Collection créée : {}", name);
-            }
-            MigrationStep::DropCollection { name } => {
-                self.manager.drop_collection(name)?;
-                println!("   -> Collection supprimée : {}", name);
-            }
-            MigrationStep::CreateIndex { collection, fields } => {
-                if let Some(field) = fields.first() {
-                    self.manager.create_index(collection, field, "btree")?;
-                    println!("   -> Index créé sur {}::{}", collection, field);
+                // Une collection recréée puis supprimée dans la même migration est ignorée
+                if !workspace.is_dropped(name) {
+                    let schema_str = schema.as_str().map(|s| s.to_string());
+                    self.manager.create_collection(name, schema_str)?;
+                    tracing::info!("   -> Collection créée : {}", name);
                 }
             }
-            MigrationStep::DropIndex { collection, name } => {
-                self.manager.drop_index(collection, name)?;
-                println!("   -> Index supprimé sur {}::{}", collection, name);
-            }
-            MigrationStep::AddField {
+            MigrationStep::CreateIndex {
                 collection,
-                field,
-                default,
+                fields,
+                unique,
             } => {
-                self.transform_all_documents(collection, |doc| {
-                    if let Some(obj) = doc.as_object_mut() {
-                        if !obj.contains_key(field) {
-                            obj.insert(field.clone(), default.clone().unwrap_or(Value::Null));
-                            return true;
-                        }
-                    }
-                    false
-                })?;
-                println!("   -> Champ ajouté : {}::{}", collection, field);
-            }
-            MigrationStep::RemoveField { collection, field } => {
-                self.transform_all_documents(collection, |doc| {
-                    if let Some(obj) = doc.as_object_mut() {
-                        if obj.remove(field).is_some() {
-                            return true;
-                        }
-                    }
-                    false
-                })?;
-                println!("   -> Champ supprimé : {}::{}", collection, field);
+                if !self.index_exists(collection, &fields.join("_"))? {
+                    let fields: Vec<&str> = fields.iter().map(|f| f.as_str()).collect();
+                    self.manager
+                        .create_index_on(collection, &fields, "btree", *unique)?;
+                    tracing::info!(
+                        "   -> Index créé sur {}::({})",
+                        collection,
+                        fields.join(", ")
+                    );
+                }
             }
-            MigrationStep::RenameField {
-                collection,
-                old_name,
-                new_name,
-            } => {
-                self.transform_all_documents(collection, |doc| {
-                    if let Some(obj) = doc.as_object_mut() {
-                        if let Some(val) = obj.remove(old_name) {
-                            obj.insert(new_name.clone(), val);
-                            return true;
-                        }
-                    }
-                    false
-                })?;
-                println!(
-                    "   -> Champ renommé : {}::{} -> {}",
-                    collection, old_name, new_name
-                );
+            MigrationStep::DropIndex { collection, name } => {
+                if !self.index_exists(collection, name)? {
+                    return Ok(());
+                }
+                self.manager.drop_index(collection, name)?;
+                tracing::info!("   -> Index supprimé sur {}::{}", collection, name);
             }
+            _ => {}
         }
         Ok(())
     }
 
-    fn transform_all_documents<F>(&self, collection: &str, mut transformer: F) -> Result<()>
-    where
-        F: FnMut(&mut Value) -> bool,
-    {
-        let docs = self.manager.list_all(collection)?;
-
-        for mut doc in docs {
-            let id = doc.get("id").and_then(|v| v.as_str()).unwrap().to_string();
+    fn collection_exists(&self, name: &str) -> bool {
+        self.manager
+            .storage
+            .config
+            .db_collection_path(&self.manager.space, &self.manager.db, name)
+            .exists()
+    }
 
-            if transformer(&mut doc) {
-                self.manager.update_document(collection, &id, doc)?;
-            }
+    fn index_exists(&self, collection: &str, name: &str) -> Result<bool> {
+        if !self.collection_exists(collection) {
+            return Ok(false);
         }
-        Ok(())
+        let idx_mgr =
+            IndexManager::new(self.manager.storage, &self.manager.space, &self.manager.db);
+        Ok(idx_mgr
+            .load_indexes(collection)?
+            .iter()
+            .any(|index| index.name == name))
     }
 }
 
+/// Version triable (`0.0.0` si invalide)
+fn version_of(version: &str) -> MigrationVersion {
+    MigrationVersion::parse(version).unwrap_or(MigrationVersion::parse("0.0.0").unwrap())
+}
+
+fn applied_at(record: &Value) -> Option<String> {
+    record
+        .get("appliedAt")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -304,4 +541,189 @@ mod tests {
         assert!(doc.get("cost").is_none());
         assert_eq!(doc["price"], 100);
     }
+
+    fn add_field(id: &str, version: &str, field: &str) -> Migration {
+        Migration {
+            id: id.to_string(),
+            version: version.to_string(),
+            description: format!("Ajout de {}", field),
+            up: vec![MigrationStep::AddField {
+                collection: "products".to_string(),
+                field: field.to_string(),
+                default: Some(json!(0)),
+            }],
+            down: vec![MigrationStep::RemoveField {
+                collection: "products".to_string(),
+                field: field.to_string(),
+            }],
+            applied_at: None,
+        }
+    }
+
+    fn products(migrator: &Migrator) {
+        migrator
+            .manager
+            .create_collection("products", None)
+            .unwrap();
+        migrator
+            .manager
+            .insert_raw("products", &json!({"id": "p1", "cost": 100}))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_status_and_checksum() {
+        let (storage, _dir) = create_test_env();
+        let migrator = Migrator::new(&storage, "space", "db");
+        products(&migrator);
+
+        let mut declared = vec![
+            add_field("m1", "1.0.0", "stock"),
+            add_field("m2", "1.1.0", "vat"),
+        ];
+        migrator.migrate_up(&declared[..1]).unwrap();
+
+        let states: Vec<MigrationState> = migrator
+            .status(&declared)
+            .unwrap()
+            .iter()
+            .map(|s| s.state)
+            .collect();
+        assert_eq!(
+            states,
+            vec![MigrationState::Applied, MigrationState::Pending]
+        );
+
+        // Migration appliquée puis éditée : détectée et bloquante
+        declared[0].description = "Ajout du stock".to_string();
+        let status = migrator.status(&declared).unwrap();
+        assert_eq!(status[0].state, MigrationState::Modified);
+        assert!(migrator.migrate_up(&declared).is_err());
+
+        let status = migrator.status(&declared[1..]).unwrap();
+        assert_eq!(status[0].state, MigrationState::Missing);
+        assert_eq!(status[0].version, "1.0.0");
+        assert_eq!(status[1].state, MigrationState::Pending);
+    }
+
+    #[test]
+    fn test_dry_run_reports_diff_without_writing() {
+        let (storage, _dir) = create_test_env();
+        let migrator = Migrator::new(&storage, "space", "db");
+        products(&migrator);
+
+        let rename = Migration {
+            id: "rename".to_string(),
+            version: "1.1.0".to_string(),
+            description: "Renommage".to_string(),
+            up: vec![MigrationStep::RenameField {
+                collection: "products".to_string(),
+                old_name: "cost".to_string(),
+                new_name: "price".to_string(),
+            }],
+            down: vec![],
+            applied_at: None,
+        };
+        // Les migrations du lot s'enchaînent : le renommage voit le champ ajouté
+        let declared = vec![add_field("m1", "1.0.0", "stock"), rename];
+        let plans = migrator.plan_up(&declared).unwrap();
+        assert_eq!(plans.len(), 2);
+        let diff = &plans[1].collections[0];
+        assert_eq!(diff.collection, "products");
+        assert_eq!(diff.documents[0].id, "p1");
+        let fields: Vec<&str> = diff.documents[0]
+            .changes
+            .iter()
+            .map(|c| c.field.as_str())
+            .collect();
+        assert_eq!(fields, vec!["cost", "price"]);
+
+        let doc = migrator.manager.get("products", "p1").unwrap().unwrap();
+        assert_eq!(doc, json!({"id": "p1", "cost": 100}));
+        assert!(migrator.applied_records().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_document_changed_after_simulation_aborts_migration() {
+        let (storage, _dir) = create_test_env();
+        let migrator = Migrator::new(&storage, "space", "db");
+        products(&migrator);
+        let migration = add_field("m1", "1.0.0", "stock");
+
+        let (_, writes) = Workspace::new(&migrator.manager)
+            .simulate(&migration, Direction::Up)
+            .unwrap();
+        migrator
+            .manager
+            .update_document("products", "p1", json!({"cost": 120}))
+            .unwrap();
+
+        // Réécriture à partir d'une lecture périmée : refusée, rien d'enregistré
+        let err = migrator
+            .commit(&migration, Direction::Up, writes)
+            .unwrap_err();
+        assert!(err.is::<RevisionConflict>());
+        let doc = migrator.manager.get("products", "p1").unwrap().unwrap();
+        assert_eq!(doc["cost"], 120);
+        assert!(doc.get("stock").is_none());
+        assert!(migrator.applied_records().unwrap().is_empty());
+
+        // Relancée, la migration part de l'état courant
+        migrator.migrate_up(&[migration]).unwrap();
+        let doc = migrator.manager.get("products", "p1").unwrap().unwrap();
+        assert_eq!((&doc["cost"], &doc["stock"]), (&json!(120), &json!(0)));
+        assert_eq!(doc["_rev"], 2);
+    }
+
+    #[test]
+    fn test_migrate_down_reverts_applied_migrations() {
+        let (storage, _dir) = create_test_env();
+        let migrator = Migrator::new(&storage, "space", "db");
+        products(&migrator);
+
+        let index = Migration {
+            id: "idx".to_string(),
+            version: "1.2.0".to_string(),
+            description: "Index stock".to_string(),
+            up: vec![MigrationStep::CreateIndex {
+                collection: "products".to_string(),
+                fields: vec!["stock".to_string()],
+                unique: false,
+            }],
+            down: vec![MigrationStep::DropIndex {
+                collection: "products".to_string(),
+                name: "stock".to_string(),
+            }],
+            applied_at: None,
+        };
+        let declared = vec![
+            add_field("m1", "1.0.0", "stock"),
+            add_field("m2", "1.1.0", "vat"),
+            index,
+        ];
+        migrator.migrate_up(&declared).unwrap();
+        // Rejouer ne fait rien
+        assert!(migrator.migrate_up(&declared).unwrap().is_empty());
+
+        let plans = migrator.migrate_down(&declared, "1.0.0").unwrap();
+        let ids: Vec<&str> = plans.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["idx", "m2"]);
+
+        let doc = migrator.manager.get("products", "p1").unwrap().unwrap();
+        assert_eq!(doc["stock"], 0);
+        assert!(doc.get("vat").is_none());
+        let idx_mgr = IndexManager::new(&storage, "space", "db");
+        assert!(idx_mgr.load_indexes("products").unwrap().is_empty());
+        let applied = migrator.applied_records().unwrap();
+        assert_eq!(applied.len(), 1);
+        assert!(applied.contains_key("m1"));
+
+        // Migration sans étapes d'annulation : refusée avant toute écriture
+        let mut irreversible = add_field("m3", "1.3.0", "weight");
+        irreversible.down.clear();
+        let declared = vec![declared[0].clone(), irreversible];
+        migrator.migrate_up(&declared).unwrap();
+        assert!(migrator.migrate_down(&declared, "0.0.0").is_err());
+        assert_eq!(migrator.applied_records().unwrap().len(), 2);
+    }
 }
@@ -1,9 +1,13 @@
 //! Système de migrations de schémas
 
+pub mod catalog;
 pub mod migrator;
+pub mod plan;
 pub mod version;
 
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Migration {
@@ -11,10 +15,42 @@ pub struct Migration {
     pub version: String,
     pub description: String,
     pub up: Vec<MigrationStep>,
+    /// Étapes inverses de `up`, exécutées par `Migrator::migrate_down`
+    #[serde(default)]
     pub down: Vec<MigrationStep>,
     pub applied_at: Option<i64>,
 }
 
+impl Migration {
+    /// Empreinte SHA-256 du contenu (hors `applied_at`), enregistrée à
+    /// l'application : une migration modifiée ensuite est détectée
+    pub fn checksum(&self) -> String {
+        let content = json!({
+            "id": self.id,
+            "version": self.version,
+            "description": self.description,
+            "up": self.up,
+            "down": self.down,
+        });
+        format!("{:x}", Sha256::digest(content.to_string().as_bytes()))
+    }
+
+    pub fn steps(&self, direction: Direction) -> &[MigrationStep] {
+        match direction {
+            Direction::Up => &self.up,
+            Direction::Down => &self.down,
+        }
+    }
+}
+
+/// Sens d'exécution d'une migration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Up,
+    Down,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MigrationStep {
     CreateCollection {
@@ -38,12 +74,30 @@ pub enum MigrationStep {
         old_name: String,
         new_name: String,
     },
+    /// Plusieurs champs = index composite (ex: `["layer", "name"]`)
     CreateIndex {
         collection: String,
         fields: Vec<String>,
+        #[serde(default)]
+        unique: bool,
     },
     DropIndex {
         collection: String,
         name: String,
     },
 }
+
+impl MigrationStep {
+    /// Collection touchée par l'étape
+    pub fn collection(&self) -> &str {
+        match self {
+            MigrationStep::CreateCollection { name, .. }
+            | MigrationStep::DropCollection { name } => name,
+            MigrationStep::AddField { collection, .. }
+            | MigrationStep::RemoveField { collection, .. }
+            | MigrationStep::RenameField { collection, .. }
+            | MigrationStep::CreateIndex { collection, .. }
+            | MigrationStep::DropIndex { collection, .. } => collection,
+        }
+    }
+}
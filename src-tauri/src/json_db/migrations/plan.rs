@@ -0,0 +1,269 @@
+// FICHIER : src-tauri/src/json_db/migrations/plan.rs
+
+//! Simulation d'une migration en mémoire : documents réécrits par les étapes
+//! et différences champ par champ, sans rien écrire (`--dry-run`).
+
+use super::{Direction, Migration, MigrationStep};
+use crate::json_db::collections::manager::CollectionsManager;
+use crate::json_db::transactions::revision;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Effet prévu d'une migration
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationPlan {
+    pub id: String,
+    pub version: String,
+    pub description: String,
+    pub direction: Direction,
+    pub collections: Vec<CollectionDiff>,
+}
+
+impl MigrationPlan {
+    /// Nombre de documents réécrits
+    pub fn documents(&self) -> usize {
+        self.collections.iter().map(|c| c.documents.len()).sum()
+    }
+}
+
+/// Effet prévu sur une collection
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionDiff {
+    pub collection: String,
+    /// Étapes touchant la collection, dans l'ordre
+    pub operations: Vec<String>,
+    /// Collection supprimée par la migration
+    pub dropped: bool,
+    pub documents: Vec<DocumentDiff>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentDiff {
+    pub id: String,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Champ de premier niveau modifié (`None` : absent)
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// Document à réécrire : `(collection, id, révision lue, document complet)`
+pub(crate) type Rewrite = (String, String, u64, Value);
+
+/// Collection simulée (`None` : absente)
+type Documents = Option<BTreeMap<String, Value>>;
+
+/// État simulé des collections touchées, chargées à la demande. Les
+/// migrations d'un même lot s'enchaînent sur cet état.
+pub(crate) struct Workspace<'m, 'a> {
+    manager: &'m CollectionsManager<'a>,
+    collections: HashMap<String, Documents>,
+}
+
+impl<'m, 'a> Workspace<'m, 'a> {
+    pub fn new(manager: &'m CollectionsManager<'a>) -> Self {
+        Self {
+            manager,
+            collections: HashMap::new(),
+        }
+    }
+
+    fn collection(&mut self, name: &str) -> Result<&mut Documents> {
+        if !self.collections.contains_key(name) {
+            let path = self.manager.storage.config.db_collection_path(
+                &self.manager.space,
+                &self.manager.db,
+                name,
+            );
+            let docs = if path.join("_meta.json").exists() {
+                let mut docs = BTreeMap::new();
+                for doc in self.manager.list_all(name)? {
+                    if let Some(id) = doc.get("id").and_then(|v| v.as_str()) {
+                        docs.insert(id.to_string(), doc);
+                    }
+                }
+                Some(docs)
+            } else {
+                None
+            };
+            self.collections.insert(name.to_string(), docs);
+        }
+        Ok(self.collections.get_mut(name).expect("collection chargée"))
+    }
+
+    /// Rejoue les étapes de `migration` dans le sens `direction`
+    pub fn simulate(
+        &mut self,
+        migration: &Migration,
+        direction: Direction,
+    ) -> Result<(MigrationPlan, Vec<Rewrite>)> {
+        let steps = migration.steps(direction);
+        let touched: BTreeSet<&str> = steps.iter().map(MigrationStep::collection).collect();
+        let mut before = HashMap::new();
+        for name in &touched {
+            before.insert(*name, self.collection(name)?.clone());
+        }
+
+        let mut operations: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for step in steps {
+            let name = step.collection();
+            let docs = self.collection(name)?;
+            let operation = match step {
+                MigrationStep::CreateCollection { .. } => {
+                    if docs.is_none() {
+                        *docs = Some(BTreeMap::new());
+                        "création de la collection".to_string()
+                    } else {
+                        "création de la collection (déjà présente)".to_string()
+                    }
+                }
+                MigrationStep::DropCollection { .. } => match docs.take() {
+                    Some(dropped) => {
+                        format!("suppression de la collection ({} documents)", dropped.len())
+                    }
+                    None => "suppression de la collection (déjà absente)".to_string(),
+                },
+                MigrationStep::CreateIndex { fields, unique, .. } => format!(
+                    "création de l'index{} ({})",
+                    if *unique { " unique" } else { "" },
+                    fields.join(", ")
+                ),
+                MigrationStep::DropIndex { name, .. } => format!("suppression de l'index {}", name),
+                MigrationStep::AddField { field, .. } => {
+                    transform(docs, name, step)?;
+                    format!("ajout du champ '{}'", field)
+                }
+                MigrationStep::RemoveField { field, .. } => {
+                    transform(docs, name, step)?;
+                    format!("suppression du champ '{}'", field)
+                }
+                MigrationStep::RenameField {
+                    old_name, new_name, ..
+                } => {
+                    transform(docs, name, step)?;
+                    format!("renommage du champ '{}' en '{}'", old_name, new_name)
+                }
+            };
+            operations.entry(name).or_default().push(operation);
+        }
+
+        let mut writes = Vec::new();
+        let mut collections = Vec::new();
+        for (name, operations) in operations {
+            let initial = before.remove(name).flatten();
+            let current = self.collections.get(name).cloned().flatten();
+            let dropped = initial.is_some() && current.is_none();
+            let mut documents = Vec::new();
+            if let (Some(initial), Some(current)) = (&initial, &current) {
+                for (id, doc) in current {
+                    let old = initial.get(id);
+                    if old == Some(doc) {
+                        continue;
+                    }
+                    documents.push(DocumentDiff {
+                        id: id.clone(),
+                        changes: field_changes(old, doc),
+                    });
+                    writes.push((
+                        name.to_string(),
+                        id.clone(),
+                        old.map(revision::revision_of).unwrap_or(0),
+                        doc.clone(),
+                    ));
+                }
+            }
+            collections.push(CollectionDiff {
+                collection: name.to_string(),
+                operations,
+                dropped,
+                documents,
+            });
+        }
+
+        let plan = MigrationPlan {
+            id: migration.id.clone(),
+            version: migration.version.clone(),
+            description: migration.description.clone(),
+            direction,
+            collections,
+        };
+        Ok((plan, writes))
+    }
+
+    /// Collection absente à l'issue de la simulation
+    pub fn is_dropped(&self, name: &str) -> bool {
+        matches!(self.collections.get(name), Some(None))
+    }
+}
+
+/// Applique une étape de champ à tous les documents de la collection
+fn transform(docs: &mut Documents, name: &str, step: &MigrationStep) -> Result<()> {
+    let docs = docs
+        .as_mut()
+        .ok_or_else(|| anyhow!("Collection introuvable : {}", name))?;
+    for doc in docs.values_mut() {
+        let Some(obj) = doc.as_object_mut() else {
+            continue;
+        };
+        match step {
+            MigrationStep::AddField { field, default, .. } => {
+                if !obj.contains_key(field) {
+                    obj.insert(field.clone(), default.clone().unwrap_or(Value::Null));
+                }
+            }
+            MigrationStep::RemoveField { field, .. } => {
+                obj.remove(field);
+            }
+            MigrationStep::RenameField {
+                old_name, new_name, ..
+            } => {
+                if let Some(val) = obj.remove(old_name) {
+                    obj.insert(new_name.clone(), val);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn field_changes(before: Option<&Value>, after: &Value) -> Vec<FieldChange> {
+    let empty = serde_json::Map::new();
+    let old = before.and_then(|d| d.as_object()).unwrap_or(&empty);
+    let new = after.as_object().unwrap_or(&empty);
+    let fields: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    fields
+        .into_iter()
+        .filter(|f| old.get(*f) != new.get(*f))
+        .map(|f| FieldChange {
+            field: f.clone(),
+            before: old.get(f).cloned(),
+            after: new.get(f).cloned(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_field_changes_lists_top_level_differences() {
+        let before = json!({"id": "p1", "cost": 100, "name": "A"});
+        let after = json!({"id": "p1", "price": 100, "name": "A"});
+        let changes = field_changes(Some(&before), &after);
+        let fields: Vec<&str> = changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["cost", "price"]);
+        assert_eq!(changes[0].before, Some(json!(100)));
+        assert!(changes[0].after.is_none());
+    }
+}
@@ -1,10 +1,13 @@
 // FICHIER : src-tauri/src/json_db/mod.rs
 
+pub mod backup;
+pub mod changes;
 pub mod collections;
 pub mod indexes;
 pub mod jsonld;
 pub mod migrations;
 pub mod query;
+pub mod replication;
 pub mod schema;
 pub mod storage;
 pub mod transactions;
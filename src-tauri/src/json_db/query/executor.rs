@@ -1,15 +1,26 @@
 // FICHIER : src-tauri/src/json_db/query/executor.rs
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
 use serde_json::Value;
 use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 
 use crate::json_db::collections::manager::CollectionsManager;
+use crate::json_db::indexes::text::TextQuery;
+use crate::json_db::indexes::{IndexManager, TextAnalyzer};
 use crate::json_db::query::{
-    optimizer::QueryOptimizer, ComparisonOperator, Condition, FilterOperator, Projection, Query,
-    QueryFilter, QueryResult, SortField, SortOrder,
+    optimizer::{IndexPlan, IndexStrategy, QueryOptimizer},
+    Aggregate, AggregateFunction, Aggregation, AggregationResult, ComparisonOperator, Condition,
+    ExecutionStats, ExplainMode, FilterOperator, Join, JoinKind, Projection, Query, QueryFilter,
+    QueryResult, SortField, SortOrder,
 };
 
+/// Requêtes `Matches` du filtre, analysées une fois par requête :
+/// (champ, texte de la requête) -> analyseur et requête analysée
+type TextMatchers = HashMap<(String, String), (TextAnalyzer, TextQuery)>;
+
 pub struct QueryEngine<'a> {
     manager: &'a CollectionsManager<'a>,
 }
@@ -19,17 +30,77 @@ impl<'a> QueryEngine<'a> {
         Self { manager }
     }
 
-    pub async fn execute_query(&self, mut query: Query) -> Result<QueryResult> {
-        let optimizer = QueryOptimizer::new();
+    /// Exécute la requête sur un état figé de la base : le snapshot du manager,
+    /// sinon un snapshot ouvert pour la durée de la requête (chargement,
+    /// jointures et sous-requêtes voient alors tous le même état)
+    pub async fn execute_query(&self, query: Query) -> Result<QueryResult> {
+        if self.manager.active_snapshot().is_some() {
+            return self.run_query(query).await;
+        }
+        let manager = self.manager.snapshot()?;
+        QueryEngine::new(&manager).run_query(query).await
+    }
+
+    async fn run_query(&self, mut query: Query) -> Result<QueryResult> {
+        // Sous-requêtes (IN (SELECT ...)) : exécutées d'abord, leur résultat
+        // devient une liste de valeurs
+        if let Some(filter) = &mut query.filter {
+            self.resolve_subqueries(filter).await?;
+        }
+
+        let idx_mgr = self.index_manager();
+        // Un _meta.json illisible ne doit pas empêcher la requête : on retombe sur un scan
+        let indexes = idx_mgr.load_indexes(&query.collection).unwrap_or_default();
+
+        let optimizer = QueryOptimizer::new().with_indexes(indexes);
         // On remplace la requête brute par sa version optimisée
         query = optimizer.optimize(query)?;
 
-        // 1. Chargement (Optimisable plus tard avec des streams/iterateurs)
-        let mut documents = self.manager.list_all(&query.collection)?;
+        // EXPLAIN : on retourne le plan sans toucher aux données
+        if query.explain == Some(ExplainMode::Plan) {
+            let plan = optimizer.explain(&query);
+            return Ok(QueryResult {
+                documents: Vec::new(),
+                total_count: 0,
+                offset: query.offset,
+                limit: query.limit,
+                plan: Some(plan),
+                scores: None,
+                aggregation: None,
+                affected: None,
+            });
+        }
+
+        let started = Instant::now();
+
+        // 1. Chargement : via index si possible, sinon scan complet.
+        // Une recherche plein texte charge les documents par pertinence décroissante.
+        let mut scores = HashMap::new();
+        let mut documents = match optimizer.select_index(&query) {
+            Some(plan) => self.load_from_index(&idx_mgr, &query.collection, &plan, &mut scores)?,
+            None => self.manager.list_all(&query.collection)?,
+        };
+        let documents_scanned = documents.len();
+
+        // 1 bis. Jointures (avant le filtre, qui peut porter sur les champs joints)
+        if !query.joins.is_empty() {
+            documents = self.apply_joins(documents, &query.joins)?;
+        }
 
-        // 2. Filtrage
+        // 2. Filtrage (les conditions indexées sont ré-évaluées, l'index peut être en retard)
         if let Some(filter) = &query.filter {
-            documents.retain(|doc| self.evaluate_filter(doc, filter));
+            let text = self.text_matchers(&optimizer, filter);
+            documents.retain(|doc| self.evaluate_filter(doc, filter, &text));
+        }
+        let documents_matched = documents.len();
+
+        // 2 bis. Agrégation : les lignes agrégées remplacent les documents pour
+        // le tri et la pagination
+        let mut columns = None;
+        if let Some(aggregation) = &query.aggregation {
+            let result = self.aggregate(aggregation, &documents);
+            columns = Some(result.columns);
+            documents = result.rows;
         }
 
         // 3. Tri
@@ -44,6 +115,56 @@ impl<'a> QueryEngine<'a> {
         // 4. Pagination
         let mut paged_docs: Vec<Value> = documents.into_iter().skip(offset).take(limit).collect();
 
+        // EXPLAIN ANALYZE : plan enrichi des mesures réelles, sans les documents
+        if query.explain == Some(ExplainMode::Analyze) {
+            let mut plan = optimizer.explain(&query);
+            plan.execution = Some(ExecutionStats {
+                documents_scanned,
+                documents_matched,
+                documents_returned: paged_docs.len(),
+                elapsed_ms: started.elapsed().as_secs_f64() * 1000.0,
+            });
+            return Ok(QueryResult {
+                documents: Vec::new(),
+                total_count,
+                offset: Some(offset),
+                limit: Some(limit),
+                plan: Some(plan),
+                scores: None,
+                aggregation: None,
+                affected: None,
+            });
+        }
+
+        if let Some(columns) = columns {
+            return Ok(QueryResult {
+                documents: Vec::new(),
+                total_count,
+                offset: Some(offset),
+                limit: Some(limit),
+                plan: None,
+                scores: None,
+                aggregation: Some(AggregationResult {
+                    columns,
+                    rows: paged_docs,
+                }),
+                affected: None,
+            });
+        }
+
+        // Scores BM25, alignés sur les documents retournés (avant projection)
+        let scores = (!scores.is_empty()).then(|| {
+            paged_docs
+                .iter()
+                .map(|doc| {
+                    doc.get("id")
+                        .and_then(|v| v.as_str())
+                        .and_then(|id| scores.get(id).copied())
+                        .unwrap_or(0.0)
+                })
+                .collect()
+        });
+
         // 5. PROJECTION (Selection des champs)
         if let Some(projection) = &query.projection {
             for doc in &mut paged_docs {
@@ -56,9 +177,242 @@ impl<'a> QueryEngine<'a> {
             total_count,
             offset: Some(offset),
             limit: Some(limit),
+            plan: None,
+            scores,
+            aggregation: None,
+            affected: None,
         })
     }
 
+    /// Remplace la valeur de chaque condition `InSubquery` / `NotInSubquery`
+    /// par les valeurs du champ projeté par la sous-requête (les tableaux sont
+    /// aplatis, les `null` ignorés).
+    async fn resolve_subqueries(&self, filter: &mut QueryFilter) -> Result<()> {
+        for cond in &mut filter.conditions {
+            if !matches!(
+                cond.operator,
+                ComparisonOperator::InSubquery | ComparisonOperator::NotInSubquery
+            ) || cond.value.is_array()
+            {
+                continue;
+            }
+            let mut sub = Query::deserialize(&cond.value)
+                .with_context(|| format!("Sous-requête invalide sur '{}'", cond.field))?;
+            let field = match sub.projection.take() {
+                Some(Projection::Include(fields)) if fields.len() == 1 => fields[0].clone(),
+                _ => bail!(
+                    "La sous-requête sur '{}' doit projeter exactement un champ",
+                    cond.field
+                ),
+            };
+
+            let result = Box::pin(self.execute_query(sub)).await?;
+            let mut values = Vec::new();
+            for doc in &result.documents {
+                match self.get_field_value(doc, &field) {
+                    Some(Value::Array(items)) => values.extend(items.iter().cloned()),
+                    Some(Value::Null) | None => {}
+                    Some(v) => values.push(v.clone()),
+                }
+            }
+            values.dedup();
+            cond.value = Value::Array(values);
+        }
+        for sub in &mut filter.filters {
+            Box::pin(self.resolve_subqueries(sub)).await?;
+        }
+        Ok(())
+    }
+
+    /// Hash join : chaque collection jointe est indexée en mémoire par la valeur
+    /// de `foreign_field`, puis chaque ligne est sondée avec `local_field`.
+    fn apply_joins(&self, mut rows: Vec<Value>, joins: &[Join]) -> Result<Vec<Value>> {
+        for join in joins {
+            let foreign = self.manager.list_all(&join.collection)?;
+            let mut table: HashMap<String, Vec<usize>> = HashMap::new();
+            for (i, doc) in foreign.iter().enumerate() {
+                for key in join_keys(self.get_field_value(doc, &join.foreign_field)) {
+                    let bucket = table.entry(key).or_default();
+                    if bucket.last() != Some(&i) {
+                        bucket.push(i);
+                    }
+                }
+            }
+
+            let mut joined = Vec::with_capacity(rows.len());
+            for row in rows {
+                let mut matches: Vec<usize> =
+                    join_keys(self.get_field_value(&row, &join.local_field))
+                        .iter()
+                        .filter_map(|key| table.get(key))
+                        .flatten()
+                        .copied()
+                        .collect();
+                matches.sort_unstable();
+                matches.dedup();
+
+                if matches.is_empty() {
+                    if join.kind == JoinKind::Left {
+                        joined.push(attach(row, &join.alias, Value::Null));
+                    }
+                    continue;
+                }
+                for i in matches {
+                    joined.push(attach(row.clone(), &join.alias, foreign[i].clone()));
+                }
+            }
+            rows = joined;
+        }
+        Ok(rows)
+    }
+
+    /// Regroupe les documents filtrés (groupes dans l'ordre de première
+    /// apparition) et calcule les agrégats de chaque groupe, puis applique HAVING
+    fn aggregate(&self, aggregation: &Aggregation, documents: &[Value]) -> AggregationResult {
+        let mut keys: Vec<Vec<Value>> = Vec::new();
+        let mut groups: Vec<Vec<&Value>> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for doc in documents {
+            let key: Vec<Value> = aggregation
+                .group_by
+                .iter()
+                .map(|f| self.get_field_value(doc, f).cloned().unwrap_or(Value::Null))
+                .collect();
+            let pos = *positions
+                .entry(Value::Array(key.clone()).to_string())
+                .or_insert_with(|| {
+                    keys.push(key);
+                    groups.push(Vec::new());
+                    groups.len() - 1
+                });
+            groups[pos].push(doc);
+        }
+        // Sans GROUP BY, une ligne même sans document (COUNT(*) = 0)
+        if aggregation.group_by.is_empty() && groups.is_empty() {
+            keys.push(Vec::new());
+            groups.push(Vec::new());
+        }
+
+        let mut rows: Vec<Value> = keys
+            .into_iter()
+            .zip(groups)
+            .map(|(key, docs)| {
+                let mut row: serde_json::Map<String, Value> =
+                    aggregation.group_by.iter().cloned().zip(key).collect();
+                for agg in &aggregation.aggregates {
+                    row.insert(agg.alias.clone(), self.compute_aggregate(agg, &docs));
+                }
+                Value::Object(row)
+            })
+            .collect();
+
+        if let Some(having) = &aggregation.having {
+            let text = TextMatchers::new();
+            rows.retain(|row| self.evaluate_filter(row, having, &text));
+        }
+
+        let mut columns = aggregation.group_by.clone();
+        columns.extend(aggregation.aggregates.iter().map(|a| a.alias.clone()));
+        AggregationResult { columns, rows }
+    }
+
+    /// Valeur d'un agrégat sur un groupe. Les valeurs `null` ou absentes sont
+    /// ignorées (sauf `COUNT(*)`) ; SUM/AVG ne retiennent que les nombres et
+    /// valent `null` sur un groupe sans valeur, comme en SQL.
+    fn compute_aggregate(&self, agg: &Aggregate, docs: &[&Value]) -> Value {
+        let Some(field) = &agg.field else {
+            return match agg.function {
+                AggregateFunction::Count => Value::from(docs.len()),
+                _ => Value::Null,
+            };
+        };
+        let mut values: Vec<&Value> = docs
+            .iter()
+            .filter_map(|doc| self.get_field_value(doc, field))
+            .filter(|v| !v.is_null())
+            .collect();
+        if agg.distinct {
+            let mut seen = HashSet::new();
+            values.retain(|v| seen.insert(v.to_string()));
+        }
+
+        match agg.function {
+            AggregateFunction::Count => Value::from(values.len()),
+            AggregateFunction::Sum | AggregateFunction::Avg => {
+                let numbers: Vec<&Value> = values.into_iter().filter(|v| v.is_number()).collect();
+                if numbers.is_empty() {
+                    return Value::Null;
+                }
+                let total: f64 = numbers.iter().filter_map(|v| v.as_f64()).sum();
+                match agg.function {
+                    AggregateFunction::Avg => Value::from(total / numbers.len() as f64),
+                    // Somme entière si toutes les valeurs sont entières et
+                    // qu'elle tient sur un i64 (sinon le total flottant)
+                    _ => match numbers
+                        .iter()
+                        .try_fold(0i64, |sum, v| sum.checked_add(v.as_i64()?))
+                    {
+                        Some(sum) => Value::from(sum),
+                        None => Value::from(total),
+                    },
+                }
+            }
+            AggregateFunction::Min => values
+                .into_iter()
+                .min_by(|a, b| self.compare_json_values(a, b))
+                .cloned()
+                .unwrap_or(Value::Null),
+            AggregateFunction::Max => values
+                .into_iter()
+                .max_by(|a, b| self.compare_json_values(a, b))
+                .cloned()
+                .unwrap_or(Value::Null),
+        }
+    }
+
+    fn index_manager(&self) -> IndexManager<'a> {
+        IndexManager::new(self.manager.storage, &self.manager.space, &self.manager.db)
+    }
+
+    /// Charge uniquement les documents désignés par l'index, dans son ordre.
+    /// Une recherche plein texte renseigne le score de chaque document.
+    fn load_from_index(
+        &self,
+        idx_mgr: &IndexManager,
+        collection: &str,
+        plan: &IndexPlan,
+        scores: &mut HashMap<String, f64>,
+    ) -> Result<Vec<Value>> {
+        let ids = match &plan.strategy {
+            IndexStrategy::Lookup(values) => idx_mgr.lookup(collection, &plan.index, values)?,
+            IndexStrategy::Range(bounds) => idx_mgr.scan_range(collection, &plan.index, |key| {
+                bounds
+                    .iter()
+                    .all(|c| self.matches_operator(Some(key), &c.operator, &c.value))
+            })?,
+            IndexStrategy::FullText(text) => {
+                let hits = idx_mgr.search(collection, &plan.index, text)?;
+                let ids = hits.iter().map(|(id, _)| id.clone()).collect();
+                scores.extend(hits);
+                ids
+            }
+        };
+
+        // L'index reflète l'état courant : candidats relus dans le snapshot,
+        // avec les documents modifiés depuis (le filtre est ré-évalué ensuite)
+        if let Some(snapshot) = self.manager.active_snapshot() {
+            return snapshot.read_candidates(collection, &ids);
+        }
+        let mut documents = Vec::with_capacity(ids.len());
+        for id in ids {
+            // Entrée d'index orpheline (document supprimé hors IndexManager) : ignorée
+            if let Some(doc) = self.manager.get_document(collection, &id)? {
+                documents.push(doc);
+            }
+        }
+        Ok(documents)
+    }
+
     fn project_fields(&self, doc: &Value, projection: &Projection) -> Value {
         if let Value::Object(map) = doc {
             let mut new_map = serde_json::Map::new();
@@ -67,8 +421,9 @@ impl<'a> QueryEngine<'a> {
                     if fields.is_empty() {
                         return doc.clone();
                     }
+                    // Un chemin (`c.name`) est recopié sous ce nom
                     for field in fields {
-                        if let Some(val) = map.get(field) {
+                        if let Some(val) = self.get_field_value(doc, field) {
                             new_map.insert(field.clone(), val.clone());
                         }
                     }
@@ -87,54 +442,167 @@ impl<'a> QueryEngine<'a> {
         }
     }
 
-    fn evaluate_filter(&self, document: &Value, filter: &QueryFilter) -> bool {
+    /// Analyse les conditions `Matches` avec l'analyseur de l'index texte du
+    /// champ, ou l'analyseur français à défaut d'index
+    fn text_matchers(&self, optimizer: &QueryOptimizer, filter: &QueryFilter) -> TextMatchers {
+        filter
+            .all_conditions()
+            .into_iter()
+            .filter(|c| matches!(c.operator, ComparisonOperator::Matches))
+            .filter_map(|c| {
+                let text = c.value.as_str()?;
+                let analyzer = optimizer
+                    .text_index(&c.field)
+                    .map(|def| def.text_analyzer())
+                    .unwrap_or(TextAnalyzer::French);
+                Some((
+                    (c.field.clone(), text.to_string()),
+                    (analyzer, TextQuery::parse(text, analyzer)),
+                ))
+            })
+            .collect()
+    }
+
+    fn evaluate_filter(&self, document: &Value, filter: &QueryFilter, text: &TextMatchers) -> bool {
+        // Conditions d'abord (triées par coût), puis sous-filtres
+        let mut children = filter
+            .conditions
+            .iter()
+            .map(|c| self.evaluate_condition(document, c, text))
+            .chain(
+                filter
+                    .filters
+                    .iter()
+                    .map(|f| self.evaluate_filter(document, f, text)),
+            );
         match filter.operator {
-            FilterOperator::And => filter
-                .conditions
-                .iter()
-                .all(|c| self.evaluate_condition(document, c)),
-            FilterOperator::Or => filter
-                .conditions
-                .iter()
-                .any(|c| self.evaluate_condition(document, c)),
-            FilterOperator::Not => !filter
-                .conditions
-                .iter()
-                .any(|c| self.evaluate_condition(document, c)),
+            FilterOperator::And => children.all(|ok| ok),
+            FilterOperator::Or => children.any(|ok| ok),
+            FilterOperator::Not => !children.any(|ok| ok),
         }
     }
 
-    fn evaluate_condition(&self, document: &Value, condition: &Condition) -> bool {
+    fn evaluate_condition(
+        &self,
+        document: &Value,
+        condition: &Condition,
+        text: &TextMatchers,
+    ) -> bool {
         let val = self.get_field_value(document, &condition.field);
-        match &condition.operator {
-            ComparisonOperator::Eq => val == Some(&condition.value),
-            ComparisonOperator::Ne => val != Some(&condition.value),
-            ComparisonOperator::Gt => {
-                self.compare_values(val, &condition.value) == Some(Ordering::Greater)
+        match condition.operator {
+            ComparisonOperator::Matches => {
+                let (Some(content), Some(query)) =
+                    (val.and_then(|v| v.as_str()), condition.value.as_str())
+                else {
+                    return false;
+                };
+                match text.get(&(condition.field.clone(), query.to_string())) {
+                    Some((analyzer, parsed)) => parsed.matches(content, *analyzer),
+                    // Condition imbriquée (ElemMatch) : analysée à la volée
+                    None => TextQuery::parse(query, TextAnalyzer::French)
+                        .matches(content, TextAnalyzer::French),
+                }
             }
+            ComparisonOperator::ElemMatch => {
+                let Ok(sub) = QueryFilter::deserialize(&condition.value) else {
+                    return false;
+                };
+                val.and_then(|v| v.as_array()).is_some_and(|items| {
+                    items
+                        .iter()
+                        .any(|item| self.evaluate_filter(item, &sub, text))
+                })
+            }
+            _ => self.matches_operator(val, &condition.operator, &condition.value),
+        }
+    }
+
+    /// Applique un opérateur de comparaison à une valeur (partagé avec les scans d'index)
+    fn matches_operator(
+        &self,
+        val: Option<&Value>,
+        op: &ComparisonOperator,
+        value: &Value,
+    ) -> bool {
+        match op {
+            ComparisonOperator::Eq => val == Some(value),
+            ComparisonOperator::Ne => val != Some(value),
+            ComparisonOperator::Gt => self.compare_values(val, value) == Some(Ordering::Greater),
             ComparisonOperator::Gte => {
-                let o = self.compare_values(val, &condition.value);
+                let o = self.compare_values(val, value);
                 o == Some(Ordering::Greater) || o == Some(Ordering::Equal)
             }
-            ComparisonOperator::Lt => {
-                self.compare_values(val, &condition.value) == Some(Ordering::Less)
-            }
+            ComparisonOperator::Lt => self.compare_values(val, value) == Some(Ordering::Less),
             ComparisonOperator::Lte => {
-                let o = self.compare_values(val, &condition.value);
+                let o = self.compare_values(val, value);
                 o == Some(Ordering::Less) || o == Some(Ordering::Equal)
             }
-            ComparisonOperator::Contains | ComparisonOperator::Like => {
-                if let (Some(s1), Some(s2)) =
-                    (val.and_then(|v| v.as_str()), condition.value.as_str())
-                {
+            ComparisonOperator::In => match (val, value.as_array()) {
+                (Some(v), Some(candidates)) => candidates.contains(v),
+                _ => false,
+            },
+            ComparisonOperator::NotIn => match (val, value.as_array()) {
+                (Some(v), Some(candidates)) => !candidates.contains(v),
+                (None, Some(_)) => true,
+                _ => false,
+            },
+            ComparisonOperator::Contains => {
+                if let (Some(s1), Some(s2)) = (val.and_then(|v| v.as_str()), value.as_str()) {
                     s1.contains(s2)
-                } else if let (Some(arr), _) = (val.and_then(|v| v.as_array()), &condition.value) {
-                    arr.contains(&condition.value)
+                } else if let Some(arr) = val.and_then(|v| v.as_array()) {
+                    arr.contains(value)
                 } else {
                     false
                 }
             }
-            _ => false,
+            ComparisonOperator::Like => match value.as_str() {
+                Some(pattern) if pattern.contains(['%', '_']) => {
+                    any_string(val, |s| like_match(s, pattern))
+                }
+                _ => self.matches_operator(val, &ComparisonOperator::Contains, value),
+            },
+            ComparisonOperator::StartsWith => match value.as_str() {
+                Some(prefix) => any_string(val, |s| s.starts_with(prefix)),
+                None => false,
+            },
+            ComparisonOperator::EndsWith => match value.as_str() {
+                Some(suffix) => any_string(val, |s| s.ends_with(suffix)),
+                None => false,
+            },
+            ComparisonOperator::ContainsAll => match val {
+                Some(v) => {
+                    let items = as_items(v);
+                    as_items(value).iter().all(|x| items.contains(x))
+                }
+                None => false,
+            },
+            ComparisonOperator::ContainsAny
+            | ComparisonOperator::InSubquery
+            | ComparisonOperator::NotInSubquery => {
+                // Sous-requête non résolue (imbriquée dans un ElemMatch) : rien ne correspond
+                let Some(candidates) = value.as_array() else {
+                    return false;
+                };
+                let shared =
+                    val.is_some_and(|v| as_items(v).iter().any(|x| candidates.contains(x)));
+                match op {
+                    ComparisonOperator::NotInSubquery => !shared,
+                    _ => shared,
+                }
+            }
+            ComparisonOperator::Size => match (val.and_then(|v| v.as_array()), value.as_u64()) {
+                (Some(items), Some(n)) => items.len() as u64 == n,
+                _ => false,
+            },
+            ComparisonOperator::Exists => val.is_some() == value.as_bool().unwrap_or(true),
+            ComparisonOperator::IsNull => val.is_none_or(Value::is_null),
+            ComparisonOperator::IsNotNull => val.is_some_and(|v| !v.is_null()),
+            ComparisonOperator::IsType => match (val, value.as_str()) {
+                (Some(v), Some(expected)) => json_type_matches(v, expected),
+                _ => false,
+            },
+            // Évalués par `evaluate_condition`
+            ComparisonOperator::Matches | ComparisonOperator::ElemMatch => false,
         }
     }
 
@@ -158,10 +626,19 @@ impl<'a> QueryEngine<'a> {
         Ordering::Equal
     }
 
+    /// Valeur d'un champ (`a.b` ou `a.0.b` pour un chemin imbriqué, `""` pour
+    /// le document lui-même, cf. `ElemMatch`)
     fn get_field_value<'b>(&self, doc: &'b Value, path: &str) -> Option<&'b Value> {
+        if path.is_empty() {
+            return Some(doc);
+        }
         if !path.contains('.') {
             return doc.get(path);
         }
+        // Colonne d'une ligne agrégée (`GROUP BY address.city`)
+        if let Some(v) = doc.get(path) {
+            return Some(v);
+        }
         doc.pointer(&format!("/{}", path.replace('.', "/")))
     }
 
@@ -182,3 +659,130 @@ impl<'a> QueryEngine<'a> {
         a.to_string().cmp(&b.to_string())
     }
 }
+
+/// Clés de jointure d'une valeur (JSON stringifié) : chaque élément d'un
+/// tableau, aucune pour `null` ou un champ absent
+fn join_keys(value: Option<&Value>) -> Vec<String> {
+    match value {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter(|v| !v.is_null())
+            .map(|v| v.to_string())
+            .collect(),
+        Some(v) => vec![v.to_string()],
+    }
+}
+
+/// Attache le document joint sous `alias`
+fn attach(mut row: Value, alias: &str, joined: Value) -> Value {
+    if let Value::Object(map) = &mut row {
+        map.insert(alias.to_string(), joined);
+    }
+    row
+}
+
+/// Éléments d'un tableau, ou la valeur seule pour un scalaire
+fn as_items(value: &Value) -> &[Value] {
+    match value {
+        Value::Array(items) => items,
+        v => std::slice::from_ref(v),
+    }
+}
+
+/// Vrai si la chaîne, ou l'une des chaînes du tableau, satisfait le prédicat
+fn any_string(val: Option<&Value>, pred: impl Fn(&str) -> bool) -> bool {
+    match val {
+        Some(Value::String(s)) => pred(s),
+        Some(Value::Array(items)) => items.iter().filter_map(|v| v.as_str()).any(pred),
+        _ => false,
+    }
+}
+
+fn json_type_matches(v: &Value, expected: &str) -> bool {
+    match expected {
+        "string" => v.is_string(),
+        "number" => v.is_number(),
+        "integer" => v.is_i64() || v.is_u64(),
+        "boolean" => v.is_boolean(),
+        "array" => v.is_array(),
+        "object" => v.is_object(),
+        "null" => v.is_null(),
+        _ => false,
+    }
+}
+
+/// Motif SQL LIKE : `%` (suite quelconque) et `_` (un caractère)
+fn like_match(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    // Programmation dynamique : matched[j] = pattern[..i] reconnaît text[..j]
+    let mut matched = vec![false; text.len() + 1];
+    matched[0] = true;
+    for p in &pattern {
+        let mut next = vec![false; text.len() + 1];
+        if *p == '%' {
+            let mut any = false;
+            for j in 0..=text.len() {
+                any |= matched[j];
+                next[j] = any;
+            }
+        } else {
+            for j in 1..=text.len() {
+                next[j] = matched[j - 1] && (*p == '_' || *p == text[j - 1]);
+            }
+        }
+        matched = next;
+    }
+    matched[text.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_like_match() {
+        assert!(like_match("SYS-042", "SYS-%"));
+        assert!(like_match("SYS-042", "%-0_2"));
+        assert!(!like_match("SYS-042", "SYS_"));
+        assert!(like_match("", "%"));
+    }
+
+    #[test]
+    fn test_array_helpers() {
+        let tags = json!(["core", "paris"]);
+        assert!(any_string(Some(&tags), |s| s.starts_with("par")));
+        assert!(!any_string(Some(&json!(42)), |_| true));
+        assert_eq!(as_items(&json!("x")), &[json!("x")]);
+        assert!(json_type_matches(&json!(3), "integer"));
+        assert!(!json_type_matches(&json!(3.5), "integer"));
+    }
+
+    #[test]
+    fn test_sum_falls_back_to_float_on_overflow() {
+        use crate::json_db::storage::{JsonDbConfig, StorageEngine};
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = StorageEngine::new(JsonDbConfig::new(dir.path().to_path_buf()));
+        let manager = CollectionsManager::new(&storage, "s", "d");
+        let engine = QueryEngine::new(&manager);
+        let sum = Aggregate {
+            function: AggregateFunction::Sum,
+            field: Some("n".to_string()),
+            alias: "total".to_string(),
+            distinct: false,
+        };
+
+        let small = [json!({"n": 2}), json!({"n": 3})];
+        let docs: Vec<&Value> = small.iter().collect();
+        assert_eq!(engine.compute_aggregate(&sum, &docs), json!(5));
+
+        let huge = [json!({"n": i64::MAX}), json!({"n": 1})];
+        let docs: Vec<&Value> = huge.iter().collect();
+        let total = engine.compute_aggregate(&sum, &docs);
+        assert!(total.is_f64());
+        assert_eq!(total.as_f64(), Some(i64::MAX as f64 + 1.0));
+    }
+}
@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 pub use executor::QueryEngine;
+pub use optimizer::IndexPlan;
 
 // --- Structures de Données ---
 
@@ -21,6 +22,16 @@ pub struct Query {
     pub offset: Option<usize>,
     /// Liste des champs à inclure. Si None ou vide -> SELECT *
     pub projection: Option<Projection>,
+    /// Si renseigné, le moteur retourne le plan d'exécution au lieu des documents
+    #[serde(default)]
+    pub explain: Option<ExplainMode>,
+    /// GROUP BY / agrégats : le résultat est une ligne par groupe
+    /// (`QueryResult.aggregation`) au lieu des documents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aggregation: Option<Aggregation>,
+    /// Jointures, appliquées dans l'ordre avant le filtre
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub joins: Vec<Join>,
 }
 
 impl Query {
@@ -32,10 +43,113 @@ impl Query {
             limit: None,
             offset: None,
             projection: None,
+            explain: None,
+            aggregation: None,
+            joins: Vec::new(),
         }
     }
 }
 
+/// Mode EXPLAIN : `Plan` n'exécute rien, `Analyze` exécute et mesure
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ExplainMode {
+    Plan,
+    Analyze,
+}
+
+/// Jointure avec une autre collection, exécutée par hash join. Chaque document
+/// joint est attaché au document courant sous `alias` (`null` pour un LEFT
+/// JOIN sans correspondance), ses champs sont donc lus via `alias.champ`.
+/// Un document ayant plusieurs correspondances produit une ligne par
+/// correspondance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Join {
+    pub collection: String,
+    pub alias: String,
+    pub kind: JoinKind,
+    /// Champ du document courant (`alias.champ` pour une jointure précédente)
+    pub local_field: String,
+    /// Champ de la collection jointe
+    pub foreign_field: String,
+}
+
+/// Les deux champs de jointure peuvent être des tableaux d'IDs (liens Arcadia) :
+/// chaque élément est alors une clé de jointure.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+}
+
+/// Regroupement des documents filtrés (GROUP BY, COUNT, SUM, ...).
+/// Le tri, `limit` et `offset` de la requête portent alors sur les lignes
+/// agrégées, désignées par leurs colonnes (champs de regroupement et alias).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Aggregation {
+    /// Champs de regroupement ; vide = un seul groupe pour toute la collection
+    #[serde(default)]
+    pub group_by: Vec<String>,
+    #[serde(default)]
+    pub aggregates: Vec<Aggregate>,
+    /// Filtre sur les lignes agrégées (HAVING)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub having: Option<QueryFilter>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Aggregate {
+    pub function: AggregateFunction,
+    /// Champ agrégé ; `None` pour `COUNT(*)`
+    #[serde(default)]
+    pub field: Option<String>,
+    /// Colonne du résultat
+    pub alias: String,
+    /// Valeurs distinctes uniquement (`COUNT(DISTINCT layer)`)
+    #[serde(default)]
+    pub distinct: bool,
+}
+
+impl Aggregate {
+    /// Nom de colonne par défaut, tel qu'écrit en SQL : `COUNT(*)`, `AVG(x_age)`
+    pub fn default_alias(
+        function: AggregateFunction,
+        field: Option<&str>,
+        distinct: bool,
+    ) -> String {
+        format!(
+            "{}({}{})",
+            format!("{:?}", function).to_uppercase(),
+            if distinct { "DISTINCT " } else { "" },
+            field.unwrap_or("*")
+        )
+    }
+
+    /// Même calcul (alias mis à part)
+    pub fn same_as(&self, other: &Aggregate) -> bool {
+        self.function == other.function
+            && self.field == other.field
+            && self.distinct == other.distinct
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+/// Lignes d'une requête agrégée
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AggregationResult {
+    /// Colonnes : champs de regroupement puis alias des agrégats
+    pub columns: Vec<String>,
+    /// Une ligne par groupe (objet colonne -> valeur)
+    pub rows: Vec<Value>,
+}
+
 // Nouvelle Enum pour gérer proprement les projections (SELECT a, b)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Projection {
@@ -43,13 +157,42 @@ pub enum Projection {
     Exclude(Vec<String>),
 }
 
+/// Arbre de filtres : `operator` combine les conditions puis les sous-filtres
+/// (ex: `a = 1 AND (b = 2 OR c = 3)` = AND de `a = 1` et d'un sous-filtre OR).
+/// `Not` est vrai si aucun enfant n'est vrai.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryFilter {
     pub operator: FilterOperator,
     pub conditions: Vec<Condition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<QueryFilter>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl QueryFilter {
+    pub fn new(operator: FilterOperator, conditions: Vec<Condition>) -> Self {
+        Self {
+            operator,
+            conditions,
+            filters: Vec::new(),
+        }
+    }
+
+    /// Conditions du filtre et de ses sous-filtres, dans l'ordre d'évaluation
+    pub fn all_conditions(&self) -> Vec<&Condition> {
+        let mut out: Vec<&Condition> = self.conditions.iter().collect();
+        for sub in &self.filters {
+            out.extend(sub.all_conditions());
+        }
+        out
+    }
+
+    /// Profondeur de l'arbre (1 pour un filtre sans sous-filtre)
+    pub fn depth(&self) -> usize {
+        1 + self.filters.iter().map(|f| f.depth()).max().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum FilterOperator {
     And,
     Or,
@@ -74,6 +217,12 @@ impl Condition {
     }
 }
 
+/// Opérateurs de comparaison.
+///
+/// Les opérateurs de tableau (`ContainsAll`, `ContainsAny`, `Size`, `ElemMatch`)
+/// s'appliquent aux liens Arcadia, presque toujours des tableaux d'IDs. `Contains`,
+/// `StartsWith`, `EndsWith` et `Like` sont vrais si l'un des éléments d'un tableau
+/// de chaînes satisfait la condition.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ComparisonOperator {
     Eq,
@@ -82,12 +231,41 @@ pub enum ComparisonOperator {
     Gte,
     Lt,
     Lte,
+    /// Égalité stricte avec l'une des valeurs (tableau)
     In,
+    /// Différent de toutes les valeurs (tableau) ; vrai si le champ est absent
+    NotIn,
     Contains,
     StartsWith,
     EndsWith,
     Matches,
+    /// Motif SQL (`%` : suite quelconque, `_` : un caractère). Sans joker, le
+    /// motif est cherché comme sous-chaîne.
     Like,
+    /// Le champ contient toutes les valeurs (tableau). Un scalaire vaut un tableau à un élément.
+    ContainsAll,
+    /// Le champ contient au moins une des valeurs (tableau)
+    ContainsAny,
+    /// Nombre d'éléments du tableau
+    Size,
+    /// Au moins un élément du tableau satisfait le sous-filtre (`QueryFilter` en
+    /// JSON). Les champs du sous-filtre sont relatifs à l'élément, `""` désigne
+    /// l'élément lui-même.
+    ElemMatch,
+    /// Présence (`true`) ou absence (`false`) du champ
+    Exists,
+    /// Champ absent ou `null`
+    IsNull,
+    /// Champ présent et non `null`
+    IsNotNull,
+    /// Type JSON du champ : `string`, `number`, `integer`, `boolean`, `array`, `object`, `null`
+    IsType,
+    /// Le champ partage une valeur avec le résultat d'une sous-requête (`Query` en
+    /// JSON, un seul champ projeté). Résolu avant l'exécution : la valeur devient
+    /// la liste des valeurs retournées, évaluée comme `ContainsAny`.
+    InSubquery,
+    /// Négation de `InSubquery`
+    NotInSubquery,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,4 +286,62 @@ pub struct QueryResult {
     pub total_count: u64,
     pub offset: Option<usize>,
     pub limit: Option<usize>,
+    /// Plan d'exécution (uniquement pour les requêtes EXPLAIN)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<QueryPlan>,
+    /// Score BM25 de chaque document retourné, dans le même ordre
+    /// (uniquement quand un index texte sert une condition `Matches`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scores: Option<Vec<f64>>,
+    /// Lignes agrégées (requêtes GROUP BY / agrégats) ; `documents` est alors
+    /// vide et `total_count` compte les groupes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aggregation: Option<AggregationResult>,
+    /// Nombre de documents écrits (instructions INSERT / UPDATE / DELETE)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub affected: Option<u64>,
+}
+
+// --- Plan d'exécution (EXPLAIN) ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryPlan {
+    pub collection: String,
+    pub filter_operator: Option<FilterOperator>,
+    /// Conditions (sous-filtres compris) dans l'ordre d'évaluation retenu par l'optimiseur
+    pub conditions: Vec<PlannedCondition>,
+    /// Fraction estimée des documents retenus par le filtre (0.0 - 1.0)
+    pub estimated_selectivity: f64,
+    /// Index utilisé pour le chargement (None = scan complet)
+    pub index: Option<IndexPlan>,
+    pub sort: SortStrategy,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+    pub hints: Vec<String>,
+    /// Mesures réelles (EXPLAIN ANALYZE uniquement)
+    pub execution: Option<ExecutionStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedCondition {
+    pub condition: Condition,
+    /// Coût estimé (plus bas = plus sélectif, évalué en premier)
+    pub cost: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SortStrategy {
+    None,
+    InMemory(Vec<SortField>),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutionStats {
+    /// Documents chargés depuis le disque (index ou scan)
+    pub documents_scanned: usize,
+    /// Documents satisfaisant le filtre
+    pub documents_matched: usize,
+    /// Documents retournés après pagination
+    pub documents_returned: usize,
+    pub elapsed_ms: f64,
 }
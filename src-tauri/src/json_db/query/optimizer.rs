@@ -6,14 +6,40 @@
 //! - Réorganisation des conditions de filtre (sélectivité)
 //! - Simplification des filtres redondants
 //! - Optimisation de la pagination
+//! - Choix d'un index (Hash / BTree / Text) déclaré dans `_meta.json`
 
-use super::{ComparisonOperator, Condition, FilterOperator, Query, QueryFilter};
+use super::{
+    ComparisonOperator, Condition, FilterOperator, PlannedCondition, Query, QueryFilter, QueryPlan,
+    SortStrategy,
+};
+use crate::json_db::indexes::{IndexDefinition, IndexType};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 /// Optimiseur de requêtes
 #[derive(Debug, Default)]
 pub struct QueryOptimizer {
     config: OptimizerConfig,
+    /// Index disponibles sur la collection interrogée
+    indexes: Vec<IndexDefinition>,
+}
+
+/// Plan d'accès retenu pour une requête indexée
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexPlan {
+    pub index: IndexDefinition,
+    pub strategy: IndexStrategy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum IndexStrategy {
+    /// Recherche exacte (Eq / In) : une clé par valeur
+    Lookup(Vec<Value>),
+    /// Parcours de plage B-Tree : bornes portant sur le champ indexé
+    Range(Vec<Condition>),
+    /// Recherche plein texte (`Matches`) sur un index texte, classée par BM25
+    FullText(String),
 }
 
 /// Configuration de l'optimiseur
@@ -36,11 +62,21 @@ impl QueryOptimizer {
     pub fn new() -> Self {
         Self {
             config: OptimizerConfig::default(),
+            indexes: Vec::new(),
         }
     }
 
     pub fn with_config(config: OptimizerConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            indexes: Vec::new(),
+        }
+    }
+
+    /// Déclare les index utilisables pour le choix du plan d'accès
+    pub fn with_indexes(mut self, indexes: Vec<IndexDefinition>) -> Self {
+        self.indexes = indexes;
+        self
     }
 
     /// Optimise une requête
@@ -66,21 +102,30 @@ impl QueryOptimizer {
     }
 
     fn simplify_filter(&self, filter: QueryFilter) -> Result<QueryFilter> {
-        let mut simplified = filter.clone();
-
-        // Déduplication basique
-        simplified.conditions = self.deduplicate_conditions(&simplified.conditions);
+        let mut simplified = QueryFilter {
+            filters: Vec::new(),
+            ..filter
+        };
 
-        // Si on a un AND avec une seule condition, c'est optimal, rien à faire de spécial ici
-        // mais on garde la logique pour d'éventuelles simplifications futures (ex: nested AND)
-        if simplified.conditions.len() == 1 && matches!(simplified.operator, FilterOperator::And) {
-            // (Logique placeholder pour éviter warning unused import si on étendait pas)
+        // Aplatissement des sous-filtres : (a AND b) AND c -> a AND b AND c,
+        // et un sous-filtre AND/OR d'une seule condition devient cette condition
+        for sub in filter.filters {
+            let sub = self.simplify_filter(sub)?;
+            let same_operator =
+                sub.operator == simplified.operator && sub.operator != FilterOperator::Not;
+            let single = sub.operator != FilterOperator::Not
+                && sub.conditions.len() == 1
+                && sub.filters.is_empty();
+            if same_operator || single {
+                simplified.conditions.extend(sub.conditions);
+                simplified.filters.extend(sub.filters);
+            } else {
+                simplified.filters.push(sub);
+            }
         }
 
-        // Si filtre vide, on retourne un vecteur vide
-        if simplified.conditions.is_empty() {
-            simplified.conditions = vec![];
-        }
+        // Déduplication basique
+        simplified.conditions = self.deduplicate_conditions(&simplified.conditions);
 
         Ok(simplified)
     }
@@ -90,16 +135,23 @@ impl QueryOptimizer {
         filter
             .conditions
             .sort_by_key(|cond| self.estimate_selectivity(cond));
+        filter.filters = filter
+            .filters
+            .into_iter()
+            .map(|sub| self.reorder_conditions(sub))
+            .collect::<Result<_>>()?;
         Ok(filter)
     }
 
     /// Estime la sélectivité (Coût) d'une condition.
     /// Plus le score est bas, plus la condition est restrictive et rapide à vérifier.
-    fn estimate_selectivity(&self, condition: &Condition) -> u32 {
+    pub fn estimate_selectivity(&self, condition: &Condition) -> u32 {
         match condition.operator {
             // Très sélectif (Egalité stricte)
             ComparisonOperator::Eq => 1,
             ComparisonOperator::In => 2,
+            // Liste déjà résolue, en général courte
+            ComparisonOperator::InSubquery => 2,
 
             // Sélectivité moyenne (Range)
             ComparisonOperator::Gt
@@ -110,16 +162,78 @@ impl QueryOptimizer {
             // Sélectivité faible (Texte début/fin)
             ComparisonOperator::StartsWith | ComparisonOperator::EndsWith => 20,
 
+            // Tableaux, présence et type : vérification directe
+            ComparisonOperator::ContainsAll
+            | ComparisonOperator::ContainsAny
+            | ComparisonOperator::Size
+            | ComparisonOperator::Exists
+            | ComparisonOperator::IsNull
+            | ComparisonOperator::IsNotNull
+            | ComparisonOperator::IsType => 30,
+
             // Coûteux (Scan complet ou Regex)
             ComparisonOperator::Contains
             | ComparisonOperator::Like
             | ComparisonOperator::Matches => 50,
 
+            // Sous-filtre évalué sur chaque élément du tableau
+            ComparisonOperator::ElemMatch => 60,
+
             // Le moins sélectif (souvent tout sauf une valeur)
+            ComparisonOperator::NotIn | ComparisonOperator::NotInSubquery => 90,
             ComparisonOperator::Ne => 100,
         }
     }
 
+    /// Fraction estimée des documents retenus par une condition (0.0 - 1.0)
+    fn estimate_fraction(&self, condition: &Condition) -> f64 {
+        match condition.operator {
+            ComparisonOperator::Eq => 0.05,
+            ComparisonOperator::In => {
+                let n = condition.value.as_array().map(|a| a.len()).unwrap_or(1);
+                (0.05 * n as f64).min(1.0)
+            }
+            ComparisonOperator::Gt
+            | ComparisonOperator::Gte
+            | ComparisonOperator::Lt
+            | ComparisonOperator::Lte => 0.33,
+            ComparisonOperator::StartsWith | ComparisonOperator::EndsWith => 0.25,
+            ComparisonOperator::Contains
+            | ComparisonOperator::Like
+            | ComparisonOperator::Matches => 0.5,
+            ComparisonOperator::ContainsAll | ComparisonOperator::Size => 0.2,
+            ComparisonOperator::ContainsAny | ComparisonOperator::ElemMatch => 0.3,
+            ComparisonOperator::Exists | ComparisonOperator::IsType => 0.5,
+            ComparisonOperator::IsNull => 0.2,
+            ComparisonOperator::IsNotNull => 0.8,
+            ComparisonOperator::InSubquery => 0.1,
+            ComparisonOperator::NotIn | ComparisonOperator::NotInSubquery => 0.9,
+            ComparisonOperator::Ne => 0.95,
+        }
+    }
+
+    /// Sélectivité estimée d'un filtre, en supposant les conditions indépendantes
+    pub fn estimate_filter_selectivity(&self, filter: &QueryFilter) -> f64 {
+        if filter.conditions.is_empty() && filter.filters.is_empty() {
+            return 1.0;
+        }
+        let fractions = filter
+            .conditions
+            .iter()
+            .map(|c| self.estimate_fraction(c))
+            .chain(
+                filter
+                    .filters
+                    .iter()
+                    .map(|f| self.estimate_filter_selectivity(f)),
+            );
+        match filter.operator {
+            FilterOperator::And => fractions.product(),
+            FilterOperator::Or => 1.0 - fractions.map(|f| 1.0 - f).product::<f64>(),
+            FilterOperator::Not => fractions.map(|f| 1.0 - f).product(),
+        }
+    }
+
     fn deduplicate_conditions(&self, conditions: &[Condition]) -> Vec<Condition> {
         let mut seen = Vec::new();
         let mut unique = Vec::new();
@@ -156,18 +270,123 @@ impl QueryOptimizer {
         Ok(query)
     }
 
+    /// Choisit l'index le plus sélectif pour la requête.
+    ///
+    /// Seuls les filtres `And` sont éligibles : chaque document retenu doit satisfaire
+    /// la condition indexée, les autres conditions sont ré-évaluées par l'exécuteur.
+    /// Une recherche plein texte (`Matches` sur un index texte) passe en premier :
+    /// elle seule fournit le classement par pertinence. Viennent ensuite les
+    /// égalités (`Eq` / `In`), puis les plages (`Gt` / `Lt`...), qui exigent un
+    /// index B-Tree.
+    pub fn select_index(&self, query: &Query) -> Option<IndexPlan> {
+        let filter = query.filter.as_ref()?;
+        // Seules les conditions de premier niveau d'un AND restreignent à coup sûr
+        // le résultat ; les sous-filtres sont évalués sur les documents chargés
+        if !matches!(filter.operator, FilterOperator::And) || self.indexes.is_empty() {
+            return None;
+        }
+
+        // 0. Recherche plein texte
+        for cond in &filter.conditions {
+            let (ComparisonOperator::Matches, Some(text)) = (&cond.operator, cond.value.as_str())
+            else {
+                continue;
+            };
+            if let Some(def) = self.find_index(&cond.field, &[IndexType::Text]) {
+                return Some(IndexPlan {
+                    index: def.clone(),
+                    strategy: IndexStrategy::FullText(text.to_string()),
+                });
+            }
+        }
+
+        // 1. Recherche exacte
+        for cond in &filter.conditions {
+            let lookup = match cond.operator {
+                ComparisonOperator::Eq => vec![cond.value.clone()],
+                ComparisonOperator::In => match cond.value.as_array() {
+                    Some(values) => values.clone(),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            if let Some(def) = self.find_index(&cond.field, &[IndexType::Hash, IndexType::BTree]) {
+                return Some(IndexPlan {
+                    index: def.clone(),
+                    strategy: IndexStrategy::Lookup(lookup),
+                });
+            }
+        }
+
+        // 2. Parcours de plage (toutes les bornes portant sur le même champ)
+        for cond in &filter.conditions {
+            if !is_range_operator(&cond.operator) {
+                continue;
+            }
+            if let Some(def) = self.find_index(&cond.field, &[IndexType::BTree]) {
+                let bounds = filter
+                    .conditions
+                    .iter()
+                    .filter(|c| c.field == cond.field && is_range_operator(&c.operator))
+                    .cloned()
+                    .collect();
+                return Some(IndexPlan {
+                    index: def.clone(),
+                    strategy: IndexStrategy::Range(bounds),
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Index texte déclaré sur le champ (analyseur à appliquer à `Matches`)
+    pub fn text_index(&self, field: &str) -> Option<&IndexDefinition> {
+        self.find_index(field, &[IndexType::Text])
+    }
+
+    fn find_index(&self, field: &str, kinds: &[IndexType]) -> Option<&IndexDefinition> {
+        let pointer = field_to_pointer(field);
+        self.indexes
+            .iter()
+            // Un index composite n'est pas interrogeable sur un seul champ
+            .find(|def| {
+                !def.is_composite() && def.field_path == pointer && kinds.contains(&def.index_type)
+            })
+    }
+
     /// Analyse purement informative (pour logs ou debug)
     pub fn analyze_query(&self, query: &Query) -> QueryAnalysis {
         let mut analysis = QueryAnalysis::default();
 
-        if let Some(ref filter) = query.filter {
-            analysis.filter_complexity = filter.conditions.len();
-            // Estimation grossière
-            analysis.estimated_selectivity = if filter.conditions.is_empty() {
-                1.0
-            } else {
-                0.5
+        if let Some(plan) = self.select_index(query) {
+            let kind = match plan.strategy {
+                IndexStrategy::Lookup(_) => "lookup",
+                IndexStrategy::Range(_) => "range scan",
+                IndexStrategy::FullText(_) => "full-text BM25",
             };
+            analysis.optimization_hints.push(format!(
+                "Index {:?} '{}' utilisé ({})",
+                plan.index.index_type, plan.index.name, kind
+            ));
+            analysis.index_used = Some(plan.index.name);
+        } else if query.filter.is_some() {
+            analysis
+                .optimization_hints
+                .push("Aucun index applicable : scan complet de la collection".to_string());
+        }
+
+        for join in &query.joins {
+            analysis.optimization_hints.push(format!(
+                "Jointure {:?} (hash) avec '{}' : {} = {}.{}",
+                join.kind, join.collection, join.local_field, join.alias, join.foreign_field
+            ));
+        }
+
+        analysis.estimated_selectivity = 1.0;
+        if let Some(ref filter) = query.filter {
+            analysis.filter_complexity = filter.all_conditions().len();
+            analysis.estimated_selectivity = self.estimate_filter_selectivity(filter);
         }
 
         if let Some(ref sort) = query.sort {
@@ -177,6 +396,43 @@ impl QueryOptimizer {
         analysis.has_pagination = query.limit.is_some() || query.offset.is_some();
         analysis
     }
+
+    /// Construit le plan d'exécution (EXPLAIN) d'une requête déjà optimisée
+    pub fn explain(&self, query: &Query) -> QueryPlan {
+        let analysis = self.analyze_query(query);
+
+        let conditions = query
+            .filter
+            .as_ref()
+            .map(|f| {
+                f.all_conditions()
+                    .into_iter()
+                    .map(|c| PlannedCondition {
+                        condition: c.clone(),
+                        cost: self.estimate_selectivity(c),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let sort = match &query.sort {
+            Some(fields) if !fields.is_empty() => SortStrategy::InMemory(fields.clone()),
+            _ => SortStrategy::None,
+        };
+
+        QueryPlan {
+            collection: query.collection.clone(),
+            filter_operator: query.filter.as_ref().map(|f| f.operator),
+            conditions,
+            estimated_selectivity: analysis.estimated_selectivity,
+            index: self.select_index(query),
+            sort,
+            offset: query.offset,
+            limit: query.limit,
+            hints: analysis.optimization_hints,
+            execution: None,
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -185,15 +441,67 @@ pub struct QueryAnalysis {
     pub estimated_selectivity: f64,
     pub sort_fields_count: usize,
     pub has_pagination: bool,
+    /// Nom de l'index retenu par `select_index` (None = scan complet)
+    pub index_used: Option<String>,
     pub optimization_hints: Vec<String>,
 }
 
+fn is_range_operator(op: &ComparisonOperator) -> bool {
+    matches!(
+        op,
+        ComparisonOperator::Gt
+            | ComparisonOperator::Gte
+            | ComparisonOperator::Lt
+            | ComparisonOperator::Lte
+    )
+}
+
+/// Convertit un chemin de requête ("address.city") en pointeur JSON ("/address/city")
+fn field_to_pointer(field: &str) -> String {
+    if field.starts_with('/') {
+        field.to_string()
+    } else {
+        format!("/{}", field.replace('.', "/"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::json_db::query::{Condition, FilterOperator, Query, QueryFilter};
     use serde_json::json;
 
+    #[test]
+    fn test_simplify_flattens_nested_filters() {
+        let optimizer = QueryOptimizer::new();
+        let eq = |f: &str| Condition::eq(f, json!(1));
+
+        // a AND (b AND c) AND (d OR e) AND (f) -> a AND b AND c AND f AND (d OR e)
+        let mut query = Query::new("users");
+        query.filter = Some(QueryFilter {
+            operator: FilterOperator::And,
+            conditions: vec![eq("a")],
+            filters: vec![
+                QueryFilter::new(FilterOperator::And, vec![eq("b"), eq("c")]),
+                QueryFilter::new(FilterOperator::Or, vec![eq("d"), eq("e")]),
+                QueryFilter::new(FilterOperator::Or, vec![eq("f")]),
+            ],
+        });
+
+        let filter = optimizer.optimize(query).unwrap().filter.unwrap();
+        assert_eq!(filter.conditions.len(), 4);
+        assert_eq!(filter.filters.len(), 1);
+        assert_eq!(filter.filters[0].operator, FilterOperator::Or);
+        assert_eq!(filter.depth(), 2);
+
+        // Sélectivité : le OR pèse sur l'estimation du AND
+        let flat = QueryFilter::new(FilterOperator::And, filter.conditions.clone());
+        assert!(
+            optimizer.estimate_filter_selectivity(&filter)
+                < optimizer.estimate_filter_selectivity(&flat)
+        );
+    }
+
     #[test]
     fn test_optimize_reorder() {
         let optimizer = QueryOptimizer::new();
@@ -215,6 +523,7 @@ mod tests {
                     value: json!("active"),
                 },
             ],
+            filters: Vec::new(),
         });
 
         let optimized = optimizer.optimize(query).unwrap();
@@ -239,6 +548,146 @@ mod tests {
         assert_eq!(unique.len(), 1);
     }
 
+    fn index(name: &str, kind: IndexType) -> IndexDefinition {
+        IndexDefinition {
+            name: name.to_string(),
+            field_path: format!("/{}", name.replace('.', "/")),
+            index_type: kind,
+            fields: Vec::new(),
+            unique: false,
+            analyzer: None,
+        }
+    }
+
+    #[test]
+    fn test_select_index_prefers_lookup() {
+        let optimizer = QueryOptimizer::new().with_indexes(vec![
+            index("age", IndexType::BTree),
+            index("status", IndexType::Hash),
+        ]);
+
+        let mut query = Query::new("users");
+        query.filter = Some(QueryFilter {
+            operator: FilterOperator::And,
+            conditions: vec![
+                Condition {
+                    field: "age".into(),
+                    operator: ComparisonOperator::Gte,
+                    value: json!(18),
+                },
+                Condition::eq("status", json!("active")),
+            ],
+            filters: Vec::new(),
+        });
+
+        let plan = optimizer.select_index(&query).expect("index attendu");
+        assert_eq!(plan.index.name, "status");
+        assert!(
+            matches!(plan.strategy, IndexStrategy::Lookup(ref v) if v == &vec![json!("active")])
+        );
+
+        let analysis = optimizer.analyze_query(&query);
+        assert_eq!(analysis.index_used.as_deref(), Some("status"));
+    }
+
+    #[test]
+    fn test_select_index_range_requires_btree() {
+        let mut query = Query::new("users");
+        query.filter = Some(QueryFilter {
+            operator: FilterOperator::And,
+            conditions: vec![
+                Condition {
+                    field: "profile.age".into(),
+                    operator: ComparisonOperator::Gt,
+                    value: json!(18),
+                },
+                Condition {
+                    field: "profile.age".into(),
+                    operator: ComparisonOperator::Lte,
+                    value: json!(65),
+                },
+            ],
+            filters: Vec::new(),
+        });
+
+        let hash_only =
+            QueryOptimizer::new().with_indexes(vec![index("profile.age", IndexType::Hash)]);
+        assert!(hash_only.select_index(&query).is_none());
+
+        let btree =
+            QueryOptimizer::new().with_indexes(vec![index("profile.age", IndexType::BTree)]);
+        let plan = btree.select_index(&query).expect("index attendu");
+        match plan.strategy {
+            IndexStrategy::Range(bounds) => assert_eq!(bounds.len(), 2),
+            other => panic!("Plage attendue, obtenu {:?}", other),
+        }
+
+        // Un OR ne peut pas être servi par un seul index
+        query.filter.as_mut().unwrap().operator = FilterOperator::Or;
+        assert!(btree.select_index(&query).is_none());
+    }
+
+    #[test]
+    fn test_explain_reports_order_and_selectivity() {
+        let optimizer = QueryOptimizer::new().with_indexes(vec![index("status", IndexType::Hash)]);
+
+        let mut query = Query::new("users");
+        query.filter = Some(QueryFilter {
+            operator: FilterOperator::And,
+            conditions: vec![
+                Condition {
+                    field: "bio".into(),
+                    operator: ComparisonOperator::Contains,
+                    value: json!("rust"),
+                },
+                Condition::eq("status", json!("active")),
+            ],
+            filters: Vec::new(),
+        });
+
+        let plan = optimizer.explain(&optimizer.optimize(query).unwrap());
+        assert_eq!(plan.conditions[0].condition.field, "status");
+        assert_eq!(plan.conditions[0].cost, 1);
+        assert_eq!(plan.conditions[1].cost, 50);
+        assert!((plan.estimated_selectivity - 0.025).abs() < 1e-9);
+        assert_eq!(plan.index.map(|p| p.index.name), Some("status".to_string()));
+        assert!(matches!(plan.sort, SortStrategy::None));
+        assert!(plan.execution.is_none());
+    }
+
+    #[test]
+    fn test_select_index_full_text_first() {
+        let optimizer = QueryOptimizer::new().with_indexes(vec![
+            index("status", IndexType::Hash),
+            index("title", IndexType::Text),
+        ]);
+
+        let mut query = Query::new("requirements");
+        query.filter = Some(QueryFilter {
+            operator: FilterOperator::And,
+            conditions: vec![
+                Condition::eq("status", json!("validated")),
+                Condition {
+                    field: "title".into(),
+                    operator: ComparisonOperator::Matches,
+                    value: json!("exigence radar"),
+                },
+            ],
+            filters: Vec::new(),
+        });
+
+        let plan = optimizer.select_index(&query).expect("index attendu");
+        assert_eq!(plan.index.name, "title");
+        assert!(matches!(plan.strategy, IndexStrategy::FullText(ref q) if q == "exigence radar"));
+
+        // Sans index texte : l'égalité reprend la main
+        let hash_only = QueryOptimizer::new().with_indexes(vec![index("status", IndexType::Hash)]);
+        assert_eq!(
+            hash_only.select_index(&query).map(|p| p.index.name),
+            Some("status".to_string())
+        );
+    }
+
     #[test]
     fn test_optimize_pagination() {
         let optimizer = QueryOptimizer::new();
@@ -54,6 +54,7 @@ impl QueryBuilder {
             self.query.filter = Some(QueryFilter {
                 operator: op,
                 conditions: vec![c],
+                filters: Vec::new(),
             });
         }
     }
@@ -116,10 +117,13 @@ pub fn parse_filter_from_json(value: &Value) -> Result<QueryFilter> {
         .get("operator")
         .and_then(|v| v.as_str())
         .unwrap_or("and")
+        .to_lowercase()
+        .as_str()
     {
+        "and" => FilterOperator::And,
         "or" => FilterOperator::Or,
         "not" => FilterOperator::Not,
-        _ => FilterOperator::And,
+        other => bail!("Opérateur logique inconnu : '{}'", other),
     };
 
     let mut conditions = Vec::new();
@@ -132,14 +136,37 @@ pub fn parse_filter_from_json(value: &Value) -> Result<QueryFilter> {
                     .unwrap_or("")
                     .to_string();
                 let o_str = co.get("operator").and_then(|v| v.as_str()).unwrap_or("eq");
-                let v = co.get("value").cloned().unwrap_or(Value::Null);
+                let mut v = co.get("value").cloned().unwrap_or(Value::Null);
 
                 let op_enum = match o_str {
                     "eq" => ComparisonOperator::Eq,
+                    "ne" | "neq" => ComparisonOperator::Ne,
                     "gt" => ComparisonOperator::Gt,
+                    "gte" => ComparisonOperator::Gte,
                     "lt" => ComparisonOperator::Lt,
-                    "like" | "contains" => ComparisonOperator::Contains,
-                    _ => ComparisonOperator::Eq,
+                    "lte" => ComparisonOperator::Lte,
+                    "in" => ComparisonOperator::In,
+                    "nin" | "not_in" => ComparisonOperator::NotIn,
+                    "contains" => ComparisonOperator::Contains,
+                    "like" => ComparisonOperator::Like,
+                    "starts_with" | "startswith" => ComparisonOperator::StartsWith,
+                    "ends_with" | "endswith" => ComparisonOperator::EndsWith,
+                    "matches" | "match" => ComparisonOperator::Matches,
+                    "contains_all" => ComparisonOperator::ContainsAll,
+                    "contains_any" => ComparisonOperator::ContainsAny,
+                    "size" => ComparisonOperator::Size,
+                    "elem_match" | "elemmatch" => {
+                        // Sous-filtre dans le même format, converti en QueryFilter
+                        v = serde_json::to_value(parse_filter_from_json(&v)?)?;
+                        ComparisonOperator::ElemMatch
+                    }
+                    "exists" => ComparisonOperator::Exists,
+                    "is_null" | "isnull" => ComparisonOperator::IsNull,
+                    "is_not_null" | "isnotnull" => ComparisonOperator::IsNotNull,
+                    "type" | "is_type" => ComparisonOperator::IsType,
+                    "in_subquery" => ComparisonOperator::InSubquery,
+                    "not_in_subquery" => ComparisonOperator::NotInSubquery,
+                    other => bail!("Opérateur de comparaison inconnu : '{}'", other),
                 };
                 conditions.push(Condition {
                     field: f,
@@ -149,8 +176,80 @@ pub fn parse_filter_from_json(value: &Value) -> Result<QueryFilter> {
             }
         }
     }
+    // Sous-filtres (même format, récursif)
+    let mut filters = Vec::new();
+    if let Some(arr) = obj.get("filters").and_then(|v| v.as_array()) {
+        for f in arr {
+            filters.push(parse_filter_from_json(f)?);
+        }
+    }
+
     Ok(QueryFilter {
         operator: op,
         conditions,
+        filters,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_array_operators() {
+        let filter = parse_filter_from_json(&json!({
+            "conditions": [
+                { "field": "allocatedFunctions", "operator": "contains_any", "value": ["f1", "f2"] },
+                { "field": "ports", "operator": "elem_match", "value": {
+                    "conditions": [
+                        { "field": "direction", "operator": "eq", "value": "out" },
+                        { "field": "type", "operator": "is_null" }
+                    ]
+                }}
+            ]
+        }))
+        .unwrap();
+
+        assert!(matches!(
+            filter.conditions[0].operator,
+            ComparisonOperator::ContainsAny
+        ));
+        assert!(matches!(
+            filter.conditions[1].operator,
+            ComparisonOperator::ElemMatch
+        ));
+        let sub: QueryFilter = serde_json::from_value(filter.conditions[1].value.clone()).unwrap();
+        assert!(matches!(
+            sub.conditions[1].operator,
+            ComparisonOperator::IsNull
+        ));
+
+        assert!(parse_filter_from_json(&json!({
+            "conditions": [{ "field": "a", "operator": "almost" }]
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_nested_filters() {
+        // layer = 'la' AND (kind = 'node' OR kind = 'behavior')
+        let filter = parse_filter_from_json(&json!({
+            "operator": "and",
+            "conditions": [{ "field": "layer", "operator": "eq", "value": "la" }],
+            "filters": [{
+                "operator": "or",
+                "conditions": [
+                    { "field": "kind", "operator": "eq", "value": "node" },
+                    { "field": "kind", "operator": "eq", "value": "behavior" }
+                ]
+            }]
+        }))
+        .unwrap();
+        assert_eq!(filter.filters[0].operator, FilterOperator::Or);
+        assert_eq!(filter.all_conditions().len(), 3);
+        assert_eq!(filter.depth(), 2);
+
+        assert!(parse_filter_from_json(&json!({ "operator": "xor", "conditions": [] })).is_err());
+    }
+}
@@ -1,20 +1,76 @@
 // FICHIER : src-tauri/src/json_db/query/sql.rs
 
 use anyhow::{bail, Result};
-use serde_json::Value;
+use serde_json::{Map, Value};
 use sqlparser::ast::{
-    BinaryOperator, Expr, OrderByExpr, OrderByKind, Query as SqlQuery, SetExpr, Statement,
-    TableFactor, Value as SqlValue,
+    AssignmentTarget, BinaryOperator, Distinct, DuplicateTreatment, Expr, FromTable, Function,
+    FunctionArg, FunctionArgExpr, FunctionArguments, GroupByExpr, Ident, Insert, Join as SqlJoin,
+    JoinConstraint, JoinOperator, OrderByExpr, OrderByKind, Query as SqlQuery, SelectItem, SetExpr,
+    Statement, TableFactor, UnaryOperator, Value as SqlValue,
 };
 use sqlparser::dialect::GenericDialect;
 use sqlparser::parser::Parser;
 
 use super::{
-    ComparisonOperator, Condition, FilterOperator, Projection, Query, QueryFilter, SortField,
-    SortOrder,
+    Aggregate, AggregateFunction, Aggregation, ComparisonOperator, Condition, ExplainMode,
+    FilterOperator, Join, JoinKind, Projection, Query, QueryEngine, QueryFilter, QueryResult,
+    SortField, SortOrder,
 };
+use crate::json_db::collections::manager::CollectionsManager;
+use crate::json_db::transactions::manager::TransactionManager;
+use crate::json_db::transactions::revision::{self, RevisionConflict, REV_FIELD};
+use crate::json_db::transactions::TransactionRequest;
 
+/// Instruction SQL traduite : lecture (SELECT / EXPLAIN) ou écriture
+#[derive(Debug, Clone)]
+pub enum SqlStatement {
+    Query(Query),
+    Write(SqlWrite),
+}
+
+/// Écriture SQL. Les documents visés par WHERE ou SELECT ne sont connus qu'à
+/// l'exécution : `SqlWrite::plan` les résout en lot de `TransactionRequest`.
+/// UPDATE et DELETE portent la révision lue : un document modifié entre la
+/// lecture et la transaction la fait échouer, et l'instruction est rejouée.
+#[derive(Debug, Clone)]
+pub enum SqlWrite {
+    /// `INSERT INTO c (a, b) VALUES (...), (...)`
+    Insert {
+        collection: String,
+        documents: Vec<Value>,
+    },
+    /// `INSERT INTO c [(a, b)] SELECT ...`
+    InsertSelect {
+        collection: String,
+        columns: Vec<String>,
+        query: Query,
+    },
+    /// `UPDATE c SET a = 1, b.c = 'x' [WHERE ...]` : `patch` est fusionné
+    /// dans chaque document visé
+    Update {
+        collection: String,
+        filter: Option<QueryFilter>,
+        patch: Value,
+    },
+    /// `DELETE FROM c [WHERE ...]`
+    Delete {
+        collection: String,
+        filter: Option<QueryFilter>,
+    },
+}
+
+/// Traduit une requête de lecture (SELECT ou EXPLAIN)
 pub fn parse_sql(sql: &str) -> Result<Query> {
+    match parse_sql_statement(sql)? {
+        SqlStatement::Query(query) => Ok(query),
+        SqlStatement::Write(_) => {
+            bail!("Instruction d'écriture : l'exécuter via SqlStatement::execute (INSERT / UPDATE / DELETE)")
+        }
+    }
+}
+
+/// Traduit une instruction SQL : SELECT, EXPLAIN, INSERT, UPDATE ou DELETE
+pub fn parse_sql_statement(sql: &str) -> Result<SqlStatement> {
     let dialect = GenericDialect {};
     let ast = Parser::parse_sql(&dialect, sql)?;
 
@@ -23,36 +79,336 @@ pub fn parse_sql(sql: &str) -> Result<Query> {
     }
 
     match &ast[0] {
-        Statement::Query(q) => translate_query(q),
-        _ => bail!("Seules les requêtes SELECT sont supportées pour le moment"),
+        Statement::Query(q) => Ok(SqlStatement::Query(translate_query(q)?)),
+        // EXPLAIN [ANALYZE] SELECT ...
+        Statement::Explain {
+            statement, analyze, ..
+        } => match statement.as_ref() {
+            Statement::Query(q) => {
+                let mut query = translate_query(q)?;
+                query.explain = Some(if *analyze {
+                    ExplainMode::Analyze
+                } else {
+                    ExplainMode::Plan
+                });
+                Ok(SqlStatement::Query(query))
+            }
+            _ => bail!("EXPLAIN n'est supporté que pour les requêtes SELECT"),
+        },
+        Statement::Insert(insert) => translate_insert(insert).map(SqlStatement::Write),
+        Statement::Update {
+            table,
+            assignments,
+            from,
+            selection,
+            ..
+        } => {
+            if from.is_some() || !table.joins.is_empty() {
+                bail!("UPDATE ne peut cibler qu'une seule collection");
+            }
+            let (collection, alias) = table_of(&table.relation)?;
+            let mut patch = Value::Object(Map::new());
+            for assignment in assignments {
+                let AssignmentTarget::ColumnName(name) = &assignment.target else {
+                    bail!("UPDATE : affectation de tuple non supportée");
+                };
+                let mut field = name.to_string();
+                strip_qualifier(&mut field, &[collection.clone(), alias.clone()]);
+                set_path(&mut patch, &field, expr_to_value(&assignment.value)?);
+            }
+            Ok(SqlStatement::Write(SqlWrite::Update {
+                filter: translate_where(selection.as_ref(), &[collection.clone(), alias])?,
+                collection,
+                patch,
+            }))
+        }
+        Statement::Delete(delete) => {
+            let (FromTable::WithFromKeyword(tables) | FromTable::WithoutKeyword(tables)) =
+                &delete.from;
+            let [table] = tables.as_slice() else {
+                bail!("DELETE ne peut cibler qu'une seule collection");
+            };
+            if !table.joins.is_empty() || delete.using.is_some() {
+                bail!("DELETE ne peut cibler qu'une seule collection");
+            }
+            let (collection, alias) = table_of(&table.relation)?;
+            Ok(SqlStatement::Write(SqlWrite::Delete {
+                filter: translate_where(delete.selection.as_ref(), &[collection.clone(), alias])?,
+                collection,
+            }))
+        }
+        _ => bail!("Seules les instructions SELECT, INSERT, UPDATE et DELETE sont supportées"),
+    }
+}
+
+fn translate_insert(insert: &Insert) -> Result<SqlWrite> {
+    let collection = insert.table.to_string();
+    let columns: Vec<String> = insert.columns.iter().map(|c| c.value.clone()).collect();
+    let Some(source) = &insert.source else {
+        bail!("INSERT attend une clause VALUES ou SELECT");
+    };
+
+    match source.body.as_ref() {
+        SetExpr::Values(values) => {
+            if columns.is_empty() {
+                bail!("INSERT ... VALUES exige la liste des colonnes : INSERT INTO c (a, b) VALUES (...)");
+            }
+            let documents = values
+                .rows
+                .iter()
+                .map(|row| {
+                    if row.len() != columns.len() {
+                        bail!("{} valeurs pour {} colonnes", row.len(), columns.len());
+                    }
+                    let mut doc = Value::Object(Map::new());
+                    for (column, expr) in columns.iter().zip(row) {
+                        set_path(&mut doc, column, expr_to_value(expr)?);
+                    }
+                    Ok(doc)
+                })
+                .collect::<Result<_>>()?;
+            Ok(SqlWrite::Insert {
+                collection,
+                documents,
+            })
+        }
+        SetExpr::Select(_) => Ok(SqlWrite::InsertSelect {
+            collection,
+            columns,
+            query: translate_query(source)?,
+        }),
+        _ => bail!("INSERT attend une clause VALUES ou SELECT"),
+    }
+}
+
+/// Clause WHERE d'un UPDATE / DELETE (`None` : tous les documents)
+fn translate_where(selection: Option<&Expr>, qualifiers: &[String]) -> Result<Option<QueryFilter>> {
+    selection
+        .map(|expr| {
+            let mut filter = translate_expr(expr)?;
+            unqualify_filter(&mut filter, qualifiers);
+            Ok(filter)
+        })
+        .transpose()
+}
+
+/// Affecte `value` au champ pointé (`a.b` crée les objets intermédiaires)
+fn set_path(doc: &mut Value, field: &str, value: Value) {
+    let mut target = doc;
+    let mut parts = field.split('.').peekable();
+    while let Some(part) = parts.next() {
+        if !target.is_object() {
+            *target = Value::Object(Map::new());
+        }
+        let Value::Object(map) = target else {
+            unreachable!()
+        };
+        if parts.peek().is_none() {
+            map.insert(part.to_string(), value);
+            return;
+        }
+        target = map.entry(part).or_insert_with(|| Value::Object(Map::new()));
+    }
+}
+
+impl SqlWrite {
+    pub fn collection(&self) -> &str {
+        match self {
+            Self::Insert { collection, .. }
+            | Self::InsertSelect { collection, .. }
+            | Self::Update { collection, .. }
+            | Self::Delete { collection, .. } => collection,
+        }
+    }
+
+    /// Lot de requêtes de transaction équivalent. Les documents visés par
+    /// WHERE et les lignes d'un INSERT ... SELECT sont lus via `engine`.
+    pub async fn plan(&self, engine: &QueryEngine<'_>) -> Result<Vec<TransactionRequest>> {
+        match self {
+            Self::Insert {
+                collection,
+                documents,
+            } => Ok(documents
+                .iter()
+                .map(|doc| insert_request(collection, doc.clone()))
+                .collect()),
+            Self::InsertSelect {
+                collection,
+                columns,
+                query,
+            } => {
+                let result = engine.execute_query(query.clone()).await?;
+                let (fields, rows) = match result.aggregation {
+                    Some(agg) => (Some(agg.columns), agg.rows),
+                    None => match &query.projection {
+                        Some(Projection::Include(fields)) => {
+                            (Some(fields.clone()), result.documents)
+                        }
+                        _ => (None, result.documents),
+                    },
+                };
+                rows.into_iter()
+                    .map(|row| {
+                        let doc = match (&fields, columns.is_empty()) {
+                            // Lignes copiées telles quelles (sans leur révision)
+                            (_, true) => {
+                                let mut doc = row;
+                                if let Some(obj) = doc.as_object_mut() {
+                                    obj.remove(REV_FIELD);
+                                }
+                                doc
+                            }
+                            (Some(fields), false) if fields.len() == columns.len() => {
+                                let mut doc = Value::Object(Map::new());
+                                for (column, field) in columns.iter().zip(fields) {
+                                    let value = row_value(&row, field).cloned();
+                                    set_path(&mut doc, column, value.unwrap_or(Value::Null));
+                                }
+                                doc
+                            }
+                            _ => bail!(
+                                "INSERT ... SELECT : {} colonnes pour {} champs sélectionnés",
+                                columns.len(),
+                                fields.as_ref().map_or(0, |f| f.len())
+                            ),
+                        };
+                        Ok(insert_request(collection, doc))
+                    })
+                    .collect()
+            }
+            Self::Update {
+                collection,
+                filter,
+                patch,
+            } => Ok(matching_ids(engine, collection, filter)
+                .await?
+                .into_iter()
+                .map(|(id, rev)| {
+                    let mut document = patch.clone();
+                    if let Some(obj) = document.as_object_mut() {
+                        obj.insert(REV_FIELD.to_string(), Value::from(rev));
+                    }
+                    TransactionRequest::Update {
+                        collection: collection.clone(),
+                        id: Some(id),
+                        handle: None,
+                        document,
+                    }
+                })
+                .collect()),
+            Self::Delete { collection, filter } => Ok(matching_ids(engine, collection, filter)
+                .await?
+                .into_iter()
+                .map(|(id, rev)| TransactionRequest::Delete {
+                    collection: collection.clone(),
+                    id,
+                    revision: Some(rev),
+                })
+                .collect()),
+        }
+    }
+}
+
+/// Exécutions d'une écriture SQL avant d'abandonner sur `RevisionConflict`
+const MAX_WRITE_ATTEMPTS: usize = 5;
+
+impl SqlStatement {
+    /// Exécute l'instruction. Une écriture est appliquée en une seule
+    /// transaction (schémas et règles compris) ; le résultat ne contient alors
+    /// que le nombre de documents écrits (`affected`).
+    pub async fn execute(self, manager: &CollectionsManager<'_>) -> Result<QueryResult> {
+        let engine = QueryEngine::new(manager);
+        let write = match self {
+            Self::Query(query) => return engine.execute_query(query).await,
+            Self::Write(write) => write,
+        };
+
+        let tm = TransactionManager::new(&manager.storage.config, &manager.space, &manager.db);
+        let mut attempt = 1;
+        let affected = loop {
+            let requests = write.plan(&engine).await?;
+            let affected = requests.len() as u64;
+            if requests.is_empty() {
+                break affected;
+            }
+            // Document modifié depuis la sélection : nouvelle sélection, nouveau lot
+            match tm.execute_smart(requests).await {
+                Ok(()) => break affected,
+                Err(e) if e.is::<RevisionConflict>() && attempt < MAX_WRITE_ATTEMPTS => {
+                    attempt += 1
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        Ok(QueryResult {
+            documents: Vec::new(),
+            total_count: affected,
+            offset: None,
+            limit: None,
+            plan: None,
+            scores: None,
+            aggregation: None,
+            affected: Some(affected),
+        })
+    }
+}
+
+/// Insertion d'un document ; son `id` est conservé s'il en porte un. Création
+/// stricte (`_rev: 0`) : un document existant n'est jamais remplacé.
+fn insert_request(collection: &str, mut document: Value) -> TransactionRequest {
+    if let Some(obj) = document.as_object_mut() {
+        obj.insert(REV_FIELD.to_string(), Value::from(0));
+    }
+    TransactionRequest::Insert {
+        collection: collection.to_string(),
+        id: document
+            .get("id")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        document,
     }
 }
 
+/// Champ d'une ligne projetée (`c.name` : clé littérale ou chemin imbriqué)
+fn row_value<'a>(row: &'a Value, field: &str) -> Option<&'a Value> {
+    row.get(field)
+        .or_else(|| row.pointer(&format!("/{}", field.replace('.', "/"))))
+}
+
+/// Identifiants et révisions des documents de `collection` satisfaisant `filter`
+async fn matching_ids(
+    engine: &QueryEngine<'_>,
+    collection: &str,
+    filter: &Option<QueryFilter>,
+) -> Result<Vec<(String, u64)>> {
+    let mut query = Query::new(collection);
+    query.filter = filter.clone();
+    query.projection = Some(Projection::Include(vec![
+        "id".to_string(),
+        REV_FIELD.to_string(),
+    ]));
+    let result = engine.execute_query(query).await?;
+    Ok(result
+        .documents
+        .iter()
+        .filter_map(|doc| {
+            let id = doc.get("id")?.as_str()?;
+            Some((id.to_string(), revision::revision_of(doc)))
+        })
+        .collect())
+}
+
 fn translate_query(sql_query: &SqlQuery) -> Result<Query> {
     let limit = None; // Désactivé temporairement (compatibilité versions sqlparser)
     let offset = None;
 
-    let sort = if let Some(order_by_struct) = &sql_query.order_by {
-        match &order_by_struct.kind {
-            OrderByKind::Expressions(exprs) => {
-                let mut fields = Vec::new();
-                for order_expr in exprs {
-                    fields.push(translate_order_by(order_expr)?);
-                }
-                if fields.is_empty() {
-                    None
-                } else {
-                    Some(fields)
-                }
-            }
-            _ => None,
-        }
-    } else {
-        None
+    let order_by: &[OrderByExpr] = match sql_query.order_by.as_ref().map(|o| &o.kind) {
+        Some(OrderByKind::Expressions(exprs)) => exprs,
+        _ => &[],
     };
 
     match &*sql_query.body {
-        SetExpr::Select(select) => translate_select(select, limit, offset, sort),
+        SetExpr::Select(select) => translate_select(select, limit, offset, order_by),
         _ => bail!("Syntaxe de requête non supportée (pas de UNION, VALUES, etc.)"),
     }
 }
@@ -61,51 +417,51 @@ fn translate_select(
     select: &sqlparser::ast::Select,
     limit: Option<usize>,
     offset: Option<usize>,
-    sort: Option<Vec<SortField>>,
+    order_by: &[OrderByExpr],
 ) -> Result<Query> {
     if select.from.len() != 1 {
-        bail!("SELECT doit cibler exactement une collection (pas de JOIN supporté)");
+        bail!("SELECT doit cibler exactement une collection (utiliser JOIN pour en combiner plusieurs)");
     }
 
-    let collection = match &select.from[0].relation {
-        TableFactor::Table { name, .. } => name.to_string(),
-        _ => bail!("Clause FROM invalide"),
-    };
+    let (collection, base_alias) = table_of(&select.from[0].relation)?;
+    // Préfixes désignant la collection principale, dont les champs sont à la racine
+    let qualifiers = [collection.clone(), base_alias];
+    let mut joins: Vec<Join> = Vec::new();
+    for join in &select.from[0].joins {
+        let join = translate_join(join, &qualifiers)?;
+        if qualifiers.contains(&join.alias) || joins.iter().any(|j| j.alias == join.alias) {
+            bail!("Alias de jointure en double : '{}'", join.alias);
+        }
+        joins.push(join);
+    }
 
-    let projection = if select.projection.is_empty() {
-        None
-    } else {
-        let mut fields = Vec::new();
-        let mut is_wildcard = false;
+    let mut fields = Vec::new();
+    let mut aggregates = Vec::new();
+    let mut is_wildcard = false;
 
-        for item in &select.projection {
-            match item {
-                sqlparser::ast::SelectItem::UnnamedExpr(Expr::Identifier(ident)) => {
-                    fields.push(ident.value.clone());
-                }
-                sqlparser::ast::SelectItem::UnnamedExpr(Expr::CompoundIdentifier(idents)) => {
-                    fields.push(
-                        idents
-                            .iter()
-                            .map(|i| i.value.clone())
-                            .collect::<Vec<_>>()
-                            .join("."),
-                    );
-                }
-                sqlparser::ast::SelectItem::Wildcard(_) => {
-                    is_wildcard = true;
-                    break;
-                }
-                _ => {}
+    for item in &select.projection {
+        match item {
+            SelectItem::UnnamedExpr(Expr::Function(func)) => {
+                aggregates.push(aggregate_of(func)?);
             }
+            SelectItem::ExprWithAlias {
+                expr: Expr::Function(func),
+                alias,
+            } => {
+                aggregates.push(Aggregate {
+                    alias: alias.value.clone(),
+                    ..aggregate_of(func)?
+                });
+            }
+            SelectItem::UnnamedExpr(expr @ (Expr::Identifier(_) | Expr::CompoundIdentifier(_))) => {
+                fields.push(expr_to_field_name(expr)?);
+            }
+            SelectItem::Wildcard(_) => {
+                is_wildcard = true;
+            }
+            _ => {}
         }
-
-        if is_wildcard || fields.is_empty() {
-            None
-        } else {
-            Some(Projection::Include(fields))
-        }
-    };
+    }
 
     let filter = if let Some(selection) = &select.selection {
         Some(translate_expr(selection)?)
@@ -113,13 +469,251 @@ fn translate_select(
         None
     };
 
-    Ok(Query {
+    // GROUP BY / HAVING / DISTINCT
+    let mut group_by = match &select.group_by {
+        GroupByExpr::Expressions(exprs, _) => exprs
+            .iter()
+            .map(expr_to_field_name)
+            .collect::<Result<Vec<_>>>()?,
+        GroupByExpr::All(_) => bail!("GROUP BY ALL n'est pas supporté"),
+    };
+    match &select.distinct {
+        Some(Distinct::On(_)) => bail!("DISTINCT ON n'est pas supporté"),
+        // SELECT DISTINCT a, b : regroupement sur les colonnes sélectionnées
+        Some(Distinct::Distinct) if group_by.is_empty() && aggregates.is_empty() => {
+            if is_wildcard || fields.is_empty() {
+                bail!("SELECT DISTINCT attend une liste de champs");
+            }
+            group_by = fields.clone();
+        }
+        _ => {}
+    }
+    let having = match &select.having {
+        Some(expr) => Some(translate_expr(&resolve_aggregates(expr, &mut aggregates)?)?),
+        None => None,
+    };
+
+    let mut sort = Vec::new();
+    for order_expr in order_by {
+        let expr = resolve_aggregates(&order_expr.expr, &mut aggregates)?;
+        sort.push(translate_order_by(&OrderByExpr {
+            expr,
+            ..order_expr.clone()
+        })?);
+    }
+    let sort = (!sort.is_empty()).then_some(sort);
+
+    let is_aggregated = !group_by.is_empty() || !aggregates.is_empty();
+    if !is_aggregated && having.is_some() {
+        bail!("HAVING sans GROUP BY ni agrégat");
+    }
+
+    let (projection, aggregation) = if is_aggregated {
+        if is_wildcard {
+            bail!("SELECT * est incompatible avec GROUP BY et les agrégats");
+        }
+        if let Some(field) = fields.iter().find(|f| !group_by.contains(f)) {
+            bail!("La colonne '{}' doit figurer dans GROUP BY", field);
+        }
+        (
+            None,
+            Some(Aggregation {
+                group_by,
+                aggregates,
+                having,
+            }),
+        )
+    } else if is_wildcard || fields.is_empty() {
+        (None, None)
+    } else {
+        (Some(Projection::Include(fields)), None)
+    };
+
+    let mut query = Query {
         collection,
         filter,
         sort,
         limit,
         offset,
         projection,
+        explain: None,
+        aggregation,
+        joins,
+    };
+    unqualify(&mut query, &qualifiers);
+    Ok(query)
+}
+
+/// Collection et alias (le nom de la collection à défaut) d'une clause FROM / JOIN
+fn table_of(relation: &TableFactor) -> Result<(String, String)> {
+    match relation {
+        TableFactor::Table { name, alias, .. } => {
+            let name = name.to_string();
+            let alias = alias
+                .as_ref()
+                .map(|a| a.name.value.clone())
+                .unwrap_or_else(|| name.clone());
+            Ok((name, alias))
+        }
+        _ => bail!("Clause FROM invalide"),
+    }
+}
+
+/// `JOIN c ON f.id = c.owner` ou, pour un tableau d'IDs,
+/// `JOIN c ON f.id = ANY(c.allocatedFunctions)`
+fn translate_join(join: &SqlJoin, qualifiers: &[String]) -> Result<Join> {
+    let (collection, alias) = table_of(&join.relation)?;
+    let (kind, constraint) = match &join.join_operator {
+        JoinOperator::Join(c) | JoinOperator::Inner(c) => (JoinKind::Inner, c),
+        JoinOperator::Left(c) | JoinOperator::LeftOuter(c) => (JoinKind::Left, c),
+        _ => bail!("Seuls INNER JOIN et LEFT JOIN sont supportés"),
+    };
+    let JoinConstraint::On(on) = constraint else {
+        bail!("JOIN ... ON attendu (USING et NATURAL ne sont pas supportés)");
+    };
+    let (left, right) = match unnest(on) {
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::Eq,
+            right,
+        } => (left.as_ref(), unnest(right)),
+        Expr::AnyOp {
+            left,
+            compare_op: BinaryOperator::Eq,
+            right,
+            ..
+        } => (left.as_ref(), unnest(right)),
+        _ => bail!("Condition de jointure attendue : a.champ = b.champ ou a.champ = ANY(b.liens)"),
+    };
+
+    let (left, right) = (expr_to_field_name(left)?, expr_to_field_name(right)?);
+    let prefix = format!("{}.", alias);
+    let (mut local_field, foreign_field) =
+        match (left.strip_prefix(&prefix), right.strip_prefix(&prefix)) {
+            (_, Some(foreign)) => (left.clone(), foreign.to_string()),
+            (Some(foreign), None) => (right.clone(), foreign.to_string()),
+            (None, None) => bail!("La condition de jointure doit porter sur '{}'", alias),
+        };
+    strip_qualifier(&mut local_field, qualifiers);
+    Ok(Join {
+        collection,
+        alias,
+        kind,
+        local_field,
+        foreign_field,
+    })
+}
+
+fn strip_qualifier(field: &mut String, qualifiers: &[String]) {
+    if let Some(rest) = qualifiers
+        .iter()
+        .find_map(|q| field.strip_prefix(q.as_str())?.strip_prefix('.'))
+    {
+        *field = rest.to_string();
+    }
+}
+
+fn unqualify_filter(filter: &mut QueryFilter, qualifiers: &[String]) {
+    for cond in &mut filter.conditions {
+        strip_qualifier(&mut cond.field, qualifiers);
+    }
+    for sub in &mut filter.filters {
+        unqualify_filter(sub, qualifiers);
+    }
+}
+
+/// Retire le préfixe de la collection principale (`f.name` -> `name`) de tous
+/// les champs de la requête ; ceux des jointures restent préfixés par leur alias
+fn unqualify(query: &mut Query, qualifiers: &[String]) {
+    if let Some(filter) = &mut query.filter {
+        unqualify_filter(filter, qualifiers);
+    }
+    for sort in query.sort.iter_mut().flatten() {
+        strip_qualifier(&mut sort.field, qualifiers);
+    }
+    if let Some(Projection::Include(fields)) = &mut query.projection {
+        fields
+            .iter_mut()
+            .for_each(|f| strip_qualifier(f, qualifiers));
+    }
+    if let Some(aggregation) = &mut query.aggregation {
+        aggregation
+            .group_by
+            .iter_mut()
+            .for_each(|f| strip_qualifier(f, qualifiers));
+        for agg in &mut aggregation.aggregates {
+            if let Some(field) = &mut agg.field {
+                strip_qualifier(field, qualifiers);
+            }
+        }
+        if let Some(having) = &mut aggregation.having {
+            unqualify_filter(having, qualifiers);
+        }
+    }
+}
+
+/// Agrégat SQL (`COUNT(*)`, `SUM(x)`, `COUNT(DISTINCT x)`, ...) avec son alias par défaut
+fn aggregate_of(func: &Function) -> Result<Aggregate> {
+    let name = func.name.to_string().to_uppercase();
+    let function = match name.as_str() {
+        "COUNT" => AggregateFunction::Count,
+        "SUM" => AggregateFunction::Sum,
+        "AVG" => AggregateFunction::Avg,
+        "MIN" => AggregateFunction::Min,
+        "MAX" => AggregateFunction::Max,
+        _ => bail!("Fonction SQL non supportée : {}", name),
+    };
+    let FunctionArguments::List(list) = &func.args else {
+        bail!("{} attend un argument", name);
+    };
+    let distinct = matches!(list.duplicate_treatment, Some(DuplicateTreatment::Distinct));
+    let field = match list.args.as_slice() {
+        [FunctionArg::Unnamed(FunctionArgExpr::Wildcard)]
+            if function == AggregateFunction::Count && !distinct =>
+        {
+            None
+        }
+        [FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))] => Some(expr_to_field_name(expr)?),
+        _ => bail!("{} attend un seul champ", name),
+    };
+    Ok(Aggregate {
+        function,
+        alias: Aggregate::default_alias(function, field.as_deref(), distinct),
+        field,
+        distinct,
+    })
+}
+
+/// Remplace les agrégats d'une expression HAVING / ORDER BY par la colonne
+/// correspondante (`COUNT(*) > 2` -> `n > 2` si `COUNT(*) AS n`). Un agrégat
+/// absent du SELECT est ajouté aux colonnes sous son nom par défaut.
+fn resolve_aggregates(expr: &Expr, aggregates: &mut Vec<Aggregate>) -> Result<Expr> {
+    Ok(match expr {
+        Expr::Function(func) => {
+            let agg = aggregate_of(func)?;
+            let alias = match aggregates.iter().find(|a| a.same_as(&agg)) {
+                Some(existing) => existing.alias.clone(),
+                None => {
+                    let alias = agg.alias.clone();
+                    aggregates.push(agg);
+                    alias
+                }
+            };
+            Expr::Identifier(Ident::new(alias))
+        }
+        Expr::BinaryOp { left, op, right } => Expr::BinaryOp {
+            left: Box::new(resolve_aggregates(left, aggregates)?),
+            op: op.clone(),
+            right: Box::new(resolve_aggregates(right, aggregates)?),
+        },
+        Expr::Nested(inner) => Expr::Nested(Box::new(resolve_aggregates(inner, aggregates)?)),
+        Expr::UnaryOp { op, expr: inner } => Expr::UnaryOp {
+            op: *op,
+            expr: Box::new(resolve_aggregates(inner, aggregates)?),
+        },
+        Expr::IsNull(inner) => Expr::IsNull(Box::new(resolve_aggregates(inner, aggregates)?)),
+        Expr::IsNotNull(inner) => Expr::IsNotNull(Box::new(resolve_aggregates(inner, aggregates)?)),
+        other => other.clone(),
     })
 }
 
@@ -141,47 +735,19 @@ fn translate_expr(expr: &Expr) -> Result<QueryFilter> {
         // 2. Gestion des Opérateurs Binaires
         Expr::BinaryOp { left, op, right } => match op {
             // --- LOGIQUE (AND / OR) ---
-            BinaryOperator::And => {
-                let l = translate_expr(left)?;
-                let r = translate_expr(right)?;
-                // Fusion des conditions si l'opérateur est identique
-                if matches!(l.operator, FilterOperator::And)
-                    && matches!(r.operator, FilterOperator::And)
-                {
-                    let mut conds = l.conditions;
-                    conds.extend(r.conditions);
-                    Ok(QueryFilter {
-                        operator: FilterOperator::And,
-                        conditions: conds,
-                    })
-                } else {
-                    // TODO: Gérer l'imbrication complexe (AND contenant des OR).
-                    // Pour l'instant, on aplatit au mieux.
-                    let mut conds = l.conditions;
-                    conds.extend(r.conditions);
-                    Ok(QueryFilter {
-                        operator: FilterOperator::And,
-                        conditions: conds,
-                    })
-                }
-            }
-            BinaryOperator::Or => {
-                let l = translate_expr(left)?;
-                let r = translate_expr(right)?;
-                let mut conds = l.conditions;
-                conds.extend(r.conditions);
-                Ok(QueryFilter {
-                    operator: FilterOperator::Or,
-                    conditions: conds,
-                })
-            }
+            BinaryOperator::And => Ok(combine(
+                FilterOperator::And,
+                translate_expr(left)?,
+                translate_expr(right)?,
+            )),
+            BinaryOperator::Or => Ok(combine(
+                FilterOperator::Or,
+                translate_expr(left)?,
+                translate_expr(right)?,
+            )),
 
             // --- COMPARAISON (>, <, =, !=) ---
             _ => {
-                // C'est ici que ça plantait : on ne cherche un nom de champ QUE si c'est une comparaison
-                let field = expr_to_field_name(left)?;
-                let value = expr_to_value(right)?;
-
                 let operator = match op {
                     BinaryOperator::Eq => ComparisonOperator::Eq,
                     BinaryOperator::NotEq => ComparisonOperator::Ne,
@@ -189,38 +755,192 @@ fn translate_expr(expr: &Expr) -> Result<QueryFilter> {
                     BinaryOperator::GtEq => ComparisonOperator::Gte,
                     BinaryOperator::Lt => ComparisonOperator::Lt,
                     BinaryOperator::LtEq => ComparisonOperator::Lte,
-                    _ => ComparisonOperator::Eq,
+                    other => bail!("Opérateur SQL non supporté : {}", other),
                 };
+                // On ne cherche un nom de champ QUE si c'est une comparaison
+                let field = expr_to_field_name(left)?;
+                let value = expr_to_value(right)?;
 
-                Ok(QueryFilter {
-                    operator: FilterOperator::And, // Un filtre atomique est un AND de 1 condition
-                    conditions: vec![Condition {
-                        field,
-                        operator,
-                        value,
-                    }],
-                })
+                Ok(atomic(Condition {
+                    field,
+                    operator,
+                    value,
+                }))
             }
         },
 
+        // NOT (...)
+        Expr::UnaryOp {
+            op: UnaryOperator::Not,
+            expr: inner,
+        } => Ok(negate(translate_expr(inner)?)),
+
         // 3. Gestion LIKE
-        Expr::Like { expr, pattern, .. } => {
+        Expr::Like {
+            expr,
+            pattern,
+            negated,
+            ..
+        } => {
             let field = expr_to_field_name(expr)?;
-            let value = expr_to_value(pattern)?;
-            Ok(QueryFilter {
-                operator: FilterOperator::And,
-                conditions: vec![Condition {
+            let (operator, value) = translate_like(expr_to_value(pattern)?);
+            let filter = atomic(Condition {
+                field,
+                operator,
+                value,
+            });
+            Ok(if *negated { negate(filter) } else { filter })
+        }
+
+        // 4. IS NULL / IS NOT NULL (champ absent ou null)
+        Expr::IsNull(inner) => Ok(atomic(Condition {
+            field: expr_to_field_name(inner)?,
+            operator: ComparisonOperator::IsNull,
+            value: Value::Null,
+        })),
+        Expr::IsNotNull(inner) => Ok(atomic(Condition {
+            field: expr_to_field_name(inner)?,
+            operator: ComparisonOperator::IsNotNull,
+            value: Value::Null,
+        })),
+
+        // 5. IN (liste) / NOT IN (liste)
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => Ok(atomic(Condition {
+            field: expr_to_field_name(expr)?,
+            operator: if *negated {
+                ComparisonOperator::NotIn
+            } else {
+                ComparisonOperator::In
+            },
+            value: Value::Array(list.iter().map(expr_to_value).collect::<Result<_>>()?),
+        })),
+
+        // 6. IN (SELECT champ FROM ...) : sous-requête résolue par l'exécuteur
+        Expr::InSubquery {
+            expr,
+            subquery,
+            negated,
+        } => Ok(atomic(Condition {
+            field: expr_to_field_name(expr)?,
+            operator: if *negated {
+                ComparisonOperator::NotInSubquery
+            } else {
+                ComparisonOperator::InSubquery
+            },
+            value: serde_json::to_value(translate_query(subquery)?)?,
+        })),
+
+        // 7. 'valeur' = ANY(tableau) / 5 < ANY(scores)
+        Expr::AnyOp {
+            left,
+            compare_op,
+            right,
+            ..
+        } => {
+            let field = expr_to_field_name(unnest(right))?;
+            let value = expr_to_value(left)?;
+            // Égalité stricte avec un élément (`Contains` accepterait une sous-chaîne)
+            if let BinaryOperator::Eq = compare_op {
+                return Ok(atomic(Condition {
                     field,
-                    operator: ComparisonOperator::Contains,
-                    value,
-                }],
-            })
+                    operator: ComparisonOperator::ContainsAny,
+                    value: Value::Array(vec![value]),
+                }));
+            }
+            // `v < ANY(t)` : un élément e de t vérifie `e > v`
+            let operator = match compare_op {
+                BinaryOperator::NotEq => ComparisonOperator::Ne,
+                BinaryOperator::Gt => ComparisonOperator::Lt,
+                BinaryOperator::GtEq => ComparisonOperator::Lte,
+                BinaryOperator::Lt => ComparisonOperator::Gt,
+                BinaryOperator::LtEq => ComparisonOperator::Gte,
+                other => bail!("Opérateur non supporté avec ANY : {}", other),
+            };
+            let element = atomic(Condition {
+                field: String::new(),
+                operator,
+                value,
+            });
+            Ok(atomic(Condition {
+                field,
+                operator: ComparisonOperator::ElemMatch,
+                value: serde_json::to_value(element)?,
+            }))
         }
 
         _ => bail!("Expression SQL non supportée : {:?}", expr),
     }
 }
 
+/// Un filtre atomique est un AND de 1 condition
+fn atomic(condition: Condition) -> QueryFilter {
+    QueryFilter::new(FilterOperator::And, vec![condition])
+}
+
+fn is_atomic(filter: &QueryFilter) -> bool {
+    filter.operator != FilterOperator::Not
+        && filter.conditions.len() == 1
+        && filter.filters.is_empty()
+}
+
+/// `l op r` : un côté de même opérateur (ou atomique) est fusionné, sinon il
+/// devient un sous-filtre. `a = 1 AND (b = 2 OR c = 3)` donne un AND portant
+/// `a = 1` et le sous-filtre OR.
+fn combine(operator: FilterOperator, l: QueryFilter, r: QueryFilter) -> QueryFilter {
+    let mut out = QueryFilter::new(operator, Vec::new());
+    for side in [l, r] {
+        if side.operator == operator || is_atomic(&side) {
+            out.conditions.extend(side.conditions);
+            out.filters.extend(side.filters);
+        } else {
+            out.filters.push(side);
+        }
+    }
+    out
+}
+
+/// NOT x : `Not` est vrai si aucun enfant n'est vrai
+fn negate(filter: QueryFilter) -> QueryFilter {
+    if is_atomic(&filter) {
+        QueryFilter::new(FilterOperator::Not, filter.conditions)
+    } else {
+        QueryFilter {
+            operator: FilterOperator::Not,
+            conditions: Vec::new(),
+            filters: vec![filter],
+        }
+    }
+}
+
+fn unnest(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Nested(inner) => unnest(inner),
+        _ => expr,
+    }
+}
+
+/// Motif LIKE : préfixe (`x%`) et suffixe (`%x`) ont leur opérateur dédié,
+/// un motif sans joker reste une recherche de sous-chaîne (ou d'élément)
+fn translate_like(pattern: Value) -> (ComparisonOperator, Value) {
+    let Some(p) = pattern.as_str() else {
+        return (ComparisonOperator::Contains, pattern);
+    };
+    let is_plain = |s: &str| !s.is_empty() && !s.contains(['%', '_']);
+    if let Some(prefix) = p.strip_suffix('%').filter(|s| is_plain(s)) {
+        (ComparisonOperator::StartsWith, Value::from(prefix))
+    } else if let Some(suffix) = p.strip_prefix('%').filter(|s| is_plain(s)) {
+        (ComparisonOperator::EndsWith, Value::from(suffix))
+    } else if p.contains(['%', '_']) {
+        (ComparisonOperator::Like, pattern)
+    } else {
+        (ComparisonOperator::Contains, pattern)
+    }
+}
+
 fn expr_to_field_name(expr: &Expr) -> Result<String> {
     match expr {
         Expr::Identifier(ident) => Ok(ident.value.clone()),
@@ -243,7 +963,7 @@ fn expr_to_value(expr: &Expr) -> Result<Value> {
 
         // Support des nombres négatifs (-10)
         Expr::UnaryOp {
-            op: sqlparser::ast::UnaryOperator::Minus,
+            op: UnaryOperator::Minus,
             expr: inner,
         } => match expr_to_value(inner)? {
             Value::Number(n) => {
@@ -291,3 +1011,189 @@ fn sql_value_to_json(val: &SqlValue) -> Result<Value> {
         _ => Ok(Value::Null),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_explain() {
+        let q = parse_sql("SELECT * FROM actors WHERE kind = 'bot'").unwrap();
+        assert_eq!(q.explain, None);
+
+        let q = parse_sql("EXPLAIN SELECT * FROM actors WHERE kind = 'bot'").unwrap();
+        assert_eq!(q.explain, Some(ExplainMode::Plan));
+        assert_eq!(q.collection, "actors");
+        assert_eq!(q.filter.unwrap().conditions.len(), 1);
+
+        let q = parse_sql("EXPLAIN ANALYZE SELECT name FROM actors").unwrap();
+        assert_eq!(q.explain, Some(ExplainMode::Analyze));
+    }
+
+    fn conditions(sql: &str) -> Vec<Condition> {
+        parse_sql(sql).unwrap().filter.unwrap().conditions
+    }
+
+    #[test]
+    fn test_parse_array_and_null_operators() {
+        let c = conditions(
+            "SELECT * FROM pa WHERE 'f1' = ANY(allocatedFunctions) AND description IS NULL \
+             AND layer NOT IN ('oa', 'sa') AND name LIKE 'Radar%'",
+        );
+        assert!(matches!(c[0].operator, ComparisonOperator::ContainsAny));
+        assert_eq!(c[0].field, "allocatedFunctions");
+        assert_eq!(c[0].value, serde_json::json!(["f1"]));
+        assert!(matches!(c[1].operator, ComparisonOperator::IsNull));
+        assert!(matches!(c[2].operator, ComparisonOperator::NotIn));
+        assert_eq!(c[2].value, serde_json::json!(["oa", "sa"]));
+        assert!(matches!(c[3].operator, ComparisonOperator::StartsWith));
+        assert_eq!(c[3].value, "Radar");
+
+        // 10 < ANY(scores) : un élément supérieur à 10
+        let c = conditions("SELECT * FROM t WHERE 10 < ANY(scores)");
+        assert!(matches!(c[0].operator, ComparisonOperator::ElemMatch));
+        let sub: QueryFilter = serde_json::from_value(c[0].value.clone()).unwrap();
+        assert!(matches!(sub.conditions[0].operator, ComparisonOperator::Gt));
+        assert_eq!(sub.conditions[0].field, "");
+    }
+
+    #[test]
+    fn test_parse_nested_boolean_filters() {
+        let filter = parse_sql("SELECT * FROM t WHERE a = 1 AND (b = 2 OR c = 3) AND d = 4")
+            .unwrap()
+            .filter
+            .unwrap();
+        assert_eq!(filter.operator, FilterOperator::And);
+        let fields: Vec<_> = filter.conditions.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["a", "d"]);
+        assert_eq!(filter.filters.len(), 1);
+        assert_eq!(filter.filters[0].operator, FilterOperator::Or);
+        assert_eq!(filter.filters[0].conditions.len(), 2);
+
+        // NOT (x OR y) : sous-filtre nié
+        let filter = parse_sql("SELECT * FROM t WHERE NOT (b = 2 OR c = 3)")
+            .unwrap()
+            .filter
+            .unwrap();
+        assert_eq!(filter.operator, FilterOperator::Not);
+        assert_eq!(filter.filters[0].operator, FilterOperator::Or);
+
+        // Opérateur non supporté : erreur explicite au lieu d'un `=` implicite
+        let err = parse_sql("SELECT * FROM t WHERE a % 2").unwrap_err();
+        assert!(err.to_string().contains("non supporté"));
+    }
+
+    #[test]
+    fn test_parse_group_by() {
+        let q = parse_sql(
+            "SELECT layer, COUNT(*) AS n, AVG(asil) FROM functions \
+             WHERE kind = 'leaf' GROUP BY layer HAVING MAX(asil) >= 3 ORDER BY n DESC",
+        )
+        .unwrap();
+        assert!(q.projection.is_none());
+        let agg = q.aggregation.unwrap();
+        assert_eq!(agg.group_by, vec!["layer"]);
+        let aliases: Vec<_> = agg.aggregates.iter().map(|a| a.alias.as_str()).collect();
+        // MAX(asil), absent du SELECT, est ajouté pour HAVING
+        assert_eq!(aliases, vec!["n", "AVG(asil)", "MAX(asil)"]);
+        assert_eq!(agg.having.unwrap().conditions[0].field, "MAX(asil)");
+        assert_eq!(q.sort.unwrap()[0].field, "n");
+
+        let q = parse_sql("SELECT DISTINCT layer, kind FROM functions").unwrap();
+        assert_eq!(q.aggregation.unwrap().group_by, vec!["layer", "kind"]);
+
+        assert!(parse_sql("SELECT * FROM functions GROUP BY layer").is_err());
+        assert!(parse_sql("SELECT MEDIAN(asil) FROM functions").is_err());
+    }
+
+    #[test]
+    fn test_parse_joins() {
+        let q = parse_sql(
+            "SELECT f.name, c.name FROM functions f \
+             JOIN components c ON f.id = ANY(c.allocatedFunctions) \
+             LEFT JOIN actors a ON c.owner = a.id \
+             WHERE f.layer = 'la' ORDER BY c.name",
+        )
+        .unwrap();
+        assert_eq!(q.collection, "functions");
+        assert_eq!(q.joins.len(), 2);
+        assert_eq!(q.joins[0].kind, JoinKind::Inner);
+        assert_eq!(q.joins[0].local_field, "id");
+        assert_eq!(q.joins[0].foreign_field, "allocatedFunctions");
+        assert_eq!(q.joins[1].kind, JoinKind::Left);
+        assert_eq!(q.joins[1].local_field, "c.owner");
+        assert_eq!(q.joins[1].foreign_field, "id");
+
+        // Champs de la collection principale à la racine, jointures sous leur alias
+        assert_eq!(q.filter.unwrap().conditions[0].field, "layer");
+        assert_eq!(q.sort.unwrap()[0].field, "c.name");
+        assert!(
+            matches!(q.projection, Some(Projection::Include(ref f)) if f == &["name", "c.name"])
+        );
+
+        assert!(parse_sql("SELECT * FROM f JOIN c USING (id)").is_err());
+        assert!(parse_sql("SELECT * FROM f JOIN c ON f.id = f.parent").is_err());
+    }
+
+    #[test]
+    fn test_parse_in_subquery() {
+        let c = conditions(
+            "SELECT * FROM pc WHERE id IN (SELECT owner FROM functions WHERE kind = 'leaf')",
+        );
+        assert!(matches!(c[0].operator, ComparisonOperator::InSubquery));
+        let sub: Query = serde_json::from_value(c[0].value.clone()).unwrap();
+        assert_eq!(sub.collection, "functions");
+        assert!(matches!(sub.projection, Some(Projection::Include(ref f)) if f == &["owner"]));
+    }
+
+    #[test]
+    fn test_parse_write_statements() {
+        let sql = "INSERT INTO actors (id, handle, \"x_meta.city\") VALUES ('a1', 'alice', 'Paris'), ('a2', 'bob', NULL)";
+        let Ok(SqlStatement::Write(SqlWrite::Insert {
+            collection,
+            documents,
+        })) = parse_sql_statement(sql)
+        else {
+            panic!("INSERT attendu");
+        };
+        assert_eq!(collection, "actors");
+        assert_eq!(
+            documents,
+            vec![
+                serde_json::json!({ "id": "a1", "handle": "alice", "x_meta": { "city": "Paris" } }),
+                serde_json::json!({ "id": "a2", "handle": "bob", "x_meta": { "city": null } }),
+            ]
+        );
+
+        let sql =
+            "UPDATE actors a SET a.x_active = false, x_meta.city = 'Lyon' WHERE a.kind = 'bot'";
+        let Ok(SqlStatement::Write(SqlWrite::Update { filter, patch, .. })) =
+            parse_sql_statement(sql)
+        else {
+            panic!("UPDATE attendu");
+        };
+        assert_eq!(
+            patch,
+            serde_json::json!({ "x_active": false, "x_meta": { "city": "Lyon" } })
+        );
+        assert_eq!(filter.unwrap().conditions[0].field, "kind");
+
+        let Ok(SqlStatement::Write(write)) = parse_sql_statement("DELETE FROM actors") else {
+            panic!("DELETE attendu");
+        };
+        assert!(matches!(&write, SqlWrite::Delete { filter: None, .. }));
+        assert_eq!(write.collection(), "actors");
+
+        let sql = "INSERT INTO archive (handle) SELECT handle FROM actors WHERE x_active = false";
+        assert!(matches!(
+            parse_sql_statement(sql),
+            Ok(SqlStatement::Write(SqlWrite::InsertSelect { .. }))
+        ));
+
+        // Colonnes obligatoires, valeurs littérales uniquement
+        assert!(parse_sql_statement("INSERT INTO actors VALUES ('x')").is_err());
+        assert!(parse_sql_statement("INSERT INTO actors (a) VALUES (1, 2)").is_err());
+        assert!(parse_sql_statement("UPDATE actors SET x_age = x_age + 1").is_err());
+        assert!(parse_sql_statement("DROP TABLE actors").is_err());
+    }
+}
@@ -0,0 +1,118 @@
+// FICHIER : src-tauri/src/json_db/replication/merge.rs
+
+//! Fusion à trois voies, champ par champ, de deux versions concurrentes d'un
+//! document à partir de leur dernier état commun.
+
+use serde_json::{Map, Value};
+
+/// Fusionne `local` et `remote` depuis leur ancêtre `base`.
+///
+/// Un champ modifié d'un seul côté prend la valeur modifiée. Modifié des deux
+/// côtés différemment : les objets sont fusionnés récursivement, les autres
+/// valeurs tranchées en faveur de `remote` si `prefer_remote`, de `local` sinon.
+/// Retourne le document fusionné et le chemin (`a.b`) des champs en conflit.
+pub fn three_way(
+    base: &Value,
+    local: &Value,
+    remote: &Value,
+    prefer_remote: bool,
+) -> (Value, Vec<String>) {
+    let mut conflicts = Vec::new();
+    let merged = merge_value(
+        Some(base),
+        Some(local),
+        Some(remote),
+        prefer_remote,
+        "",
+        &mut conflicts,
+    );
+    (merged.unwrap_or(Value::Null), conflicts)
+}
+
+fn merge_value(
+    base: Option<&Value>,
+    local: Option<&Value>,
+    remote: Option<&Value>,
+    prefer_remote: bool,
+    path: &str,
+    conflicts: &mut Vec<String>,
+) -> Option<Value> {
+    if local == remote || remote == base {
+        return local.cloned();
+    }
+    if local == base {
+        return remote.cloned();
+    }
+
+    let empty = Map::new();
+    match (local, remote) {
+        (Some(Value::Object(l)), Some(Value::Object(r))) => {
+            let b = match base {
+                Some(Value::Object(b)) => b,
+                _ => &empty,
+            };
+            let mut merged = Map::new();
+            let keys = l.keys().chain(r.keys().filter(|k| !l.contains_key(*k)));
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                if let Some(value) = merge_value(
+                    b.get(key),
+                    l.get(key),
+                    r.get(key),
+                    prefer_remote,
+                    &child,
+                    conflicts,
+                ) {
+                    merged.insert(key.clone(), value);
+                }
+            }
+            Some(Value::Object(merged))
+        }
+        _ => {
+            conflicts.push(path.to_string());
+            if prefer_remote {
+                remote.cloned()
+            } else {
+                local.cloned()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_three_way_merges_disjoint_changes() {
+        let base = json!({"id": "a", "name": "A", "tags": ["x"], "meta": {"n": 1, "m": 1}});
+        let local = json!({"id": "a", "name": "A2", "tags": ["x"], "meta": {"n": 2, "m": 1}});
+        let remote = json!({"id": "a", "name": "A", "meta": {"n": 1, "m": 3}, "extra": true});
+
+        let (merged, conflicts) = three_way(&base, &local, &remote, false);
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            merged,
+            json!({"id": "a", "name": "A2", "meta": {"n": 2, "m": 3}, "extra": true})
+        );
+    }
+
+    #[test]
+    fn test_three_way_reports_conflicting_fields() {
+        let base = json!({"id": "a", "meta": {"n": 1}, "v": 1});
+        let local = json!({"id": "a", "meta": {"n": 2}, "v": 1});
+        let remote = json!({"id": "a", "meta": {"n": 3}, "v": 2});
+
+        let (merged, conflicts) = three_way(&base, &local, &remote, true);
+        assert_eq!(conflicts, vec!["meta.n".to_string()]);
+        assert_eq!(merged, json!({"id": "a", "meta": {"n": 3}, "v": 2}));
+
+        let (merged, _) = three_way(&base, &local, &remote, false);
+        assert_eq!(merged["meta"]["n"], 2);
+    }
+}
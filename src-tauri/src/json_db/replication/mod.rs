@@ -0,0 +1,1077 @@
+// FICHIER : src-tauri/src/json_db/replication/mod.rs
+
+//! Réplication hors ligne entre deux copies d'une base (deux `JsonDbConfig`,
+//! ou échange de fichiers de lot entre machines).
+//!
+//! - Versions : chaque réplique tient dans `_replication/state.json` un vecteur
+//!   de versions par document, alimenté par le flux de changements (une écriture
+//!   locale incrémente le compteur de la réplique). Les suppressions restent
+//!   connues (pierres tombales) pour être propagées.
+//! - Lot : état complet de la base (documents, pierres tombales, vecteurs),
+//!   JSON compressé zstd. `sync` échange directement les lots de deux racines.
+//! - Fusion : une version qui domine l'autre s'applique ; deux versions
+//!   concurrentes au contenu différent forment un conflit, réglé selon la
+//!   `MergeStrategy` de la collection et consigné dans `_conflicts`.
+//!   Le dernier écrivain est désigné par l'`updatedAt` des documents (identifiant
+//!   de réplique en cas d'égalité : les deux côtés choisissent le même gagnant).
+//! - Écriture : une seule transaction (WAL) par fusion, en `Operation::Replace`
+//!   (documents écrits tels quels, sans champs calculés) avec contrôle de
+//!   révision. Une écriture locale concurrente fait reprendre la fusion.
+//!
+//! Limites : la suppression d'une collection entière n'est pas répliquée, pas
+//! plus que les index secondaires (une collection reçue est créée avec le schéma
+//! d'origine et sans index) ; les lots sont complets, pas incrémentaux.
+
+pub mod merge;
+pub mod state;
+pub mod vector;
+
+pub use state::{content_hash, DocVersion, ReplicaState};
+pub use vector::VersionVector;
+
+use crate::json_db::changes::ChangeCursor;
+use crate::json_db::collections::manager::CollectionsManager;
+use crate::json_db::storage::{file_storage, JsonDbConfig, StorageEngine};
+use crate::json_db::transactions::lock_manager::{FileLock, LockMode, DEFAULT_LOCK_TIMEOUT};
+use crate::json_db::transactions::manager::TransactionManager;
+use crate::json_db::transactions::revision::{self, RevisionConflict};
+use crate::json_db::transactions::Operation;
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use state::is_replicated;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Version du format de lot produit
+pub const BUNDLE_FORMAT: u32 = 1;
+
+/// Collection des conflits détectés (propre à chaque réplique)
+pub const CONFLICTS_COLLECTION: &str = "_conflicts";
+
+/// Tentatives de fusion face aux écritures locales concurrentes
+const MAX_ATTEMPTS: usize = 3;
+
+/// Règlement des modifications concurrentes d'un même document
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeStrategy {
+    /// La version la plus récente (`updatedAt`) l'emporte en entier
+    #[default]
+    LastWriterWins,
+    /// Fusion champ par champ depuis le dernier état commun ; un champ modifié
+    /// des deux côtés revient au dernier écrivain
+    ThreeWay,
+    /// Aucune écriture : le conflit reste ouvert jusqu'à `resolve_conflict`
+    Manual,
+}
+
+impl MergeStrategy {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "last-writer-wins" | "lww" | "" => Ok(Self::LastWriterWins),
+            "three-way" | "merge" => Ok(Self::ThreeWay),
+            "manual" => Ok(Self::Manual),
+            other => Err(anyhow!(
+                "Stratégie inconnue : {} (lww | three-way | manual)",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::LastWriterWins => "last-writer-wins",
+            Self::ThreeWay => "three-way",
+            Self::Manual => "manual",
+        }
+    }
+}
+
+/// Options de fusion : stratégie par défaut et stratégies par collection
+#[derive(Debug, Clone, Default)]
+pub struct SyncOptions {
+    pub strategy: MergeStrategy,
+    pub collections: HashMap<String, MergeStrategy>,
+}
+
+impl SyncOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_strategy(mut self, strategy: MergeStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn with_collection_strategy(mut self, collection: &str, strategy: MergeStrategy) -> Self {
+        self.collections.insert(collection.to_string(), strategy);
+        self
+    }
+
+    pub fn strategy_for(&self, collection: &str) -> MergeStrategy {
+        self.collections
+            .get(collection)
+            .copied()
+            .unwrap_or(self.strategy)
+    }
+}
+
+/// Bilan d'une fusion
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncReport {
+    /// Documents écrits ou supprimés localement
+    pub applied: usize,
+    /// Modifications concurrentes détectées
+    pub conflicts: usize,
+    /// Conflits laissés ouverts (stratégie manuelle)
+    pub open: usize,
+}
+
+/// Bilan d'une synchronisation entre deux racines
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplicationReport {
+    /// Fusion des documents distants dans la base locale
+    pub pulled: SyncReport,
+    /// Fusion des documents locaux dans la base distante
+    pub pushed: SyncReport,
+}
+
+/// Document d'un lot, avec sa version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleEntry {
+    pub collection: String,
+    pub id: String,
+    pub versions: VersionVector,
+    pub updated_at: String,
+    /// Dernier état commun connu de l'émetteur (empreinte)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    /// Contenu (`None` : document supprimé)
+    pub document: Option<Value>,
+}
+
+/// État complet d'une réplique, échangeable hors ligne
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bundle {
+    pub format: u32,
+    pub replica: String,
+    pub space: String,
+    pub db: String,
+    /// Date du lot (ms depuis l'epoch)
+    pub created_at: i64,
+    /// Schéma de chaque collection, pour créer celles encore inconnues
+    pub collections: BTreeMap<String, String>,
+    pub documents: Vec<BundleEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStatus {
+    Open,
+    Resolved,
+}
+
+/// Conflit consigné dans `_conflicts` (identifiant : `<collection>--<id>`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conflict {
+    pub id: String,
+    pub collection: String,
+    pub document_id: String,
+    pub strategy: MergeStrategy,
+    pub status: ConflictStatus,
+    /// Version retenue : `local`, `remote`, `merged` ou `custom`
+    pub resolution: Option<String>,
+    pub local: Option<Value>,
+    pub remote: Option<Value>,
+    /// Dernier état commun, s'il est connu
+    pub base: Option<Value>,
+    /// Champs modifiés des deux côtés (fusion à trois voies)
+    #[serde(default)]
+    pub fields: Vec<String>,
+    pub local_versions: VersionVector,
+    pub remote_versions: VersionVector,
+    pub remote_replica: String,
+    pub remote_updated_at: String,
+    pub detected_at: String,
+    pub resolved_at: Option<String>,
+}
+
+/// Choix de résolution d'un conflit ouvert
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictChoice {
+    Local,
+    Remote,
+    /// Contenu fourni par l'utilisateur (`None` : suppression)
+    Document(Option<Value>),
+}
+
+impl ConflictChoice {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "remote" => Ok(Self::Remote),
+            other => Err(anyhow!("Choix inconnu : {} (local | remote)", other)),
+        }
+    }
+}
+
+pub fn conflict_id(collection: &str, id: &str) -> String {
+    format!("{}--{}", collection, id)
+}
+
+fn replication_lock(config: &JsonDbConfig, space: &str, db: &str) -> Result<FileLock> {
+    let path = config
+        .db_root(space, db)
+        .join("_locks")
+        .join("_replication.lock");
+    FileLock::acquire(&path, LockMode::Exclusive, DEFAULT_LOCK_TIMEOUT)
+}
+
+// --- LOTS ---
+
+/// État complet de la base, écritures locales récentes comprises
+pub fn export_bundle(config: &JsonDbConfig, space: &str, db: &str) -> Result<Bundle> {
+    if !config.db_root(space, db).exists() {
+        bail!("Base introuvable : {}/{}", space, db);
+    }
+    let _guard = replication_lock(config, space, db)?;
+    let mut state = ReplicaState::load(config, space, db)?;
+    state.refresh(config, space, db)?;
+
+    let keys: Vec<(String, String)> = state
+        .docs
+        .iter()
+        .flat_map(|(c, docs)| docs.keys().map(move |id| (c.clone(), id.clone())))
+        .collect();
+    let mut documents = Vec::with_capacity(keys.len());
+    let mut collections = BTreeMap::new();
+    for (collection, id) in keys {
+        if !collections.contains_key(&collection) {
+            collections.insert(
+                collection.clone(),
+                schema_of(config, space, db, &collection)?,
+            );
+        }
+        let document = file_storage::read_document(config, space, db, &collection, &id)?;
+        // Écriture postérieure au rafraîchissement : versionnée avant l'envoi
+        state.observe(&collection, &id, document.as_ref());
+        let Some(version) = state.get(&collection, &id) else {
+            continue;
+        };
+        documents.push(BundleEntry {
+            collection,
+            id,
+            versions: version.versions.clone(),
+            updated_at: version.updated_at.clone(),
+            base: version.base.clone(),
+            document,
+        });
+    }
+    state.save(config, space, db)?;
+
+    Ok(Bundle {
+        format: BUNDLE_FORMAT,
+        replica: state.replica,
+        space: space.to_string(),
+        db: db.to_string(),
+        created_at: Utc::now().timestamp_millis(),
+        collections,
+        documents,
+    })
+}
+
+/// Écrit un lot (JSON compressé zstd), atomiquement
+pub fn write_bundle(bundle: &Bundle, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let data = zstd::encode_all(serde_json::to_vec(bundle)?.as_slice(), 0)?;
+    file_storage::atomic_write(path, data)
+}
+
+pub fn read_bundle(path: &Path) -> Result<Bundle> {
+    let data = zstd::decode_all(fs::File::open(path)?)?;
+    let bundle: Bundle = serde_json::from_slice(&data)?;
+    if bundle.format != BUNDLE_FORMAT {
+        bail!("Format de lot non supporté : {}", bundle.format);
+    }
+    Ok(bundle)
+}
+
+fn schema_of(config: &JsonDbConfig, space: &str, db: &str, collection: &str) -> Result<String> {
+    let meta_path = config
+        .db_collection_path(space, db, collection)
+        .join("_meta.json");
+    if !meta_path.exists() {
+        return Ok(String::new());
+    }
+    let meta: Value = serde_json::from_str(&fs::read_to_string(&meta_path)?)?;
+    Ok(meta
+        .get("schema")
+        .and_then(|s| s.as_str())
+        .unwrap_or_default()
+        .to_string())
+}
+
+// --- FUSION ---
+
+/// Synchronise deux racines : les documents distants sont fusionnés dans la
+/// base locale, puis le résultat est renvoyé vers la base distante
+pub fn sync(
+    local: &JsonDbConfig,
+    remote: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    options: &SyncOptions,
+) -> Result<ReplicationReport> {
+    if local.db_root(space, db) == remote.db_root(space, db) {
+        bail!("Les deux racines désignent la même base : {}/{}", space, db);
+    }
+    let theirs = export_bundle(remote, space, db)?;
+    let pulled = merge_bundle(local, space, db, &theirs, options)?;
+    let ours = export_bundle(local, space, db)?;
+    let pushed = merge_bundle(remote, space, db, &ours, options)?;
+    Ok(ReplicationReport { pulled, pushed })
+}
+
+/// Fusionne un lot reçu dans la base, en une transaction
+pub fn merge_bundle(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    bundle: &Bundle,
+    options: &SyncOptions,
+) -> Result<SyncReport> {
+    if !config.db_root(space, db).exists() {
+        bail!("Base introuvable : {}/{}", space, db);
+    }
+    let _guard = replication_lock(config, space, db)?;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match Merge::new(config, space, db, bundle, options)?.run() {
+            Err(e) if attempt < MAX_ATTEMPTS && e.downcast_ref::<RevisionConflict>().is_some() => {
+                continue
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Document à réécrire localement, à la révision lue lors du calcul du plan
+struct Write {
+    collection: String,
+    id: String,
+    revision: u64,
+    document: Option<Value>,
+}
+
+/// Conflit à fusionner champ par champ, une fois les états communs retrouvés
+struct Pending<'b> {
+    entry: &'b BundleEntry,
+    local: DocVersion,
+    remote_wins: bool,
+}
+
+/// Une tentative de fusion : plan calculé sur l'état rafraîchi, puis appliqué
+struct Merge<'a, 'b> {
+    config: &'a JsonDbConfig,
+    space: &'a str,
+    db: &'a str,
+    bundle: &'b Bundle,
+    options: &'a SyncOptions,
+    state: ReplicaState,
+    writes: Vec<Write>,
+    versions: Vec<(String, String, DocVersion)>,
+    /// Conflits à consigner, avec la révision de leur document au calcul du plan
+    conflicts: Vec<(Conflict, u64)>,
+    report: SyncReport,
+}
+
+impl<'a, 'b> Merge<'a, 'b> {
+    fn new(
+        config: &'a JsonDbConfig,
+        space: &'a str,
+        db: &'a str,
+        bundle: &'b Bundle,
+        options: &'a SyncOptions,
+    ) -> Result<Self> {
+        let mut state = ReplicaState::load(config, space, db)?;
+        state.refresh(config, space, db)?;
+        if state.replica == bundle.replica {
+            bail!("Le lot provient de la réplique elle-même");
+        }
+        Ok(Self {
+            config,
+            space,
+            db,
+            bundle,
+            options,
+            state,
+            writes: Vec::new(),
+            versions: Vec::new(),
+            conflicts: Vec::new(),
+            report: SyncReport::default(),
+        })
+    }
+
+    fn run(mut self) -> Result<SyncReport> {
+        self.plan()?;
+        self.apply()
+    }
+
+    /// Calcule les écritures, versions et conflits sans rien écrire
+    fn plan(&mut self) -> Result<()> {
+        let bundle = self.bundle;
+        let mut pending = Vec::new();
+        for entry in &bundle.documents {
+            if !is_replicated(&entry.collection) {
+                continue;
+            }
+            let remote_hash = entry.document.as_ref().map(content_hash);
+            let Some(local) = self.state.get(&entry.collection, &entry.id).cloned() else {
+                // Document inconnu ici
+                if entry.document.is_some() {
+                    self.write(entry, None, entry.document.clone())?;
+                }
+                self.adopt(
+                    entry,
+                    entry.versions.clone(),
+                    remote_hash.clone(),
+                    remote_hash,
+                );
+                continue;
+            };
+
+            match local.versions.partial_cmp(&entry.versions) {
+                Some(Ordering::Greater) => {}
+                Some(Ordering::Less) => {
+                    if local.hash != remote_hash {
+                        self.write(entry, Some(&local), entry.document.clone())?;
+                    }
+                    self.close_open_conflict(entry)?;
+                    self.adopt(
+                        entry,
+                        entry.versions.clone(),
+                        remote_hash.clone(),
+                        remote_hash,
+                    );
+                }
+                // Même contenu des deux côtés : seules les versions sont réunies
+                _ if local.hash == remote_hash => {
+                    let mut versions = local.versions.clone();
+                    versions.merge(&entry.versions);
+                    if versions != local.versions || local.base != remote_hash {
+                        self.close_open_conflict(entry)?;
+                        self.adopt(entry, versions, remote_hash.clone(), remote_hash);
+                    }
+                }
+                _ => {
+                    self.report.conflicts += 1;
+                    let remote_wins = remote_wins(
+                        &local.updated_at,
+                        &self.state.replica,
+                        &entry.updated_at,
+                        &bundle.replica,
+                    );
+                    match self.options.strategy_for(&entry.collection) {
+                        MergeStrategy::Manual => {
+                            self.report.open += 1;
+                            let conflict = self.conflict(entry, &local, MergeStrategy::Manual)?;
+                            self.push_conflict(conflict)?;
+                        }
+                        MergeStrategy::ThreeWay
+                            if local.hash.is_some()
+                                && (local.base.is_some() || entry.base.is_some())
+                                && entry.document.is_some() =>
+                        {
+                            pending.push(Pending {
+                                entry,
+                                local,
+                                remote_wins,
+                            });
+                        }
+                        strategy => self.last_writer_wins(entry, &local, strategy, remote_wins)?,
+                    }
+                }
+            }
+        }
+        self.three_way(pending)
+    }
+
+    /// Le document prend la version et le contenu `hash` du lot
+    fn adopt(
+        &mut self,
+        entry: &BundleEntry,
+        versions: VersionVector,
+        hash: Option<String>,
+        base: Option<String>,
+    ) {
+        let version = DocVersion {
+            versions,
+            hash,
+            updated_at: entry.updated_at.clone(),
+            base,
+        };
+        self.versions
+            .push((entry.collection.clone(), entry.id.clone(), version));
+    }
+
+    /// Réécriture du document, conditionnée à sa révision actuelle : celle du
+    /// contenu `local` versionné par le rafraîchissement (0 si inconnu ici)
+    fn write(
+        &mut self,
+        entry: &BundleEntry,
+        local: Option<&DocVersion>,
+        document: Option<Value>,
+    ) -> Result<()> {
+        let revision = match local {
+            Some(local) => self
+                .read_local(&entry.collection, &entry.id, local)?
+                .as_ref()
+                .map(revision::revision_of)
+                .unwrap_or(0),
+            None => 0,
+        };
+        self.report.applied += 1;
+        self.writes.push(Write {
+            collection: entry.collection.clone(),
+            id: entry.id.clone(),
+            revision,
+            document,
+        });
+        Ok(())
+    }
+
+    /// Version d'un conflit réglé : successeur des deux versions concurrentes
+    fn resolved_versions(&self, local: &DocVersion, entry: &BundleEntry) -> VersionVector {
+        let mut versions = local.versions.clone();
+        versions.merge(&entry.versions);
+        versions.increment(&self.state.replica);
+        versions
+    }
+
+    fn last_writer_wins(
+        &mut self,
+        entry: &BundleEntry,
+        local: &DocVersion,
+        strategy: MergeStrategy,
+        remote_wins: bool,
+    ) -> Result<()> {
+        let mut conflict = self.conflict(entry, local, strategy)?;
+        let remote_hash = entry.document.as_ref().map(content_hash);
+        let versions = self.resolved_versions(local, entry);
+        let version = if remote_wins {
+            self.write(entry, Some(local), entry.document.clone())?;
+            conflict.resolution = Some("remote".to_string());
+            DocVersion {
+                versions,
+                hash: remote_hash.clone(),
+                updated_at: entry.updated_at.clone(),
+                base: remote_hash,
+            }
+        } else {
+            conflict.resolution = Some("local".to_string());
+            DocVersion {
+                versions,
+                hash: local.hash.clone(),
+                updated_at: local.updated_at.clone(),
+                base: remote_hash,
+            }
+        };
+        self.versions
+            .push((entry.collection.clone(), entry.id.clone(), version));
+        self.push_conflict(resolved(conflict))
+    }
+
+    fn three_way(&mut self, pending: Vec<Pending<'b>>) -> Result<()> {
+        if pending.is_empty() {
+            return Ok(());
+        }
+        // État commun : le nôtre, à défaut celui de l'émetteur s'il est passé ici
+        let wanted: HashMap<(String, String), String> = pending
+            .iter()
+            .filter_map(|p| {
+                let key = (p.entry.collection.clone(), p.entry.id.clone());
+                let base = p.local.base.clone().or_else(|| p.entry.base.clone());
+                base.map(|base| (key, base))
+            })
+            .collect();
+        let mut bases = find_bases(self.config, self.space, self.db, &wanted)?;
+
+        for p in pending {
+            let key = (p.entry.collection.clone(), p.entry.id.clone());
+            let current = self.read_local(&p.entry.collection, &p.entry.id, &p.local)?;
+            let (Some(base), Some(current), Some(remote)) =
+                (bases.remove(&key), current, p.entry.document.as_ref())
+            else {
+                // État commun introuvable : le document est tranché en entier
+                self.last_writer_wins(p.entry, &p.local, MergeStrategy::ThreeWay, p.remote_wins)?;
+                continue;
+            };
+
+            let (merged, fields) = merge::three_way(&base, &current, remote, p.remote_wins);
+            let hash = Some(content_hash(&merged));
+            let mut conflict = self.conflict(p.entry, &p.local, MergeStrategy::ThreeWay)?;
+            conflict.base = Some(base);
+            conflict.fields = fields;
+            conflict.resolution = Some("merged".to_string());
+
+            let versions = self.resolved_versions(&p.local, p.entry);
+            let updated_at = merged
+                .get("updatedAt")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| Utc::now().to_rfc3339());
+            if hash != p.local.hash {
+                self.write(p.entry, Some(&p.local), Some(merged))?;
+            }
+            let version = DocVersion {
+                versions,
+                hash,
+                updated_at,
+                base: p.entry.document.as_ref().map(content_hash),
+            };
+            self.versions
+                .push((p.entry.collection.clone(), p.entry.id.clone(), version));
+            self.push_conflict(resolved(conflict))?;
+        }
+        Ok(())
+    }
+
+    /// Contenu local actuel, qui doit être celui versionné par le rafraîchissement
+    fn read_local(&self, collection: &str, id: &str, local: &DocVersion) -> Result<Option<Value>> {
+        let doc = file_storage::read_document(self.config, self.space, self.db, collection, id)?;
+        if doc.as_ref().map(content_hash) != local.hash {
+            let actual = doc.as_ref().map(revision::revision_of).unwrap_or(0);
+            return Err(RevisionConflict {
+                collection: collection.to_string(),
+                id: id.to_string(),
+                expected: actual,
+                actual,
+            }
+            .into());
+        }
+        Ok(doc)
+    }
+
+    fn conflict(
+        &self,
+        entry: &BundleEntry,
+        local: &DocVersion,
+        strategy: MergeStrategy,
+    ) -> Result<Conflict> {
+        let current = self.read_local(&entry.collection, &entry.id, local)?;
+        Ok(Conflict {
+            id: conflict_id(&entry.collection, &entry.id),
+            collection: entry.collection.clone(),
+            document_id: entry.id.clone(),
+            strategy,
+            status: ConflictStatus::Open,
+            resolution: None,
+            local: current,
+            remote: entry.document.clone(),
+            base: None,
+            fields: Vec::new(),
+            local_versions: local.versions.clone(),
+            remote_versions: entry.versions.clone(),
+            remote_replica: self.bundle.replica.clone(),
+            remote_updated_at: entry.updated_at.clone(),
+            detected_at: Utc::now().to_rfc3339(),
+            resolved_at: None,
+        })
+    }
+
+    /// Conflit à consigner par-dessus le document de conflit actuel
+    fn push_conflict(&mut self, conflict: Conflict) -> Result<()> {
+        let revision = read_conflict(
+            self.config,
+            self.space,
+            self.db,
+            &conflict.collection,
+            &conflict.document_id,
+        )?
+        .map_or(0, |(_, revision)| revision);
+        self.conflicts.push((conflict, revision));
+        Ok(())
+    }
+
+    /// Conflit ouvert devenu caduc : la version reçue succède aux deux
+    fn close_open_conflict(&mut self, entry: &BundleEntry) -> Result<()> {
+        if let Some((conflict, revision)) = read_conflict(
+            self.config,
+            self.space,
+            self.db,
+            &entry.collection,
+            &entry.id,
+        )? {
+            if conflict.status == ConflictStatus::Open {
+                let mut conflict = resolved(conflict);
+                conflict.resolution = Some("remote".to_string());
+                self.conflicts.push((conflict, revision));
+            }
+        }
+        Ok(())
+    }
+
+    /// Écrit le plan en une transaction puis enregistre les nouvelles versions.
+    /// Chaque remplacement porte la révision lue au calcul du plan : un document
+    /// modifié depuis fait échouer la transaction (`RevisionConflict`).
+    fn apply(mut self) -> Result<SyncReport> {
+        let storage = StorageEngine::new(self.config.clone());
+        let mgr = CollectionsManager::new(&storage, self.space, self.db);
+        let mut operations = Vec::new();
+        for write in &self.writes {
+            if write.document.is_some()
+                && !self
+                    .config
+                    .db_collection_path(self.space, self.db, &write.collection)
+                    .join("_meta.json")
+                    .exists()
+            {
+                let schema = self.bundle.collections.get(&write.collection).cloned();
+                mgr.create_collection(&write.collection, schema)?;
+            }
+            operations.push(Operation::Replace {
+                collection: write.collection.clone(),
+                id: write.id.clone(),
+                revision: write.revision,
+                document: write.document.clone(),
+            });
+        }
+        if !self.conflicts.is_empty() {
+            mgr.create_collection(CONFLICTS_COLLECTION, Some(String::new()))?;
+        }
+        for (conflict, revision) in &self.conflicts {
+            operations.push(Operation::Replace {
+                collection: CONFLICTS_COLLECTION.to_string(),
+                id: conflict.id.clone(),
+                revision: *revision,
+                document: Some(serde_json::to_value(conflict)?),
+            });
+        }
+
+        if !operations.is_empty() {
+            TransactionManager::new(self.config, self.space, self.db).execute(|tx| {
+                tx.operations = operations;
+                Ok(())
+            })?;
+        }
+        for (collection, id, version) in std::mem::take(&mut self.versions) {
+            self.state.set(&collection, &id, version);
+        }
+        self.state.save(self.config, self.space, self.db)?;
+        Ok(self.report)
+    }
+}
+
+fn resolved(mut conflict: Conflict) -> Conflict {
+    conflict.status = ConflictStatus::Resolved;
+    conflict.resolved_at = Some(Utc::now().to_rfc3339());
+    conflict
+}
+
+/// Dernier écrivain : date `updatedAt` la plus récente, puis identifiant de
+/// réplique le plus grand (même arbitrage des deux côtés)
+fn remote_wins(local_at: &str, local_replica: &str, remote_at: &str, remote_replica: &str) -> bool {
+    let parse = |at: &str| DateTime::parse_from_rfc3339(at).ok();
+    (parse(remote_at), remote_replica) > (parse(local_at), local_replica)
+}
+
+/// Derniers états communs (empreinte `base`) retrouvés dans le flux de changements
+fn find_bases(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    wanted: &HashMap<(String, String), String>,
+) -> Result<HashMap<(String, String), Value>> {
+    let mut found = HashMap::new();
+    // Un seul parcours du journal, lot par lot
+    let mut cursor = ChangeCursor::new(config, space, db, 0);
+    loop {
+        let batch = cursor.next_batch()?;
+        if batch.is_empty() {
+            break;
+        }
+        for event in batch {
+            let key = (event.collection, event.id);
+            let Some(hash) = wanted.get(&key) else {
+                continue;
+            };
+            for image in [event.before, event.after].into_iter().flatten() {
+                if content_hash(&image) == *hash {
+                    found.insert(key.clone(), image);
+                }
+            }
+        }
+    }
+    Ok(found)
+}
+
+fn read_conflict(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    collection: &str,
+    id: &str,
+) -> Result<Option<(Conflict, u64)>> {
+    let doc = file_storage::read_document(
+        config,
+        space,
+        db,
+        CONFLICTS_COLLECTION,
+        &conflict_id(collection, id),
+    )?;
+    doc.map(|d| {
+        let revision = revision::revision_of(&d);
+        Ok((serde_json::from_value(d)?, revision))
+    })
+    .transpose()
+}
+
+// --- RÉSOLUTION MANUELLE ---
+
+/// Règle un conflit ouvert. La version retenue succède aux deux versions en
+/// conflit : elle sera propagée à l'autre réplique à la prochaine synchronisation.
+/// Retourne le contenu retenu (`None` : document supprimé).
+pub fn resolve_conflict(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    conflict_id: &str,
+    choice: ConflictChoice,
+) -> Result<Option<Value>> {
+    let _guard = replication_lock(config, space, db)?;
+    let mut state = ReplicaState::load(config, space, db)?;
+    state.refresh(config, space, db)?;
+
+    let doc = file_storage::read_document(config, space, db, CONFLICTS_COLLECTION, conflict_id)?
+        .ok_or_else(|| anyhow!("Conflit introuvable : {}", conflict_id))?;
+    let conflict: Conflict = serde_json::from_value(doc)?;
+    if conflict.status != ConflictStatus::Open {
+        bail!("Conflit déjà résolu : {}", conflict_id);
+    }
+    let (collection, id) = (conflict.collection.as_str(), conflict.document_id.as_str());
+    let current = file_storage::read_document(config, space, db, collection, id)?;
+    let (resolution, chosen) = match choice {
+        ConflictChoice::Local => ("local", current.clone()),
+        ConflictChoice::Remote => ("remote", conflict.remote.clone()),
+        ConflictChoice::Document(doc) => ("custom", doc),
+    };
+    let hash = chosen.as_ref().map(content_hash);
+
+    let mut versions = state
+        .get(collection, id)
+        .map(|v| v.versions.clone())
+        .unwrap_or_default();
+    versions.merge(&conflict.remote_versions);
+    versions.increment(&state.replica);
+    let version = DocVersion {
+        versions,
+        hash: hash.clone(),
+        updated_at: Utc::now().to_rfc3339(),
+        base: conflict.remote.as_ref().map(content_hash),
+    };
+
+    let mut closed = resolved(conflict.clone());
+    closed.resolution = Some(resolution.to_string());
+    let mut operations = Vec::new();
+    if current.as_ref().map(content_hash) != hash {
+        operations.push(Operation::Replace {
+            collection: collection.to_string(),
+            id: id.to_string(),
+            revision: current.as_ref().map(revision::revision_of).unwrap_or(0),
+            document: chosen.clone(),
+        });
+    }
+    let stored = file_storage::read_document(config, space, db, CONFLICTS_COLLECTION, conflict_id)?;
+    operations.push(Operation::Replace {
+        collection: CONFLICTS_COLLECTION.to_string(),
+        id: conflict_id.to_string(),
+        revision: stored.as_ref().map(revision::revision_of).unwrap_or(0),
+        document: Some(serde_json::to_value(&closed)?),
+    });
+    TransactionManager::new(config, space, db).execute(|tx| {
+        tx.operations = operations;
+        Ok(())
+    })?;
+
+    state.set(collection, id, version);
+    state.save(config, space, db)?;
+    Ok(chosen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_db::storage::StorageEngine;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn setup(root: &Path) -> JsonDbConfig {
+        let config = JsonDbConfig::new(root.to_path_buf());
+        let storage = StorageEngine::new(config.clone());
+        CollectionsManager::new(&storage, "s", "d")
+            .create_collection("notes", None)
+            .unwrap();
+        config
+    }
+
+    fn put(config: &JsonDbConfig, doc: Value) {
+        let storage = StorageEngine::new(config.clone());
+        CollectionsManager::new(&storage, "s", "d")
+            .insert_raw("notes", &doc)
+            .unwrap();
+    }
+
+    fn get(config: &JsonDbConfig, id: &str) -> Option<Value> {
+        file_storage::read_document(config, "s", "d", "notes", id).unwrap()
+    }
+
+    #[test]
+    fn test_strategy_parse() {
+        assert_eq!(
+            MergeStrategy::parse("LWW").unwrap(),
+            MergeStrategy::LastWriterWins
+        );
+        assert_eq!(
+            MergeStrategy::parse("three-way").unwrap(),
+            MergeStrategy::ThreeWay
+        );
+        assert!(MergeStrategy::parse("random").is_err());
+        let options = SyncOptions::new().with_collection_strategy("notes", MergeStrategy::Manual);
+        assert_eq!(options.strategy_for("notes"), MergeStrategy::Manual);
+        assert_eq!(options.strategy_for("other"), MergeStrategy::LastWriterWins);
+    }
+
+    #[test]
+    fn test_sync_propagates_writes_and_deletes() {
+        let (a_dir, b_dir) = (tempdir().unwrap(), tempdir().unwrap());
+        let (a, b) = (setup(a_dir.path()), setup(b_dir.path()));
+        put(&a, json!({"id": "n1", "v": 1}));
+        put(&b, json!({"id": "n2", "v": 1}));
+
+        let report = sync(&a, &b, "s", "d", &SyncOptions::new()).unwrap();
+        assert_eq!((report.pulled.applied, report.pushed.applied), (1, 1));
+        assert_eq!(get(&a, "n2").unwrap()["v"], 1);
+        assert_eq!(get(&b, "n1").unwrap()["v"], 1);
+
+        // Suppression propagée ; une nouvelle synchronisation n'écrit plus rien
+        let storage = StorageEngine::new(b.clone());
+        CollectionsManager::new(&storage, "s", "d")
+            .delete_document("notes", "n1")
+            .unwrap();
+        sync(&a, &b, "s", "d", &SyncOptions::new()).unwrap();
+        assert!(get(&a, "n1").is_none());
+        let report = sync(&a, &b, "s", "d", &SyncOptions::new()).unwrap();
+        assert_eq!(report.pulled, SyncReport::default());
+        assert_eq!(report.pushed, SyncReport::default());
+    }
+
+    #[test]
+    fn test_document_changed_after_planning_aborts_merge() {
+        let (a_dir, b_dir) = (tempdir().unwrap(), tempdir().unwrap());
+        let (a, b) = (setup(a_dir.path()), setup(b_dir.path()));
+        put(&a, json!({"id": "n1", "v": 1}));
+        sync(&a, &b, "s", "d", &SyncOptions::new()).unwrap();
+        put(&b, json!({"id": "n1", "v": 2}));
+
+        let bundle = export_bundle(&b, "s", "d").unwrap();
+        let options = SyncOptions::new();
+        let mut merge = Merge::new(&a, "s", "d", &bundle, &options).unwrap();
+        merge.plan().unwrap();
+        assert_eq!(merge.writes.len(), 1);
+
+        // Écriture locale entre le calcul du plan et son application
+        let storage = StorageEngine::new(a.clone());
+        CollectionsManager::new(&storage, "s", "d")
+            .update_document("notes", "n1", json!({"v": 3}))
+            .unwrap();
+
+        let err = merge.apply().unwrap_err();
+        assert!(err.downcast_ref::<RevisionConflict>().is_some());
+        assert_eq!(get(&a, "n1").unwrap()["v"], 3);
+    }
+
+    #[test]
+    fn test_concurrent_edits_by_strategy() {
+        let (a_dir, b_dir) = (tempdir().unwrap(), tempdir().unwrap());
+        let (a, b) = (setup(a_dir.path()), setup(b_dir.path()));
+        put(
+            &a,
+            json!({"id": "n1", "title": "t", "body": "b", "updatedAt": "2024-01-01T00:00:00Z"}),
+        );
+        sync(&a, &b, "s", "d", &SyncOptions::new()).unwrap();
+
+        // Modifications concurrentes de champs distincts
+        put(
+            &a,
+            json!({"id": "n1", "title": "t-a", "body": "b", "updatedAt": "2024-01-02T00:00:00Z"}),
+        );
+        put(
+            &b,
+            json!({"id": "n1", "title": "t", "body": "b-b", "updatedAt": "2024-01-03T00:00:00Z"}),
+        );
+        let three_way = SyncOptions::new().with_strategy(MergeStrategy::ThreeWay);
+        let report = sync(&a, &b, "s", "d", &three_way).unwrap();
+        assert_eq!(report.pulled.conflicts, 1);
+        for config in [&a, &b] {
+            let doc = get(config, "n1").unwrap();
+            assert_eq!(
+                (doc["title"].as_str(), doc["body"].as_str()),
+                (Some("t-a"), Some("b-b"))
+            );
+        }
+        let conflict = file_storage::read_document(&a, "s", "d", CONFLICTS_COLLECTION, "notes--n1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(conflict["resolution"], "merged");
+        assert_eq!(conflict["fields"], json!(["updatedAt"]));
+
+        // Dernier écrivain : le document le plus récent l'emporte en entier
+        put(
+            &a,
+            json!({"id": "n1", "title": "a", "updatedAt": "2024-02-02T00:00:00Z"}),
+        );
+        put(
+            &b,
+            json!({"id": "n1", "title": "b", "updatedAt": "2024-02-01T00:00:00Z"}),
+        );
+        sync(&a, &b, "s", "d", &SyncOptions::new()).unwrap();
+        assert_eq!(get(&b, "n1").unwrap()["title"], "a");
+    }
+
+    #[test]
+    fn test_manual_conflict_and_resolution() {
+        let (a_dir, b_dir) = (tempdir().unwrap(), tempdir().unwrap());
+        let (a, b) = (setup(a_dir.path()), setup(b_dir.path()));
+        put(&a, json!({"id": "n1", "v": 0}));
+        sync(&a, &b, "s", "d", &SyncOptions::new()).unwrap();
+        put(&a, json!({"id": "n1", "v": 1}));
+        put(&b, json!({"id": "n1", "v": 2}));
+
+        let manual = SyncOptions::new().with_strategy(MergeStrategy::Manual);
+        let report = sync(&a, &b, "s", "d", &manual).unwrap();
+        assert_eq!((report.pulled.open, report.pushed.open), (1, 1));
+        assert_eq!(get(&a, "n1").unwrap()["v"], 1);
+        assert_eq!(get(&b, "n1").unwrap()["v"], 2);
+
+        let chosen = resolve_conflict(&a, "s", "d", "notes--n1", ConflictChoice::Remote).unwrap();
+        assert_eq!(chosen.unwrap()["v"], 2);
+        assert!(resolve_conflict(&a, "s", "d", "notes--n1", ConflictChoice::Local).is_err());
+
+        // La résolution se propage et clôt le conflit resté ouvert en face
+        let report = sync(&a, &b, "s", "d", &manual).unwrap();
+        assert_eq!(report.pushed.conflicts, 0);
+        let closed = file_storage::read_document(&b, "s", "d", CONFLICTS_COLLECTION, "notes--n1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(closed["status"], "resolved");
+    }
+}
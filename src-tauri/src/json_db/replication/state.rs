@@ -0,0 +1,248 @@
+// FICHIER : src-tauri/src/json_db/replication/state.rs
+
+//! État de réplication d'une base (`<db>/_replication/state.json`) : identité
+//! de la réplique et version de chaque document connu, tenue à jour à partir
+//! du flux de changements.
+
+use super::vector::VersionVector;
+use crate::json_db::changes;
+use crate::json_db::collections::manager::CollectionsManager;
+use crate::json_db::storage::{file_storage, JsonDbConfig, StorageEngine};
+use crate::json_db::transactions::revision::REV_FIELD;
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+
+/// Version connue d'un document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocVersion {
+    pub versions: VersionVector,
+    /// Empreinte du contenu (`None` : document supprimé)
+    pub hash: Option<String>,
+    /// Date de la dernière modification (RFC 3339) : `updatedAt` du document,
+    /// ou date d'observation si le document ne l'a pas changé (suppression)
+    pub updated_at: String,
+    /// Empreinte du dernier contenu commun avec une autre réplique
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicaState {
+    /// Identifiant de la réplique
+    pub replica: String,
+    /// Dossier de la base au moment de l'attribution de l'identifiant
+    pub root: PathBuf,
+    /// Dernier événement du flux de changements pris en compte
+    pub seq: u64,
+    /// Versions par collection puis par document
+    pub docs: BTreeMap<String, BTreeMap<String, DocVersion>>,
+}
+
+fn state_path(config: &JsonDbConfig, space: &str, db: &str) -> PathBuf {
+    config
+        .db_root(space, db)
+        .join("_replication")
+        .join("state.json")
+}
+
+/// Collections internes (`_conflicts`...) : jamais répliquées
+pub(crate) fn is_replicated(collection: &str) -> bool {
+    !collection.starts_with('_')
+}
+
+/// Empreinte du contenu d'un document, indépendante de l'ordre des clés et
+/// des champs propres à chaque réplique (`_rev`, `$schema`)
+pub fn content_hash(doc: &Value) -> String {
+    let mut canonical = String::new();
+    write_canonical(doc, true, &mut canonical);
+    format!("{:x}", Sha256::digest(canonical.as_bytes()))
+}
+
+fn write_canonical(value: &Value, root: bool, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let keys: BTreeSet<&String> = map
+                .keys()
+                .filter(|k| !root || (k.as_str() != REV_FIELD && k.as_str() != "$schema"))
+                .collect();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], false, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, false, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+fn updated_at_of(doc: Option<&Value>) -> Option<String> {
+    doc.and_then(|d| d.get("updatedAt"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+impl ReplicaState {
+    /// État enregistré de la base, ou état vierge. Une base copiée ou restaurée
+    /// dans un autre dossier devient une nouvelle réplique (nouvel identifiant),
+    /// en conservant l'historique des versions.
+    pub fn load(config: &JsonDbConfig, space: &str, db: &str) -> Result<Self> {
+        let root = config.db_root(space, db);
+        let path = state_path(config, space, db);
+        let mut state = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            Self {
+                replica: String::new(),
+                root: root.clone(),
+                seq: 0,
+                docs: BTreeMap::new(),
+            }
+        };
+        if state.replica.is_empty() || state.root != root {
+            state.replica = uuid::Uuid::new_v4().to_string();
+            state.root = root;
+        }
+        Ok(state)
+    }
+
+    pub fn save(&self, config: &JsonDbConfig, space: &str, db: &str) -> Result<()> {
+        let path = state_path(config, space, db);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        file_storage::atomic_write(&path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn get(&self, collection: &str, id: &str) -> Option<&DocVersion> {
+        self.docs.get(collection).and_then(|docs| docs.get(id))
+    }
+
+    pub fn set(&mut self, collection: &str, id: &str, version: DocVersion) {
+        self.docs
+            .entry(collection.to_string())
+            .or_default()
+            .insert(id.to_string(), version);
+    }
+
+    /// Prend en compte les écritures locales : inventaire complet au premier
+    /// passage, puis documents touchés par le flux de changements depuis `seq`
+    pub fn refresh(&mut self, config: &JsonDbConfig, space: &str, db: &str) -> Result<()> {
+        // Jeton lu avant l'inventaire : une écriture concurrente est revue ensuite
+        let last = changes::last_seq(config, space, db)?;
+        if self.seq == 0 && self.docs.is_empty() {
+            let storage = StorageEngine::new(config.clone());
+            let mgr = CollectionsManager::new(&storage, space, db);
+            for collection in mgr.list_collections()? {
+                for doc in mgr.list_all(&collection)? {
+                    if let Some(id) = doc.get("id").and_then(|v| v.as_str()) {
+                        self.observe(&collection, id, Some(&doc));
+                    }
+                }
+            }
+            self.seq = last;
+            return Ok(());
+        }
+
+        let mut touched = BTreeSet::new();
+        for event in changes::read_since(config, space, db, self.seq, usize::MAX)? {
+            if is_replicated(&event.collection) {
+                touched.insert((event.collection, event.id));
+            }
+            self.seq = self.seq.max(event.seq);
+        }
+        // État courant du document : les états intermédiaires ne comptent pas
+        for (collection, id) in touched {
+            let doc = file_storage::read_document(config, space, db, &collection, &id)?;
+            self.observe(&collection, &id, doc.as_ref());
+        }
+        Ok(())
+    }
+
+    /// Enregistre l'état courant d'un document : une modification locale
+    /// incrémente le compteur de la réplique
+    pub fn observe(&mut self, collection: &str, id: &str, doc: Option<&Value>) {
+        let hash = doc.map(content_hash);
+        let now = Utc::now().to_rfc3339();
+        match self.docs.get_mut(collection).and_then(|d| d.get_mut(id)) {
+            Some(known) if known.hash == hash => {}
+            Some(known) => {
+                known.versions.increment(&self.replica);
+                known.updated_at = updated_at_of(doc)
+                    .filter(|at| *at != known.updated_at)
+                    .unwrap_or(now);
+                known.hash = hash;
+            }
+            None if doc.is_none() => {}
+            None => {
+                let mut versions = VersionVector::new();
+                versions.increment(&self.replica);
+                let version = DocVersion {
+                    versions,
+                    hash,
+                    updated_at: updated_at_of(doc).unwrap_or(now),
+                    base: None,
+                };
+                self.set(collection, id, version);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_content_hash_ignores_key_order_and_local_fields() {
+        let a = json!({"id": "x", "b": {"y": 1, "x": [1, 2]}, "_rev": 3});
+        let b = json!({"b": {"x": [1, 2], "y": 1}, "id": "x", "$schema": "db://s/d/x.json"});
+        assert_eq!(content_hash(&a), content_hash(&b));
+        assert_ne!(content_hash(&a), content_hash(&json!({"id": "x"})));
+    }
+
+    #[test]
+    fn test_observe_bumps_own_counter_on_change() {
+        let mut state = ReplicaState {
+            replica: "r1".into(),
+            root: PathBuf::new(),
+            seq: 0,
+            docs: BTreeMap::new(),
+        };
+        let doc = json!({"id": "a", "v": 1, "updatedAt": "2024-01-01T00:00:00Z"});
+        state.observe("c", "a", Some(&doc));
+        state.observe("c", "a", Some(&doc));
+        assert_eq!(state.get("c", "a").unwrap().versions.get("r1"), 1);
+
+        state.observe("c", "a", Some(&json!({"id": "a", "v": 2})));
+        let known = state.get("c", "a").unwrap();
+        assert_eq!(known.versions.get("r1"), 2);
+        assert_ne!(known.updated_at, "2024-01-01T00:00:00Z");
+
+        state.observe("c", "a", None);
+        assert!(state.get("c", "a").unwrap().hash.is_none());
+        state.observe("c", "gone", None);
+        assert!(state.get("c", "gone").is_none());
+    }
+}
@@ -0,0 +1,85 @@
+// FICHIER : src-tauri/src/json_db/replication/vector.rs
+
+//! Vecteur de versions d'un document : nombre de modifications observées par
+//! chaque réplique. Deux vecteurs dont aucun ne domine l'autre signalent des
+//! modifications concurrentes.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VersionVector(BTreeMap<String, u64>);
+
+impl VersionVector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compteur de la réplique `replica` (0 si elle n'a jamais modifié le document)
+    pub fn get(&self, replica: &str) -> u64 {
+        self.0.get(replica).copied().unwrap_or(0)
+    }
+
+    /// Enregistre une modification faite par `replica`
+    pub fn increment(&mut self, replica: &str) {
+        *self.0.entry(replica.to_string()).or_insert(0) += 1;
+    }
+
+    /// Maximum composante par composante
+    pub fn merge(&mut self, other: &VersionVector) {
+        for (replica, count) in &other.0 {
+            let entry = self.0.entry(replica.clone()).or_insert(0);
+            *entry = (*entry).max(*count);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Ordre partiel : `None` pour des versions concurrentes
+impl PartialOrd for VersionVector {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let mut less = false;
+        let mut greater = false;
+        for replica in self.0.keys().chain(other.0.keys()) {
+            match self.get(replica).cmp(&other.get(replica)) {
+                Ordering::Less => less = true,
+                Ordering::Greater => greater = true,
+                Ordering::Equal => {}
+            }
+        }
+        match (less, greater) {
+            (false, false) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (true, true) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_ordering_and_merge() {
+        let mut a = VersionVector::new();
+        a.increment("a");
+        let mut b = a.clone();
+        assert_eq!(a.partial_cmp(&b), Some(Ordering::Equal));
+
+        b.increment("b");
+        assert!(a < b);
+
+        a.increment("a");
+        assert_eq!(a.partial_cmp(&b), None);
+
+        a.merge(&b);
+        assert_eq!((a.get("a"), a.get("b"), a.get("c")), (2, 1, 0));
+        assert!(a > b);
+    }
+}
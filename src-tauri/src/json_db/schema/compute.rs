@@ -0,0 +1,338 @@
+// FICHIER : src-tauri/src/json_db/schema/compute.rs
+
+//! Champs calculés déclarés dans les schémas (`x_compute`).
+//!
+//! ```json
+//! "createdAt": {
+//!   "type": "string",
+//!   "x_compute": { "update": "if_missing", "expr": "now" }
+//! }
+//! ```
+//!
+//! `expr` est une expression GenRules (`rules_engine::Expr`) évaluée sur le
+//! document entier : valeur par défaut (`{"val": ...}`), `uuid`, `now`,
+//! `concat`, `lookup`... Les annotations sont recherchées dans `properties`
+//! (récursivement), en suivant `$ref` et `allOf`. Les branches conditionnelles
+//! (`anyOf`, `oneOf`, `if/then/else`) ne sont pas parcourues.
+
+use super::registry::SchemaRegistry;
+use crate::rules_engine::{Analyzer, DataProvider, EvalError, Evaluator, Expr};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Quand recalculer le champ
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdatePolicy {
+    /// Seulement si le champ est absent (identifiants, dates de création, défauts)
+    #[default]
+    IfMissing,
+    /// À chaque écriture (dates de mise à jour, champs dérivés)
+    Always,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ComputeSpec {
+    #[serde(default)]
+    update: UpdatePolicy,
+    #[serde(default)]
+    expr: Option<Expr>,
+    /// Ancien format `plan/v1` (`{"op": "uuid_v4" | "now_rfc3339" | "const"}`),
+    /// encore présent dans les schémas copiés par les bases existantes
+    #[serde(default)]
+    plan: Option<Value>,
+}
+
+impl ComputeSpec {
+    fn into_expr(self) -> Result<Expr> {
+        if let Some(expr) = self.expr {
+            return Ok(expr);
+        }
+        let plan = self.plan.ok_or_else(|| anyhow!("champ 'expr' manquant"))?;
+        match plan.get("op").and_then(|v| v.as_str()) {
+            Some("uuid_v4") => Ok(Expr::Uuid),
+            Some("now_rfc3339") => Ok(Expr::Now),
+            Some("const") => Ok(Expr::Val(plan.get("value").cloned().unwrap_or(Value::Null))),
+            op => Err(anyhow!("opération plan/v1 inconnue : {:?}", op)),
+        }
+    }
+}
+
+/// Un champ `x_compute` trouvé dans le schéma
+#[derive(Debug, Clone)]
+pub struct ComputedField {
+    /// Chemin du champ dans le document (noms de propriétés)
+    pub path: Vec<String>,
+    pub update: UpdatePolicy,
+    pub expr: Expr,
+}
+
+/// Liste les champs calculés d'un schéma, triés selon leurs dépendances
+pub fn collect_computed_fields(
+    reg: &SchemaRegistry,
+    schema_uri: &str,
+) -> Result<Vec<ComputedField>> {
+    let root = reg
+        .get_by_uri(schema_uri)
+        .ok_or_else(|| anyhow!("Schema not found in registry: {}", schema_uri))?;
+    let mut fields = Vec::new();
+    let mut path = Vec::new();
+    let mut visiting = HashSet::new();
+    walk(root, schema_uri, reg, &mut path, &mut visiting, &mut fields)?;
+    Ok(sort_by_dependencies(fields))
+}
+
+fn walk(
+    schema: &Value,
+    uri: &str,
+    reg: &SchemaRegistry,
+    path: &mut Vec<String>,
+    visiting: &mut HashSet<usize>,
+    fields: &mut Vec<ComputedField>,
+) -> Result<()> {
+    let Some(map) = schema.as_object() else {
+        return Ok(());
+    };
+    // Schémas récursifs : un nœud déjà en cours de parcours n'est pas redescendu
+    let node = schema as *const Value as usize;
+    if !visiting.insert(node) {
+        return Ok(());
+    }
+
+    if let Some(spec) = map.get("x_compute") {
+        let field = serde_json::from_value::<ComputeSpec>(spec.clone())
+            .map_err(anyhow::Error::from)
+            .and_then(|spec| {
+                Ok(ComputedField {
+                    path: path.clone(),
+                    update: spec.update,
+                    expr: spec.into_expr()?,
+                })
+            })
+            .map_err(|e| {
+                anyhow!(
+                    "x_compute invalide sur '{}' ({}): {}",
+                    path.join("."),
+                    uri,
+                    e
+                )
+            })?;
+        fields.push(field);
+    }
+    if let Some(ref_str) = map.get("$ref").and_then(|v| v.as_str()) {
+        let (target, target_uri) = reg.resolve_ref(ref_str, uri)?;
+        walk(target, &target_uri, reg, path, visiting, fields)?;
+    }
+    for sub in map
+        .get("allOf")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        walk(sub, uri, reg, path, visiting, fields)?;
+    }
+    if let Some(props) = map.get("properties").and_then(|v| v.as_object()) {
+        for (key, sub) in props {
+            path.push(key.clone());
+            walk(sub, uri, reg, path, visiting, fields)?;
+            path.pop();
+        }
+    }
+
+    visiting.remove(&node);
+    Ok(())
+}
+
+/// Évalue les champs calculés sur le document, dans l'ordre des dépendances.
+/// Un champ imbriqué n'est calculé que si son objet parent existe. Une
+/// expression dont une variable manque, ou qui vaut `null` (lookup
+/// introuvable), laisse le champ tel quel : la validation signalera l'absence.
+pub fn apply_computed_fields(
+    doc: &mut Value,
+    fields: &[ComputedField],
+    provider: &dyn DataProvider,
+) -> Result<()> {
+    for field in fields {
+        let Some((key, parents)) = field.path.split_last() else {
+            continue;
+        };
+        let present = match parent_object(doc, parents) {
+            Some(parent) => parent.contains_key(key),
+            None => continue,
+        };
+        if present && field.update == UpdatePolicy::IfMissing {
+            continue;
+        }
+
+        let value = match Evaluator::evaluate(&field.expr, doc, provider) {
+            Ok(Value::Null) | Err(EvalError::VarNotFound(_)) => continue,
+            Ok(value) => value,
+            Err(e) => return Err(anyhow!("x_compute '{}': {}", field.path.join("."), e)),
+        };
+        if let Some(parent) = parent_object_mut(doc, parents) {
+            parent.insert(key.clone(), value);
+        }
+    }
+    Ok(())
+}
+
+/// Place chaque champ après les champs calculés qu'il lit (ordre stable ;
+/// en cas de cycle, les champs restants gardent leur ordre)
+fn sort_by_dependencies(fields: Vec<ComputedField>) -> Vec<ComputedField> {
+    let paths: Vec<String> = fields.iter().map(|f| f.path.join(".")).collect();
+    let deps: Vec<HashSet<String>> = fields
+        .iter()
+        .map(|f| {
+            Analyzer::get_dependencies(&f.expr)
+                .into_iter()
+                .map(|d| d.trim_start_matches('/').replace('/', "."))
+                .collect()
+        })
+        .collect();
+
+    let mut remaining: Vec<usize> = (0..fields.len()).collect();
+    let mut order = Vec::with_capacity(fields.len());
+    while !remaining.is_empty() {
+        let ready = remaining.iter().position(|&i| {
+            !remaining
+                .iter()
+                .any(|&j| j != i && deps[i].contains(&paths[j]))
+        });
+        order.push(remaining.remove(ready.unwrap_or(0)));
+    }
+
+    let mut slots: Vec<Option<ComputedField>> = fields.into_iter().map(Some).collect();
+    order.into_iter().filter_map(|i| slots[i].take()).collect()
+}
+
+fn parent_object<'d>(
+    doc: &'d Value,
+    parents: &[String],
+) -> Option<&'d serde_json::Map<String, Value>> {
+    parents
+        .iter()
+        .try_fold(doc, |current, key| current.get(key))?
+        .as_object()
+}
+
+fn parent_object_mut<'d>(
+    doc: &'d mut Value,
+    parents: &[String],
+) -> Option<&'d mut serde_json::Map<String, Value>> {
+    parents
+        .iter()
+        .try_fold(doc, |current, key| current.get_mut(key))?
+        .as_object_mut()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules_engine::NoOpDataProvider;
+    use serde_json::json;
+
+    const ROOT: &str = "db://s/d/schemas/v1/test/root.schema.json";
+
+    struct Users;
+    impl DataProvider for Users {
+        fn get_value(&self, collection: &str, id: &str, field: &str) -> Option<Value> {
+            (collection == "users" && id == "u1" && field == "email").then(|| json!("a@b.io"))
+        }
+    }
+
+    fn registry(schema: Value) -> SchemaRegistry {
+        let mut reg = SchemaRegistry::new();
+        reg.register(ROOT.to_string(), schema);
+        reg.register(
+            "db://s/d/schemas/v1/common/base.schema.json".to_string(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "id": { "$ref": "#/$defs/id" },
+                    "updatedAt": { "type": "string", "x_compute": { "update": "always", "expr": "now" } }
+                },
+                "$defs": { "id": { "type": "string", "x_compute": { "expr": "uuid" } } }
+            }),
+        );
+        reg
+    }
+
+    #[test]
+    fn test_collects_through_refs_and_nested_properties() {
+        let reg = registry(json!({
+            "allOf": [{ "$ref": "../common/base.schema.json" }],
+            "properties": {
+                "meta": { "properties": { "status": { "x_compute": { "expr": { "val": "draft" } } } } },
+                "children": { "type": "array", "items": { "$ref": "#" } }
+            }
+        }));
+        let fields = collect_computed_fields(&reg, ROOT).unwrap();
+        let paths: Vec<String> = fields.iter().map(|f| f.path.join(".")).collect();
+        assert_eq!(paths, vec!["id", "updatedAt", "meta.status"]);
+        assert_eq!(fields[1].update, UpdatePolicy::Always);
+    }
+
+    #[test]
+    fn test_apply_defaults_concat_and_lookups() {
+        let reg = registry(json!({
+            "allOf": [{ "$ref": "../common/base.schema.json" }],
+            "properties": {
+                "kind": { "x_compute": { "expr": { "val": "human" } } },
+                "label": { "x_compute": { "update": "always", "expr": { "concat": [{ "var": "kind" }, { "val": ":" }, { "var": "name" }] } } },
+                "code": { "x_compute": { "expr": { "upper": { "var": "label" } } } },
+                "ownerEmail": { "x_compute": { "expr": { "lookup": { "collection": "users", "id": { "var": "owner" }, "field": "email" } } } },
+                "meta": { "properties": { "status": { "x_compute": { "expr": { "val": "draft" } } } } }
+            }
+        }));
+        let fields = collect_computed_fields(&reg, ROOT).unwrap();
+
+        let mut doc = json!({ "id": "keep", "name": "alice", "owner": "u1", "label": "old" });
+        apply_computed_fields(&mut doc, &fields, &Users).unwrap();
+        assert_eq!(doc["id"], "keep");
+        assert!(doc["updatedAt"].is_string());
+        assert_eq!(doc["kind"], "human");
+        assert_eq!(doc["label"], "human:alice");
+        // `code` lit `label` : calculé après lui
+        assert_eq!(doc["code"], "HUMAN:ALICE");
+        assert_eq!(doc["ownerEmail"], "a@b.io");
+        // Parent absent : pas de création implicite
+        assert!(doc.get("meta").is_none());
+
+        // Variable manquante ou lookup introuvable : champ laissé absent
+        let mut doc = json!({ "owner": "nobody", "meta": {} });
+        apply_computed_fields(&mut doc, &fields, &NoOpDataProvider).unwrap();
+        assert_eq!(doc["id"].as_str().map(|s| s.len()), Some(36));
+        assert!(doc.get("label").is_none());
+        assert!(doc.get("ownerEmail").is_none());
+        assert_eq!(doc["meta"]["status"], "draft");
+    }
+
+    #[test]
+    fn test_legacy_plans_and_invalid_annotations() {
+        let reg = registry(json!({
+            "properties": {
+                "at": { "x_compute": { "engine": "plan/v1", "update": "if_missing", "plan": { "op": "now_rfc3339" } } },
+                "v": { "x_compute": { "plan": { "op": "const", "value": "1.0.0" } } }
+            }
+        }));
+        let fields = collect_computed_fields(&reg, ROOT).unwrap();
+        assert_eq!(fields[0].expr, Expr::Now);
+        assert_eq!(fields[1].expr, Expr::Val(json!("1.0.0")));
+
+        for bad in [
+            json!({ "expr": { "unknown_op": 1 } }),
+            json!({ "plan": { "op": "sha256" } }),
+            json!({ "update": "sometimes", "expr": "now" }),
+        ] {
+            let reg = registry(json!({ "properties": { "x": { "x_compute": bad } } }));
+            let err = collect_computed_fields(&reg, ROOT).unwrap_err();
+            assert!(
+                err.to_string().contains("x_compute invalide sur 'x'"),
+                "{}",
+                err
+            );
+        }
+    }
+}
@@ -1,10 +1,11 @@
 // FICHIER : src-tauri/src/json_db/schema/registry.rs
 
 use crate::json_db::storage::JsonDbConfig;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::fs;
+use std::path::{Component, Path, PathBuf};
 use walkdir::WalkDir;
 
 #[derive(Debug, Clone)]
@@ -72,6 +73,38 @@ impl SchemaRegistry {
         self.by_uri.get(uri)
     }
 
+    /// Schéma déclarant cet `$id` (ex. `https://raise.local/schemas/v1/...`), avec son URI `db://`
+    pub fn find_by_id(&self, id: &str) -> Option<(&Value, String)> {
+        self.by_uri.iter().find_map(|(uri, schema)| {
+            (schema.get("$id").and_then(|v| v.as_str()) == Some(id)).then(|| (schema, uri.clone()))
+        })
+    }
+
+    /// Résout un `$ref` (pointeur JSON, `$anchor`, chemin relatif ou `$id` absolu)
+    /// et renvoie le sous-schéma visé avec l'URI du fichier qui le contient
+    pub fn resolve_ref(&self, ref_str: &str, current_uri: &str) -> Result<(&Value, String)> {
+        let (path, fragment) = split_uri_fragment(ref_str);
+        let file_uri = resolve_path_uri(current_uri, path);
+        let fragment = fragment.map(|f| &f[1..]);
+
+        let (target_root, file_uri) = match self.get_by_uri(&file_uri) {
+            Some(root) => (root, file_uri),
+            None => self
+                .find_by_id(&file_uri)
+                .ok_or_else(|| anyhow!("Ref schema not found: {}", file_uri))?,
+        };
+
+        let target_schema = match fragment {
+            None | Some("") => target_root,
+            Some(pointer) if pointer.starts_with('/') => target_root
+                .pointer(pointer)
+                .ok_or_else(|| anyhow!("Pointer {} not found in {}", pointer, file_uri))?,
+            Some(anchor) => find_anchor(target_root, anchor)
+                .ok_or_else(|| anyhow!("Anchor {} not found in {}", anchor, file_uri))?,
+        };
+        Ok((target_schema, file_uri))
+    }
+
     pub fn list_uris(&self) -> Vec<String> {
         self.by_uri.keys().cloned().collect()
     }
@@ -81,3 +114,69 @@ impl SchemaRegistry {
         format!("{}{}", self.base_prefix, relative_path)
     }
 }
+
+fn find_anchor<'s>(schema: &'s Value, anchor: &str) -> Option<&'s Value> {
+    match schema {
+        Value::Object(map) => {
+            if map.get("$anchor").and_then(|v| v.as_str()) == Some(anchor) {
+                return Some(schema);
+            }
+            map.values().find_map(|v| find_anchor(v, anchor))
+        }
+        Value::Array(arr) => arr.iter().find_map(|v| find_anchor(v, anchor)),
+        _ => None,
+    }
+}
+
+fn split_uri_fragment(uri: &str) -> (&str, Option<&str>) {
+    if let Some(idx) = uri.find('#') {
+        (&uri[0..idx], Some(&uri[idx..]))
+    } else {
+        (uri, None)
+    }
+}
+
+fn resolve_path_uri(base: &str, target_path: &str) -> String {
+    if target_path.contains("://") {
+        return target_path.to_string();
+    }
+    if target_path.is_empty() {
+        return base.to_string();
+    }
+
+    let (prefix, base_path_str) = if let Some(stripped) = base.strip_prefix("db://") {
+        ("db://", stripped)
+    } else {
+        ("", base)
+    };
+
+    let base_path = Path::new(base_path_str);
+    let parent = base_path.parent().unwrap_or(Path::new(""));
+    let joined = parent.join(target_path);
+    let normalized = normalize_path(&joined);
+
+    format!(
+        "{}{}",
+        prefix,
+        normalized.to_string_lossy().replace("\\", "/")
+    )
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                components.pop();
+            }
+            Component::Normal(c) => components.push(c),
+            Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    let mut result = PathBuf::new();
+    for c in components {
+        result.push(c);
+    }
+    result
+}
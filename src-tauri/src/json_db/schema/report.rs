@@ -0,0 +1,71 @@
+// FICHIER : src-tauri/src/json_db/schema/report.rs
+
+//! Rapport de validation : toutes les violations d'un document, localisées.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// Une violation d'un mot-clé du schéma
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    /// Emplacement dans le document (JSON Pointer, "" = racine).
+    /// Pour `required`, pointe vers la propriété manquante.
+    pub instance_path: String,
+    /// Chemin du mot-clé dans le schéma (ex : "/allOf/0/$ref/properties/name/minLength")
+    pub keyword_path: String,
+    /// Mot-clé en échec (`type`, `required`, `pattern`...)
+    pub keyword: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual: Option<Value>,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.instance_path.is_empty() {
+            "/"
+        } else {
+            &self.instance_path
+        };
+        write!(f, "{} : {}", path, self.message)
+    }
+}
+
+/// Résultat complet d'une validation.
+/// Renvoyé en erreur par `SchemaValidator::validate` ; récupérable via
+/// `err.downcast_ref::<ValidationReport>()`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, thiserror::Error)]
+pub struct ValidationReport {
+    /// Schéma racine utilisé
+    pub schema_uri: String,
+    pub errors: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Violations situées sur `pointer` ou à l'intérieur
+    pub fn errors_at<'a>(&'a self, pointer: &'a str) -> impl Iterator<Item = &'a ValidationIssue> {
+        self.errors.iter().filter(move |e| {
+            e.instance_path == pointer
+                || e.instance_path
+                    .strip_prefix(pointer)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Document invalide ({} erreur(s))", self.errors.len())?;
+        for issue in &self.errors {
+            write!(f, "\n  - {}", issue)?;
+        }
+        Ok(())
+    }
+}
@@ -1,16 +1,37 @@
 // FICHIER : src-tauri/src/json_db/schema/validator.rs
 
+//! Validation JSON Schema draft 2020-12.
+//!
+//! Toutes les violations sont collectées dans un `ValidationReport`, chacune
+//! localisée dans le document (JSON Pointer) et dans le schéma (chemin du
+//! mot-clé). Chaque nœud renvoie les propriétés / éléments qu'il a évalués
+//! (annotations), remontés à travers `$ref`, `allOf`, `anyOf`, `oneOf`,
+//! `if/then/else` et `dependentSchemas` pour `unevaluatedProperties` et
+//! `unevaluatedItems`.
+
+use super::compute::{apply_computed_fields, collect_computed_fields};
 use super::registry::SchemaRegistry;
+use super::report::{ValidationIssue, ValidationReport};
+use crate::json_db::transactions::revision::REV_FIELD;
+use crate::rules_engine::{DataProvider, NoOpDataProvider};
 use anyhow::{anyhow, Result};
 use regex::Regex;
-use serde_json::Value;
-use std::path::{Component, Path, PathBuf};
+use serde_json::{json, Map, Value};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::{Arc, Mutex};
+
+/// Champs injectés par le système, tolérés même si le schéma est fermé
+const SYSTEM_FIELDS: [&str; 2] = ["$schema", REV_FIELD];
 
 #[derive(Debug, Clone)]
 pub struct SchemaValidator {
     root_uri: String,
     schema: Value,
     reg: SchemaRegistry,
+    /// Regex `pattern` / `patternProperties` déjà compilées
+    patterns: Arc<Mutex<HashMap<String, Regex>>>,
 }
 
 impl SchemaValidator {
@@ -23,220 +44,1139 @@ impl SchemaValidator {
             root_uri: root_uri.to_string(),
             schema,
             reg: reg.clone(),
+            patterns: Arc::new(Mutex::new(HashMap::new())),
         })
     }
 
+    /// Évalue les champs `x_compute` du schéma (voir `compute.rs`).
+    /// `provider` donne accès aux autres collections pour les `lookup`.
+    pub fn compute(&self, instance: &mut Value, provider: &dyn DataProvider) -> Result<()> {
+        let fields = collect_computed_fields(&self.reg, &self.root_uri)?;
+        apply_computed_fields(instance, &fields, provider)
+    }
+
+    /// `compute` sans accès base (les `lookup` restent vides) puis `validate`
     pub fn compute_then_validate(&self, instance: &mut Value) -> Result<()> {
-        // L'ancien moteur "x_compute" est désactivé.
-        // Les calculs sont désormais gérés par le Rules Engine dans manager.rs avant d'arriver ici.
+        self.compute(instance, &NoOpDataProvider)?;
         self.validate(instance)
     }
 
+    /// Valide le document ; en cas d'échec l'erreur est le `ValidationReport` complet
     pub fn validate(&self, instance: &Value) -> Result<()> {
-        validate_node(instance, &self.schema, &self.reg, &self.root_uri)
+        let report = self.report(instance);
+        if report.is_valid() {
+            Ok(())
+        } else {
+            Err(report.into())
+        }
+    }
+
+    /// Collecte toutes les violations du document
+    pub fn report(&self, instance: &Value) -> ValidationReport {
+        let active_refs = RefCell::new(HashSet::new());
+        let ctx = Ctx {
+            reg: &self.reg,
+            patterns: &self.patterns,
+            active_refs: &active_refs,
+            uri: self.root_uri.clone(),
+            instance_path: String::new(),
+            keyword_path: String::new(),
+        };
+        let mut errors = Vec::new();
+        validate_node(instance, &self.schema, &ctx, &mut errors);
+        ValidationReport {
+            schema_uri: self.root_uri.clone(),
+            errors,
+        }
+    }
+}
+
+/// Position courante dans le document et dans le schéma
+struct Ctx<'a> {
+    reg: &'a SchemaRegistry,
+    patterns: &'a Mutex<HashMap<String, Regex>>,
+    /// `$ref` en cours d'évaluation : (schéma cible, position dans le document)
+    active_refs: &'a RefCell<HashSet<(usize, String)>>,
+    /// Fichier du schéma courant (base de résolution des `$ref`)
+    uri: String,
+    instance_path: String,
+    keyword_path: String,
+}
+
+impl<'a> Ctx<'a> {
+    /// Descend dans le schéma (`/allOf/0`, `/properties/name`...)
+    fn keyword(&self, segments: &[&str]) -> Ctx<'a> {
+        let mut keyword_path = self.keyword_path.clone();
+        for segment in segments {
+            keyword_path.push('/');
+            keyword_path.push_str(&escape_pointer(segment));
+        }
+        Ctx {
+            reg: self.reg,
+            patterns: self.patterns,
+            active_refs: self.active_refs,
+            uri: self.uri.clone(),
+            instance_path: self.instance_path.clone(),
+            keyword_path,
+        }
     }
+
+    /// Descend dans le document (propriété ou index)
+    fn child(mut self, key: &str) -> Ctx<'a> {
+        self.instance_path.push('/');
+        self.instance_path.push_str(&escape_pointer(key));
+        self
+    }
+
+    /// Regex compilée une seule fois par validateur
+    fn pattern(&self, pattern: &str) -> Result<Regex> {
+        let mut patterns = self.patterns.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(re) = patterns.get(pattern) {
+            return Ok(re.clone());
+        }
+        let re = Regex::new(pattern).map_err(|e| anyhow!("Invalid regex '{}': {}", pattern, e))?;
+        patterns.insert(pattern.to_string(), re.clone());
+        Ok(re)
+    }
+
+    fn issue(
+        &self,
+        out: &mut Vec<ValidationIssue>,
+        keyword: &str,
+        message: String,
+        expected: Option<Value>,
+        actual: Option<Value>,
+    ) {
+        out.push(ValidationIssue {
+            instance_path: self.instance_path.clone(),
+            keyword_path: format!("{}/{}", self.keyword_path, escape_pointer(keyword)),
+            keyword: keyword.to_string(),
+            message,
+            expected,
+            actual,
+        });
+    }
+}
+
+fn escape_pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+/// Propriétés et éléments évalués par un schéma sur l'instance courante
+#[derive(Debug, Default)]
+struct Evaluated {
+    props: HashSet<String>,
+    items: HashSet<usize>,
+}
+
+impl Evaluated {
+    fn merge(&mut self, other: Evaluated) {
+        self.props.extend(other.props);
+        self.items.extend(other.items);
+    }
+}
+
+/// Évalue un sous-schéma sans rapporter ses erreurs (combinateurs, `contains`)
+fn probe(instance: &Value, schema: &Value, ctx: &Ctx) -> (Evaluated, Vec<ValidationIssue>) {
+    let mut errors = Vec::new();
+    let evaluated = validate_node(instance, schema, ctx, &mut errors);
+    (evaluated, errors)
+}
+
+fn summarize(errors: &[ValidationIssue]) -> String {
+    errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join(" ; ")
 }
 
 fn validate_node(
     instance: &Value,
     schema: &Value,
-    reg: &SchemaRegistry,
-    current_uri: &str,
-) -> Result<()> {
+    ctx: &Ctx,
+    out: &mut Vec<ValidationIssue>,
+) -> Evaluated {
+    let schema = match schema {
+        Value::Bool(true) => return Evaluated::default(),
+        Value::Bool(false) => {
+            ctx.issue(
+                out,
+                "false",
+                "No value allowed here (schema false)".to_string(),
+                None,
+                Some(instance.clone()),
+            );
+            return Evaluated::default();
+        }
+        Value::Object(map) => map,
+        _ => return Evaluated::default(),
+    };
+    let mut evaluated = Evaluated::default();
+
+    // 1. Référence (appliquée avec les mots-clés voisins, comme en 2020-12)
     if let Some(ref_str) = schema.get("$ref").and_then(|v| v.as_str()) {
-        let (file_uri, fragment) = if ref_str.starts_with('#') {
-            (current_uri.to_string(), Some(ref_str.to_string()))
-        } else {
-            let resolved = resolve_path_uri(current_uri, ref_str);
-            let (f, frag) = split_uri_fragment(&resolved);
-            (f.to_string(), frag.map(|s| s.to_string()))
-        };
+        match ctx.reg.resolve_ref(ref_str, &ctx.uri) {
+            Ok((target, target_uri)) => {
+                // Même schéma sur la même position : la récursion ne progresse plus
+                let key = (target as *const Value as usize, ctx.instance_path.clone());
+                if ctx.active_refs.borrow_mut().insert(key.clone()) {
+                    let mut sub = ctx.keyword(&["$ref"]);
+                    sub.uri = target_uri;
+                    evaluated.merge(validate_node(instance, target, &sub, out));
+                    ctx.active_refs.borrow_mut().remove(&key);
+                } else {
+                    ctx.issue(
+                        out,
+                        "$ref",
+                        format!("Circular $ref '{}' at the same location", ref_str),
+                        Some(json!(ref_str)),
+                        None,
+                    );
+                }
+            }
+            Err(e) => ctx.issue(out, "$ref", e.to_string(), Some(json!(ref_str)), None),
+        }
+    }
 
-        let target_root = reg
-            .get_by_uri(&file_uri)
-            .ok_or_else(|| anyhow!("Ref schema not found: {}", file_uri))?;
+    // 2. Type, enum, const
+    if let Some(t) = schema.get("type") {
+        check_type(instance, t, ctx, out);
+    }
+    if let Some(values) = schema.get("enum").and_then(|v| v.as_array()) {
+        if !values.iter().any(|v| json_eq(v, instance)) {
+            ctx.issue(
+                out,
+                "enum",
+                format!(
+                    "Value {} not in enum {}",
+                    instance,
+                    Value::Array(values.clone())
+                ),
+                Some(Value::Array(values.clone())),
+                Some(instance.clone()),
+            );
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if !json_eq(expected, instance) {
+            ctx.issue(
+                out,
+                "const",
+                format!("Expected const {}, got {}", expected, instance),
+                Some(expected.clone()),
+                Some(instance.clone()),
+            );
+        }
+    }
 
-        let target_schema = if let Some(frag) = fragment {
-            let pointer = frag.replace("#", "");
-            target_root
-                .pointer(&pointer)
-                .ok_or_else(|| anyhow!("Pointer {} not found in {}", pointer, file_uri))?
-        } else {
-            target_root
-        };
+    // 3. Combinateurs
+    if let Some(subs) = schema.get("allOf").and_then(|v| v.as_array()) {
+        for (i, sub) in subs.iter().enumerate() {
+            let sub_ctx = ctx.keyword(&["allOf", &i.to_string()]);
+            evaluated.merge(validate_node(instance, sub, &sub_ctx, out));
+        }
+    }
+    if let Some(subs) = schema.get("anyOf").and_then(|v| v.as_array()) {
+        let mut failures = Vec::new();
+        for (i, sub) in subs.iter().enumerate() {
+            let (ev, errors) = probe(instance, sub, &ctx.keyword(&["anyOf", &i.to_string()]));
+            if errors.is_empty() {
+                evaluated.merge(ev);
+            } else {
+                failures.push(summarize(&errors));
+            }
+        }
+        if !subs.is_empty() && failures.len() == subs.len() {
+            ctx.issue(
+                out,
+                "anyOf",
+                format!(
+                    "Value does not match any schema in anyOf ({})",
+                    failures.join(" | ")
+                ),
+                None,
+                Some(instance.clone()),
+            );
+        }
+    }
+    if let Some(subs) = schema.get("oneOf").and_then(|v| v.as_array()) {
+        let mut matched = Vec::new();
+        let mut failures = Vec::new();
+        for (i, sub) in subs.iter().enumerate() {
+            let (ev, errors) = probe(instance, sub, &ctx.keyword(&["oneOf", &i.to_string()]));
+            if errors.is_empty() {
+                matched.push((i, ev));
+            } else {
+                failures.push(summarize(&errors));
+            }
+        }
+        match matched.len() {
+            1 => evaluated.merge(matched.pop().unwrap().1),
+            0 => ctx.issue(
+                out,
+                "oneOf",
+                format!(
+                    "Value does not match any schema in oneOf ({})",
+                    failures.join(" | ")
+                ),
+                None,
+                Some(instance.clone()),
+            ),
+            _ => {
+                let indexes: Vec<String> = matched.iter().map(|(i, _)| i.to_string()).collect();
+                ctx.issue(
+                    out,
+                    "oneOf",
+                    format!(
+                        "Value matches several schemas in oneOf ({}), expected exactly one",
+                        indexes.join(", ")
+                    ),
+                    None,
+                    Some(instance.clone()),
+                );
+            }
+        }
+    }
+    if let Some(sub) = schema.get("not") {
+        if probe(instance, sub, &ctx.keyword(&["not"])).1.is_empty() {
+            ctx.issue(
+                out,
+                "not",
+                "Value must not match the 'not' schema".to_string(),
+                None,
+                Some(instance.clone()),
+            );
+        }
+    }
+    if let Some(condition) = schema.get("if") {
+        let (ev, errors) = probe(instance, condition, &ctx.keyword(&["if"]));
+        if errors.is_empty() {
+            evaluated.merge(ev);
+            if let Some(then) = schema.get("then") {
+                evaluated.merge(validate_node(instance, then, &ctx.keyword(&["then"]), out));
+            }
+        } else if let Some(otherwise) = schema.get("else") {
+            evaluated.merge(validate_node(
+                instance,
+                otherwise,
+                &ctx.keyword(&["else"]),
+                out,
+            ));
+        }
+    }
 
-        return validate_node(instance, target_schema, reg, &file_uri);
+    // 4. Mots-clés spécifiques au type de l'instance
+    match instance {
+        Value::String(s) => validate_string(s, schema, ctx, out),
+        Value::Number(_) => validate_number(instance, schema, ctx, out),
+        Value::Array(arr) => validate_array(arr, schema, ctx, out, &mut evaluated),
+        Value::Object(obj) => validate_object(instance, obj, schema, ctx, out, &mut evaluated),
+        _ => {}
     }
+    evaluated
+}
 
-    if let Some(t) = schema.get("type").and_then(|v| v.as_str()) {
-        match t {
-            "object" => {
-                if !instance.is_object() {
-                    return Err(anyhow!("Expected object, got {:?}", instance));
-                }
-                validate_object(instance, schema, reg, current_uri)?;
-            }
-            "string" => {
-                if !instance.is_string() {
-                    return Err(anyhow!("Expected string"));
-                }
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) if is_integer(instance) => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn check_type(instance: &Value, t: &Value, ctx: &Ctx, out: &mut Vec<ValidationIssue>) {
+    let matches = |name: &str| match name {
+        "object" => instance.is_object(),
+        "string" => instance.is_string(),
+        "number" => instance.is_number(),
+        "integer" => is_integer(instance),
+        "boolean" => instance.is_boolean(),
+        "array" => instance.is_array(),
+        "null" => instance.is_null(),
+        _ => true,
+    };
+    let message = match t {
+        Value::String(name) if !matches(name) => {
+            format!("Expected {}, got {}", name, type_name(instance))
+        }
+        Value::Array(names) if !names.iter().filter_map(|n| n.as_str()).any(matches) => {
+            format!("Expected one of types {}, got {}", t, type_name(instance))
+        }
+        _ => return,
+    };
+    ctx.issue(
+        out,
+        "type",
+        message,
+        Some(t.clone()),
+        Some(json!(type_name(instance))),
+    );
+}
+
+fn is_integer(instance: &Value) -> bool {
+    instance.is_i64()
+        || instance.is_u64()
+        || instance
+            .as_f64()
+            .is_some_and(|f| f.is_finite() && f.fract() == 0.0)
+}
+
+/// Égalité JSON au sens du schéma (1 == 1.0)
+fn json_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => match (x.as_i64(), y.as_i64()) {
+            (Some(x), Some(y)) => x == y,
+            _ => match (x.as_u64(), y.as_u64()) {
+                (Some(x), Some(y)) => x == y,
+                _ => x.as_f64() == y.as_f64(),
+            },
+        },
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(a, b)| json_eq(a, b))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(k, v)| y.get(k).is_some_and(|other| json_eq(v, other)))
+        }
+        _ => a == b,
+    }
+}
+
+fn validate_string(
+    s: &str,
+    schema: &Map<String, Value>,
+    ctx: &Ctx,
+    out: &mut Vec<ValidationIssue>,
+) {
+    let len = s.chars().count() as u64;
+    if let Some(min) = schema.get("minLength").and_then(|v| v.as_u64()) {
+        if len < min {
+            ctx.issue(
+                out,
+                "minLength",
+                format!("String shorter than minLength {}", min),
+                Some(json!(min)),
+                Some(json!(len)),
+            );
+        }
+    }
+    if let Some(max) = schema.get("maxLength").and_then(|v| v.as_u64()) {
+        if len > max {
+            ctx.issue(
+                out,
+                "maxLength",
+                format!("String longer than maxLength {}", max),
+                Some(json!(max)),
+                Some(json!(len)),
+            );
+        }
+    }
+    if let Some(pattern) = schema.get("pattern").and_then(|v| v.as_str()) {
+        match ctx.pattern(pattern) {
+            Ok(re) if re.is_match(s) => {}
+            Ok(_) => ctx.issue(
+                out,
+                "pattern",
+                format!("String '{}' does not match pattern {}", s, pattern),
+                Some(json!(pattern)),
+                Some(json!(s)),
+            ),
+            Err(e) => ctx.issue(out, "pattern", e.to_string(), Some(json!(pattern)), None),
+        }
+    }
+    if let Some(format) = schema.get("format").and_then(|v| v.as_str()) {
+        if !check_format(format, s) {
+            ctx.issue(
+                out,
+                "format",
+                format!("String '{}' is not a valid {}", s, format),
+                Some(json!(format)),
+                Some(json!(s)),
+            );
+        }
+    }
+}
+
+/// Formats vérifiés. Les formats inconnus (ex. `markdown`) sont de simples annotations.
+fn check_format(format: &str, s: &str) -> bool {
+    match format {
+        "date-time" => chrono::DateTime::parse_from_rfc3339(s).is_ok(),
+        "date" => chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok(),
+        "time" => chrono::DateTime::parse_from_rfc3339(&format!("1970-01-01T{}", s)).is_ok(),
+        "email" => match s.rsplit_once('@') {
+            Some((local, domain)) => !local.is_empty() && is_hostname(domain),
+            None => false,
+        },
+        "hostname" => is_hostname(s),
+        "ipv4" => s.parse::<Ipv4Addr>().is_ok(),
+        "ipv6" => s.parse::<Ipv6Addr>().is_ok(),
+        "uri" => is_uri_reference(s) && has_uri_scheme(s),
+        "uri-reference" => is_uri_reference(s),
+        "uuid" => uuid::Uuid::parse_str(s).is_ok() && s.len() == 36,
+        "regex" => Regex::new(s).is_ok(),
+        "json-pointer" => is_json_pointer(s),
+        _ => true,
+    }
+}
+
+fn is_hostname(s: &str) -> bool {
+    !s.is_empty()
+        && s.len() <= 253
+        && s.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+fn has_uri_scheme(s: &str) -> bool {
+    match s.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+fn is_uri_reference(s: &str) -> bool {
+    !s.chars()
+        .any(|c| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | '"'))
+}
+
+fn is_json_pointer(s: &str) -> bool {
+    if s.is_empty() {
+        return true;
+    }
+    s.starts_with('/')
+        && s.match_indices('~')
+            .all(|(i, _)| matches!(s.as_bytes().get(i + 1), Some(b'0') | Some(b'1')))
+}
+
+fn validate_number(
+    instance: &Value,
+    schema: &Map<String, Value>,
+    ctx: &Ctx,
+    out: &mut Vec<ValidationIssue>,
+) {
+    let x = instance.as_f64().unwrap_or_default();
+    let bound = |key: &str| schema.get(key).and_then(|v| v.as_f64());
+    let mut fail = |keyword: &str, message: String| {
+        ctx.issue(
+            out,
+            keyword,
+            message,
+            schema.get(keyword).cloned(),
+            Some(instance.clone()),
+        )
+    };
+
+    if let Some(min) = bound("minimum") {
+        if x < min {
+            fail(
+                "minimum",
+                format!("{} is less than minimum {}", instance, min),
+            );
+        }
+    }
+    if let Some(max) = bound("maximum") {
+        if x > max {
+            fail(
+                "maximum",
+                format!("{} is greater than maximum {}", instance, max),
+            );
+        }
+    }
+    if let Some(min) = bound("exclusiveMinimum") {
+        if x <= min {
+            fail(
+                "exclusiveMinimum",
+                format!("{} must be greater than {}", instance, min),
+            );
+        }
+    }
+    if let Some(max) = bound("exclusiveMaximum") {
+        if x >= max {
+            fail(
+                "exclusiveMaximum",
+                format!("{} must be less than {}", instance, max),
+            );
+        }
+    }
+    if let Some(factor) = bound("multipleOf") {
+        let multiple = match (
+            instance.as_i64(),
+            schema.get("multipleOf").and_then(|v| v.as_i64()),
+        ) {
+            (Some(x), Some(f)) if f != 0 => x % f == 0,
+            _ => {
+                let q = x / factor;
+                q.is_finite() && (q - q.round()).abs() < 1e-9
             }
-            "number" => {
-                if !instance.is_number() {
-                    return Err(anyhow!("Expected number"));
-                }
+        };
+        if !multiple {
+            fail(
+                "multipleOf",
+                format!("{} is not a multiple of {}", instance, factor),
+            );
+        }
+    }
+}
+
+fn validate_array(
+    arr: &[Value],
+    schema: &Map<String, Value>,
+    ctx: &Ctx,
+    out: &mut Vec<ValidationIssue>,
+    evaluated: &mut Evaluated,
+) {
+    let len = arr.len() as u64;
+    if let Some(min) = schema.get("minItems").and_then(|v| v.as_u64()) {
+        if len < min {
+            ctx.issue(
+                out,
+                "minItems",
+                format!("Array has fewer than {} items", min),
+                Some(json!(min)),
+                Some(json!(len)),
+            );
+        }
+    }
+    if let Some(max) = schema.get("maxItems").and_then(|v| v.as_u64()) {
+        if len > max {
+            ctx.issue(
+                out,
+                "maxItems",
+                format!("Array has more than {} items", max),
+                Some(json!(max)),
+                Some(json!(len)),
+            );
+        }
+    }
+    if schema.get("uniqueItems").and_then(|v| v.as_bool()) == Some(true) {
+        for (i, a) in arr.iter().enumerate() {
+            if let Some(j) = arr[i + 1..].iter().position(|b| json_eq(a, b)) {
+                ctx.keyword(&[]).child(&(i + 1 + j).to_string()).issue(
+                    out,
+                    "uniqueItems",
+                    format!("Array items are not unique: {}", a),
+                    Some(json!(true)),
+                    Some(a.clone()),
+                );
+                break;
             }
-            "integer" => {
-                if !instance.is_i64() && !instance.is_u64() {
-                    return Err(anyhow!("Expected integer"));
-                }
+        }
+    }
+
+    // prefixItems puis items pour le reste
+    let mut prefix_len = 0;
+    if let Some(prefix) = schema.get("prefixItems").and_then(|v| v.as_array()) {
+        for (i, (item, sub)) in arr.iter().zip(prefix).enumerate() {
+            let sub_ctx = ctx
+                .keyword(&["prefixItems", &i.to_string()])
+                .child(&i.to_string());
+            validate_node(item, sub, &sub_ctx, out);
+            evaluated.items.insert(i);
+        }
+        prefix_len = prefix.len();
+    }
+    if let Some(sub) = schema.get("items") {
+        for (i, item) in arr.iter().enumerate().skip(prefix_len) {
+            let sub_ctx = ctx.keyword(&["items"]).child(&i.to_string());
+            validate_node(item, sub, &sub_ctx, out);
+            evaluated.items.insert(i);
+        }
+    }
+
+    if let Some(sub) = schema.get("contains") {
+        let mut count = 0u64;
+        for (i, item) in arr.iter().enumerate() {
+            let sub_ctx = ctx.keyword(&["contains"]).child(&i.to_string());
+            if probe(item, sub, &sub_ctx).1.is_empty() {
+                evaluated.items.insert(i);
+                count += 1;
             }
-            "boolean" => {
-                if !instance.is_boolean() {
-                    return Err(anyhow!("Expected boolean"));
-                }
+        }
+        let min = schema
+            .get("minContains")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1);
+        if count < min {
+            let keyword = if schema.contains_key("minContains") {
+                "minContains"
+            } else {
+                "contains"
+            };
+            ctx.issue(
+                out,
+                keyword,
+                format!(
+                    "Array must contain at least {} matching item(s), found {}",
+                    min, count
+                ),
+                Some(json!(min)),
+                Some(json!(count)),
+            );
+        }
+        if let Some(max) = schema.get("maxContains").and_then(|v| v.as_u64()) {
+            if count > max {
+                ctx.issue(
+                    out,
+                    "maxContains",
+                    format!(
+                        "Array must contain at most {} matching item(s), found {}",
+                        max, count
+                    ),
+                    Some(json!(max)),
+                    Some(json!(count)),
+                );
             }
-            "array" => {
-                if !instance.is_array() {
-                    return Err(anyhow!("Expected array"));
-                }
+        }
+    }
+
+    if let Some(sub) = schema.get("unevaluatedItems") {
+        for (i, item) in arr.iter().enumerate() {
+            if evaluated.items.contains(&i) {
+                continue;
             }
-            "null" => {
-                if !instance.is_null() {
-                    return Err(anyhow!("Expected null"));
-                }
+            let sub_ctx = ctx.keyword(&["unevaluatedItems"]).child(&i.to_string());
+            if sub == &Value::Bool(false) {
+                sub_ctx.issue(
+                    out,
+                    "unevaluatedItems",
+                    format!("Unevaluated item not allowed: [{}]", i),
+                    Some(json!(false)),
+                    Some(item.clone()),
+                );
+            } else {
+                validate_node(item, sub, &sub_ctx, out);
             }
-            _ => {}
         }
+        evaluated.items.extend(0..arr.len());
     }
-    Ok(())
 }
 
 fn validate_object(
     instance: &Value,
-    schema: &Value,
-    reg: &SchemaRegistry,
-    current_uri: &str,
-) -> Result<()> {
-    let obj = instance.as_object().unwrap();
-
-    // 1. Required
+    obj: &Map<String, Value>,
+    schema: &Map<String, Value>,
+    ctx: &Ctx,
+    out: &mut Vec<ValidationIssue>,
+    evaluated: &mut Evaluated,
+) {
+    // 1. Required / dependentRequired
     if let Some(req) = schema.get("required").and_then(|v| v.as_array()) {
-        for r in req {
-            if let Some(key) = r.as_str() {
-                if !obj.contains_key(key) {
-                    return Err(anyhow!("Missing required property: {}", key));
+        for key in req.iter().filter_map(|r| r.as_str()) {
+            if !obj.contains_key(key) {
+                ctx.keyword(&[]).child(key).issue(
+                    out,
+                    "required",
+                    format!("Missing required property: {}", key),
+                    Some(json!(key)),
+                    None,
+                );
+            }
+        }
+    }
+    if let Some(deps) = schema.get("dependentRequired").and_then(|v| v.as_object()) {
+        for (key, required) in deps {
+            if !obj.contains_key(key) {
+                continue;
+            }
+            for dep in required.as_array().into_iter().flatten() {
+                if let Some(dep) = dep.as_str() {
+                    if !obj.contains_key(dep) {
+                        ctx.keyword(&["dependentRequired", key]).child(dep).issue(
+                            out,
+                            "dependentRequired",
+                            format!("Missing required property: {} (required by '{}')", dep, key),
+                            Some(json!(dep)),
+                            None,
+                        );
+                    }
                 }
             }
         }
     }
 
-    // 2. Properties
+    // 2. Cardinalité et noms de propriétés
+    let count = obj.len() as u64;
+    if let Some(min) = schema.get("minProperties").and_then(|v| v.as_u64()) {
+        if count < min {
+            ctx.issue(
+                out,
+                "minProperties",
+                format!("Object has fewer than {} properties", min),
+                Some(json!(min)),
+                Some(json!(count)),
+            );
+        }
+    }
+    if let Some(max) = schema.get("maxProperties").and_then(|v| v.as_u64()) {
+        if count > max {
+            ctx.issue(
+                out,
+                "maxProperties",
+                format!("Object has more than {} properties", max),
+                Some(json!(max)),
+                Some(json!(count)),
+            );
+        }
+    }
+    if let Some(names) = schema.get("propertyNames") {
+        for key in obj.keys() {
+            let sub_ctx = ctx.keyword(&["propertyNames"]).child(key);
+            validate_node(&Value::String(key.clone()), names, &sub_ctx, out);
+        }
+    }
+
+    // 3. Properties
+    let mut matched: HashSet<&str> = HashSet::new();
     if let Some(props) = schema.get("properties").and_then(|v| v.as_object()) {
         for (key, sub_schema) in props {
             if let Some(val) = obj.get(key) {
-                validate_node(val, sub_schema, reg, current_uri)
-                    .map_err(|e| anyhow!("Property '{}': {}", key, e))?;
+                let sub_ctx = ctx.keyword(&["properties", key]).child(key);
+                validate_node(val, sub_schema, &sub_ctx, out);
+                matched.insert(key);
             }
         }
     }
 
-    // 3. Pattern Properties (CORRECTION : Ajout du support)
-    let mut compiled_patterns = Vec::new();
+    // 4. Pattern Properties
     if let Some(patterns) = schema.get("patternProperties").and_then(|v| v.as_object()) {
         for (pattern, sub_schema) in patterns {
-            // On compile le regex
-            let re = Regex::new(pattern)
-                .map_err(|e| anyhow!("Invalid regex in patternProperties '{}': {}", pattern, e))?;
-
-            // On valide toutes les clés qui matchent
+            let re = match ctx.pattern(pattern) {
+                Ok(re) => re,
+                Err(e) => {
+                    ctx.issue(
+                        out,
+                        "patternProperties",
+                        format!("Invalid regex in patternProperties: {}", e),
+                        Some(json!(pattern)),
+                        None,
+                    );
+                    continue;
+                }
+            };
             for (key, val) in obj {
                 if re.is_match(key) {
-                    validate_node(val, sub_schema, reg, current_uri)
-                        .map_err(|e| anyhow!("Pattern property '{}': {}", key, e))?;
+                    let sub_ctx = ctx.keyword(&["patternProperties", pattern]).child(key);
+                    validate_node(val, sub_schema, &sub_ctx, out);
+                    matched.insert(key);
                 }
             }
-            compiled_patterns.push(re);
         }
     }
 
-    // 4. Additional Properties
+    // 5. Additional Properties (hors propriétés système injectées, cf. SYSTEM_FIELDS)
     if let Some(ap) = schema.get("additionalProperties") {
-        // Si additionalProperties est false
-        if ap.is_boolean() && !ap.as_bool().unwrap() {
-            let defined_props: Vec<&String> = schema
-                .get("properties")
-                .and_then(|v| v.as_object())
-                .map(|m| m.keys().collect())
-                .unwrap_or_default();
-
-            for k in obj.keys() {
-                // Est-ce une propriété définie explicitement ?
-                let is_defined = defined_props.contains(&k);
-
-                // Est-ce une propriété correspondant à un pattern ?
-                let matches_pattern = compiled_patterns.iter().any(|re| re.is_match(k));
-
-                // Si ni l'un ni l'autre (et pas $schema/id qui sont souvent implicites ou injectés)
-                // Note: On tolère $schema et id s'ils sont injectés par le système, mais idéalement ils devraient être dans le schéma.
-                if !is_defined && !matches_pattern && k != "$schema" {
-                    return Err(anyhow!("Additional property not allowed: {}", k));
-                }
+        for (key, val) in obj {
+            if matched.contains(key.as_str()) || SYSTEM_FIELDS.contains(&key.as_str()) {
+                continue;
+            }
+            let sub_ctx = ctx.keyword(&[]).child(key);
+            if ap == &Value::Bool(false) {
+                sub_ctx.issue(
+                    out,
+                    "additionalProperties",
+                    format!("Additional property not allowed: {}", key),
+                    Some(json!(false)),
+                    Some(val.clone()),
+                );
+            } else {
+                validate_node(
+                    val,
+                    ap,
+                    &ctx.keyword(&["additionalProperties"]).child(key),
+                    out,
+                );
+            }
+            matched.insert(key);
+        }
+    }
+    evaluated
+        .props
+        .extend(matched.into_iter().map(|k| k.to_string()));
+
+    // 6. Dependent Schemas
+    if let Some(deps) = schema.get("dependentSchemas").and_then(|v| v.as_object()) {
+        for (key, sub_schema) in deps {
+            if obj.contains_key(key) {
+                let sub_ctx = ctx.keyword(&["dependentSchemas", key]);
+                evaluated.merge(validate_node(instance, sub_schema, &sub_ctx, out));
             }
         }
     }
-    Ok(())
-}
 
-fn split_uri_fragment(uri: &str) -> (&str, Option<&str>) {
-    if let Some(idx) = uri.find('#') {
-        (&uri[0..idx], Some(&uri[idx..]))
-    } else {
-        (uri, None)
+    // 7. Unevaluated Properties (après toutes les annotations de ce niveau)
+    if let Some(up) = schema.get("unevaluatedProperties") {
+        for (key, val) in obj {
+            if evaluated.props.contains(key) || SYSTEM_FIELDS.contains(&key.as_str()) {
+                continue;
+            }
+            if up == &Value::Bool(false) {
+                ctx.keyword(&[]).child(key).issue(
+                    out,
+                    "unevaluatedProperties",
+                    format!("Unevaluated property not allowed: {}", key),
+                    Some(json!(false)),
+                    Some(val.clone()),
+                );
+            } else {
+                let sub_ctx = ctx.keyword(&["unevaluatedProperties"]).child(key);
+                validate_node(val, up, &sub_ctx, out);
+            }
+        }
+        evaluated.props.extend(obj.keys().cloned());
     }
 }
 
-fn resolve_path_uri(base: &str, target_path: &str) -> String {
-    if target_path.starts_with("db://") {
-        return target_path.to_string();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ROOT: &str = "db://s/d/schemas/v1/test/root.schema.json";
+
+    fn validator(schema: Value) -> SchemaValidator {
+        let mut reg = SchemaRegistry::new();
+        reg.register(ROOT.to_string(), schema);
+        reg.register(
+            "db://s/d/schemas/v1/common/base.schema.json".to_string(),
+            json!({
+                "$id": "https://raise.local/schemas/v1/common/base.schema.json",
+                "type": "object",
+                "properties": { "id": { "type": "string", "minLength": 1 } },
+                "required": ["id"],
+                "$defs": { "tag": { "$anchor": "tag", "type": "string", "pattern": "^[a-z]+$" } }
+            }),
+        );
+        SchemaValidator::compile_with_registry(ROOT, &reg).unwrap()
+    }
+
+    #[test]
+    fn test_scalar_keywords() {
+        let v = validator(json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "minLength": 2, "maxLength": 5, "pattern": "^[A-Z]" },
+                "kind": { "enum": ["a", "b"] },
+                "version": { "const": 1 },
+                "score": { "type": ["number", "null"], "minimum": 0, "exclusiveMaximum": 10, "multipleOf": 0.5 },
+                "at": { "type": "string", "format": "date-time" },
+                "mail": { "type": "string", "format": "email" },
+                "notes": { "type": "string", "format": "markdown" }
+            }
+        }));
+
+        assert!(v
+            .validate(&json!({
+                "name": "Abc", "kind": "a", "version": 1.0, "score": 9.5,
+                "at": "2024-01-01T00:00:00Z", "mail": "a@b.io", "notes": "*libre*"
+            }))
+            .is_ok());
+        assert!(v.validate(&json!({ "score": null })).is_ok());
+
+        for bad in [
+            json!({ "name": "A" }),
+            json!({ "name": "abc" }),
+            json!({ "kind": "c" }),
+            json!({ "version": 2 }),
+            json!({ "score": 10 }),
+            json!({ "score": 1.2 }),
+            json!({ "score": "1" }),
+            json!({ "at": "hier" }),
+            json!({ "mail": "personne" }),
+        ] {
+            assert!(v.validate(&bad).is_err(), "devrait être rejeté : {}", bad);
+        }
     }
-    if target_path.is_empty() {
-        return base.to_string();
+
+    #[test]
+    fn test_array_keywords() {
+        let v = validator(json!({
+            "type": "array",
+            "prefixItems": [{ "type": "string" }],
+            "items": { "type": "integer" },
+            "uniqueItems": true,
+            "contains": { "const": 0 },
+            "maxContains": 1,
+            "minItems": 2
+        }));
+
+        assert!(v.validate(&json!(["x", 0, 1])).is_ok());
+        assert!(v.validate(&json!(["x"])).is_err());
+        assert!(v.validate(&json!(["x", 1, 1])).is_err());
+        assert!(v.validate(&json!(["x", 1, 2])).is_err());
+        assert!(v.validate(&json!(["x", 0, 1.5])).is_err());
+        assert!(v.validate(&json!([1, 0])).is_err());
     }
 
-    let (prefix, base_path_str) = if let Some(stripped) = base.strip_prefix("db://") {
-        ("db://", stripped)
-    } else {
-        ("", base)
-    };
+    #[test]
+    fn test_combinators_and_conditionals() {
+        let v = validator(json!({
+            "type": "object",
+            "properties": {
+                "ref": { "oneOf": [{ "type": "string" }, { "type": "object", "required": ["file"] }] },
+                "level": { "anyOf": [{ "type": "integer" }, { "enum": ["max"] }] },
+                "code": { "not": { "const": "forbidden" } }
+            },
+            "if": { "properties": { "kind": { "const": "bot" } }, "required": ["kind"] },
+            "then": { "required": ["owner"] },
+            "else": { "properties": { "owner": false } },
+            "dependentRequired": { "start": ["end"] }
+        }));
 
-    let base_path = Path::new(base_path_str);
-    let parent = base_path.parent().unwrap_or(Path::new(""));
-    let joined = parent.join(target_path);
-    let normalized = normalize_path(&joined);
+        assert!(v
+            .validate(&json!({ "ref": "a", "level": "max", "code": "ok" }))
+            .is_ok());
+        assert!(v.validate(&json!({ "kind": "bot", "owner": "me" })).is_ok());
+        assert!(v.validate(&json!({ "ref": 3 })).is_err());
+        assert!(v.validate(&json!({ "level": "min" })).is_err());
+        assert!(v.validate(&json!({ "code": "forbidden" })).is_err());
+        assert!(v.validate(&json!({ "kind": "bot" })).is_err());
+        assert!(v
+            .validate(&json!({ "kind": "human", "owner": "me" }))
+            .is_err());
+        assert!(v.validate(&json!({ "start": 1 })).is_err());
+    }
 
-    format!(
-        "{}{}",
-        prefix,
-        normalized.to_string_lossy().replace("\\", "/")
-    )
-}
+    #[test]
+    fn test_unevaluated_properties_through_refs() {
+        let v = validator(json!({
+            "type": "object",
+            "allOf": [{ "$ref": "../common/base.schema.json" }],
+            "properties": { "name": { "type": "string" } },
+            "if": { "required": ["kind"] },
+            "then": { "properties": { "kind": true } },
+            "unevaluatedProperties": false
+        }));
+
+        assert!(v.validate(&json!({ "id": "a", "name": "n" })).is_ok());
+        assert!(v
+            .validate(&json!({ "id": "a", "kind": "x", "$schema": ROOT, "_rev": 2 }))
+            .is_ok());
+        let err = v
+            .validate(&json!({ "id": "a", "extra": 1 }))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("extra"), "{}", err);
+        assert!(v.validate(&json!({ "name": "n" })).is_err());
+    }
+
+    #[test]
+    fn test_refs_by_anchor_id_and_boolean_schemas() {
+        let v = validator(json!({
+            "type": "object",
+            "properties": {
+                "tag": { "$ref": "https://raise.local/schemas/v1/common/base.schema.json#tag" },
+                "any": true,
+                "none": false
+            },
+            "propertyNames": { "maxLength": 4 }
+        }));
 
-fn normalize_path(path: &Path) -> PathBuf {
-    let mut components = Vec::new();
-    for component in path.components() {
-        match component {
-            Component::CurDir => {}
-            Component::ParentDir => {
-                components.pop();
+        assert!(v.validate(&json!({ "tag": "abc", "any": [1] })).is_ok());
+        assert!(v.validate(&json!({ "tag": "ABC" })).is_err());
+        assert!(v.validate(&json!({ "none": 1 })).is_err());
+        assert!(v.validate(&json!({ "toolong": 1 })).is_err());
+    }
+
+    #[test]
+    fn test_recursive_refs_and_pattern_cache() {
+        let v = validator(json!({
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string", "pattern": "^[a-z]+$" },
+                        "children": { "type": "array", "items": { "$ref": "#/$defs/node" } }
+                    }
+                },
+                "loop": { "$ref": "#/$defs/loop" }
+            },
+            "properties": {
+                "tree": { "$ref": "#/$defs/node" },
+                "cycle": { "$ref": "#/$defs/loop" }
             }
-            Component::Normal(c) => components.push(c),
-            Component::RootDir | Component::Prefix(_) => {}
-        }
+        }));
+
+        let tree =
+            json!({ "name": "root", "children": [{ "name": "a", "children": [{ "name": "b" }] }] });
+        assert!(v.validate(&json!({ "tree": tree })).is_ok());
+        assert!(v
+            .validate(&json!({ "tree": { "children": [{ "name": "B" }] } }))
+            .is_err());
+        assert_eq!(v.patterns.lock().unwrap().len(), 1);
+
+        // Référence qui se rappelle elle-même : signalée au lieu de déborder la pile
+        let report = v.report(&json!({ "cycle": 1 }));
+        let issue = report.errors_at("/cycle").next().unwrap();
+        assert_eq!(issue.keyword, "$ref");
     }
-    let mut result = PathBuf::new();
-    for c in components {
-        result.push(c);
+
+    #[test]
+    fn test_report_collects_every_violation() {
+        let v = validator(json!({
+            "type": "object",
+            "allOf": [{ "$ref": "../common/base.schema.json" }],
+            "properties": {
+                "name": { "type": "string", "minLength": 3 },
+                "tags": { "type": "array", "items": { "$ref": "../common/base.schema.json#tag" } },
+                "a/b": { "type": "integer" }
+            },
+            "additionalProperties": false
+        }));
+
+        let report = v.report(&json!({
+            "name": "x",
+            "tags": ["ok", "KO", 3],
+            "a/b": "1",
+            "extra": true
+        }));
+        assert!(!report.is_valid());
+        let paths: Vec<&str> = report
+            .errors
+            .iter()
+            .map(|e| e.instance_path.as_str())
+            .collect();
+        for expected in ["/id", "/name", "/tags/1", "/tags/2", "/a~1b", "/extra"] {
+            assert!(
+                paths.contains(&expected),
+                "{} absent de {:?}",
+                expected,
+                paths
+            );
+        }
+
+        let missing = report.errors_at("/id").next().unwrap();
+        assert_eq!(missing.keyword, "required");
+        assert_eq!(missing.keyword_path, "/allOf/0/$ref/required");
+
+        let short = report.errors_at("/name").next().unwrap();
+        assert_eq!(short.keyword_path, "/properties/name/minLength");
+        assert_eq!(short.expected, Some(json!(3)));
+        assert_eq!(short.actual, Some(json!(1)));
+
+        let wrong_type = report.errors_at("/tags/2").next().unwrap();
+        assert_eq!(wrong_type.keyword_path, "/properties/tags/items/$ref/type");
+        assert_eq!(wrong_type.actual, Some(json!("integer")));
+        assert_eq!(report.errors_at("/tags").count(), 2);
+
+        // validate() renvoie le rapport complet, récupérable par downcast
+        let err = v.validate(&json!({ "name": 1 })).unwrap_err();
+        let report = err.downcast_ref::<ValidationReport>().unwrap();
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(report.schema_uri, ROOT);
     }
-    result
 }
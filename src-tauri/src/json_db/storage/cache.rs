@@ -1,25 +1,125 @@
 //! Module de gestion de cache générique en mémoire.
+//!
+//! Politique d'éviction LRU (Least Recently Used) : chaque accès attribue un
+//! numéro d'ordre croissant à l'entrée ; lors d'une éviction, l'entrée au plus
+//! petit numéro (la moins récemment utilisée) est retirée en O(log n).
+//! Le cache peut être borné en nombre d'entrées et/ou en octets (via un "weigher").
 
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::hash::Hash;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
 struct CacheEntry<V> {
     value: V,
+    /// Poids estimé en octets (0 si aucun weigher)
+    size: usize,
     #[allow(dead_code)]
     created_at: Instant,
-    #[allow(dead_code)]
-    last_accessed: Instant,
+    /// Numéro d'ordre du dernier accès (clé dans `CacheInner::order`)
+    tick: u64,
+    /// Numéro d'ordre de l'insertion : identifie la valeur malgré les promotions
+    generation: u64,
     expires_at: Option<Instant>,
 }
 
+/// Compteurs d'utilisation du cache (exposés à l'UI et au CLI)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entrées retirées pour respecter la capacité ou le budget mémoire
+    pub evictions: u64,
+    /// Entrées retirées car leur TTL était dépassé
+    pub expirations: u64,
+    /// Entrées retirées car périmées (ex: fichier modifié par un autre processus)
+    #[serde(default)]
+    pub invalidations: u64,
+    pub entries: usize,
+    pub bytes: usize,
+    pub capacity: usize,
+    pub max_bytes: Option<usize>,
+}
+
+impl CacheStats {
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CacheInner<K, V> {
+    entries: HashMap<K, CacheEntry<V>>,
+    /// Ordre d'accès : tick -> clé (la première entrée est la moins récente)
+    order: BTreeMap<u64, K>,
+    next_tick: u64,
+    bytes: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    expirations: u64,
+    invalidations: u64,
+}
+
+impl<K: Eq + Hash + Clone, V> CacheInner<K, V> {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            next_tick: 0,
+            bytes: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+            expirations: 0,
+            invalidations: 0,
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.next_tick += 1;
+        self.next_tick
+    }
+
+    fn remove_entry(&mut self, key: &K) -> Option<CacheEntry<V>> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.tick);
+        self.bytes -= entry.size;
+        Some(entry)
+    }
+
+    /// Retire l'entrée la moins récemment utilisée (comptée comme expirée si
+    /// son TTL est dépassé)
+    fn evict_lru(&mut self, now: Instant) -> bool {
+        let Some((_, key)) = self.order.pop_first() else {
+            return false;
+        };
+        if let Some(entry) = self.entries.remove(&key) {
+            self.bytes -= entry.size;
+            if entry.expires_at.is_some_and(|exp| exp <= now) {
+                self.expirations += 1;
+            } else {
+                self.evictions += 1;
+            }
+        }
+        true
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Cache<K, V> {
-    // Arc<RwLock> permet le clonage léger et l'accès concurrent
-    store: Arc<RwLock<HashMap<K, CacheEntry<V>>>>,
+    // Arc<Mutex> permet le clonage léger ; chaque lecture met à jour l'ordre LRU
+    inner: Arc<Mutex<CacheInner<K, V>>>,
     capacity: usize,
+    max_bytes: Option<usize>,
+    weigher: Option<fn(&V) -> usize>,
     default_ttl: Option<Duration>,
 }
 
@@ -30,69 +130,312 @@ where
 {
     pub fn new(capacity: usize, default_ttl: Option<Duration>) -> Self {
         Self {
-            store: Arc::new(RwLock::new(HashMap::new())),
+            inner: Arc::new(Mutex::new(CacheInner::new())),
             capacity,
+            max_bytes: None,
+            weigher: None,
             default_ttl,
         }
     }
 
+    /// Borne le cache en octets : `weigher` estime le poids de chaque valeur.
+    pub fn with_memory_budget(mut self, max_bytes: usize, weigher: fn(&V) -> usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self.weigher = Some(weigher);
+        self
+    }
+
     pub fn get(&self, key: &K) -> Option<V> {
-        let store = self.store.read().ok()?;
-        if let Some(entry) = store.get(key) {
-            if let Some(expires_at) = entry.expires_at {
-                if Instant::now() > expires_at {
+        self.get_if(key, |_| true)
+    }
+
+    /// Comme `get`, mais l'entrée est invalidée (et comptée comme miss)
+    /// si `is_valid` la rejette.
+    ///
+    /// `is_valid` peut être coûteux (stat() du fichier) : il est évalué sur une
+    /// copie de la valeur, verrou relâché, pour ne pas bloquer les autres lecteurs.
+    pub fn get_if<F>(&self, key: &K, is_valid: F) -> Option<V>
+    where
+        F: FnOnce(&V) -> bool,
+    {
+        let (value, generation) = {
+            let mut guard = self.inner.lock().ok()?;
+            let now = Instant::now();
+            let (expired, candidate) = match guard.entries.get(key) {
+                None => {
+                    guard.misses += 1;
                     return None;
                 }
+                Some(entry) => (
+                    entry.expires_at.is_some_and(|exp| now > exp),
+                    (entry.value.clone(), entry.generation),
+                ),
+            };
+            if expired {
+                guard.remove_entry(key);
+                guard.expirations += 1;
+                guard.misses += 1;
+                return None;
+            }
+            candidate
+        };
+
+        let valid = is_valid(&value);
+
+        let mut guard = self.inner.lock().ok()?;
+        let current = guard
+            .entries
+            .get(key)
+            .is_some_and(|entry| entry.generation == generation);
+        if !valid {
+            // Une valeur remplacée entre-temps par `put` est conservée
+            if current {
+                guard.remove_entry(key);
+                guard.invalidations += 1;
+            }
+            guard.misses += 1;
+            return None;
+        }
+
+        // Promotion en tête de l'ordre LRU
+        if current {
+            let tick = guard.tick();
+            let inner = &mut *guard;
+            if let Some(entry) = inner.entries.get_mut(key) {
+                inner.order.remove(&entry.tick);
+                inner.order.insert(tick, key.clone());
+                entry.tick = tick;
             }
-            return Some(entry.value.clone());
         }
-        None
+        guard.hits += 1;
+        Some(value)
     }
 
     pub fn put(&self, key: K, value: V) {
         let now = Instant::now();
-        let expires_at = self.default_ttl.map(|ttl| now + ttl);
+        let size = self.weigher.map(|w| w(&value)).unwrap_or(0);
 
-        let entry = CacheEntry {
-            value,
-            created_at: now,
-            last_accessed: now,
-            expires_at,
+        let Ok(mut guard) = self.inner.lock() else {
+            return;
         };
+        guard.remove_entry(&key);
 
-        if let Ok(mut guard) = self.store.write() {
-            if guard.len() >= self.capacity && !guard.contains_key(&key) {
-                // Nettoyage expiré
-                guard.retain(|_, v| v.expires_at.map(|exp| exp > now).unwrap_or(true));
+        // Une valeur plus lourde que le budget total n'est jamais mise en cache
+        if self.max_bytes.is_some_and(|max| size > max) || self.capacity == 0 {
+            return;
+        }
 
-                // Eviction simple si toujours plein
-                if guard.len() >= self.capacity {
-                    if let Some(k) = guard.keys().next().cloned() {
-                        guard.remove(&k);
-                    }
-                }
+        // Éviction LRU jusqu'à respecter capacité et budget. Les entrées
+        // expirées ne sont pas recherchées : retirées par `get`, ou ici quand
+        // elles arrivent en tête de l'ordre
+        while guard.entries.len() >= self.capacity
+            || self.max_bytes.is_some_and(|max| guard.bytes + size > max)
+        {
+            if !guard.evict_lru(now) {
+                break;
             }
-            guard.insert(key, entry);
         }
+
+        let tick = guard.tick();
+        guard.order.insert(tick, key.clone());
+        guard.bytes += size;
+        guard.entries.insert(
+            key,
+            CacheEntry {
+                value,
+                size,
+                created_at: now,
+                tick,
+                generation: tick,
+                expires_at: self.default_ttl.map(|ttl| now + ttl),
+            },
+        );
     }
 
     pub fn remove(&self, key: &K) {
-        if let Ok(mut guard) = self.store.write() {
-            guard.remove(key);
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.remove_entry(key);
+        }
+    }
+
+    /// Conserve uniquement les entrées acceptées par `keep`.
+    /// Retourne le nombre d'entrées invalidées.
+    pub fn retain<F>(&self, mut keep: F) -> usize
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        let Ok(mut guard) = self.inner.lock() else {
+            return 0;
+        };
+        let stale: Vec<K> = guard
+            .entries
+            .iter()
+            .filter(|(k, e)| !keep(k, &e.value))
+            .map(|(k, _)| k.clone())
+            .collect();
+        for k in &stale {
+            guard.remove_entry(k);
         }
+        guard.invalidations += stale.len() as u64;
+        stale.len()
     }
 
     pub fn clear(&self) {
-        if let Ok(mut guard) = self.store.write() {
-            guard.clear();
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.entries.clear();
+            guard.order.clear();
+            guard.bytes = 0;
         }
     }
 
     pub fn len(&self) -> usize {
-        self.store.read().map(|g| g.len()).unwrap_or(0)
+        self.inner.lock().map(|g| g.entries.len()).unwrap_or(0)
     }
 
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    pub fn stats(&self) -> CacheStats {
+        let Ok(guard) = self.inner.lock() else {
+            return CacheStats::default();
+        };
+        CacheStats {
+            hits: guard.hits,
+            misses: guard.misses,
+            evictions: guard.evictions,
+            expirations: guard.expirations,
+            invalidations: guard.invalidations,
+            entries: guard.entries.len(),
+            bytes: guard.bytes,
+            capacity: self.capacity,
+            max_bytes: self.max_bytes,
+        }
+    }
+
+    /// Remet les compteurs à zéro (les entrées sont conservées)
+    pub fn reset_stats(&self) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.hits = 0;
+            guard.misses = 0;
+            guard.evictions = 0;
+            guard.expirations = 0;
+            guard.invalidations = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Pour les tests, la valeur est son propre poids
+    fn weigh(v: &usize) -> usize {
+        *v
+    }
+
+    #[test]
+    fn test_lru_evicts_least_recently_used() {
+        let cache: Cache<&str, i32> = Cache::new(2, None);
+        cache.put("a", 1);
+        cache.put("b", 2);
+
+        // "a" devient le plus récent : c'est "b" qui doit partir
+        assert_eq!(cache.get(&"a"), Some(1));
+        cache.put("c", 3);
+
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(cache.get(&"c"), Some(3));
+
+        let stats = cache.stats();
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.entries, 2);
+    }
+
+    #[test]
+    fn test_memory_budget() {
+        let cache: Cache<u32, usize> = Cache::new(100, None).with_memory_budget(10, weigh);
+        cache.put(1, 4);
+        cache.put(2, 4);
+        assert_eq!(cache.stats().bytes, 8);
+
+        // 8 + 4 > 10 : éviction de la plus ancienne entrée
+        cache.put(3, 4);
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.stats().bytes, 8);
+
+        // Remplacement d'une clé : le poids est recalculé
+        cache.put(2, 1);
+        assert_eq!(cache.stats().bytes, 5);
+
+        // Plus lourd que le budget total : jamais mis en cache
+        cache.put(4, 11);
+        assert_eq!(cache.get(&4), None);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_get_if_invalidates_rejected_entry() {
+        let cache: Cache<u32, u32> = Cache::new(10, None);
+        cache.put(1, 10);
+        cache.put(2, 20);
+
+        assert_eq!(cache.get_if(&1, |v| *v == 10), Some(10));
+        assert_eq!(cache.get_if(&1, |_| false), None);
+        assert_eq!(cache.get(&1), None);
+
+        assert_eq!(cache.retain(|_, v| *v != 20), 1);
+        assert!(cache.is_empty());
+
+        let stats = cache.stats();
+        assert_eq!(stats.invalidations, 2);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 2);
+    }
+
+    #[test]
+    fn test_get_if_validates_outside_the_lock() {
+        let cache: Cache<u32, u32> = Cache::new(10, None);
+        cache.put(1, 10);
+
+        // La validation peut utiliser le cache ; la valeur remplacée entre-temps est conservée
+        let rejected = cache.get_if(&1, |_| {
+            cache.put(1, 11);
+            false
+        });
+        assert_eq!(rejected, None);
+        assert_eq!(cache.get(&1), Some(11));
+        assert_eq!(cache.stats().invalidations, 0);
+    }
+
+    #[test]
+    fn test_ttl_expiration() {
+        let cache: Cache<u32, u32> = Cache::new(10, Some(Duration::from_millis(0)));
+        cache.put(1, 1);
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(cache.get(&1), None);
+
+        let stats = cache.stats();
+        assert_eq!(stats.expirations, 1);
+        assert_eq!(stats.misses, 1);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_expired_entry_evicted_first_counts_as_expiration() {
+        let cache: Cache<u32, u32> = Cache::new(2, Some(Duration::from_millis(0)));
+        cache.put(1, 1);
+        cache.put(2, 2);
+        std::thread::sleep(Duration::from_millis(2));
+
+        // Pleine : la tête de l'ordre (expirée) est retirée, sans parcours du cache
+        cache.put(3, 3);
+        let stats = cache.stats();
+        assert_eq!(stats.expirations, 1);
+        assert_eq!(stats.evictions, 0);
+        assert_eq!(stats.entries, 2);
+    }
 }
@@ -1 +1,301 @@
-//TBC
+// FICHIER : src-tauri/src/json_db/storage/compression.rs
+
+//! Compression des documents sur disque.
+//!
+//! Un document est stocké soit en JSON lisible (`{id}.json`), soit compressé en
+//! zstd (`{id}.json.zst`). Le choix se fait par base ou par collection via
+//! `JsonDbConfig::compression`. La lecture est "mixte" : les deux formes peuvent
+//! coexister dans une même collection pendant une migration, et le contenu est
+//! décodé d'après son en-tête (magic zstd) plutôt que d'après son extension.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{file_storage, segment};
+use super::{JsonDbConfig, ScopedSetting};
+use crate::json_db::transactions::lock_manager::LockManager;
+
+/// Extension d'un document en clair
+pub const JSON_EXT: &str = "json";
+/// Extension d'un document compressé (`{id}.json.zst`)
+pub const ZSTD_EXT: &str = "json.zst";
+
+/// En-tête de toute trame zstd (RFC 8878)
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Codec de stockage d'un document
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", tag = "codec")]
+pub enum Compression {
+    /// JSON indenté, lisible et éditable à la main
+    #[default]
+    None,
+    /// zstd au niveau donné (1 = rapide, 19 = compact)
+    Zstd { level: i32 },
+}
+
+impl Compression {
+    pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+    pub fn zstd() -> Self {
+        Compression::Zstd {
+            level: Self::DEFAULT_ZSTD_LEVEL,
+        }
+    }
+
+    /// Parse "none", "zstd" ou "zstd:<niveau>" (CLI, variables d'environnement)
+    pub fn parse(s: &str) -> Result<Self> {
+        let s = s.trim().to_lowercase();
+        match s.split_once(':') {
+            None if s == "none" || s.is_empty() => Ok(Compression::None),
+            None if s == "zstd" => Ok(Self::zstd()),
+            Some(("zstd", level)) => {
+                let level: i32 = level
+                    .parse()
+                    .map_err(|_| anyhow!("Niveau zstd invalide : {}", level))?;
+                Ok(Compression::Zstd { level })
+            }
+            _ => Err(anyhow!(
+                "Compression inconnue : {} (none | zstd[:niveau])",
+                s
+            )),
+        }
+    }
+
+    /// Extension du fichier produit par ce codec
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::None => JSON_EXT,
+            Compression::Zstd { .. } => ZSTD_EXT,
+        }
+    }
+}
+
+/// Réglages de compression : défaut global, surcharges par base puis par collection
+pub type CompressionSettings = ScopedSetting<Compression>;
+
+// --- ENCODAGE ---
+
+pub fn encode(doc: &Value, compression: Compression) -> Result<Vec<u8>> {
+    match compression {
+        Compression::None => Ok(serde_json::to_string_pretty(doc)?.into_bytes()),
+        Compression::Zstd { level } => {
+            let raw = serde_json::to_vec(doc)?;
+            zstd::stream::encode_all(raw.as_slice(), level).context("Compression zstd échouée")
+        }
+    }
+}
+
+/// Vrai si le contenu est une trame zstd
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ZSTD_MAGIC)
+}
+
+/// Décode un contenu en clair ou compressé (détection par l'en-tête)
+pub fn decode(bytes: &[u8]) -> Result<Value> {
+    if is_compressed(bytes) {
+        let raw = zstd::stream::decode_all(bytes).context("Décompression zstd échouée")?;
+        Ok(serde_json::from_slice(&raw)?)
+    } else {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// Lit et décode un fichier document, quelle que soit sa forme
+pub fn read_document_file(path: &Path) -> Result<Value> {
+    let bytes = fs::read(path).with_context(|| format!("Lecture {}", path.display()))?;
+    decode(&bytes).with_context(|| format!("Document invalide : {}", path.display()))
+}
+
+// --- NOMMAGE DES FICHIERS ---
+
+/// Extrait l'ID d'un fichier document (`{id}.json` ou `{id}.json.zst`).
+/// Les fichiers techniques (`_meta.json`, `.tmp`, ...) retournent `None`.
+pub fn document_id(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let id = name
+        .strip_suffix(".json.zst")
+        .or_else(|| name.strip_suffix(".json"))?;
+    if id.is_empty() || id.starts_with('_') {
+        return None;
+    }
+    Some(id.to_string())
+}
+
+/// Chemins possibles d'un document, la forme préférée en premier
+pub fn candidate_paths(collection_dir: &Path, id: &str, preferred: Compression) -> [PathBuf; 2] {
+    let plain = collection_dir.join(format!("{}.{}", id, JSON_EXT));
+    let packed = collection_dir.join(format!("{}.{}", id, ZSTD_EXT));
+    match preferred {
+        Compression::None => [plain, packed],
+        Compression::Zstd { .. } => [packed, plain],
+    }
+}
+
+// --- MIGRATION ---
+
+/// Bilan d'une conversion de collection
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MigrationReport {
+    pub collection: String,
+    pub converted: usize,
+    pub unchanged: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Réécrit chaque document de la collection avec le codec `target`.
+/// Chaque document est converti atomiquement, sous son verrou d'écriture : une
+/// interruption laisse une collection mixte, toujours lisible, et la commande
+/// peut être relancée.
+pub fn migrate_collection(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    collection: &str,
+    target: Compression,
+) -> Result<MigrationReport> {
+    let col_path = config.db_collection_path(space, db, collection);
+    let mut report = MigrationReport {
+        collection: collection.to_string(),
+        ..Default::default()
+    };
+    if !col_path.exists() {
+        return Ok(report);
+    }
+
+    let locks = LockManager::new();
+    for entry in fs::read_dir(&col_path)? {
+        let path = entry?.path();
+        let Some(id) = document_id(&path) else {
+            continue;
+        };
+        let _guards = locks.lock_document(config, space, db, collection, &id)?;
+        // Supprimé ou déjà converti par une écriture depuis le listage
+        let Ok(metadata) = fs::metadata(&path) else {
+            continue;
+        };
+        let size = metadata.len();
+        report.bytes_before += size;
+
+        // Déjà sous la forme cible (un changement de niveau zstd ne réécrit pas)
+        let target_path = col_path.join(format!("{}.{}", id, target.extension()));
+        if path == target_path {
+            report.unchanged += 1;
+            report.bytes_after += size;
+            continue;
+        }
+
+        // Les deux formes coexistent (écriture interrompue avant le retrait de
+        // l'ancienne) : la forme cible, écrite en dernier, est gardée telle quelle
+        if target_path.exists() {
+            fs::remove_file(&path)?;
+            continue;
+        }
+
+        let doc = read_document_file(&path)?;
+        let encoded = encode(&doc, target)?;
+        file_storage::atomic_write(&target_path, &encoded)?;
+        fs::remove_file(&path)?;
+        report.converted += 1;
+        report.bytes_after += encoded.len() as u64;
+    }
+
+    // Documents du segment : ré-encodés pendant une compaction
+    let seg_path = segment::segment_path(config, space, db, collection);
+    if seg_path.exists() {
+        report.bytes_before += fs::metadata(&seg_path)?.len();
+        let stats = segment::compact_with(&seg_path, |payload| {
+            if is_compressed(payload) == (target != Compression::None) {
+                report.unchanged += 1;
+                return Ok(payload.to_vec());
+            }
+            report.converted += 1;
+            encode(&decode(payload)?, target)
+        })?;
+        report.bytes_after += stats.file_bytes;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_db::test_utils::init_test_env;
+    use serde_json::json;
+
+    #[test]
+    fn test_roundtrip_and_detection() {
+        let doc = json!({ "id": "a", "text": "lorem ipsum ".repeat(200) });
+
+        let plain = encode(&doc, Compression::None).unwrap();
+        let packed = encode(&doc, Compression::zstd()).unwrap();
+        assert!(packed.len() < plain.len());
+
+        assert_eq!(decode(&plain).unwrap(), doc);
+        assert_eq!(decode(&packed).unwrap(), doc);
+    }
+
+    #[test]
+    fn test_settings_resolution() {
+        let mut settings = CompressionSettings::default();
+        settings
+            .databases
+            .insert("s/db".into(), Compression::zstd());
+        settings
+            .collections
+            .insert("s/db/hot".into(), Compression::None);
+
+        assert_eq!(settings.resolve("s", "db", "cold"), Compression::zstd());
+        assert_eq!(settings.resolve("s", "db", "hot"), Compression::None);
+        assert_eq!(settings.resolve("s", "other", "x"), Compression::None);
+    }
+
+    #[test]
+    fn test_parse_and_document_id() {
+        assert_eq!(
+            Compression::parse("zstd:19").unwrap(),
+            Compression::Zstd { level: 19 }
+        );
+        assert_eq!(Compression::parse("none").unwrap(), Compression::None);
+        assert!(Compression::parse("gzip").is_err());
+
+        assert_eq!(document_id(Path::new("x/abc.json.zst")), Some("abc".into()));
+        assert_eq!(document_id(Path::new("x/abc.json")), Some("abc".into()));
+        assert_eq!(document_id(Path::new("x/_meta.json")), None);
+        assert_eq!(document_id(Path::new("x/abc.tmp")), None);
+    }
+
+    #[test]
+    fn test_migration_keeps_existing_target_form() {
+        let env = init_test_env();
+        let (cfg, space, db) = (&env.cfg, env.space.as_str(), env.db.as_str());
+        let col_path = cfg.db_collection_path(space, db, "logs");
+        fs::create_dir_all(&col_path).unwrap();
+
+        // Écriture interrompue : la nouvelle version compressée, l'ancienne en clair
+        let packed = col_path.join("a.json.zst");
+        let plain = col_path.join("a.json");
+        fs::write(
+            &packed,
+            encode(&json!({ "id": "a", "v": 2 }), Compression::zstd()).unwrap(),
+        )
+        .unwrap();
+        fs::write(
+            &plain,
+            encode(&json!({ "id": "a", "v": 1 }), Compression::None).unwrap(),
+        )
+        .unwrap();
+
+        let report = migrate_collection(cfg, space, db, "logs", Compression::zstd()).unwrap();
+        assert_eq!(report.converted, 0);
+        assert!(!plain.exists());
+        assert_eq!(
+            read_document_file(&packed).unwrap(),
+            json!({ "id": "a", "v": 2 })
+        );
+    }
+}
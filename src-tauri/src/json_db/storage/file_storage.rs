@@ -1,11 +1,14 @@
 // FICHIER : src-tauri/src/json_db/storage/file_storage.rs
 
-use crate::json_db::storage::JsonDbConfig;
+use crate::json_db::storage::segment::{self, StorageLayout};
+use crate::json_db::storage::{compression, JsonDbConfig};
+use crate::json_db::transactions::recovery;
 use anyhow::{Context, Result};
 use include_dir::{include_dir, Dir};
 use serde_json::Value;
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 // --- EMBARQUEMENT DES SCHÉMAS DANS LA LIBRAIRIE ---
 // Le chemin est relatif au Cargo.toml de la LIBRAIRIE (src-tauri/Cargo.toml)
@@ -23,6 +26,8 @@ pub fn open_db(config: &JsonDbConfig, space: &str, db: &str) -> Result<()> {
     if !db_path.exists() {
         return Err(anyhow::anyhow!("Database does not exist: {:?}", db_path));
     }
+    // Transactions interrompues par un crash : rejeu ou restauration
+    recovery::recover(config, space, db)?;
     Ok(())
 }
 
@@ -76,6 +81,32 @@ pub fn drop_db(config: &JsonDbConfig, space: &str, db: &str, mode: DropMode) ->
     Ok(())
 }
 
+/// Emplacement physique d'un document (fichier individuel ou segment).
+/// Lecture mixte : retourne la forme existante (format et codec configurés en
+/// priorité), sinon l'emplacement où la configuration l'écrirait.
+pub fn document_path(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    collection: &str,
+    id: &str,
+) -> PathBuf {
+    let col_path = config.db_collection_path(space, db, collection);
+    let [preferred, other] =
+        compression::candidate_paths(&col_path, id, config.compression_for(space, db, collection));
+    let seg_path = segment::segment_path(config, space, db, collection);
+    let in_segment = || segment::locate(&seg_path, id).is_some();
+
+    match config.layout_for(space, db, collection) {
+        StorageLayout::Segments if in_segment() => seg_path,
+        _ if preferred.exists() => preferred,
+        _ if other.exists() => other,
+        StorageLayout::Files if in_segment() => seg_path,
+        StorageLayout::Segments => seg_path,
+        StorageLayout::Files => preferred,
+    }
+}
+
 pub fn write_document(
     config: &JsonDbConfig,
     space: &str,
@@ -88,9 +119,26 @@ pub fn write_document(
     if !col_path.exists() {
         fs::create_dir_all(&col_path)?;
     }
-    let file_path = col_path.join(format!("{}.json", id));
-    let content = serde_json::to_string_pretty(doc)?;
-    atomic_write(file_path, content)?;
+    let codec = config.compression_for(space, db, collection);
+    let [target, other] = compression::candidate_paths(&col_path, id, codec);
+    let seg_path = segment::segment_path(config, space, db, collection);
+    let payload = compression::encode(doc, codec)?;
+
+    // Une seule forme par document : l'ancienne est retirée après l'écriture
+    match config.layout_for(space, db, collection) {
+        StorageLayout::Files => {
+            atomic_write(&target, payload)?;
+            remove_if_exists(&other)?;
+            if segment::locate(&seg_path, id).is_some() {
+                segment::append(&seg_path, id, None)?;
+            }
+        }
+        StorageLayout::Segments => {
+            segment::append(&seg_path, id, Some(payload.as_slice()))?;
+            remove_if_exists(&target)?;
+            remove_if_exists(&other)?;
+        }
+    }
     Ok(())
 }
 
@@ -101,15 +149,16 @@ pub fn read_document(
     collection: &str,
     id: &str,
 ) -> Result<Option<Value>> {
-    let file_path = config
-        .db_collection_path(space, db, collection)
-        .join(format!("{}.json", id));
-    if !file_path.exists() {
+    let path = document_path(config, space, db, collection, id);
+    if segment::is_segment_path(&path) {
+        return segment::read(&path, id)?
+            .map(|payload| compression::decode(&payload))
+            .transpose();
+    }
+    if !path.exists() {
         return Ok(None);
     }
-    let content = fs::read_to_string(file_path)?;
-    let doc = serde_json::from_str(&content)?;
-    Ok(Some(doc))
+    Ok(Some(compression::read_document_file(&path)?))
 }
 
 pub fn delete_document(
@@ -119,11 +168,81 @@ pub fn delete_document(
     collection: &str,
     id: &str,
 ) -> Result<()> {
-    let file_path = config
-        .db_collection_path(space, db, collection)
-        .join(format!("{}.json", id));
-    if file_path.exists() {
-        fs::remove_file(file_path)?;
+    let col_path = config.db_collection_path(space, db, collection);
+    for file_path in compression::candidate_paths(&col_path, id, Default::default()) {
+        remove_if_exists(&file_path)?;
+    }
+    let seg_path = segment::segment_path(config, space, db, collection);
+    if segment::locate(&seg_path, id).is_some() {
+        segment::append(&seg_path, id, None)?;
+    }
+    Ok(())
+}
+
+/// IDs des documents d'une collection, tous formats confondus (triés)
+pub fn list_document_ids(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    collection: &str,
+) -> Result<Vec<String>> {
+    let col_path = config.db_collection_path(space, db, collection);
+    let mut ids: HashSet<String> = HashSet::new();
+    if col_path.exists() {
+        for entry in fs::read_dir(&col_path)? {
+            let path = entry?.path();
+            if path.is_file() {
+                ids.extend(compression::document_id(&path));
+            }
+        }
+    }
+    ids.extend(segment::ids(&segment::segment_path(
+        config, space, db, collection,
+    ))?);
+    let mut ids: Vec<String> = ids.into_iter().collect();
+    ids.sort();
+    Ok(ids)
+}
+
+/// Tous les documents d'une collection, tous formats confondus.
+/// Le segment est lu en un seul passage séquentiel ; les documents illisibles
+/// sont ignorés.
+pub fn list_documents(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    collection: &str,
+) -> Result<Vec<Value>> {
+    let col_path = config.db_collection_path(space, db, collection);
+    let seg_path = segment::segment_path(config, space, db, collection);
+    let mut seen = HashSet::new();
+    let mut docs = Vec::new();
+
+    for (id, payload) in segment::read_all(&seg_path)? {
+        if let Ok(doc) = compression::decode(&payload) {
+            docs.push(doc);
+        }
+        seen.insert(id);
+    }
+    if col_path.exists() {
+        for entry in fs::read_dir(&col_path)? {
+            let path = entry?.path();
+            let Some(id) = compression::document_id(&path) else {
+                continue;
+            };
+            if seen.insert(id) {
+                if let Ok(doc) = compression::read_document_file(&path) {
+                    docs.push(doc);
+                }
+            }
+        }
+    }
+    Ok(docs)
+}
+
+pub(crate) fn remove_if_exists(path: &Path) -> Result<()> {
+    if path.exists() {
+        fs::remove_file(path)?;
     }
     Ok(())
 }
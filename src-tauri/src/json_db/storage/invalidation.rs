@@ -0,0 +1,119 @@
+// FICHIER : src-tauri/src/json_db/storage/invalidation.rs
+
+//! Invalidation inter-processus du cache de documents.
+//!
+//! Le cache du `StorageEngine` ne voit que les écritures de son propre processus.
+//! Chaque entrée mémorise donc l'empreinte (`FileStamp`) du fichier au moment où
+//! elle a été lue ou écrite : date de modification, taille et inode (unix).
+//! Comme `atomic_write` remplace le fichier via `rename`, toute écriture externe
+//! (jsondb_cli, agents, seconde fenêtre) change l'empreinte : l'entrée est alors
+//! évincée à la lecture suivante au lieu d'être servie périmée.
+//! Pour un document stocké dans un segment, la version est la position de son
+//! dernier enregistrement (qui change à chaque ajout ou compaction).
+
+use super::segment::{self, RecordLocation};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Empreinte d'un fichier sur disque (sa "génération")
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    inode: u64,
+}
+
+impl FileStamp {
+    /// Empreinte courante du fichier, `None` s'il n'existe pas
+    pub fn of(path: &Path) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+        Some(Self {
+            modified: meta.modified().ok(),
+            len: meta.len(),
+            inode: inode(&meta),
+        })
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn inode(meta: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.ino()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn inode(_meta: &fs::Metadata) -> u64 {
+    0
+}
+
+/// Version d'un document sur disque, selon son format de stockage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocVersion {
+    File(FileStamp),
+    Segment(RecordLocation),
+}
+
+impl DocVersion {
+    /// Version actuelle du document `id` stocké à `path`, `None` s'il n'y est plus
+    pub fn current(path: &Path, id: &str) -> Option<Self> {
+        if segment::is_segment_path(path) {
+            segment::locate(path, id).map(DocVersion::Segment)
+        } else {
+            FileStamp::of(path).map(DocVersion::File)
+        }
+    }
+}
+
+/// Document en cache accompagné de la version de sa source
+#[derive(Debug, Clone)]
+pub struct CachedDocument {
+    pub doc: Value,
+    pub id: String,
+    pub path: PathBuf,
+    pub version: Option<DocVersion>,
+}
+
+impl CachedDocument {
+    /// Capture la version actuelle (à appeler juste après lecture/écriture)
+    pub fn new(doc: Value, path: PathBuf, id: &str) -> Self {
+        let version = DocVersion::current(&path, id);
+        Self {
+            doc,
+            id: id.to_string(),
+            path,
+            version,
+        }
+    }
+
+    /// Vrai si la source n'a pas changé (ni disparu) depuis la mise en cache
+    pub fn is_fresh(&self) -> bool {
+        self.version.is_some() && DocVersion::current(&self.path, &self.id) == self.version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_db::storage::file_storage::atomic_write;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_external_rewrite_changes_stamp() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("doc.json");
+        atomic_write(&path, r#"{"v":1}"#).unwrap();
+
+        let cached = CachedDocument::new(json!({"v": 1}), path.clone(), "doc");
+        assert!(cached.is_fresh());
+
+        // Réécriture par un "autre processus" (rename => nouvel inode / taille)
+        atomic_write(&path, r#"{"v":22}"#).unwrap();
+        assert!(!cached.is_fresh());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(!cached.is_fresh());
+    }
+}
@@ -1,29 +1,128 @@
 // FICHIER : src-tauri/src/json_db/storage/mod.rs
 
 pub mod cache;
+pub mod compression;
 pub mod file_storage;
+pub mod invalidation;
+pub mod segment;
 
 use anyhow::Result;
+use compression::{Compression, CompressionSettings};
+use invalidation::CachedDocument;
+use segment::StorageLayout;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 // --- CONFIGURATION ---
 
+/// Réglage de stockage avec surcharges : défaut global, par base ("space/db"),
+/// puis par collection ("space/db/collection"). Le plus spécifique l'emporte.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScopedSetting<T> {
+    #[serde(default)]
+    pub default: T,
+    #[serde(default)]
+    pub databases: HashMap<String, T>,
+    #[serde(default)]
+    pub collections: HashMap<String, T>,
+}
+
+impl<T: Copy> ScopedSetting<T> {
+    pub fn resolve(&self, space: &str, db: &str, collection: &str) -> T {
+        self.collections
+            .get(&format!("{}/{}/{}", space, db, collection))
+            .or_else(|| self.databases.get(&format!("{}/{}", space, db)))
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    pub fn set_db(&mut self, space: &str, db: &str, value: T) {
+        self.databases.insert(format!("{}/{}", space, db), value);
+    }
+
+    pub fn set_collection(&mut self, space: &str, db: &str, collection: &str, value: T) {
+        self.collections
+            .insert(format!("{}/{}/{}", space, db, collection), value);
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonDbConfig {
     pub data_root: PathBuf,
+    /// Codec de stockage des documents (par défaut : JSON en clair)
+    #[serde(default)]
+    pub compression: CompressionSettings,
+    /// Organisation des fichiers (par défaut : un fichier par document)
+    #[serde(default)]
+    pub layout: ScopedSetting<StorageLayout>,
 }
 
 impl JsonDbConfig {
     pub fn new(data_root: PathBuf) -> Self {
-        Self { data_root }
+        Self {
+            data_root,
+            compression: CompressionSettings::default(),
+            layout: ScopedSetting::default(),
+        }
     }
 
     pub fn from(path_str: String) -> Result<Self, String> {
-        Ok(Self {
-            data_root: PathBuf::from(path_str),
-        })
+        Ok(Self::new(PathBuf::from(path_str)))
+    }
+
+    /// Codec appliqué à toutes les bases sans réglage spécifique
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression.default = compression;
+        self
+    }
+
+    pub fn with_db_compression(mut self, space: &str, db: &str, compression: Compression) -> Self {
+        self.compression.set_db(space, db, compression);
+        self
+    }
+
+    pub fn with_collection_compression(
+        mut self,
+        space: &str,
+        db: &str,
+        collection: &str,
+        compression: Compression,
+    ) -> Self {
+        self.compression
+            .set_collection(space, db, collection, compression);
+        self
+    }
+
+    pub fn compression_for(&self, space: &str, db: &str, collection: &str) -> Compression {
+        self.compression.resolve(space, db, collection)
+    }
+
+    /// Format appliqué à toutes les bases sans réglage spécifique
+    pub fn with_layout(mut self, layout: StorageLayout) -> Self {
+        self.layout.default = layout;
+        self
+    }
+
+    pub fn with_db_layout(mut self, space: &str, db: &str, layout: StorageLayout) -> Self {
+        self.layout.set_db(space, db, layout);
+        self
+    }
+
+    pub fn with_collection_layout(
+        mut self,
+        space: &str,
+        db: &str,
+        collection: &str,
+        layout: StorageLayout,
+    ) -> Self {
+        self.layout.set_collection(space, db, collection, layout);
+        self
+    }
+
+    pub fn layout_for(&self, space: &str, db: &str, collection: &str) -> StorageLayout {
+        self.layout.resolve(space, db, collection)
     }
 
     pub fn db_root(&self, space: &str, db: &str) -> PathBuf {
@@ -44,20 +143,53 @@ impl JsonDbConfig {
 
 // --- MOTEUR DE STOCKAGE ---
 
+/// Nombre maximal de documents gardés en cache par défaut
+pub const DEFAULT_CACHE_ENTRIES: usize = 10_000;
+/// Budget mémoire par défaut du cache de documents (64 Mo)
+pub const DEFAULT_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
 #[derive(Debug, Clone)]
 pub struct StorageEngine {
     pub config: JsonDbConfig,
-    pub cache: cache::Cache<String, Value>,
+    pub cache: cache::Cache<String, CachedDocument>,
 }
 
 impl StorageEngine {
     pub fn new(config: JsonDbConfig) -> Self {
+        Self::with_cache_limits(config, DEFAULT_CACHE_ENTRIES, DEFAULT_CACHE_BYTES)
+    }
+
+    /// Moteur avec un cache LRU borné en nombre de documents et en octets
+    pub fn with_cache_limits(config: JsonDbConfig, max_entries: usize, max_bytes: usize) -> Self {
         Self {
             config,
-            cache: cache::Cache::new(1000, None),
+            cache: cache::Cache::new(max_entries, None)
+                .with_memory_budget(max_bytes, |c: &CachedDocument| {
+                    estimate_document_size(&c.doc)
+                }),
         }
     }
 
+    pub fn cache_stats(&self) -> cache::CacheStats {
+        self.cache.stats()
+    }
+
+    /// Évince toutes les entrées dont le fichier a changé sur disque depuis leur
+    /// mise en cache. Retourne le nombre d'entrées retirées.
+    pub fn evict_stale(&self) -> usize {
+        self.cache.retain(|_, cached| cached.is_fresh())
+    }
+
+    /// Compacte le segment d'une collection (sans effet en format fichiers)
+    pub fn compact_collection(
+        &self,
+        space: &str,
+        db: &str,
+        collection: &str,
+    ) -> Result<segment::SegmentStats> {
+        segment::compact(&segment::segment_path(&self.config, space, db, collection))
+    }
+
     pub fn write_document(
         &self,
         space: &str,
@@ -68,7 +200,9 @@ impl StorageEngine {
     ) -> Result<()> {
         file_storage::write_document(&self.config, space, db, collection, id, doc)?;
         let cache_key = format!("{}/{}/{}/{}", space, db, collection, id);
-        self.cache.put(cache_key, doc.clone());
+        let path = file_storage::document_path(&self.config, space, db, collection, id);
+        self.cache
+            .put(cache_key, CachedDocument::new(doc.clone(), path, id));
         Ok(())
     }
 
@@ -80,12 +214,25 @@ impl StorageEngine {
         id: &str,
     ) -> Result<Option<Value>> {
         let cache_key = format!("{}/{}/{}/{}", space, db, collection, id);
-        if let Some(doc) = self.cache.get(&cache_key) {
-            return Ok(Some(doc));
+        // Une entrée dont le fichier a été modifié par un autre processus est évincée
+        if let Some(cached) = self.cache.get_if(&cache_key, CachedDocument::is_fresh) {
+            return Ok(Some(cached.doc));
         }
+        let path = file_storage::document_path(&self.config, space, db, collection, id);
+        // Empreinte prise AVANT la lecture : une écriture concurrente ne peut que
+        // rendre l'entrée périmée (relue au prochain accès), jamais la masquer.
+        let version = invalidation::DocVersion::current(&path, id);
         let doc_opt = file_storage::read_document(&self.config, space, db, collection, id)?;
         if let Some(doc) = &doc_opt {
-            self.cache.put(cache_key, doc.clone());
+            self.cache.put(
+                cache_key,
+                CachedDocument {
+                    doc: doc.clone(),
+                    id: id.to_string(),
+                    path,
+                    version,
+                },
+            );
         }
         Ok(doc_opt)
     }
@@ -97,3 +244,20 @@ impl StorageEngine {
         Ok(())
     }
 }
+
+/// Estimation de l'empreinte mémoire d'un document JSON (en octets).
+/// Approximation : taille des nœuds `Value` + contenu des chaînes et des clés.
+pub fn estimate_document_size(doc: &Value) -> usize {
+    let node = std::mem::size_of::<Value>();
+    match doc {
+        Value::String(s) => node + s.len(),
+        Value::Array(arr) => node + arr.iter().map(estimate_document_size).sum::<usize>(),
+        Value::Object(map) => {
+            node + map
+                .iter()
+                .map(|(k, v)| k.len() + estimate_document_size(v))
+                .sum::<usize>()
+        }
+        _ => node,
+    }
+}
@@ -0,0 +1,635 @@
+// FICHIER : src-tauri/src/json_db/storage/segment.rs
+
+//! Format "segment" : stockage append-only d'une collection dans un seul fichier.
+//!
+//! Alternative au format "un fichier par document" pour les grosses collections
+//! (modèles de 50k éléments) : `collections/{col}/_segments/data.seg`.
+//!
+//! - **Écriture** : chaque mise à jour ajoute un enregistrement en fin de fichier ;
+//!   une suppression ajoute un "tombstone". Les ajouts sont sérialisés entre
+//!   processus par un verrou fichier (`data.lock`). Un enregistrement tronqué
+//!   (crash en cours d'écriture) est ignoré à la relecture, puis coupé avant
+//!   l'ajout suivant.
+//! - **Index d'offsets** : en mémoire, par fichier segment. Il est rafraîchi de
+//!   façon incrémentale (seule la fin du fichier est relue), ce qui rend visibles
+//!   les ajouts faits par d'autres processus.
+//! - **Compaction** : réécrit les seuls enregistrements vivants dans un nouveau
+//!   fichier puis le renomme. Déclenchée en arrière-plan quand les octets morts
+//!   dépassent les octets vivants.
+//!
+//! Format binaire :
+//! `RSEG` `version:u8` `generation:u64 LE` puis des enregistrements
+//! `len:u32 LE` | `op:u8` | `id_len:u16 LE` | `id` | `payload`
+//! où `payload` est le document encodé par `compression::encode`.
+//! La génération identifie le fichier : tirée de l'horloge à sa création,
+//! incrémentée à chaque compaction. Les segments de version 1 (sans
+//! génération, lue comme 0) restent lisibles.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{compression, file_storage, JsonDbConfig};
+use crate::json_db::transactions::lock_manager::{FileLock, LockMode, DEFAULT_LOCK_TIMEOUT};
+
+pub const SEGMENT_DIR: &str = "_segments";
+pub const SEGMENT_FILE: &str = "data.seg";
+
+const MAGIC: [u8; 4] = *b"RSEG";
+const VERSION: u8 = 2;
+/// `RSEG` `version` (version 1, sans génération)
+const HEADER_V1_LEN: u64 = 5;
+/// `RSEG` `version` `generation`
+const HEADER_LEN: u64 = 13;
+const OP_PUT: u8 = 1;
+const OP_DELETE: u8 = 0;
+/// `op` + `id_len`
+const RECORD_PREFIX: u64 = 3;
+
+/// En dessous de ce volume d'octets morts, la compaction ne vaut pas la peine
+pub const COMPACTION_MIN_DEAD_BYTES: u64 = 1024 * 1024;
+
+/// Organisation physique des documents d'une collection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageLayout {
+    /// Un fichier `{id}.json` (ou `.json.zst`) par document
+    #[default]
+    Files,
+    /// Fichier segment append-only + index d'offsets
+    Segments,
+}
+
+impl StorageLayout {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "files" | "file" | "" => Ok(StorageLayout::Files),
+            "segments" | "segment" => Ok(StorageLayout::Segments),
+            other => Err(anyhow!("Format inconnu : {} (files | segments)", other)),
+        }
+    }
+}
+
+/// Position d'un enregistrement : change à chaque réécriture du document
+/// ou compaction du segment (utilisé pour l'invalidation du cache).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordLocation {
+    pub generation: u64,
+    pub offset: u64,
+}
+
+/// Occupation d'un fichier segment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SegmentStats {
+    pub live_records: usize,
+    pub live_bytes: u64,
+    pub dead_bytes: u64,
+    pub file_bytes: u64,
+}
+
+impl SegmentStats {
+    pub fn needs_compaction(&self) -> bool {
+        self.dead_bytes >= COMPACTION_MIN_DEAD_BYTES && self.dead_bytes > self.live_bytes
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    record_offset: u64,
+    record_len: u64,
+    payload_offset: u64,
+    payload_len: usize,
+}
+
+#[derive(Debug, Default)]
+struct SegmentIndex {
+    /// Génération du fichier indexé (en-tête) : change après une compaction
+    generation: u64,
+    /// Octets déjà parcourus (les suivants sont relus au prochain rafraîchissement)
+    scanned_len: u64,
+    entries: HashMap<String, Entry>,
+    live_bytes: u64,
+    dead_bytes: u64,
+    compacting: bool,
+}
+
+impl SegmentIndex {
+    fn stats(&self) -> SegmentStats {
+        SegmentStats {
+            live_records: self.entries.len(),
+            live_bytes: self.live_bytes,
+            dead_bytes: self.dead_bytes,
+            file_bytes: self.scanned_len,
+        }
+    }
+
+    /// Met l'index à jour avec les enregistrements ajoutés depuis le dernier passage
+    fn refresh(&mut self, path: &Path) -> Result<()> {
+        let compacting = self.compacting;
+        let Ok(mut file) = File::open(path) else {
+            *self = SegmentIndex::default();
+            return Ok(());
+        };
+        let len = file.metadata()?.len();
+        let Some((generation, header_len)) = read_header(&mut file, len, path)? else {
+            // En-tête incomplet : création interrompue
+            *self = SegmentIndex {
+                compacting,
+                ..Default::default()
+            };
+            return Ok(());
+        };
+        if self.scanned_len == 0 || generation != self.generation || len < self.scanned_len {
+            // Fichier remplacé (compaction, autre processus) : réindexation complète
+            *self = SegmentIndex {
+                generation,
+                scanned_len: header_len,
+                compacting,
+                ..Default::default()
+            };
+        }
+        if len <= self.scanned_len {
+            return Ok(());
+        }
+
+        let mut reader = BufReader::new(file);
+        reader.seek(SeekFrom::Start(self.scanned_len))?;
+
+        let mut pos = self.scanned_len;
+        while pos + 4 <= len {
+            let mut len_buf = [0u8; 4];
+            reader.read_exact(&mut len_buf)?;
+            let body_len = u32::from_le_bytes(len_buf) as u64;
+            if pos + 4 + body_len > len {
+                // Enregistrement incomplet : écriture en cours ou crash
+                break;
+            }
+            if body_len < RECORD_PREFIX {
+                bail!("Segment corrompu à l'offset {} : {}", pos, path.display());
+            }
+
+            let mut prefix = [0u8; RECORD_PREFIX as usize];
+            reader.read_exact(&mut prefix)?;
+            let id_len = u16::from_le_bytes([prefix[1], prefix[2]]) as u64;
+            if RECORD_PREFIX + id_len > body_len {
+                bail!("Segment corrompu à l'offset {} : {}", pos, path.display());
+            }
+            let mut id = vec![0u8; id_len as usize];
+            reader.read_exact(&mut id)?;
+            let id = String::from_utf8(id).context("ID de document non UTF-8")?;
+            let payload_len = body_len - RECORD_PREFIX - id_len;
+            reader.seek_relative(payload_len as i64)?;
+
+            let record_len = 4 + body_len;
+            self.apply(
+                prefix[0],
+                id,
+                Entry {
+                    record_offset: pos,
+                    record_len,
+                    payload_offset: pos + 4 + RECORD_PREFIX + id_len,
+                    payload_len: payload_len as usize,
+                },
+            );
+            pos += record_len;
+        }
+        self.scanned_len = pos;
+        Ok(())
+    }
+
+    fn apply(&mut self, op: u8, id: String, entry: Entry) {
+        if let Some(old) = self.entries.remove(&id) {
+            self.live_bytes -= old.record_len;
+            self.dead_bytes += old.record_len;
+        }
+        if op == OP_PUT {
+            self.live_bytes += entry.record_len;
+            self.entries.insert(id, entry);
+        } else {
+            // Le tombstone lui-même est de l'espace récupérable
+            self.dead_bytes += entry.record_len;
+        }
+    }
+
+    fn read_payload(&self, path: &Path, entry: &Entry) -> Result<Vec<u8>> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(entry.payload_offset))?;
+        let mut payload = vec![0u8; entry.payload_len];
+        file.read_exact(&mut payload)?;
+        Ok(payload)
+    }
+}
+
+/// En-tête d'un nouveau fichier segment
+fn header(generation: u64) -> Vec<u8> {
+    let mut header = MAGIC.to_vec();
+    header.push(VERSION);
+    header.extend_from_slice(&generation.to_le_bytes());
+    header
+}
+
+/// Génération d'un segment créé : l'horloge, pour qu'un segment supprimé puis
+/// recréé ne reprenne pas la génération de l'ancien
+fn initial_generation() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(1, |d| d.as_nanos() as u64)
+}
+
+/// Lit l'en-tête : (génération, taille de l'en-tête), `None` s'il est incomplet
+fn read_header(file: &mut File, len: u64, path: &Path) -> Result<Option<(u64, u64)>> {
+    if len < HEADER_V1_LEN {
+        return Ok(None);
+    }
+    let mut prefix = [0u8; HEADER_V1_LEN as usize];
+    file.read_exact(&mut prefix)?;
+    match (prefix[..4] == MAGIC, prefix[4]) {
+        (true, 1) => Ok(Some((0, HEADER_V1_LEN))),
+        (true, VERSION) if len < HEADER_LEN => Ok(None),
+        (true, VERSION) => {
+            let mut generation = [0u8; 8];
+            file.read_exact(&mut generation)?;
+            Ok(Some((u64::from_le_bytes(generation), HEADER_LEN)))
+        }
+        _ => bail!("Fichier segment invalide : {}", path.display()),
+    }
+}
+
+// --- REGISTRE DES SEGMENTS OUVERTS ---
+// Un index par fichier segment, partagé par tous les StorageEngine du processus
+// (et par les fonctions de `file_storage` qui ne reçoivent qu'une configuration).
+
+type SegmentHandle = Arc<Mutex<SegmentIndex>>;
+
+/// Verrou inter-processus des écritures d'un segment (`_segments/data.lock`)
+fn lock_segment(path: &Path) -> Result<FileLock> {
+    FileLock::acquire(
+        &path.with_extension("lock"),
+        LockMode::Exclusive,
+        DEFAULT_LOCK_TIMEOUT,
+    )
+}
+
+fn handle(path: &Path) -> SegmentHandle {
+    static REGISTRY: OnceLock<Mutex<HashMap<PathBuf, SegmentHandle>>> = OnceLock::new();
+    let mut registry = REGISTRY
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    registry.entry(path.to_path_buf()).or_default().clone()
+}
+
+/// Index verrouillé et à jour ; `None` si le segment n'existe pas
+fn with_index<R>(path: &Path, f: impl FnOnce(&mut SegmentIndex) -> Result<R>) -> Result<Option<R>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let handle = handle(path);
+    let mut index = handle
+        .lock()
+        .map_err(|_| anyhow!("Index segment empoisonné"))?;
+    index.refresh(path)?;
+    f(&mut index).map(Some)
+}
+
+// --- API ---
+
+pub fn segment_path(config: &JsonDbConfig, space: &str, db: &str, collection: &str) -> PathBuf {
+    config
+        .db_collection_path(space, db, collection)
+        .join(SEGMENT_DIR)
+        .join(SEGMENT_FILE)
+}
+
+pub fn is_segment_path(path: &Path) -> bool {
+    path.file_name().is_some_and(|n| n == SEGMENT_FILE)
+}
+
+/// Ajoute une version du document (`Some`) ou un tombstone (`None`)
+pub fn append(path: &Path, id: &str, payload: Option<&[u8]>) -> Result<()> {
+    let id_len = u16::try_from(id.len()).map_err(|_| anyhow!("ID trop long : {}", id))?;
+    let op = if payload.is_some() { OP_PUT } else { OP_DELETE };
+    let payload = payload.unwrap_or_default();
+    let body_len = RECORD_PREFIX as usize + id.len() + payload.len();
+    let body_len = u32::try_from(body_len).map_err(|_| anyhow!("Document trop volumineux"))?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let handle = handle(path);
+    let mut index = handle
+        .lock()
+        .map_err(|_| anyhow!("Index segment empoisonné"))?;
+    let _lock = lock_segment(path)?;
+    index.refresh(path)?;
+
+    // Verrou détenu : une fin incomplète ne peut venir que d'un crash. Elle est
+    // coupée, sans quoi le nouvel enregistrement suivrait des octets illisibles.
+    let len = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if len > index.scanned_len {
+        OpenOptions::new()
+            .write(true)
+            .open(path)?
+            .set_len(index.scanned_len)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut record = Vec::with_capacity(HEADER_LEN as usize + 4 + body_len as usize);
+    if file.metadata()?.len() == 0 {
+        record.extend_from_slice(&header(initial_generation()));
+    }
+    record.extend_from_slice(&body_len.to_le_bytes());
+    record.push(op);
+    record.extend_from_slice(&id_len.to_le_bytes());
+    record.extend_from_slice(id.as_bytes());
+    record.extend_from_slice(payload);
+    // Un seul write en mode append : l'enregistrement n'est jamais entrelacé
+    file.write_all(&record)?;
+    index.refresh(path)?;
+
+    if index.stats().needs_compaction() && !index.compacting {
+        index.compacting = true;
+        let path = path.to_path_buf();
+        std::thread::spawn(move || {
+            if let Err(e) = compact(&path) {
+                eprintln!("⚠️ Compaction du segment {:?} échouée : {}", path, e);
+            }
+        });
+    }
+    Ok(())
+}
+
+pub fn read(path: &Path, id: &str) -> Result<Option<Vec<u8>>> {
+    Ok(with_index(path, |index| match index.entries.get(id) {
+        Some(entry) => index.read_payload(path, entry).map(Some),
+        None => Ok(None),
+    })?
+    .flatten())
+}
+
+/// Tous les documents vivants (lecture séquentielle unique du fichier)
+pub fn read_all(path: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    Ok(with_index(path, |index| {
+        let bytes = fs::read(path)?;
+        let mut entries: Vec<_> = index.entries.iter().collect();
+        entries.sort_by_key(|(_, e)| e.record_offset);
+        entries
+            .into_iter()
+            .map(|(id, e)| {
+                let start = e.payload_offset as usize;
+                let payload = bytes
+                    .get(start..start + e.payload_len)
+                    .ok_or_else(|| anyhow!("Segment tronqué : {}", path.display()))?;
+                Ok((id.clone(), payload.to_vec()))
+            })
+            .collect()
+    })?
+    .unwrap_or_default())
+}
+
+pub fn ids(path: &Path) -> Result<Vec<String>> {
+    Ok(with_index(path, |index| Ok(index.entries.keys().cloned().collect()))?.unwrap_or_default())
+}
+
+pub fn locate(path: &Path, id: &str) -> Option<RecordLocation> {
+    with_index(path, |index| {
+        Ok(index.entries.get(id).map(|e| RecordLocation {
+            generation: index.generation,
+            offset: e.record_offset,
+        }))
+    })
+    .ok()
+    .flatten()
+    .flatten()
+}
+
+pub fn stats(path: &Path) -> Result<SegmentStats> {
+    Ok(with_index(path, |index| Ok(index.stats()))?.unwrap_or_default())
+}
+
+/// Réécrit le segment avec ses seuls enregistrements vivants
+pub fn compact(path: &Path) -> Result<SegmentStats> {
+    compact_with(path, |payload| Ok(payload.to_vec()))
+}
+
+/// Compaction avec transformation de chaque document (ex: changement de codec).
+/// Le verrou du segment est tenu de la relecture au renommage : les ajouts
+/// des autres processus attendent la fin de la compaction.
+pub fn compact_with<F>(path: &Path, mut transform: F) -> Result<SegmentStats>
+where
+    F: FnMut(&[u8]) -> Result<Vec<u8>>,
+{
+    if !path.exists() {
+        return Ok(SegmentStats::default());
+    }
+    let handle = handle(path);
+    let mut index = handle
+        .lock()
+        .map_err(|_| anyhow!("Index segment empoisonné"))?;
+    let _lock = lock_segment(path)?;
+    let result = rewrite_live_records(&mut index, path, &mut transform);
+    index.compacting = false;
+    result
+}
+
+fn rewrite_live_records<F>(
+    index: &mut SegmentIndex,
+    path: &Path,
+    transform: &mut F,
+) -> Result<SegmentStats>
+where
+    F: FnMut(&[u8]) -> Result<Vec<u8>>,
+{
+    index.refresh(path)?;
+    let bytes = fs::read(path)?;
+    let mut entries: Vec<_> = index.entries.iter().collect();
+    entries.sort_by_key(|(_, e)| e.record_offset);
+
+    let mut out = header(index.generation + 1);
+    for (id, e) in entries {
+        let start = e.payload_offset as usize;
+        let payload = bytes
+            .get(start..start + e.payload_len)
+            .ok_or_else(|| anyhow!("Segment tronqué : {}", path.display()))?;
+        let payload = transform(payload)?;
+        let body_len = RECORD_PREFIX as usize + id.len() + payload.len();
+        out.extend_from_slice(&(body_len as u32).to_le_bytes());
+        out.push(OP_PUT);
+        out.extend_from_slice(&(id.len() as u16).to_le_bytes());
+        out.extend_from_slice(id.as_bytes());
+        out.extend_from_slice(&payload);
+    }
+    file_storage::atomic_write(path, out)?;
+    *index = SegmentIndex::default();
+    index.refresh(path)?;
+    Ok(index.stats())
+}
+
+// --- MIGRATION ---
+
+/// Bilan d'un changement de format
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LayoutReport {
+    pub collection: String,
+    pub moved: usize,
+}
+
+/// Déplace les documents d'une collection vers le format `target`.
+/// Les documents sont copiés tels quels (sans ré-encodage) : un document
+/// compressé le reste.
+pub fn migrate_layout(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    collection: &str,
+    target: StorageLayout,
+) -> Result<LayoutReport> {
+    let col_path = config.db_collection_path(space, db, collection);
+    let seg_path = segment_path(config, space, db, collection);
+    let mut report = LayoutReport {
+        collection: collection.to_string(),
+        ..Default::default()
+    };
+    if !col_path.exists() {
+        return Ok(report);
+    }
+
+    match target {
+        StorageLayout::Segments => {
+            for entry in fs::read_dir(&col_path)? {
+                let path = entry?.path();
+                let Some(id) = compression::document_id(&path) else {
+                    continue;
+                };
+                append(&seg_path, &id, Some(fs::read(&path)?.as_slice()))?;
+                fs::remove_file(&path)?;
+                report.moved += 1;
+            }
+        }
+        StorageLayout::Files => {
+            for (id, payload) in read_all(&seg_path)? {
+                let ext = if compression::is_compressed(&payload) {
+                    compression::ZSTD_EXT
+                } else {
+                    compression::JSON_EXT
+                };
+                file_storage::atomic_write(col_path.join(format!("{}.{}", id, ext)), &payload)?;
+                report.moved += 1;
+            }
+            if seg_path.exists() {
+                fs::remove_file(&seg_path)?;
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_append_read_delete() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(SEGMENT_FILE);
+
+        append(&path, "a", Some(b"{\"v\":1}".as_slice())).unwrap();
+        append(&path, "b", Some(b"{\"v\":2}".as_slice())).unwrap();
+        append(&path, "a", Some(b"{\"v\":3}".as_slice())).unwrap();
+        append(&path, "b", None).unwrap();
+
+        assert_eq!(read(&path, "a").unwrap().unwrap(), b"{\"v\":3}");
+        assert_eq!(read(&path, "b").unwrap(), None);
+        assert_eq!(ids(&path).unwrap(), vec!["a".to_string()]);
+
+        let stats = stats(&path).unwrap();
+        assert_eq!(stats.live_records, 1);
+        assert!(stats.dead_bytes > 0);
+    }
+
+    #[test]
+    fn test_truncated_record_is_ignored() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(SEGMENT_FILE);
+        append(&path, "a", Some(b"{}".as_slice())).unwrap();
+
+        // Simule un crash au milieu d'un ajout
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[200, 0, 0, 0, OP_PUT, 1]).unwrap();
+
+        let mut fresh = SegmentIndex::default();
+        fresh.refresh(&path).unwrap();
+        assert_eq!(fresh.entries.len(), 1);
+        assert!(fresh.entries.contains_key("a"));
+
+        // L'ajout suivant coupe la fin incomplète et reste lisible
+        append(&path, "b", Some(b"{\"v\":2}".as_slice())).unwrap();
+        assert_eq!(read(&path, "b").unwrap().unwrap(), b"{\"v\":2}");
+        let mut fresh = SegmentIndex::default();
+        fresh.refresh(&path).unwrap();
+        assert_eq!(fresh.entries.len(), 2);
+        assert_eq!(fresh.scanned_len, fs::metadata(&path).unwrap().len());
+    }
+
+    #[test]
+    fn test_compaction_keeps_live_records() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(SEGMENT_FILE);
+        for i in 0..10 {
+            append(&path, "hot", Some(format!("{{\"v\":{}}}", i).as_bytes())).unwrap();
+        }
+        append(&path, "cold", Some(b"{}".as_slice())).unwrap();
+        let before = fs::metadata(&path).unwrap().len();
+        let location = locate(&path, "hot").unwrap();
+
+        let stats = compact(&path).unwrap();
+        assert_eq!(stats.live_records, 2);
+        assert_eq!(stats.dead_bytes, 0);
+        assert!(fs::metadata(&path).unwrap().len() < before);
+        assert_eq!(read(&path, "hot").unwrap().unwrap(), b"{\"v\":9}");
+        // La compaction change la position : les caches sont invalidés
+        assert_ne!(locate(&path, "hot"), Some(location));
+    }
+
+    #[test]
+    fn test_generation_is_kept_in_header() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(SEGMENT_FILE);
+        append(&path, "a", Some(b"{}".as_slice())).unwrap();
+        let generation = locate(&path, "a").unwrap().generation;
+
+        compact(&path).unwrap();
+        assert_eq!(locate(&path, "a").unwrap().generation, generation + 1);
+
+        // Segment recréé : nouvelle génération, même à position identique
+        fs::remove_file(&path).unwrap();
+        append(&path, "a", Some(b"{}".as_slice())).unwrap();
+        let recreated = locate(&path, "a").unwrap();
+        assert_ne!(recreated.generation, generation + 1);
+    }
+
+    #[test]
+    fn test_version_1_segment_is_readable_and_upgraded() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(SEGMENT_FILE);
+        let mut bytes = vec![b'R', b'S', b'E', b'G', 1];
+        bytes.extend_from_slice(&6u32.to_le_bytes());
+        bytes.extend_from_slice(&[OP_PUT, 1, 0, b'a', b'{', b'}']);
+        fs::write(&path, bytes).unwrap();
+
+        assert_eq!(read(&path, "a").unwrap().unwrap(), b"{}");
+        assert_eq!(locate(&path, "a").unwrap().generation, 0);
+        append(&path, "b", Some(b"{}".as_slice())).unwrap();
+
+        compact(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap()[4], VERSION);
+        assert_eq!(locate(&path, "b").unwrap().generation, 1);
+        assert_eq!(ids(&path).unwrap().len(), 2);
+    }
+}
@@ -31,9 +31,7 @@ pub fn init_test_env() -> TestEnv {
     let tmp_dir = tempfile::tempdir().expect("create temp dir");
     let data_root = tmp_dir.path().to_path_buf();
 
-    let cfg = JsonDbConfig {
-        data_root: data_root.clone(),
-    };
+    let cfg = JsonDbConfig::new(data_root.clone());
 
     // 1. Création de la structure de base
     let db_root = cfg.db_root(TEST_SPACE, TEST_DB);
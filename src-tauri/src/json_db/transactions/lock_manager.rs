@@ -1,26 +1,267 @@
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+// FICHIER : src-tauri/src/json_db/transactions/lock_manager.rs
 
-/// Gestionnaire de verrous simple (granularité : Collection)
-#[derive(Debug, Default, Clone)]
+//! Verrous d'écriture de JSON-DB.
+//!
+//! Les verrous sont des verrous consultatifs sur fichiers (`flock` sous unix,
+//! `LockFileEx` sous Windows), rangés dans `<db>/_locks/` :
+//! - `<collection>.lock` : pris en partagé par chaque écriture de document, en
+//!   exclusif par les opérations sur la collection entière (suppression...) ;
+//! - `<collection>/<stripe>.lock` : pris en exclusif pour écrire un document.
+//!   Les documents sont répartis par hachage de leur ID sur un nombre fixe de
+//!   fichiers (`DOCUMENT_LOCK_STRIPES`) : deux documents d'un même fichier
+//!   s'excluent, mais le nombre d'inodes ne croît pas avec la collection ;
+//! - `<collection>/unique.lock` : pris en exclusif, après les verrous de
+//!   documents, par les écritures d'une collection dotée d'index uniques.
+//!   Contrôle d'unicité, écriture et mise à jour des index forment ainsi une
+//!   seule section critique (deux insertions de la même clé ne peuvent pas
+//!   passer le contrôle toutes les deux).
+//! - `_system.lock` : pris en exclusif, en dernier, autour de chaque
+//!   lecture-modification-écriture de l'index système `_system.json`.
+//!
+//! Un verrou fichier est attaché au descripteur ouvert : il exclut aussi bien
+//! les autres threads du processus que les autres processus (app Tauri, CLI,
+//! agents). Il est relâché au `drop` du `FileLock`, ou par le système si le
+//! processus meurt. Les fichiers `.lock` restent en place (les supprimer
+//! pendant qu'un autre processus les attend casserait l'exclusion).
+
+use crate::json_db::storage::JsonDbConfig;
+use anyhow::{anyhow, Result};
+use std::collections::BTreeSet;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Délai d'attente par défaut avant d'abandonner l'acquisition d'un verrou
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+const RETRY_DELAY: Duration = Duration::from_millis(5);
+
+/// Nombre de fichiers de verrou de documents par collection
+pub const DOCUMENT_LOCK_STRIPES: u64 = 64;
+
+/// Fichier de verrou d'un document. Hachage FNV-1a : stable entre processus
+/// et versions du compilateur (contrairement à `DefaultHasher`).
+fn stripe(id: &str) -> u64 {
+    let hash = id.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    hash % DOCUMENT_LOCK_STRIPES
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// Plusieurs détenteurs simultanés
+    Shared,
+    /// Un seul détenteur
+    Exclusive,
+}
+
+/// Verrou consultatif détenu sur un fichier (relâché au drop)
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+    _file: File,
+}
+
+impl FileLock {
+    /// Acquiert le verrou, en réessayant jusqu'à `timeout`
+    pub fn acquire(path: &Path, mode: LockMode, timeout: Duration) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let attempt = match mode {
+                LockMode::Shared => file.try_lock_shared(),
+                LockMode::Exclusive => file.try_lock(),
+            };
+            match attempt {
+                Ok(()) => {
+                    return Ok(Self {
+                        path: path.to_path_buf(),
+                        _file: file,
+                    })
+                }
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    thread::sleep(RETRY_DELAY)
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(anyhow!(
+                        "Verrou occupé après {:?} : {}",
+                        timeout,
+                        path.display()
+                    ))
+                }
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Gestionnaire de verrous (granularité : Collection ou Document)
+#[derive(Debug, Clone)]
 pub struct LockManager {
-    // Clé = "space/db/collection"
-    locks: Arc<RwLock<HashMap<String, Arc<RwLock<()>>>>>,
+    timeout: Duration,
+}
+
+impl Default for LockManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LockManager {
     pub fn new() -> Self {
         Self {
-            locks: Arc::new(RwLock::new(HashMap::new())),
+            timeout: DEFAULT_LOCK_TIMEOUT,
         }
     }
 
-    /// Récupère un verrou d'écriture pour une collection
-    pub fn get_write_lock(&self, space: &str, db: &str, collection: &str) -> Arc<RwLock<()>> {
-        let key = format!("{}/{}/{}", space, db, collection);
-        let mut map = self.locks.write().unwrap();
-        map.entry(key)
-            .or_insert_with(|| Arc::new(RwLock::new(())))
-            .clone()
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn locks_root(config: &JsonDbConfig, space: &str, db: &str) -> PathBuf {
+        config.db_root(space, db).join("_locks")
+    }
+
+    /// Verrou d'une collection entière
+    pub fn lock_collection(
+        &self,
+        config: &JsonDbConfig,
+        space: &str,
+        db: &str,
+        collection: &str,
+        mode: LockMode,
+    ) -> Result<FileLock> {
+        let path = Self::locks_root(config, space, db).join(format!("{}.lock", collection));
+        FileLock::acquire(&path, mode, self.timeout)
+    }
+
+    /// Verrou exclusif d'un document (la collection est prise en partagé)
+    pub fn lock_document(
+        &self,
+        config: &JsonDbConfig,
+        space: &str,
+        db: &str,
+        collection: &str,
+        id: &str,
+    ) -> Result<Vec<FileLock>> {
+        self.lock_documents(
+            config,
+            space,
+            db,
+            &[(collection.to_string(), id.to_string())],
+        )
+    }
+
+    /// Verrous d'un ensemble de documents `(collection, id)`.
+    /// Ordre d'acquisition déterministe (collections puis fichiers, triés)
+    /// pour éviter les deadlocks entre transactions ; un fichier partagé par
+    /// plusieurs documents du lot n'est pris qu'une fois.
+    pub fn lock_documents(
+        &self,
+        config: &JsonDbConfig,
+        space: &str,
+        db: &str,
+        keys: &[(String, String)],
+    ) -> Result<Vec<FileLock>> {
+        let stripes: BTreeSet<(&str, u64)> = keys
+            .iter()
+            .map(|(collection, id)| (collection.as_str(), stripe(id)))
+            .collect();
+        let collections: BTreeSet<&str> = stripes.iter().map(|(c, _)| *c).collect();
+
+        let mut guards = Vec::with_capacity(collections.len() + stripes.len());
+        for collection in collections {
+            guards.push(self.lock_collection(config, space, db, collection, LockMode::Shared)?);
+        }
+        let root = Self::locks_root(config, space, db);
+        for (collection, stripe) in stripes {
+            let path = root.join(collection).join(format!("{:02x}.lock", stripe));
+            guards.push(FileLock::acquire(&path, LockMode::Exclusive, self.timeout)?);
+        }
+        Ok(guards)
+    }
+
+    /// Verrou d'unicité d'une collection (à prendre après ses verrous de documents)
+    pub fn lock_unique(
+        &self,
+        config: &JsonDbConfig,
+        space: &str,
+        db: &str,
+        collection: &str,
+    ) -> Result<FileLock> {
+        let path = Self::locks_root(config, space, db)
+            .join(collection)
+            .join("unique.lock");
+        FileLock::acquire(&path, LockMode::Exclusive, self.timeout)
+    }
+
+    /// Verrou de l'index système `_system.json` (à ne pas reprendre s'il est tenu)
+    pub fn lock_system(&self, config: &JsonDbConfig, space: &str, db: &str) -> Result<FileLock> {
+        let path = Self::locks_root(config, space, db).join("_system.lock");
+        FileLock::acquire(&path, LockMode::Exclusive, self.timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_document_locks_exclude_each_other() {
+        let dir = tempdir().unwrap();
+        let config = JsonDbConfig::new(dir.path().to_path_buf());
+        let lm = LockManager::new().with_timeout(Duration::from_millis(50));
+
+        let held = lm.lock_document(&config, "s", "d", "users", "u1").unwrap();
+
+        // Même document : refusé ; autre document de la collection : accepté
+        assert!(lm.lock_document(&config, "s", "d", "users", "u1").is_err());
+        assert!(lm.lock_document(&config, "s", "d", "users", "u2").is_ok());
+
+        // Collection entière en exclusif : bloquée tant qu'un document est verrouillé
+        assert!(lm
+            .lock_collection(&config, "s", "d", "users", LockMode::Exclusive)
+            .is_err());
+
+        drop(held);
+        assert!(lm.lock_document(&config, "s", "d", "users", "u1").is_ok());
+        assert!(lm
+            .lock_collection(&config, "s", "d", "users", LockMode::Exclusive)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_document_lock_files_are_bounded() {
+        let dir = tempdir().unwrap();
+        let config = JsonDbConfig::new(dir.path().to_path_buf());
+        let lm = LockManager::new().with_timeout(Duration::from_millis(50));
+
+        // Lot de documents partageant des fichiers : chaque fichier pris une fois
+        let keys: Vec<(String, String)> = (0..200)
+            .map(|i| ("users".to_string(), format!("d{}", i)))
+            .collect();
+        let held = lm.lock_documents(&config, "s", "d", &keys).unwrap();
+        let stripes = held.len() - 1;
+        assert!(stripes <= DOCUMENT_LOCK_STRIPES as usize);
+        drop(held);
+
+        let files = fs::read_dir(dir.path().join("s/d/_locks/users")).unwrap();
+        assert_eq!(files.count(), stripes);
     }
 }
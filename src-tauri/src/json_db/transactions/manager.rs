@@ -1,6 +1,9 @@
 // FICHIER : src-tauri/src/json_db/transactions/manager.rs
 
-use crate::json_db::collections::manager::CollectionsManager;
+use crate::json_db::changes;
+use crate::json_db::collections::manager::{
+    apply_business_rules, CollectionsManager, DbDataProvider,
+};
 use crate::json_db::indexes::IndexManager;
 use crate::json_db::query::{
     ComparisonOperator, Condition, FilterOperator, Query, QueryEngine, QueryFilter,
@@ -8,10 +11,13 @@ use crate::json_db::query::{
 use crate::json_db::schema::{SchemaRegistry, SchemaValidator};
 use crate::json_db::storage::{file_storage, JsonDbConfig, StorageEngine};
 use crate::json_db::transactions::lock_manager::LockManager;
-use crate::json_db::transactions::{Operation, Transaction, TransactionRequest};
-use anyhow::{anyhow, Context, Result};
+use crate::json_db::transactions::{
+    revision, snapshot, wal, DocumentImage, Operation, Transaction, TransactionLog,
+    TransactionRequest, TransactionStatus,
+};
+use anyhow::{anyhow, bail, Context, Result};
 use serde_json::{json, Value};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap};
 use std::fs;
 
 pub struct TransactionManager<'a> {
@@ -50,7 +56,7 @@ impl<'a> TransactionManager<'a> {
                 } => {
                     let final_id = id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
 
-                    // Injection préliminaire (sera confirmée par apply_transaction)
+                    // Injection préliminaire (sera confirmée par prepare_images)
                     if let Some(obj) = document.as_object_mut() {
                         obj.insert("id".to_string(), Value::String(final_id.clone()));
                     }
@@ -80,11 +86,15 @@ impl<'a> TransactionManager<'a> {
                                     operator: ComparisonOperator::Eq,
                                     value: Value::String(h.clone()),
                                 }],
+                                filters: Vec::new(),
                             }),
                             sort: None,
                             limit: Some(1),
                             offset: None,
                             projection: None,
+                            explain: None,
+                            aggregation: None,
+                            joins: Vec::new(),
                         };
 
                         let res = query_engine.execute_query(q).await?;
@@ -111,8 +121,21 @@ impl<'a> TransactionManager<'a> {
                     });
                 }
 
-                TransactionRequest::Delete { collection, id } => {
-                    prepared_ops.push(Operation::Delete { collection, id });
+                TransactionRequest::Delete {
+                    collection,
+                    id,
+                    revision,
+                } => {
+                    prepared_ops.push(match revision {
+                        // Suppression conditionnelle : remplacement par « rien »
+                        Some(revision) => Operation::Replace {
+                            collection,
+                            id,
+                            revision,
+                            document: None,
+                        },
+                        None => Operation::Delete { collection, id },
+                    });
                 }
 
                 TransactionRequest::InsertFrom { collection, path } => {
@@ -132,7 +155,7 @@ impl<'a> TransactionManager<'a> {
                         .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
 
                     if let Some(obj) = doc.as_object_mut() {
-                        obj.insert("id".to_string(), Value::String(id.clone()));
+                        obj.insert("id".to_string(), Value::String(id.to_string()));
                     }
 
                     prepared_ops.push(Operation::Insert {
@@ -167,157 +190,285 @@ impl<'a> TransactionManager<'a> {
         let mut tx = Transaction::new();
         op_block(&mut tx)?;
 
-        // 1. VERROUILLAGE
-        let collections_to_lock: HashSet<String> = tx
+        // 1. VERROUILLAGE : documents en exclusif (fichiers de verrou, inter-processus)
+        let keys: Vec<(String, String)> = tx
             .operations
             .iter()
-            .map(|op| match op {
-                Operation::Insert { collection, .. } => collection.clone(),
-                Operation::Update { collection, .. } => collection.clone(),
-                Operation::Delete { collection, .. } => collection.clone(),
+            .map(|op| {
+                let (collection, id) = op.target();
+                (collection.to_string(), id.to_string())
             })
             .collect();
-
-        let mut sorted_collections: Vec<String> = collections_to_lock.into_iter().collect();
-        sorted_collections.sort();
-
-        let mut locks = Vec::new();
-        let mut _guards = Vec::new();
-
-        for col in sorted_collections {
-            locks.push(
-                self.lock_manager
-                    .get_write_lock(&self.space, &self.db, &col),
-            );
-        }
-        for lock in &locks {
-            _guards.push(lock.write().unwrap());
+        let mut guards =
+            self.lock_manager
+                .lock_documents(self.config, &self.space, &self.db, &keys)?;
+        // Collections à index uniques : contrôle, écriture et index sous un même verrou
+        let storage = StorageEngine::new(self.config.clone());
+        let idx = IndexManager::new(&storage, &self.space, &self.db);
+        let collections: BTreeSet<&str> = keys.iter().map(|(c, _)| c.as_str()).collect();
+        for collection in collections {
+            if idx.has_unique(collection)? {
+                guards.push(self.lock_manager.lock_unique(
+                    self.config,
+                    &self.space,
+                    &self.db,
+                    collection,
+                )?);
+            }
         }
 
-        // 2. EXÉCUTION ATOMIQUE
-        self.write_wal(&tx)?;
-        match self.apply_transaction(&tx) {
+        // 2. PRÉPARATION : états avant/après calculés sans rien écrire
+        let mut log = TransactionLog::pending(&tx);
+        self.prepare_images(&tx, &mut log)?;
+
+        // 3. EXÉCUTION ATOMIQUE
+        self.write_wal(&log)?;
+        match self.apply_images(&log.before_images, &log.after_images) {
             Ok(_) => {
-                self.commit_wal(&tx)?;
+                self.commit_wal(&mut log)?;
                 Ok(())
             }
-            Err(e) => {
-                self.rollback_wal(&tx)?;
-                Err(e)
-            }
+            Err(e) => Err(self.abort(&mut log, e)),
         }
     }
 
-    fn write_wal(&self, tx: &Transaction) -> Result<()> {
-        let wal_path = self.config.db_root(&self.space, &self.db).join("wal");
-        if !wal_path.exists() {
-            fs::create_dir_all(&wal_path)?;
+    /// Écriture directe du `CollectionsManager` (verrous déjà pris par
+    /// l'appelant), journalisée comme une transaction d'une opération : son
+    /// événement est publié avant qu'elle ne soit acquittée. Si la publication
+    /// échoue, l'écriture échoue et la récupération la complète.
+    pub(crate) fn write_direct<F>(&self, mut log: TransactionLog, write: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        self.write_wal(&log)?;
+        match write() {
+            Ok(_) => self.commit_wal(&mut log),
+            Err(e) => Err(self.abort(&mut log, e)),
         }
-        let tx_file = wal_path.join(format!("{}.json", tx.id));
-        fs::write(tx_file, serde_json::to_string_pretty(tx)?)?;
-        Ok(())
     }
 
-    fn apply_transaction(&self, tx: &Transaction) -> Result<()> {
-        let storage = StorageEngine::new(self.config.clone());
-        let mut idx = IndexManager::new(&storage, &self.space, &self.db);
+    /// Annule une écriture échouée, en signalant un rollback lui-même incomplet
+    fn abort(&self, log: &mut TransactionLog, e: anyhow::Error) -> anyhow::Error {
+        match self.rollback_wal(log) {
+            Ok(_) => e,
+            Err(rb) => e.context(format!(
+                "Rollback incomplet ({}) : il sera repris à la prochaine ouverture de la base",
+                rb
+            )),
+        }
+    }
 
-        let sys_path = self
-            .config
-            .db_root(&self.space, &self.db)
-            .join("_system.json");
-        let mut system_index = if sys_path.exists() {
-            let c = fs::read_to_string(&sys_path)?;
-            serde_json::from_str::<Value>(&c).unwrap_or(json!({ "collections": {} }))
-        } else {
-            json!({ "collections": {} })
-        };
+    fn write_wal(&self, log: &TransactionLog) -> Result<()> {
+        wal::write_log(self.config, &self.space, &self.db, log)
+    }
+
+    /// Calcule l'état initial (before-image) et final (after-image) de chaque
+    /// document touché. Aucune écriture ici : une erreur de validation annule
+    /// la transaction avant toute modification.
+    fn prepare_images(&self, tx: &Transaction, log: &mut TransactionLog) -> Result<()> {
+        // Ordre de première apparition + état courant de chaque document
+        let mut order: Vec<(String, String)> = Vec::new();
+        let mut current: HashMap<(String, String), Option<Value>> = HashMap::new();
 
         for op in &tx.operations {
-            match op {
-                Operation::Insert {
+            let (collection, id) = op.target();
+            let key = (collection.to_string(), id.to_string());
+            if !current.contains_key(&key) {
+                let before = file_storage::read_document(
+                    self.config,
+                    &self.space,
+                    &self.db,
                     collection,
                     id,
-                    document,
-                } => {
+                )?;
+                log.before_images.push(DocumentImage {
+                    collection: collection.to_string(),
+                    id: id.to_string(),
+                    document: before.clone(),
+                });
+                current.insert(key.clone(), before);
+                order.push(key.clone());
+            }
+            let state = current.entry(key).or_default();
+
+            match op {
+                Operation::Insert { document, .. } => {
+                    // Création stricte : une insertion qui porte `_rev: 0` exige
+                    // que le document n'existe pas encore
+                    if revision::expected_revision(document) == Some(0) && state.is_some() {
+                        bail!(
+                            "Insertion refusée : le document {}/{} existe déjà",
+                            collection,
+                            id
+                        );
+                    }
                     let mut final_doc = document.clone();
 
                     // CORRECTION CRITIQUE : Assurance que l'ID est dans le corps du document
                     // Cela évite l'erreur "Document sans ID" dans IndexManager
                     if let Some(obj) = final_doc.as_object_mut() {
                         if !obj.contains_key("id") {
-                            obj.insert("id".to_string(), Value::String(id.clone()));
+                            obj.insert("id".to_string(), Value::String(id.to_string()));
                         }
                     }
 
-                    self.apply_schema_logic(collection, &mut final_doc)?;
-
-                    file_storage::write_document(
-                        self.config,
-                        &self.space,
-                        &self.db,
-                        collection,
-                        id,
-                        &final_doc,
-                    )?;
-                    idx.index_document(collection, &final_doc)?;
-                    self.update_index_entry(&mut system_index, collection, id, false)?;
+                    self.apply_schema_logic(collection, &mut final_doc, state.as_ref())?;
+                    revision::stamp(&mut final_doc, state.as_ref());
+                    *state = Some(final_doc);
                 }
-                Operation::Update {
-                    collection,
-                    id,
-                    document,
-                } => {
-                    let existing_opt = file_storage::read_document(
-                        self.config,
-                        &self.space,
-                        &self.db,
-                        collection,
-                        id,
-                    )?;
-                    let mut final_doc = existing_opt.ok_or_else(|| {
+                Operation::Update { document, .. } => {
+                    let mut final_doc = state.clone().ok_or_else(|| {
                         anyhow!("Update échoué : doc {}/{} introuvable", collection, id)
                     })?;
+                    // Compare-and-swap si le patch porte le `_rev` lu par l'appelant
+                    revision::check(collection, id, state.as_ref(), document)?;
 
                     json_merge(&mut final_doc, document.clone());
 
                     // S'assurer que l'ID n'a pas été perdu ou corrompu par le merge
                     if let Some(obj) = final_doc.as_object_mut() {
                         if !obj.contains_key("id") {
-                            obj.insert("id".to_string(), Value::String(id.clone()));
+                            obj.insert("id".to_string(), Value::String(id.to_string()));
+                        }
+                    }
+
+                    self.apply_schema_logic(collection, &mut final_doc, state.as_ref())?;
+                    revision::stamp(&mut final_doc, state.as_ref());
+                    *state = Some(final_doc);
+                }
+                Operation::Delete { .. } => *state = None,
+                Operation::Replace {
+                    revision: expected,
+                    document,
+                    ..
+                } => {
+                    let actual = state.as_ref().map(revision::revision_of).unwrap_or(0);
+                    if actual != *expected {
+                        return Err(revision::RevisionConflict {
+                            collection: collection.to_string(),
+                            id: id.to_string(),
+                            expected: *expected,
+                            actual,
                         }
+                        .into());
                     }
+                    let replaced = document.clone().map(|mut final_doc| {
+                        if let Some(obj) = final_doc.as_object_mut() {
+                            obj.insert("id".to_string(), Value::String(id.to_string()));
+                        }
+                        revision::stamp(&mut final_doc, state.as_ref());
+                        final_doc
+                    });
+                    *state = replaced;
+                }
+            }
+        }
+
+        log.after_images = order
+            .into_iter()
+            .map(|key| {
+                let document = current.remove(&key).flatten();
+                DocumentImage {
+                    collection: key.0,
+                    id: key.1,
+                    document,
+                }
+            })
+            .collect();
+
+        // Contraintes d'unicité : le lot entier est contrôlé avant le WAL
+        let storage = StorageEngine::new(self.config.clone());
+        let idx = IndexManager::new(&storage, &self.space, &self.db);
+        let mut by_collection: HashMap<&str, Vec<(&str, Option<&Value>)>> = HashMap::new();
+        for image in &log.after_images {
+            by_collection
+                .entry(image.collection.as_str())
+                .or_default()
+                .push((image.id.as_str(), image.document.as_ref()));
+        }
+        for (collection, writes) in &by_collection {
+            idx.check_unique_batch(collection, writes)?;
+        }
+        Ok(())
+    }
 
-                    self.apply_schema_logic(collection, &mut final_doc)?;
+    /// Écrit les images `to` : documents, index secondaires et index système.
+    /// `from` est l'état à désindexer (before-images pour appliquer ou rejouer,
+    /// after-images pour annuler). Idempotent : peut être rejoué après un crash.
+    pub(crate) fn apply_images(&self, from: &[DocumentImage], to: &[DocumentImage]) -> Result<()> {
+        let storage = StorageEngine::new(self.config.clone());
+        let mut idx = IndexManager::new(&storage, &self.space, &self.db);
+
+        for image in to {
+            let collection = &image.collection;
+            let previous = from
+                .iter()
+                .find(|p| p.collection == *collection && p.id == image.id)
+                .and_then(|p| p.document.as_ref());
+            if let Some(old) = previous {
+                idx.remove_document(collection, old)?;
+            }
+
+            match &image.document {
+                Some(doc) => {
+                    // Retrait préalable : un rejeu ne crée pas d'entrée en double
+                    idx.remove_document(collection, doc)?;
                     file_storage::write_document(
                         self.config,
                         &self.space,
                         &self.db,
                         collection,
-                        id,
-                        &final_doc,
+                        &image.id,
+                        doc,
                     )?;
-                    idx.index_document(collection, &final_doc)?;
-                    self.update_index_entry(&mut system_index, collection, id, false)?;
+                    idx.index_document(collection, doc)?;
                 }
-                Operation::Delete { collection, id } => {
+                None => {
                     file_storage::delete_document(
                         self.config,
                         &self.space,
                         &self.db,
                         collection,
-                        id,
+                        &image.id,
                     )?;
-                    idx.remove_document(collection, &serde_json::Value::Null)?; // Idéalement faudrait lire l'ancien doc avant pour désindexer proprement
-                    self.update_index_entry(&mut system_index, collection, id, true)?;
                 }
             }
         }
-        fs::write(&sys_path, serde_json::to_string_pretty(&system_index)?)?;
-        Ok(())
+
+        // Index système : lecture-modification-écriture sous son propre verrou,
+        // les transactions sur d'autres documents le modifient en parallèle
+        let _system = self
+            .lock_manager
+            .lock_system(self.config, &self.space, &self.db)?;
+        let sys_path = self
+            .config
+            .db_root(&self.space, &self.db)
+            .join("_system.json");
+        let mut system_index = if sys_path.exists() {
+            let c = fs::read_to_string(&sys_path)?;
+            serde_json::from_str::<Value>(&c).unwrap_or(json!({ "collections": {} }))
+        } else {
+            json!({ "collections": {} })
+        };
+        for image in to {
+            self.update_index_entry(
+                &mut system_index,
+                &image.collection,
+                &image.id,
+                image.document.is_none(),
+            )?;
+        }
+        file_storage::atomic_write(&sys_path, serde_json::to_string_pretty(&system_index)?)
     }
 
-    fn apply_schema_logic(&self, collection: &str, doc: &mut Value) -> Result<()> {
+    /// Champs calculés (`x_compute`), règles métier (`x_rules`) puis validation,
+    /// dans l'ordre du `CollectionsManager`. `old` : état avant l'opération.
+    fn apply_schema_logic(
+        &self,
+        collection: &str,
+        doc: &mut Value,
+        old: Option<&Value>,
+    ) -> Result<()> {
         let meta_path = self
             .config
             .db_collection_path(&self.space, &self.db, collection)
@@ -347,7 +498,25 @@ impl<'a> TransactionManager<'a> {
                 let reg = SchemaRegistry::from_db(self.config, &self.space, &self.db)?;
                 let validator = SchemaValidator::compile_with_registry(&uri, &reg)
                     .context(format!("Schema error: {}", uri))?;
-                validator.compute_then_validate(doc)?;
+                let provider = DbDataProvider {
+                    cfg: self.config,
+                    space: &self.space,
+                    db: &self.db,
+                };
+                validator.compute(doc, &provider)?;
+                if let Err(e) = apply_business_rules(
+                    self.config,
+                    &self.space,
+                    &self.db,
+                    collection,
+                    doc,
+                    old,
+                    &reg,
+                    &uri,
+                ) {
+                    eprintln!("⚠️ Erreur règles métier (non bloquant): {}", e);
+                }
+                validator.validate(doc)?;
             }
         }
         Ok(())
@@ -392,20 +561,28 @@ impl<'a> TransactionManager<'a> {
         Ok(())
     }
 
-    fn commit_wal(&self, tx: &Transaction) -> Result<()> {
-        let path = self
-            .config
-            .db_root(&self.space, &self.db)
-            .join("wal")
-            .join(format!("{}.json", tx.id));
-        if path.exists() {
-            fs::remove_file(path)?;
-        }
-        Ok(())
+    /// Marque la transaction comme appliquée puis supprime son journal.
+    /// Un crash entre les deux étapes est rattrapé par un rejeu (idempotent).
+    fn commit_wal(&self, log: &mut TransactionLog) -> Result<()> {
+        log.status = TransactionStatus::Committed;
+        self.write_wal(log)?;
+        // Numéro de commit + version conservée pour les snapshots en cours
+        snapshot::publish(self.config, &self.space, &self.db, log)?;
+        // Publié avant le retrait du journal : la récupération le complète au besoin
+        changes::record_transaction(self.config, &self.space, &self.db, log)?;
+        wal::remove_entry(self.config, &self.space, &self.db, &log.id)
     }
 
-    fn rollback_wal(&self, tx: &Transaction) -> Result<()> {
-        self.commit_wal(tx)
+    /// Annule une application partielle en réindexant les seuls documents de
+    /// la transaction (after-images désindexées, before-images réécrites) : les
+    /// entrées d'index des transactions concurrentes ne sont pas touchées. Le
+    /// journal passe en `Rollback` d'abord : si l'annulation échoue, la
+    /// récupération la reprend.
+    fn rollback_wal(&self, log: &mut TransactionLog) -> Result<()> {
+        log.status = TransactionStatus::Rollback;
+        self.write_wal(log)?;
+        self.apply_images(&log.after_images, &log.before_images)?;
+        wal::remove_entry(self.config, &self.space, &self.db, &log.id)
     }
 }
 
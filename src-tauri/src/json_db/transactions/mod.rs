@@ -5,6 +5,9 @@ use serde_json::Value;
 
 pub mod lock_manager;
 pub mod manager;
+pub mod recovery;
+pub mod revision;
+pub mod snapshot;
 pub mod wal;
 
 #[cfg(test)]
@@ -30,6 +33,10 @@ pub enum TransactionRequest {
     Delete {
         collection: String,
         id: String,
+        /// Révision lue par l'appelant : suppression refusée (`RevisionConflict`)
+        /// si le document a changé depuis
+        #[serde(default)]
+        revision: Option<u64>,
     },
     InsertFrom {
         collection: String,
@@ -85,19 +92,111 @@ pub enum Operation {
         collection: String,
         id: String,
     },
+    /// Remplacement à l'identique (réplication) : document écrit tel quel, sans
+    /// champs calculés ni validation, ou supprimé si `None`. Refusé par une
+    /// `RevisionConflict` si la révision stockée n'est plus `revision`.
+    Replace {
+        collection: String,
+        id: String,
+        revision: u64,
+        document: Option<Value>,
+    },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Operation {
+    /// Document visé : `(collection, id)`
+    pub fn target(&self) -> (&str, &str) {
+        match self {
+            Operation::Insert { collection, id, .. }
+            | Operation::Update { collection, id, .. }
+            | Operation::Delete { collection, id }
+            | Operation::Replace { collection, id, .. } => (collection, id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TransactionStatus {
+    /// Écriture en cours : en cas de crash, les before-images sont restaurées
     Pending,
+    /// Tout a été appliqué : en cas de crash, les after-images sont rejouées
     Committed,
     Rollback,
 }
 
+/// État d'un document avant ou après la transaction (`None` = absent)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentImage {
+    pub collection: String,
+    pub id: String,
+    pub document: Option<Value>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionLog {
     pub id: String,
     pub status: TransactionStatus,
     pub operations: Vec<Operation>,
     pub timestamp: i64,
+    /// Version de chaque document touché avant la transaction (undo)
+    #[serde(default)]
+    pub before_images: Vec<DocumentImage>,
+    /// Version finale de chaque document touché (redo)
+    #[serde(default)]
+    pub after_images: Vec<DocumentImage>,
+    /// Numéro de commit attribué à la validation (lectures snapshot)
+    #[serde(default)]
+    pub commit_seq: Option<u64>,
+    /// Écriture directe du `CollectionsManager` : publiée dans le flux de
+    /// changements sans transaction d'origine
+    #[serde(default)]
+    pub direct: bool,
+}
+
+impl TransactionLog {
+    pub fn pending(tx: &Transaction) -> Self {
+        Self {
+            id: tx.id.clone(),
+            status: TransactionStatus::Pending,
+            operations: tx.operations.clone(),
+            timestamp: chrono::Utc::now().timestamp(),
+            before_images: Vec::new(),
+            after_images: Vec::new(),
+            commit_seq: None,
+            direct: false,
+        }
+    }
+
+    /// Journal d'une écriture directe d'un document (`after = None` : suppression)
+    pub fn direct(collection: &str, id: &str, before: Option<Value>, after: Option<Value>) -> Self {
+        let operation = match &after {
+            Some(document) if before.is_some() => Operation::Update {
+                collection: collection.to_string(),
+                id: id.to_string(),
+                document: document.clone(),
+            },
+            Some(document) => Operation::Insert {
+                collection: collection.to_string(),
+                id: id.to_string(),
+                document: document.clone(),
+            },
+            None => Operation::Delete {
+                collection: collection.to_string(),
+                id: id.to_string(),
+            },
+        };
+        let image = |document| DocumentImage {
+            collection: collection.to_string(),
+            id: id.to_string(),
+            document,
+        };
+        let mut log = Self::pending(&Transaction {
+            id: uuid::Uuid::new_v4().to_string(),
+            operations: vec![operation],
+        });
+        log.before_images = vec![image(before)];
+        log.after_images = vec![image(after)];
+        log.direct = true;
+        log
+    }
 }
@@ -0,0 +1,98 @@
+// FICHIER : src-tauri/src/json_db/transactions/recovery.rs
+
+//! Récupération après crash.
+//!
+//! À l'ouverture d'une base, tout journal restant dans `wal/` correspond à une
+//! transaction interrompue :
+//! - `Committed` : toutes les écritures étaient décidées, on rejoue les after-images ;
+//! - `Pending` / `Rollback` : l'application a pu s'arrêter à mi-chemin, on
+//!   restaure les before-images ;
+//! - journal illisible (ancien format, fichier tronqué) : mis de côté en
+//!   `<tx>.json.orphan` pour inspection manuelle.
+//!
+//! L'application des images est idempotente : une récupération elle-même
+//! interrompue peut être relancée sans risque.
+
+use crate::json_db::changes;
+use crate::json_db::storage::JsonDbConfig;
+use crate::json_db::transactions::lock_manager::LockManager;
+use crate::json_db::transactions::manager::TransactionManager;
+use crate::json_db::transactions::{wal, TransactionStatus};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Bilan d'une récupération (IDs de transaction)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryReport {
+    /// Transactions validées dont les écritures ont été rejouées
+    pub rolled_forward: Vec<String>,
+    /// Transactions incomplètes dont les documents ont été restaurés
+    pub rolled_back: Vec<String>,
+    /// Journaux inexploitables mis de côté
+    pub discarded: Vec<String>,
+}
+
+impl RecoveryReport {
+    pub fn is_empty(&self) -> bool {
+        self.rolled_forward.is_empty() && self.rolled_back.is_empty() && self.discarded.is_empty()
+    }
+}
+
+/// Traite tous les journaux en attente de la base, du plus ancien au plus récent
+pub fn recover(config: &JsonDbConfig, space: &str, db: &str) -> Result<RecoveryReport> {
+    let mut report = RecoveryReport::default();
+    let pending = wal::list_pending(config, space, db)?;
+    if pending.is_empty() {
+        return Ok(report);
+    }
+
+    let tm = TransactionManager::new(config, space, db);
+    for tx_id in pending {
+        let log = match wal::read_log(config, space, db, &tx_id) {
+            Ok(log) => log,
+            Err(e) => {
+                tracing::warn!("⚠️ WAL {} ignoré : {}", tx_id, e);
+                wal::quarantine_entry(config, space, db, &tx_id)?;
+                report.discarded.push(tx_id);
+                continue;
+            }
+        };
+
+        // Un autre processus peut écrire dans la base pendant la récupération
+        let keys: Vec<(String, String)> = log
+            .before_images
+            .iter()
+            .map(|img| (img.collection.clone(), img.id.clone()))
+            .collect();
+        let _guards = LockManager::new().lock_documents(config, space, db, &keys)?;
+        // Transaction encore en cours ailleurs : une fois ses verrous relâchés,
+        // son journal a pu être validé ou supprimé
+        if !wal::entry_path(config, space, db, &tx_id).exists() {
+            continue;
+        }
+        let log = wal::read_log(config, space, db, &tx_id)?;
+
+        match log.status {
+            TransactionStatus::Committed => {
+                tm.apply_images(&log.before_images, &log.after_images)?;
+                changes::record_transaction(config, space, db, &log)?;
+                report.rolled_forward.push(tx_id.clone());
+            }
+            TransactionStatus::Pending | TransactionStatus::Rollback => {
+                tm.apply_images(&log.after_images, &log.before_images)?;
+                report.rolled_back.push(tx_id.clone());
+            }
+        }
+        wal::remove_entry(config, space, db, &tx_id)?;
+    }
+
+    tracing::info!(
+        "🩹 Récupération WAL {}/{} : {} rejouée(s), {} annulée(s), {} mise(s) de côté",
+        space,
+        db,
+        report.rolled_forward.len(),
+        report.rolled_back.len(),
+        report.discarded.len()
+    );
+    Ok(report)
+}
@@ -0,0 +1,86 @@
+// FICHIER : src-tauri/src/json_db/transactions/revision.rs
+
+//! Concurrence optimiste : chaque document porte un numéro de révision `_rev`,
+//! incrémenté à chaque écriture. Un appelant qui renvoie le `_rev` qu'il a lu
+//! obtient un compare-and-swap : si le document a changé entre-temps, la mise à
+//! jour est refusée avec une `RevisionConflict` au lieu d'écraser l'autre écriture.
+//! Sans `_rev` dans la mise à jour, l'écriture est inconditionnelle.
+
+use serde_json::Value;
+
+/// Champ système portant la révision d'un document
+pub const REV_FIELD: &str = "_rev";
+
+/// Conflit d'écriture détecté par le contrôle de révision.
+/// Récupérable via `err.downcast_ref::<RevisionConflict>()`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "Conflit d'écriture sur {collection}/{id} : révision attendue {expected}, actuelle {actual}"
+)]
+pub struct RevisionConflict {
+    pub collection: String,
+    pub id: String,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+/// Révision d'un document (0 s'il n'en a pas encore)
+pub fn revision_of(doc: &Value) -> u64 {
+    doc.get(REV_FIELD).and_then(|v| v.as_u64()).unwrap_or(0)
+}
+
+/// Révision attendue par une mise à jour, si l'appelant en a fourni une
+pub fn expected_revision(update: &Value) -> Option<u64> {
+    update.get(REV_FIELD).and_then(|v| v.as_u64())
+}
+
+/// Compare la révision attendue par `update` à celle du document stocké
+pub fn check(
+    collection: &str,
+    id: &str,
+    stored: Option<&Value>,
+    update: &Value,
+) -> Result<(), RevisionConflict> {
+    let Some(expected) = expected_revision(update) else {
+        return Ok(());
+    };
+    let actual = stored.map(revision_of).unwrap_or(0);
+    if expected != actual {
+        return Err(RevisionConflict {
+            collection: collection.to_string(),
+            id: id.to_string(),
+            expected,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Attribue à `doc` la révision suivant celle de `previous`
+pub fn stamp(doc: &mut Value, previous: Option<&Value>) {
+    let next = previous.map(revision_of).unwrap_or(0) + 1;
+    if let Some(obj) = doc.as_object_mut() {
+        obj.insert(REV_FIELD.to_string(), Value::from(next));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_check_and_stamp() {
+        let stored = json!({"id": "a", "_rev": 3});
+
+        assert!(check("c", "a", Some(&stored), &json!({"x": 1})).is_ok());
+        assert!(check("c", "a", Some(&stored), &json!({"_rev": 3})).is_ok());
+
+        let err = check("c", "a", Some(&stored), &json!({"_rev": 2})).unwrap_err();
+        assert_eq!((err.expected, err.actual), (2, 3));
+
+        let mut next = json!({"id": "a"});
+        stamp(&mut next, Some(&stored));
+        assert_eq!(revision_of(&next), 4);
+    }
+}
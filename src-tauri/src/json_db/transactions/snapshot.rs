@@ -0,0 +1,343 @@
+// FICHIER : src-tauri/src/json_db/transactions/snapshot.rs
+
+//! Lectures cohérentes (snapshot isolation) par versions issues du WAL.
+//!
+//! Chaque transaction validée reçoit un numéro de commit croissant
+//! (`_history/_seq`). Un `Snapshot` mémorise le dernier numéro au moment de sa
+//! création et voit la base telle qu'elle était alors :
+//! - un document modifié depuis est lu dans la before-image de la première
+//!   transaction qui l'a touché après le snapshot ;
+//! - ces journaux validés sont conservés dans `_history/<seq>.json` tant qu'un
+//!   snapshot actif (déclaré dans `_snapshots/`) peut en avoir besoin ;
+//! - les transactions en cours (`wal/`) sont vues à travers leurs before-images ;
+//!   un journal validé porte son numéro de commit dès sa publication, et n'est
+//!   plus appliqué aux snapshots qui incluent déjà ce commit.
+//!
+//! Le disque est toujours lu AVANT les journaux : une transaction qui démarre
+//! entre les deux est alors retrouvée dans `wal/` ou `_history/`.
+//! Les écritures directes du `CollectionsManager`, journalisées elles aussi
+//! dans le WAL, sont versionnées comme les transactions.
+
+use crate::json_db::storage::{file_storage, JsonDbConfig};
+use crate::json_db::transactions::lock_manager::{FileLock, LockMode, DEFAULT_LOCK_TIMEOUT};
+use crate::json_db::transactions::{wal, TransactionLog};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Au-delà, une déclaration de snapshot est considérée comme abandonnée
+/// (processus tué sans avoir relâché son snapshot)
+pub const SNAPSHOT_MAX_AGE: Duration = Duration::from_secs(3600);
+
+/// Snapshot dont la déclaration a été retirée (plus vieux que
+/// `SNAPSHOT_MAX_AGE`) : l'historique dont il dépend a pu être purgé, ses
+/// lectures sont refusées. Récupérable via `err.downcast_ref::<SnapshotExpired>()`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Snapshot expiré (commit {seq}) : historique purgé, rouvrir un snapshot")]
+pub struct SnapshotExpired {
+    pub seq: u64,
+}
+
+fn history_dir(config: &JsonDbConfig, space: &str, db: &str) -> PathBuf {
+    config.db_root(space, db).join("_history")
+}
+
+fn registrations_dir(config: &JsonDbConfig, space: &str, db: &str) -> PathBuf {
+    config.db_root(space, db).join("_snapshots")
+}
+
+fn seq_lock(config: &JsonDbConfig, space: &str, db: &str, mode: LockMode) -> Result<FileLock> {
+    let path = config
+        .db_root(space, db)
+        .join("_locks")
+        .join("_snapshots.lock");
+    FileLock::acquire(&path, mode, DEFAULT_LOCK_TIMEOUT)
+}
+
+/// Dernier numéro de commit attribué
+pub fn current_seq(config: &JsonDbConfig, space: &str, db: &str) -> u64 {
+    fs::read_to_string(history_dir(config, space, db).join("_seq"))
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Journaux lus lors du dernier parcours, valables tant que le numéro de
+/// commit courant et l'ensemble des transactions en cours n'ont pas changé
+#[derive(Debug)]
+struct LogCache {
+    head: u64,
+    pending: BTreeSet<String>,
+    logs: Arc<Vec<TransactionLog>>,
+}
+
+/// Vue figée d'une base. La déclaration est retirée au `drop`.
+#[derive(Debug)]
+pub struct Snapshot {
+    config: JsonDbConfig,
+    space: String,
+    db: String,
+    seq: u64,
+    registration: Option<PathBuf>,
+    logs: Mutex<Option<LogCache>>,
+}
+
+impl Snapshot {
+    pub fn begin(config: &JsonDbConfig, space: &str, db: &str) -> Result<Self> {
+        let mut snapshot = Self {
+            config: config.clone(),
+            space: space.to_string(),
+            db: db.to_string(),
+            seq: 0,
+            registration: None,
+            logs: Mutex::new(None),
+        };
+        // Base inexistante : rien à figer (et rien à créer sur disque)
+        if !config.db_root(space, db).exists() {
+            return Ok(snapshot);
+        }
+
+        // Partagé : plusieurs snapshots peuvent démarrer ensemble, mais pas
+        // pendant qu'un commit attribue un numéro et purge l'historique
+        let _guard = seq_lock(config, space, db, LockMode::Shared)?;
+        snapshot.seq = current_seq(config, space, db);
+        let registration =
+            registrations_dir(config, space, db).join(format!("{}.json", uuid::Uuid::new_v4()));
+        file_storage::atomic_write(&registration, json!({ "seq": snapshot.seq }).to_string())?;
+        snapshot.registration = Some(registration);
+        Ok(snapshot)
+    }
+
+    /// Numéro du dernier commit visible
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Journaux postérieurs au snapshot (cf. `scan_logs`). Le parcours n'est
+    /// refait que si un commit a été publié ou si les transactions en cours
+    /// ont changé depuis le précédent.
+    fn newer_logs(&self) -> Result<Arc<Vec<TransactionLog>>> {
+        let head = current_seq(&self.config, &self.space, &self.db);
+        let pending: BTreeSet<String> = wal::list_pending(&self.config, &self.space, &self.db)?
+            .into_iter()
+            .collect();
+        let mut cache = self
+            .logs
+            .lock()
+            .map_err(|_| anyhow!("Cache du snapshot empoisonné"))?;
+        if let Some(cached) = cache.as_ref() {
+            if cached.head == head && cached.pending == pending {
+                return Ok(cached.logs.clone());
+            }
+        }
+        let logs = Arc::new(self.scan_logs()?);
+        // Vérifié après le parcours : la déclaration est retirée avant toute
+        // purge de l'historique qu'elle protège
+        if self
+            .registration
+            .as_ref()
+            .is_some_and(|path| !path.exists())
+        {
+            return Err(SnapshotExpired { seq: self.seq }.into());
+        }
+        *cache = Some(LogCache {
+            head,
+            pending,
+            logs: logs.clone(),
+        });
+        Ok(logs)
+    }
+
+    /// Journaux postérieurs au snapshot, du plus ancien au plus récent :
+    /// commits conservés puis transactions en cours
+    fn scan_logs(&self) -> Result<Vec<TransactionLog>> {
+        let mut committed: Vec<TransactionLog> = Vec::new();
+        let dir = history_dir(&self.config, &self.space, &self.db);
+        if dir.exists() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    continue;
+                }
+                // Fichier purgé entre le listage et la lecture : plus nécessaire
+                let Ok(content) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                let log: TransactionLog = serde_json::from_str(&content)?;
+                if log.commit_seq.is_some_and(|seq| seq > self.seq) {
+                    committed.push(log);
+                }
+            }
+        }
+        committed.sort_by_key(|log| log.commit_seq);
+
+        for tx_id in wal::list_pending(&self.config, &self.space, &self.db)? {
+            // Journal validé et retiré entre-temps : il est dans l'historique
+            let Ok(log) = wal::read_log(&self.config, &self.space, &self.db, &tx_id) else {
+                continue;
+            };
+            // Commit publié avant le snapshot, journal pas encore retiré : déjà visible
+            if log.commit_seq.is_some_and(|seq| seq <= self.seq) {
+                continue;
+            }
+            committed.push(log);
+        }
+        Ok(committed)
+    }
+
+    /// États des documents de `collection` modifiés depuis le snapshot
+    fn overrides(&self, collection: &str) -> Result<HashMap<String, Option<Value>>> {
+        let mut overrides = HashMap::new();
+        for log in self.newer_logs()?.iter() {
+            for image in &log.before_images {
+                if image.collection == collection {
+                    // La première transaction après le snapshot donne l'état visible
+                    overrides
+                        .entry(image.id.clone())
+                        .or_insert_with(|| image.document.clone());
+                }
+            }
+        }
+        Ok(overrides)
+    }
+
+    pub fn read_document(&self, collection: &str, id: &str) -> Result<Option<Value>> {
+        let current =
+            file_storage::read_document(&self.config, &self.space, &self.db, collection, id)?;
+        Ok(match self.overrides(collection)?.remove(id) {
+            Some(before) => before,
+            None => current,
+        })
+    }
+
+    /// Versions visibles des documents `ids`, candidats tirés d'un index (qui
+    /// reflète l'état courant), et de tous ceux modifiés depuis le snapshot,
+    /// que l'index courant peut ne plus désigner
+    pub fn read_candidates(&self, collection: &str, ids: &[String]) -> Result<Vec<Value>> {
+        let mut current = Vec::with_capacity(ids.len());
+        for id in ids {
+            current.push((
+                id,
+                file_storage::read_document(&self.config, &self.space, &self.db, collection, id)?,
+            ));
+        }
+        let mut overrides = self.overrides(collection)?;
+
+        let mut docs = Vec::with_capacity(current.len());
+        for (id, doc) in current {
+            docs.extend(overrides.remove(id).unwrap_or(doc));
+        }
+        let mut rest: Vec<String> = overrides.keys().cloned().collect();
+        rest.sort();
+        docs.extend(
+            rest.into_iter()
+                .filter_map(|id| overrides.remove(&id).flatten()),
+        );
+        Ok(docs)
+    }
+
+    pub fn list_documents(&self, collection: &str) -> Result<Vec<Value>> {
+        let current =
+            file_storage::list_documents(&self.config, &self.space, &self.db, collection)?;
+        let mut overrides = self.overrides(collection)?;
+
+        let mut docs: Vec<Value> = current
+            .into_iter()
+            .filter(|doc| {
+                let id = doc.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+                !overrides.contains_key(id)
+            })
+            .collect();
+        let mut ids: Vec<String> = overrides.keys().cloned().collect();
+        ids.sort();
+        docs.extend(
+            ids.into_iter()
+                .filter_map(|id| overrides.remove(&id).flatten()),
+        );
+        Ok(docs)
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        if let Some(path) = &self.registration {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Attribue un numéro de commit à une transaction appliquée et conserve son
+/// journal si un snapshot actif est antérieur. Purge les versions devenues inutiles.
+pub(crate) fn publish(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    log: &mut TransactionLog,
+) -> Result<()> {
+    let _guard = seq_lock(config, space, db, LockMode::Exclusive)?;
+    let dir = history_dir(config, space, db);
+    let seq = current_seq(config, space, db) + 1;
+    log.commit_seq = Some(seq);
+
+    // Journal WAL daté avant que le numéro ne devienne visible : un snapshot
+    // qui démarre avant son retrait sait qu'il inclut déjà ce commit
+    wal::write_log(config, space, db, log)?;
+
+    let oldest = oldest_active_snapshot(config, space, db)?;
+    if oldest.is_some_and(|s| s < seq) {
+        file_storage::atomic_write(
+            dir.join(format!("{:020}.json", seq)),
+            serde_json::to_string(log)?,
+        )?;
+    }
+    file_storage::atomic_write(dir.join("_seq"), seq.to_string())?;
+
+    // Versions plus vues par aucun snapshot (seq <= plus ancien snapshot actif)
+    if dir.exists() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let version = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<u64>().ok());
+            if let Some(version) = version {
+                if oldest.is_none_or(|s| version <= s) {
+                    file_storage::remove_if_exists(&path)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Plus petit numéro parmi les snapshots déclarés (les déclarations abandonnées sont retirées)
+fn oldest_active_snapshot(config: &JsonDbConfig, space: &str, db: &str) -> Result<Option<u64>> {
+    let dir = registrations_dir(config, space, db);
+    if !dir.exists() {
+        return Ok(None);
+    }
+    let mut oldest: Option<u64> = None;
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let age = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.elapsed().ok());
+        if age.is_some_and(|age| age > SNAPSHOT_MAX_AGE) {
+            file_storage::remove_if_exists(&path)?;
+            continue;
+        }
+        let seq = fs::read_to_string(&path)
+            .ok()
+            .and_then(|c| serde_json::from_str::<Value>(&c).ok())
+            .and_then(|v| v.get("seq").and_then(|s| s.as_u64()));
+        if let Some(seq) = seq {
+            oldest = Some(oldest.map_or(seq, |o| o.min(seq)));
+        }
+    }
+    Ok(oldest)
+}
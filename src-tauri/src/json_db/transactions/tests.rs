@@ -7,9 +7,7 @@ use tempfile::tempdir;
 #[test]
 fn test_transaction_commit_success() {
     let dir = tempdir().unwrap();
-    let config = JsonDbConfig {
-        data_root: dir.path().to_path_buf(),
-    };
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
     let space = "test_space";
     let db = "test_db";
 
@@ -36,9 +34,7 @@ fn test_transaction_commit_success() {
 #[test]
 fn test_transaction_rollback_on_error() {
     let dir = tempdir().unwrap();
-    let config = JsonDbConfig {
-        data_root: dir.path().to_path_buf(),
-    };
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
     let space = "test_space";
     let db = "test_db";
 
@@ -62,12 +58,474 @@ fn test_transaction_rollback_on_error() {
 #[test]
 fn test_wal_persistence() {
     let dir = tempdir().unwrap();
-    let config = JsonDbConfig {
-        data_root: dir.path().to_path_buf(),
-    };
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
     let tm = TransactionManager::new(&config, "s", "d");
 
     // Le dossier WAL doit être créé
     let _ = tm.execute(|_| Ok(()));
     assert!(config.db_root("s", "d").join("wal").exists());
 }
+
+#[test]
+fn test_recovery_restores_before_images_of_pending_tx() {
+    use crate::json_db::storage::file_storage;
+    use crate::json_db::transactions::{recovery, wal, DocumentImage, Transaction, TransactionLog};
+
+    let dir = tempdir().unwrap();
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
+    let (space, db) = ("s", "d");
+    fs::create_dir_all(config.db_root(space, db).join("users")).unwrap();
+
+    let original = json!({"id": "u1", "name": "Alice"});
+    file_storage::write_document(&config, space, db, "users", "u1", &original).unwrap();
+
+    // Crash simulé : journal Pending écrit, un document sur deux déjà modifié
+    let mut log = TransactionLog::pending(&Transaction::new());
+    log.before_images = vec![
+        DocumentImage {
+            collection: "users".into(),
+            id: "u1".into(),
+            document: Some(original.clone()),
+        },
+        DocumentImage {
+            collection: "users".into(),
+            id: "u2".into(),
+            document: None,
+        },
+    ];
+    log.after_images = vec![
+        DocumentImage {
+            collection: "users".into(),
+            id: "u1".into(),
+            document: Some(json!({"id": "u1", "name": "Alice v2"})),
+        },
+        DocumentImage {
+            collection: "users".into(),
+            id: "u2".into(),
+            document: Some(json!({"id": "u2", "name": "Bob"})),
+        },
+    ];
+    wal::write_log(&config, space, db, &log).unwrap();
+    let half = json!({"id": "u1", "name": "Alice v2"});
+    file_storage::write_document(&config, space, db, "users", "u1", &half).unwrap();
+
+    let report = recovery::recover(&config, space, db).unwrap();
+    assert_eq!(report.rolled_back, vec![log.id.clone()]);
+
+    let u1 = file_storage::read_document(&config, space, db, "users", "u1").unwrap();
+    assert_eq!(u1, Some(original));
+    let u2 = file_storage::read_document(&config, space, db, "users", "u2").unwrap();
+    assert_eq!(u2, None);
+    assert!(wal::list_pending(&config, space, db).unwrap().is_empty());
+}
+
+#[test]
+fn test_recovery_rolls_committed_tx_forward() {
+    use crate::json_db::storage::file_storage;
+    use crate::json_db::transactions::{
+        wal, DocumentImage, Transaction, TransactionLog, TransactionStatus,
+    };
+
+    let dir = tempdir().unwrap();
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
+    let (space, db) = ("s", "d");
+    fs::create_dir_all(config.db_root(space, db).join("users")).unwrap();
+
+    // Crash simulé : journal marqué Committed, mais écritures non terminées
+    let mut log = TransactionLog::pending(&Transaction::new());
+    log.status = TransactionStatus::Committed;
+    log.before_images = vec![DocumentImage {
+        collection: "users".into(),
+        id: "u3".into(),
+        document: None,
+    }];
+    log.after_images = vec![DocumentImage {
+        collection: "users".into(),
+        id: "u3".into(),
+        document: Some(json!({"id": "u3", "name": "Carol"})),
+    }];
+    wal::write_log(&config, space, db, &log).unwrap();
+
+    // Un journal illisible est mis de côté, pas rejoué
+    let wal_dir = config.db_root(space, db).join("wal");
+    fs::write(wal_dir.join("legacy.json"), "{ tronqué").unwrap();
+
+    // L'ouverture de la base déclenche la récupération
+    file_storage::open_db(&config, space, db).unwrap();
+
+    let u3 = file_storage::read_document(&config, space, db, "users", "u3").unwrap();
+    assert_eq!(u3.unwrap()["name"], "Carol");
+    assert!(wal_dir.join("legacy.json.orphan").exists());
+    assert!(wal::list_pending(&config, space, db).unwrap().is_empty());
+
+    let system: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(config.db_root(space, db).join("_system.json")).unwrap(),
+    )
+    .unwrap();
+    assert!(system["collections"]["users"]["items"]
+        .as_array()
+        .is_some_and(|items| !items.is_empty()));
+}
+
+#[test]
+fn test_failed_apply_restores_documents_and_indexes() {
+    use crate::json_db::indexes::IndexManager;
+    use crate::json_db::storage::{file_storage, StorageEngine};
+    use crate::json_db::transactions::wal;
+
+    let dir = tempdir().unwrap();
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
+    let (space, db) = ("s", "d");
+    fs::create_dir_all(config.db_root(space, db).join("users")).unwrap();
+
+    let tm = TransactionManager::new(&config, space, db);
+    tm.execute(|tx| {
+        tx.add_insert("users", "u1", json!({"name": "Alice"}));
+        Ok(())
+    })
+    .unwrap();
+
+    let storage = StorageEngine::new(config.clone());
+    let mut idx = IndexManager::new(&storage, space, db);
+    idx.create_index("users", "name", "hash").unwrap();
+    let def = idx.load_indexes("users").unwrap().remove(0);
+
+    let sys_path = config.db_root(space, db).join("_system.json");
+    let system_before = fs::read_to_string(&sys_path).unwrap();
+
+    // Collection "cassée" : un fichier à la place du dossier, l'écriture échoue
+    fs::write(config.db_collection_path(space, db, "broken"), "").unwrap();
+
+    let res = tm.execute(|tx| {
+        tx.operations
+            .push(crate::json_db::transactions::Operation::Update {
+                collection: "users".into(),
+                id: "u1".into(),
+                document: json!({"name": "Alice v2"}),
+            });
+        tx.add_insert("users", "u2", json!({"name": "Bob"}));
+        tx.add_insert("broken", "x", json!({"name": "X"}));
+        Ok(())
+    });
+    assert!(res.is_err());
+
+    // Documents : état d'avant la transaction
+    let u1 = file_storage::read_document(&config, space, db, "users", "u1").unwrap();
+    assert_eq!(u1.unwrap()["name"], "Alice");
+    assert!(
+        file_storage::read_document(&config, space, db, "users", "u2")
+            .unwrap()
+            .is_none()
+    );
+
+    // Index secondaires et index système : inchangés
+    let hits = |v: &str| idx.lookup("users", &def, &[json!(v)]).unwrap();
+    assert_eq!(hits("Alice"), vec!["u1".to_string()]);
+    assert!(hits("Alice v2").is_empty());
+    assert!(hits("Bob").is_empty());
+    assert_eq!(fs::read_to_string(&sys_path).unwrap(), system_before);
+
+    // Rien à récupérer : le rollback a été mené à terme
+    assert!(wal::list_pending(&config, space, db).unwrap().is_empty());
+}
+
+#[test]
+fn test_unique_index_rejects_transaction_before_wal() {
+    use crate::json_db::indexes::{DuplicateKeyError, IndexManager};
+    use crate::json_db::storage::{file_storage, StorageEngine};
+    use crate::json_db::transactions::wal;
+
+    let dir = tempdir().unwrap();
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
+    let (space, db) = ("s", "d");
+    fs::create_dir_all(config.db_collection_path(space, db, "users")).unwrap();
+
+    let storage = StorageEngine::new(config.clone());
+    let mut idx = IndexManager::new(&storage, space, db);
+    idx.create_index_on("users", &["email"], "unique", false)
+        .unwrap();
+
+    let tm = TransactionManager::new(&config, space, db);
+    tm.execute(|tx| {
+        tx.add_insert("users", "u1", json!({"email": "a@raise.io"}));
+        Ok(())
+    })
+    .unwrap();
+
+    // Doublon dans le lot : refus typé, rien d'écrit
+    let err = tm
+        .execute(|tx| {
+            tx.add_insert("users", "u2", json!({"email": "b@raise.io"}));
+            tx.add_insert("users", "u3", json!({"email": "a@raise.io"}));
+            Ok(())
+        })
+        .unwrap_err();
+    let dup = err
+        .downcast_ref::<DuplicateKeyError>()
+        .expect("typed error");
+    assert_eq!(dup.existing_id, "u1");
+    assert!(
+        file_storage::read_document(&config, space, db, "users", "u2")
+            .unwrap()
+            .is_none()
+    );
+    assert!(wal::list_pending(&config, space, db).unwrap().is_empty());
+
+    // Échange de clés dans une même transaction : autorisé
+    tm.execute(|tx| {
+        tx.operations
+            .push(crate::json_db::transactions::Operation::Delete {
+                collection: "users".into(),
+                id: "u1".into(),
+            });
+        tx.add_insert("users", "u3", json!({"email": "a@raise.io"}));
+        Ok(())
+    })
+    .unwrap();
+    let def = idx.load_indexes("users").unwrap().remove(0);
+    assert_eq!(
+        idx.lookup("users", &def, &[json!("a@raise.io")]).unwrap(),
+        vec!["u3".to_string()]
+    );
+}
+
+#[test]
+fn test_snapshot_ignores_later_commits() {
+    use crate::json_db::storage::file_storage;
+    use crate::json_db::transactions::snapshot::Snapshot;
+    use crate::json_db::transactions::{wal, DocumentImage, Operation, TransactionLog};
+
+    let dir = tempdir().unwrap();
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
+    let (space, db) = ("s", "d");
+    fs::create_dir_all(config.db_root(space, db).join("users")).unwrap();
+
+    let tm = TransactionManager::new(&config, space, db);
+    tm.execute(|tx| {
+        tx.add_insert("users", "u1", json!({"name": "Alice"}));
+        tx.add_insert("users", "u2", json!({"name": "Bob"}));
+        Ok(())
+    })
+    .unwrap();
+
+    let snap = Snapshot::begin(&config, space, db).unwrap();
+
+    // Après le snapshot : modification, suppression et insertion validées
+    tm.execute(|tx| {
+        tx.operations.push(Operation::Update {
+            collection: "users".into(),
+            id: "u1".into(),
+            document: json!({"name": "Alice v2"}),
+        });
+        tx.operations.push(Operation::Delete {
+            collection: "users".into(),
+            id: "u2".into(),
+        });
+        tx.add_insert("users", "u3", json!({"name": "Carol"}));
+        Ok(())
+    })
+    .unwrap();
+
+    let u1 = snap.read_document("users", "u1").unwrap().unwrap();
+    assert_eq!(u1["name"], "Alice");
+    let mut names: Vec<String> = snap
+        .list_documents("users")
+        .unwrap()
+        .iter()
+        .map(|d| d["name"].as_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["Alice", "Bob"]);
+
+    // Transaction en cours (appliquée à moitié) : invisible pour un nouveau snapshot
+    let fresh = Snapshot::begin(&config, space, db).unwrap();
+    let mut log = TransactionLog::pending(&crate::json_db::transactions::Transaction::new());
+    log.before_images = vec![DocumentImage {
+        collection: "users".into(),
+        id: "u3".into(),
+        document: file_storage::read_document(&config, space, db, "users", "u3").unwrap(),
+    }];
+    wal::write_log(&config, space, db, &log).unwrap();
+    let half = json!({"id": "u3", "name": "Carol v2"});
+    file_storage::write_document(&config, space, db, "users", "u3", &half).unwrap();
+
+    let u3 = fresh.read_document("users", "u3").unwrap().unwrap();
+    assert_eq!(u3["name"], "Carol");
+    wal::remove_entry(&config, space, db, &log.id).unwrap();
+
+    // Snapshots relâchés : l'historique est purgé au commit suivant
+    drop(snap);
+    drop(fresh);
+    tm.execute(|tx| {
+        tx.add_insert("users", "u4", json!({"name": "Dan"}));
+        Ok(())
+    })
+    .unwrap();
+    let history: Vec<_> = fs::read_dir(config.db_root(space, db).join("_history"))
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|x| x == "json"))
+        .collect();
+    assert!(history.is_empty());
+}
+
+#[test]
+fn test_snapshot_skips_published_commit_still_in_wal() {
+    use crate::json_db::transactions::snapshot::{self, Snapshot};
+    use crate::json_db::transactions::{
+        wal, DocumentImage, Transaction, TransactionLog, TransactionStatus,
+    };
+
+    let dir = tempdir().unwrap();
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
+    let (space, db) = ("s", "d");
+    fs::create_dir_all(config.db_root(space, db).join("users")).unwrap();
+    let tm = TransactionManager::new(&config, space, db);
+    tm.execute(|tx| {
+        tx.add_insert("users", "u1", json!({"name": "Alice v2"}));
+        Ok(())
+    })
+    .unwrap();
+
+    // Commit publié (numéro attribué) dont le journal n'est pas encore retiré
+    let mut log = TransactionLog::pending(&Transaction::new());
+    log.status = TransactionStatus::Committed;
+    log.commit_seq = Some(snapshot::current_seq(&config, space, db));
+    log.before_images = vec![DocumentImage {
+        collection: "users".into(),
+        id: "u1".into(),
+        document: Some(json!({"id": "u1", "name": "Alice"})),
+    }];
+    wal::write_log(&config, space, db, &log).unwrap();
+
+    let snap = Snapshot::begin(&config, space, db).unwrap();
+    let u1 = snap.read_document("users", "u1").unwrap().unwrap();
+    assert_eq!(u1["name"], "Alice v2");
+
+    // Commit postérieur au snapshot : before-image appliquée
+    log.commit_seq = Some(snap.seq() + 1);
+    wal::write_log(&config, space, db, &log).unwrap();
+    let older = Snapshot::begin(&config, space, db).unwrap();
+    let u1 = older.read_document("users", "u1").unwrap().unwrap();
+    assert_eq!(u1["name"], "Alice");
+}
+
+#[test]
+fn test_replace_writes_verbatim_with_revision_check() {
+    use crate::json_db::storage::file_storage;
+    use crate::json_db::transactions::revision::RevisionConflict;
+    use crate::json_db::transactions::Operation;
+
+    let dir = tempdir().unwrap();
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
+    let (space, db) = ("s", "d");
+    fs::create_dir_all(config.db_collection_path(space, db, "users")).unwrap();
+    let tm = TransactionManager::new(&config, space, db);
+
+    let replace = |revision: u64, document: Option<serde_json::Value>| {
+        tm.execute(|tx| {
+            tx.operations.push(Operation::Replace {
+                collection: "users".into(),
+                id: "u1".into(),
+                revision,
+                document,
+            });
+            Ok(())
+        })
+    };
+
+    // Écrit tel quel : `updatedAt` conservé, id et révision locale attribués
+    let remote = json!({"name": "Alice", "updatedAt": "2024-01-01T00:00:00Z", "_rev": 9});
+    replace(0, Some(remote)).unwrap();
+    let stored = file_storage::read_document(&config, space, db, "users", "u1")
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored["updatedAt"], "2024-01-01T00:00:00Z");
+    assert_eq!(stored["id"], "u1");
+    assert_eq!(stored["_rev"], 1);
+
+    // Révision périmée : refusée sans écriture
+    let err = replace(0, None).unwrap_err();
+    assert_eq!(err.downcast_ref::<RevisionConflict>().unwrap().actual, 1);
+
+    replace(1, None).unwrap();
+    assert!(
+        file_storage::read_document(&config, space, db, "users", "u1")
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_insert_with_revision_zero_never_replaces() {
+    use crate::json_db::storage::file_storage;
+
+    let dir = tempdir().unwrap();
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
+    let (space, db) = ("s", "d");
+    fs::create_dir_all(config.db_collection_path(space, db, "users")).unwrap();
+    let tm = TransactionManager::new(&config, space, db);
+    // Document existant écrit sans révision (écriture directe)
+    let original = json!({"id": "u1", "name": "Alice"});
+    file_storage::write_document(&config, space, db, "users", "u1", &original).unwrap();
+
+    let create = |id: &str, name: &str| {
+        tm.execute(|tx| {
+            tx.add_insert("users", id, json!({"name": name, "_rev": 0}));
+            Ok(())
+        })
+    };
+    assert!(create("u1", "Mallory").is_err());
+    let stored = file_storage::read_document(&config, space, db, "users", "u1")
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored, original);
+
+    create("u2", "Bob").unwrap();
+    let stored = file_storage::read_document(&config, space, db, "users", "u2")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (&stored["name"], &stored["_rev"]),
+        (&json!("Bob"), &json!(1))
+    );
+}
+
+#[test]
+fn test_expired_snapshot_refuses_reads() {
+    use crate::json_db::transactions::snapshot::{Snapshot, SnapshotExpired, SNAPSHOT_MAX_AGE};
+    use std::time::SystemTime;
+
+    let dir = tempdir().unwrap();
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
+    let (space, db) = ("s", "d");
+    fs::create_dir_all(config.db_root(space, db).join("users")).unwrap();
+    let tm = TransactionManager::new(&config, space, db);
+    let insert = |id: &str| {
+        tm.execute(|tx| {
+            tx.add_insert("users", id, json!({"name": id}));
+            Ok(())
+        })
+        .unwrap()
+    };
+    insert("u1");
+
+    let snap = Snapshot::begin(&config, space, db).unwrap();
+    // Déclaration plus vieille que SNAPSHOT_MAX_AGE : retirée au commit suivant
+    let past = SystemTime::now() - SNAPSHOT_MAX_AGE * 2;
+    for entry in fs::read_dir(config.db_root(space, db).join("_snapshots")).unwrap() {
+        let file = fs::File::options()
+            .write(true)
+            .open(entry.unwrap().path())
+            .unwrap();
+        file.set_modified(past).unwrap();
+    }
+    insert("u2");
+
+    let err = snap.list_documents("users").unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<SnapshotExpired>(),
+        Some(&SnapshotExpired { seq: snap.seq() })
+    );
+    assert!(snap.read_document("users", "u1").is_err());
+}
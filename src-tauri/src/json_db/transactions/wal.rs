@@ -1,6 +1,6 @@
-use crate::json_db::storage::JsonDbConfig;
-use crate::json_db::transactions::{Transaction, TransactionLog, TransactionStatus};
-use anyhow::Result;
+use crate::json_db::storage::{file_storage, JsonDbConfig};
+use crate::json_db::transactions::{Transaction, TransactionLog};
+use anyhow::{Context, Result};
 use std::fs;
 use std::path::PathBuf;
 
@@ -11,34 +11,38 @@ fn get_wal_dir(config: &JsonDbConfig, space: &str, db: &str) -> PathBuf {
     config.db_root(space, db).join("wal")
 }
 
+/// Chemin du journal d'une transaction
+pub fn entry_path(config: &JsonDbConfig, space: &str, db: &str, tx_id: &str) -> PathBuf {
+    get_wal_dir(config, space, db).join(format!("{}.json", tx_id))
+}
+
 /// Écrit une transaction dans le journal (Write Ahead Log)
 pub fn write_entry(config: &JsonDbConfig, space: &str, db: &str, tx: &Transaction) -> Result<()> {
-    let dir = get_wal_dir(config, space, db);
-
-    // Création du dossier si inexistant
-    if !dir.exists() {
-        fs::create_dir_all(&dir)?;
-    }
-
-    let file_path = dir.join(format!("{}.json", tx.id));
-
-    // Création de l'objet de log pour sérialisation
-    let log = TransactionLog {
-        id: tx.id.clone(),
-        status: TransactionStatus::Pending,
-        operations: tx.operations.clone(),
-        timestamp: chrono::Utc::now().timestamp(),
-    };
+    write_log(config, space, db, &TransactionLog::pending(tx))
+}
 
-    let content = serde_json::to_string_pretty(&log)?;
-    fs::write(file_path, content)?;
+/// Écrit (ou réécrit) un journal complet, de façon atomique : un crash ne
+/// laisse jamais un journal à moitié écrit.
+pub fn write_log(config: &JsonDbConfig, space: &str, db: &str, log: &TransactionLog) -> Result<()> {
+    let content = serde_json::to_string_pretty(log)?;
+    file_storage::atomic_write(entry_path(config, space, db, &log.id), content)
+}
 
-    Ok(())
+/// Relit un journal
+pub fn read_log(
+    config: &JsonDbConfig,
+    space: &str,
+    db: &str,
+    tx_id: &str,
+) -> Result<TransactionLog> {
+    let path = entry_path(config, space, db, tx_id);
+    let content = fs::read_to_string(&path)?;
+    serde_json::from_str(&content).with_context(|| format!("Journal WAL illisible : {:?}", path))
 }
 
 /// Supprime une entrée du WAL (utilisé lors du Commit ou Rollback)
 pub fn remove_entry(config: &JsonDbConfig, space: &str, db: &str, tx_id: &str) -> Result<()> {
-    let file_path = get_wal_dir(config, space, db).join(format!("{}.json", tx_id));
+    let file_path = entry_path(config, space, db, tx_id);
 
     if file_path.exists() {
         fs::remove_file(file_path)?;
@@ -47,7 +51,16 @@ pub fn remove_entry(config: &JsonDbConfig, space: &str, db: &str, tx_id: &str) -
     Ok(())
 }
 
-/// (Optionnel) Charge les transactions en attente (pour la récupération au démarrage)
+/// Met de côté un journal inexploitable (`<tx>.json.orphan`) pour inspection manuelle
+pub fn quarantine_entry(config: &JsonDbConfig, space: &str, db: &str, tx_id: &str) -> Result<()> {
+    let file_path = entry_path(config, space, db, tx_id);
+    if file_path.exists() {
+        fs::rename(&file_path, file_path.with_extension("json.orphan"))?;
+    }
+    Ok(())
+}
+
+/// Transactions dont le journal n'a pas été supprimé (pour la récupération au démarrage)
 pub fn list_pending(config: &JsonDbConfig, space: &str, db: &str) -> Result<Vec<String>> {
     let dir = get_wal_dir(config, space, db);
     let mut pending_ids = Vec::new();
@@ -63,5 +76,11 @@ pub fn list_pending(config: &JsonDbConfig, space: &str, db: &str) -> Result<Vec<
             }
         }
     }
+    // Ordre chronologique approximatif : les IDs sont des UUID, on trie par date du fichier
+    pending_ids.sort_by_key(|id| {
+        fs::metadata(entry_path(config, space, db, id))
+            .and_then(|m| m.modified())
+            .ok()
+    });
     Ok(pending_ids)
 }
@@ -19,10 +19,11 @@ use raise::commands::{
 };
 
 // Architecture JSON-DB & Plugins
+use raise::json_db::migrations::catalog;
 use raise::json_db::migrations::migrator::Migrator;
-use raise::json_db::migrations::{Migration, MigrationStep};
-use raise::json_db::storage::{JsonDbConfig, StorageEngine};
-use serde_json::Value; // Pour Value::Null
+use raise::json_db::storage::{
+    compression::Compression, segment::StorageLayout, JsonDbConfig, StorageEngine,
+};
 
 use raise::plugins::manager::PluginManager;
 
@@ -69,7 +70,21 @@ fn main() {
                 default_path
             };
 
-            let config = JsonDbConfig::new(db_root);
+            let mut config = JsonDbConfig::new(db_root);
+            // Compression optionnelle des documents ("zstd", "zstd:19"...)
+            if let Ok(codec) = env::var("RAISE_DB_COMPRESSION") {
+                match Compression::parse(&codec) {
+                    Ok(c) => config = config.with_compression(c),
+                    Err(e) => eprintln!("⚠️ RAISE_DB_COMPRESSION ignorée : {}", e),
+                }
+            }
+            // Format des collections ("files" par défaut, "segments")
+            if let Ok(layout) = env::var("RAISE_DB_LAYOUT") {
+                match StorageLayout::parse(&layout) {
+                    Ok(l) => config = config.with_layout(l),
+                    Err(e) => eprintln!("⚠️ RAISE_DB_LAYOUT ignorée : {}", e),
+                }
+            }
             let storage = StorageEngine::new(config.clone());
 
             let default_space = "un2";
@@ -189,8 +204,20 @@ fn main() {
             json_db_commands::jsondb_list_all,
             json_db_commands::jsondb_execute_query,
             json_db_commands::jsondb_execute_sql,
+            json_db_commands::jsondb_read_changes,
+            json_db_commands::jsondb_subscribe_changes,
+            json_db_commands::jsondb_unsubscribe_changes,
+            json_db_commands::jsondb_backup_db,
+            json_db_commands::jsondb_restore_db,
+            json_db_commands::jsondb_sync_db,
+            json_db_commands::jsondb_export_bundle,
+            json_db_commands::jsondb_import_bundle,
+            json_db_commands::jsondb_resolve_conflict,
+            json_db_commands::jsondb_cache_stats,
+            json_db_commands::jsondb_clear_cache,
             // <-- MOTEUR DE RÈGLES --->
             json_db_commands::jsondb_evaluate_draft,
+            json_db_commands::jsondb_validate_document,
             json_db_commands::jsondb_init_demo_rules,
             // --- MODEL & ARCHITECTURE ---
             model_commands::load_project_model,
@@ -240,42 +267,6 @@ fn main() {
 
 fn run_app_migrations(storage: &StorageEngine, space: &str, db: &str) -> anyhow::Result<()> {
     let migrator = Migrator::new(storage, space, db);
-
-    let migrations = vec![
-        Migration {
-            id: "init_001_core_collections".to_string(),
-            version: "1.0.0".to_string(),
-            description: "Création des collections de base Arcadia".to_string(),
-            up: vec![
-                MigrationStep::CreateCollection {
-                    name: "articles".to_string(),
-                    schema: Value::Null,
-                },
-                MigrationStep::CreateCollection {
-                    name: "systems".to_string(),
-                    schema: Value::Null,
-                },
-                MigrationStep::CreateCollection {
-                    name: "exchange_items".to_string(),
-                    schema: Value::Null,
-                },
-            ],
-            down: vec![],
-            applied_at: None,
-        },
-        Migration {
-            id: "idx_001_articles_title".to_string(),
-            version: "1.1.0".to_string(),
-            description: "Indexation des articles par titre".to_string(),
-            up: vec![MigrationStep::CreateIndex {
-                collection: "articles".to_string(),
-                fields: vec!["title".to_string()],
-            }],
-            down: vec![],
-            applied_at: None,
-        },
-    ];
-
-    migrator.run_migrations(migrations)?;
+    migrator.run_migrations(catalog::app_migrations())?;
     Ok(())
 }
@@ -64,12 +64,14 @@ impl<'a> ModelLoader<'a> {
             },
         };
 
-        if let Ok(collections) = self.manager.list_collections() {
+        // Lecture cohérente : une transaction validée pendant le chargement n'est pas vue à moitié
+        let manager = self.manager.snapshot()?;
+        if let Ok(collections) = manager.list_collections() {
             for col_name in collections {
                 if col_name.starts_with('_') {
                     continue;
                 }
-                if let Ok(docs) = self.manager.list_all(&col_name) {
+                if let Ok(docs) = manager.list_all(&col_name) {
                     for doc in docs {
                         if let Ok(element) = self.process_document_semantically(doc) {
                             self.dispatch_element(&mut model, element);
@@ -14,7 +14,7 @@ mod integration_tests {
     fn setup_env(space: &str, db: &str) -> (tempfile::TempDir, JsonDbConfig) {
         let tmp_dir = tempfile::tempdir().expect("create temp dir");
         let data_root = tmp_dir.path().to_path_buf();
-        let config = JsonDbConfig { data_root };
+        let config = JsonDbConfig::new(data_root);
 
         let db_root = config.db_root(space, db);
         fs::create_dir_all(&db_root).expect("create db root");
@@ -13,7 +13,7 @@ impl Analyzer {
     fn visit(expr: &Expr, deps: &mut HashSet<String>) {
         match expr {
             // Primitives sans dépendances
-            Expr::Val(_) | Expr::Now => {}
+            Expr::Val(_) | Expr::Now | Expr::Uuid => {}
 
             // Variable locale
             Expr::Var(name) => {
@@ -44,6 +44,10 @@ pub enum Expr {
         days: Box<Expr>,
     },
 
+    // --- 🆔 IDENTIFIANTS ---
+    /// Nouvel UUID v4
+    Uuid,
+
     // --- 🔤 NOUVEAU : STRINGS ---
     Concat(Vec<Expr>),
     Upper(Box<Expr>),
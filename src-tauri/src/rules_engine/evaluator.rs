@@ -81,6 +81,8 @@ impl Evaluator {
                 Ok(json!(new_date.to_rfc3339()))
             }
 
+            Expr::Uuid => Ok(json!(uuid::Uuid::new_v4().to_string())),
+
             // --- 🔤 STRINGS ---
             Expr::Concat(args) => {
                 let mut result = String::new();
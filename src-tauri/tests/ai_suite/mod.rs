@@ -41,9 +41,7 @@ pub fn init_ai_test_env() -> AiTestEnv {
     let data_root = tmp_dir.path().to_path_buf();
 
     // On utilise une nouvelle config basée sur le dossier temporaire
-    let config = JsonDbConfig {
-        data_root: data_root.clone(),
-    };
+    let config = JsonDbConfig::new(data_root.clone());
 
     // IMPORTANT : On utilise "un2" car c'est l'espace par défaut codé en dur
     // dans les prompts des agents pour l'instant.
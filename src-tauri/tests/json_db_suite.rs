@@ -13,6 +13,18 @@ use std::sync::Once;
 #[path = "json_db_suite/dataset_integration.rs"]
 pub mod dataset_integration;
 
+#[path = "json_db_suite/json_db_backup.rs"]
+pub mod json_db_backup;
+
+#[path = "json_db_suite/json_db_changes.rs"]
+pub mod json_db_changes;
+
+#[path = "json_db_suite/json_db_compression.rs"]
+pub mod json_db_compression;
+
+#[path = "json_db_suite/json_db_concurrency.rs"]
+pub mod json_db_concurrency;
+
 #[path = "json_db_suite/json_db_errors.rs"]
 pub mod json_db_errors;
 
@@ -25,9 +37,18 @@ pub mod json_db_integration;
 #[path = "json_db_suite/json_db_lifecycle.rs"]
 pub mod json_db_lifecycle;
 
+#[path = "json_db_suite/json_db_migrations.rs"]
+pub mod json_db_migrations;
+
 #[path = "json_db_suite/json_db_query_integration.rs"]
 pub mod json_db_query_integration;
 
+#[path = "json_db_suite/json_db_replication.rs"]
+pub mod json_db_replication;
+
+#[path = "json_db_suite/json_db_segments.rs"]
+pub mod json_db_segments;
+
 #[path = "json_db_suite/json_db_sql.rs"]
 pub mod json_db_sql;
 
@@ -68,9 +89,7 @@ pub fn init_test_env() -> TestEnv {
 
     let tmp_dir = tempfile::tempdir().expect("create temp dir");
     let data_root = tmp_dir.path().to_path_buf();
-    let cfg = JsonDbConfig {
-        data_root: data_root.clone(),
-    };
+    let cfg = JsonDbConfig::new(data_root.clone());
 
     let db_root = cfg.db_root(TEST_SPACE, TEST_DB);
     fs::create_dir_all(&db_root).expect("create db root");
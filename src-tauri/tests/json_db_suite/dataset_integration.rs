@@ -2,7 +2,9 @@
 
 use crate::{ensure_db_exists, get_dataset_file, init_test_env, TEST_DB, TEST_SPACE};
 use raise::json_db::collections::manager::CollectionsManager;
+use raise::json_db::schema::ValidationReport;
 use raise::json_db::storage::StorageEngine;
+use serde_json::json;
 use std::fs;
 
 #[test]
@@ -49,3 +51,83 @@ fn debug_import_exchange_item() {
         Err(e) => panic!("❌ ÉCHEC INSERTION : {}", e),
     }
 }
+
+#[test]
+fn invalid_exchange_item_is_rejected() {
+    let env = init_test_env();
+    let mgr = CollectionsManager::new(&env.storage, &env.space, &env.db);
+
+    let db_schema_uri = format!(
+        "db://{}/{}/schemas/v1/arcadia/data/exchange-item.schema.json",
+        TEST_SPACE, TEST_DB
+    );
+    mgr.create_collection("exchange-items", Some(db_schema_uri))
+        .expect("create collection");
+
+    // Valeur hors enum
+    let err = mgr
+        .insert_with_schema(
+            "exchange-items",
+            json!({ "name": "GPS", "exchangeMechanism": "Pigeon" }),
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("exchangeMechanism"), "{}", err);
+
+    // `name` est requis par les propriétés communes Arcadia (allOf)
+    assert!(mgr
+        .insert_with_schema("exchange-items", json!({ "exchangeMechanism": "Flow" }))
+        .is_err());
+
+    // Type incorrect (tableau attendu)
+    assert!(mgr
+        .insert_with_schema(
+            "exchange-items",
+            json!({ "name": "GPS", "elements": "pas-un-tableau" }),
+        )
+        .is_err());
+
+    assert!(mgr.list_all("exchange-items").unwrap().is_empty());
+}
+
+#[test]
+fn validation_report_lists_every_violation() {
+    let env = init_test_env();
+    let mgr = CollectionsManager::new(&env.storage, &env.space, &env.db);
+
+    let db_schema_uri = format!(
+        "db://{}/{}/schemas/v1/arcadia/data/exchange-item.schema.json",
+        TEST_SPACE, TEST_DB
+    );
+    mgr.create_collection("exchange-items", Some(db_schema_uri.clone()))
+        .expect("create collection");
+
+    let draft = json!({ "exchangeMechanism": "Pigeon", "elements": "pas-un-tableau" });
+
+    // Brouillon : rapport complet sans écriture
+    let report = mgr
+        .validate_document("exchange-items", draft.clone())
+        .unwrap();
+    assert_eq!(report.schema_uri, db_schema_uri);
+    for path in ["/name", "/exchangeMechanism", "/elements"] {
+        assert!(
+            report.errors_at(path).next().is_some(),
+            "{} absent : {}",
+            path,
+            report
+        );
+    }
+    let mechanism = report.errors_at("/exchangeMechanism").next().unwrap();
+    assert_eq!(mechanism.keyword, "enum");
+    assert_eq!(mechanism.actual, Some(json!("Pigeon")));
+
+    // Insertion : même rapport, récupérable depuis l'erreur
+    let err = mgr.insert_with_schema("exchange-items", draft).unwrap_err();
+    let rejected = err.downcast_ref::<ValidationReport>().expect("rapport");
+    assert_eq!(rejected.errors.len(), report.errors.len());
+    assert!(mgr.list_all("exchange-items").unwrap().is_empty());
+
+    assert!(mgr
+        .validate_document("exchange-items", json!({ "name": "GPS" }))
+        .unwrap()
+        .is_valid());
+}
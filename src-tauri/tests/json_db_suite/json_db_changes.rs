@@ -0,0 +1,135 @@
+// FICHIER : src-tauri/tests/json_db_suite/json_db_changes.rs
+
+use crate::{init_test_env, TEST_DB, TEST_SPACE};
+use futures::stream::{BoxStream, StreamExt};
+use raise::json_db::changes::{self, ChangeEvent, ChangeKind};
+use raise::json_db::collections::manager::CollectionsManager;
+use raise::json_db::transactions::manager::TransactionManager;
+use raise::json_db::transactions::TransactionRequest;
+use serde_json::json;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_change_feed_records_direct_and_transactional_writes() {
+    let env = init_test_env();
+    let mgr = CollectionsManager::new(&env.storage, TEST_SPACE, TEST_DB);
+    mgr.create_collection("notes", None).unwrap();
+    let start = changes::last_seq(&env.cfg, TEST_SPACE, TEST_DB).unwrap();
+
+    // Écritures directes
+    mgr.insert_raw("notes", &json!({ "id": "n1", "text": "a" }))
+        .unwrap();
+    mgr.update_document("notes", "n1", json!({ "text": "b" }))
+        .unwrap();
+
+    // Transaction : une insertion et une suppression dans le même lot
+    let tm = TransactionManager::new(&env.cfg, TEST_SPACE, TEST_DB);
+    tm.execute_smart(vec![
+        TransactionRequest::Insert {
+            collection: "notes".to_string(),
+            id: Some("n2".to_string()),
+            document: json!({ "text": "c" }),
+        },
+        TransactionRequest::Delete {
+            collection: "notes".to_string(),
+            id: "n1".to_string(),
+            revision: None,
+        },
+    ])
+    .await
+    .unwrap();
+
+    let events = changes::read_since(&env.cfg, TEST_SPACE, TEST_DB, start, 100).unwrap();
+    let summary: Vec<(ChangeKind, &str)> = events.iter().map(|e| (e.kind, e.id.as_str())).collect();
+    assert_eq!(
+        summary,
+        vec![
+            (ChangeKind::Insert, "n1"),
+            (ChangeKind::Update, "n1"),
+            (ChangeKind::Insert, "n2"),
+            (ChangeKind::Delete, "n1"),
+        ]
+    );
+    assert!(events.windows(2).all(|w| w[1].seq == w[0].seq + 1));
+
+    // Images avant / après
+    assert_eq!(events[1].before.as_ref().unwrap()["text"], "a");
+    assert_eq!(events[1].after.as_ref().unwrap()["text"], "b");
+    assert!(events[3].after.is_none());
+
+    // Les événements d'une transaction partagent son identifiant
+    assert!(events[0].transaction.is_none());
+    assert!(events[2].transaction.is_some());
+    assert_eq!(events[2].transaction, events[3].transaction);
+}
+
+#[test]
+fn test_direct_write_fails_until_its_change_is_recorded() {
+    use raise::json_db::indexes::IndexManager;
+    use raise::json_db::transactions::recovery;
+
+    let env = init_test_env();
+    let mgr = CollectionsManager::new(&env.storage, TEST_SPACE, TEST_DB);
+    mgr.create_collection("notes", None).unwrap();
+    mgr.create_index("notes", "text", "hash").unwrap();
+    let start = changes::last_seq(&env.cfg, TEST_SPACE, TEST_DB).unwrap();
+
+    // Journal de changements rendu inaccessible : un répertoire à sa place
+    let feed = env
+        .cfg
+        .db_root(TEST_SPACE, TEST_DB)
+        .join("_changes")
+        .join("changes.jsonl");
+    std::fs::create_dir_all(&feed).unwrap();
+
+    // Écriture non acquittée tant que son événement n'est pas publié
+    assert!(mgr
+        .insert_raw("notes", &json!({ "id": "n1", "text": "a" }))
+        .is_err());
+
+    // La récupération publie l'écriture restée dans le WAL, une seule fois
+    std::fs::remove_dir(&feed).unwrap();
+    let report = recovery::recover(&env.cfg, TEST_SPACE, TEST_DB).unwrap();
+    assert_eq!(report.rolled_forward.len(), 1);
+    recovery::recover(&env.cfg, TEST_SPACE, TEST_DB).unwrap();
+
+    let events = changes::read_since(&env.cfg, TEST_SPACE, TEST_DB, start, 100).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, ChangeKind::Insert);
+    assert!(events[0].transaction.is_none());
+
+    let idx = IndexManager::new(&env.storage, TEST_SPACE, TEST_DB);
+    let def = idx.load_indexes("notes").unwrap().remove(0);
+    assert_eq!(
+        idx.lookup("notes", &def, &[json!("a")]).unwrap(),
+        vec!["n1"]
+    );
+}
+
+async fn next_event(stream: &mut BoxStream<'static, anyhow::Result<ChangeEvent>>) -> ChangeEvent {
+    tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("événement attendu")
+        .expect("flux terminé")
+        .expect("lecture du journal")
+}
+
+#[tokio::test]
+async fn test_change_stream_resumes_from_token() {
+    let env = init_test_env();
+    let mgr = CollectionsManager::new(&env.storage, TEST_SPACE, TEST_DB);
+    mgr.create_collection("notes", None).unwrap();
+    mgr.insert_raw("notes", &json!({ "id": "n1" })).unwrap();
+    let token = changes::last_seq(&env.cfg, TEST_SPACE, TEST_DB).unwrap();
+    mgr.insert_raw("notes", &json!({ "id": "n2" })).unwrap();
+
+    // Historique postérieur au jeton, puis événements au fil de l'eau
+    let mut stream = changes::subscribe(&env.cfg, TEST_SPACE, TEST_DB, token);
+    let event = next_event(&mut stream).await;
+    assert_eq!((event.kind, event.id.as_str()), (ChangeKind::Insert, "n2"));
+
+    mgr.delete_document("notes", "n2").unwrap();
+    let event = next_event(&mut stream).await;
+    assert_eq!((event.kind, event.id.as_str()), (ChangeKind::Delete, "n2"));
+    assert_eq!(event.seq, token + 2);
+}
@@ -0,0 +1,62 @@
+// FICHIER : src-tauri/tests/json_db_suite/json_db_compression.rs
+
+use crate::{ensure_db_exists, init_test_env};
+use raise::json_db::collections::manager::CollectionsManager;
+use raise::json_db::storage::compression::{self, Compression};
+use raise::json_db::storage::StorageEngine;
+use serde_json::json;
+
+#[test]
+fn compressed_and_plain_documents_coexist() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, &env.space, &env.db);
+    let col_path = env.cfg.db_collection_path(&env.space, &env.db, "logs");
+
+    // 1. Écriture d'un document en clair (ancien réglage)
+    let plain = StorageEngine::new(env.cfg.clone());
+    plain
+        .write_document(&env.space, &env.db, "logs", "old", &json!({"id": "old"}))
+        .unwrap();
+    assert!(col_path.join("old.json").exists());
+
+    // 2. Nouveau réglage : collection compressée
+    let cfg = env.cfg.clone().with_collection_compression(
+        &env.space,
+        &env.db,
+        "logs",
+        Compression::zstd(),
+    );
+    let packed = StorageEngine::new(cfg.clone());
+    packed
+        .write_document(&env.space, &env.db, "logs", "new", &json!({"id": "new"}))
+        .unwrap();
+    assert!(col_path.join("new.json.zst").exists());
+    assert!(!col_path.join("new.json").exists());
+
+    // 3. Lecture mixte : les deux formes sont lisibles par les deux moteurs
+    for engine in [&plain, &packed] {
+        for id in ["old", "new"] {
+            let doc = engine
+                .read_document(&env.space, &env.db, "logs", id)
+                .unwrap();
+            assert_eq!(doc, Some(json!({"id": id})));
+        }
+    }
+    let mgr = CollectionsManager::new(&packed, &env.space, &env.db);
+    assert_eq!(mgr.list_all("logs").unwrap().len(), 2);
+
+    // 4. Migration : seul le document en clair est converti
+    let report =
+        compression::migrate_collection(&cfg, &env.space, &env.db, "logs", Compression::zstd())
+            .unwrap();
+    assert_eq!(report.converted, 1);
+    assert_eq!(report.unchanged, 1);
+    assert!(col_path.join("old.json.zst").exists());
+    assert!(!col_path.join("old.json").exists());
+
+    // Le cache du moteur "plain" détecte le changement de fichier
+    let doc = plain
+        .read_document(&env.space, &env.db, "logs", "old")
+        .unwrap();
+    assert_eq!(doc, Some(json!({"id": "old"})));
+}
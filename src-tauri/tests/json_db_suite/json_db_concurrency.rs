@@ -0,0 +1,202 @@
+// FICHIER : src-tauri/tests/json_db_suite/json_db_concurrency.rs
+
+use crate::{ensure_db_exists, init_test_env};
+use raise::json_db::collections::manager::CollectionsManager;
+use raise::json_db::indexes::DuplicateKeyError;
+use raise::json_db::storage::StorageEngine;
+use raise::json_db::transactions::manager::TransactionManager;
+use raise::json_db::transactions::revision::RevisionConflict;
+use serde_json::json;
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+#[test]
+fn update_with_stale_rev_is_rejected() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, &env.space, &env.db);
+
+    let storage = StorageEngine::new(env.cfg.clone());
+    let mgr = CollectionsManager::new(&storage, &env.space, &env.db);
+    mgr.create_collection("counters", None).unwrap();
+
+    let doc = mgr
+        .insert_with_schema("counters", json!({ "id": "c1", "value": 0 }))
+        .unwrap();
+    assert_eq!(doc["_rev"], 1);
+
+    // Deux lecteurs partent de la même révision : le second perd
+    let mut first = doc.clone();
+    first["value"] = json!(1);
+    let mut second = doc.clone();
+    second["value"] = json!(2);
+
+    let saved = mgr.update_document("counters", "c1", first).unwrap();
+    assert_eq!(saved["_rev"], 2);
+
+    let err = mgr.update_document("counters", "c1", second).unwrap_err();
+    let conflict = err
+        .downcast_ref::<RevisionConflict>()
+        .expect("erreur typée attendue");
+    assert_eq!((conflict.expected, conflict.actual), (1, 2));
+
+    let stored = mgr.get("counters", "c1").unwrap().unwrap();
+    assert_eq!(stored["value"], 1);
+}
+
+#[test]
+fn concurrent_cas_increments_are_not_lost() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, &env.space, &env.db);
+
+    {
+        let storage = StorageEngine::new(env.cfg.clone());
+        let mgr = CollectionsManager::new(&storage, &env.space, &env.db);
+        mgr.create_collection("counters", None).unwrap();
+        mgr.insert_with_schema("counters", json!({ "id": "c1", "value": 0 }))
+            .unwrap();
+    }
+
+    const WRITERS: usize = 4;
+    const INCREMENTS: u64 = 10;
+
+    // Chaque thread a son propre moteur (cache compris), comme des processus distincts
+    let handles: Vec<_> = (0..WRITERS)
+        .map(|_| {
+            let (cfg, space, db) = (env.cfg.clone(), env.space.clone(), env.db.clone());
+            thread::spawn(move || {
+                let storage = StorageEngine::new(cfg);
+                let mgr = CollectionsManager::new(&storage, &space, &db);
+                for _ in 0..INCREMENTS {
+                    loop {
+                        let mut doc = mgr.get("counters", "c1").unwrap().unwrap();
+                        doc["value"] = json!(doc["value"].as_u64().unwrap() + 1);
+                        match mgr.update_document("counters", "c1", doc) {
+                            Ok(_) => break,
+                            Err(e) if e.is::<RevisionConflict>() => continue,
+                            Err(e) => panic!("{}", e),
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+
+    let storage = StorageEngine::new(env.cfg.clone());
+    let mgr = CollectionsManager::new(&storage, &env.space, &env.db);
+    let doc = mgr.get("counters", "c1").unwrap().unwrap();
+    assert_eq!(doc["value"], WRITERS as u64 * INCREMENTS);
+    assert_eq!(doc["_rev"], 1 + WRITERS as u64 * INCREMENTS);
+}
+
+#[test]
+fn concurrent_inserts_of_a_unique_key_admit_only_one() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, &env.space, &env.db);
+
+    {
+        let storage = StorageEngine::new(env.cfg.clone());
+        let mgr = CollectionsManager::new(&storage, &env.space, &env.db);
+        mgr.create_collection("users", None).unwrap();
+        mgr.create_index_on("users", &["email"], "hash", true)
+            .unwrap();
+    }
+
+    const WRITERS: usize = 8;
+    let barrier = Arc::new(Barrier::new(WRITERS));
+
+    // Moitié en écriture directe, moitié en transaction : même clé, IDs distincts
+    let handles: Vec<_> = (0..WRITERS)
+        .map(|i| {
+            let (cfg, space, db) = (env.cfg.clone(), env.space.clone(), env.db.clone());
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let doc = json!({ "id": format!("u{}", i), "email": "a@raise.io" });
+                barrier.wait();
+                let result = if i % 2 == 0 {
+                    let storage = StorageEngine::new(cfg);
+                    let mgr = CollectionsManager::new(&storage, &space, &db);
+                    mgr.insert_raw("users", &doc)
+                } else {
+                    let tm = TransactionManager::new(&cfg, &space, &db);
+                    tm.execute(|tx| {
+                        tx.add_insert("users", &format!("u{}", i), doc);
+                        Ok(())
+                    })
+                };
+                match result {
+                    Ok(()) => true,
+                    Err(e) if e.is::<DuplicateKeyError>() => false,
+                    Err(e) => panic!("{}", e),
+                }
+            })
+        })
+        .collect();
+    let inserted = handles
+        .into_iter()
+        .map(|h| h.join().unwrap())
+        .filter(|ok| *ok)
+        .count();
+    assert_eq!(inserted, 1);
+
+    let storage = StorageEngine::new(env.cfg.clone());
+    let mgr = CollectionsManager::new(&storage, &env.space, &env.db);
+    let stored: Vec<_> = mgr
+        .list_all("users")
+        .unwrap()
+        .into_iter()
+        .filter(|d| d["email"] == "a@raise.io")
+        .collect();
+    assert_eq!(stored.len(), 1);
+}
+
+#[test]
+fn concurrent_writes_keep_every_entry_of_the_system_index() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, &env.space, &env.db);
+    {
+        let storage = StorageEngine::new(env.cfg.clone());
+        let mgr = CollectionsManager::new(&storage, &env.space, &env.db);
+        mgr.create_collection("notes", None).unwrap();
+    }
+
+    const WRITERS: usize = 8;
+    let barrier = Arc::new(Barrier::new(WRITERS));
+
+    // Documents distincts : seul l'index système est partagé
+    let handles: Vec<_> = (0..WRITERS)
+        .map(|i| {
+            let (cfg, space, db) = (env.cfg.clone(), env.space.clone(), env.db.clone());
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let id = format!("n{}", i);
+                barrier.wait();
+                if i % 2 == 0 {
+                    let storage = StorageEngine::new(cfg);
+                    let mgr = CollectionsManager::new(&storage, &space, &db);
+                    mgr.insert_raw("notes", &json!({ "id": id })).unwrap();
+                } else {
+                    let tm = TransactionManager::new(&cfg, &space, &db);
+                    tm.execute(|tx| {
+                        tx.add_insert("notes", &id, json!({}));
+                        Ok(())
+                    })
+                    .unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let system: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(env.cfg.db_root(&env.space, &env.db).join("_system.json"))
+            .unwrap(),
+    )
+    .unwrap();
+    let items = system["collections"]["notes"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), WRITERS);
+}
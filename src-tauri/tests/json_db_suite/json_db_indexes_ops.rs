@@ -2,6 +2,7 @@
 
 use crate::{ensure_db_exists, init_test_env}; // Imports nettoyés
 use raise::json_db::collections::manager::CollectionsManager;
+use raise::json_db::indexes::DuplicateKeyError;
 use raise::json_db::storage::StorageEngine;
 use serde_json::json;
 use std::fs;
@@ -79,3 +80,71 @@ fn test_create_and_drop_index_lifecycle() {
         "Le fichier physique de l'index doit avoir été supprimé"
     );
 }
+
+#[test]
+fn test_unique_composite_index_rejects_duplicates() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, &env.space, &env.db);
+    let mgr = CollectionsManager::new(&env.storage, &env.space, &env.db);
+    let collection = "components";
+    mgr.create_collection(collection, None).unwrap();
+
+    mgr.insert_raw(
+        collection,
+        &json!({ "id": "c1", "layer": "oa", "name": "Radar" }),
+    )
+    .unwrap();
+    mgr.insert_raw(
+        collection,
+        &json!({ "id": "c2", "layer": "sa", "name": "Radar" }),
+    )
+    .unwrap();
+
+    // Un doublon existant empêche la création de l'index unique
+    mgr.insert_raw(
+        collection,
+        &json!({ "id": "c3", "layer": "oa", "name": "Radar" }),
+    )
+    .unwrap();
+    let err = mgr
+        .create_index_on(collection, &["layer", "name"], "hash", true)
+        .unwrap_err();
+    assert!(err.downcast_ref::<DuplicateKeyError>().is_some());
+    mgr.delete_document(collection, "c3").unwrap();
+
+    mgr.create_index_on(collection, &["layer", "name"], "hash", true)
+        .expect("create composite unique index");
+
+    // Insertion : même couple (layer, name) refusé, document non écrit
+    let err = mgr
+        .insert_raw(
+            collection,
+            &json!({ "id": "c4", "layer": "sa", "name": "Radar" }),
+        )
+        .unwrap_err();
+    let dup = err
+        .downcast_ref::<DuplicateKeyError>()
+        .expect("DuplicateKeyError attendu");
+    assert_eq!(dup.index, "layer_name");
+    assert_eq!(dup.existing_id, "c2");
+    assert!(mgr.get_document(collection, "c4").unwrap().is_none());
+
+    // Mise à jour vers un couple déjà pris : refus, document inchangé
+    let err = mgr
+        .update_document(collection, "c1", json!({ "layer": "sa", "name": "Radar" }))
+        .unwrap_err();
+    assert!(err.downcast_ref::<DuplicateKeyError>().is_some());
+    assert_eq!(
+        mgr.get_document(collection, "c1").unwrap().unwrap()["layer"],
+        "oa"
+    );
+
+    // La clé libérée par une mise à jour redevient disponible
+    mgr.update_document(collection, "c2", json!({ "layer": "pa", "name": "Radar" }))
+        .unwrap();
+    mgr.insert_raw(
+        collection,
+        &json!({ "id": "c4", "layer": "sa", "name": "Radar" }),
+    )
+    .expect("clé libérée");
+}
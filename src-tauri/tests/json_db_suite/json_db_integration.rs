@@ -105,3 +105,40 @@ fn query_find_many_with_sort_and_limit_simulated() {
     let all = mgr.list_all("articles").unwrap();
     assert_eq!(all.len(), 5);
 }
+
+#[test]
+fn cache_sees_writes_from_another_process() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, &env.space, &env.db);
+
+    // Deux moteurs = deux caches indépendants (ex: l'app et jsondb_cli)
+    let app = StorageEngine::new(env.cfg.clone());
+    let cli = StorageEngine::new(env.cfg.clone());
+
+    app.write_document(&env.space, &env.db, "notes", "n1", &json!({"v": 1}))
+        .unwrap();
+    let first = app
+        .read_document(&env.space, &env.db, "notes", "n1")
+        .unwrap();
+    assert_eq!(first, Some(json!({"v": 1})));
+
+    cli.write_document(&env.space, &env.db, "notes", "n1", &json!({"v": 2}))
+        .unwrap();
+    let fresh = app
+        .read_document(&env.space, &env.db, "notes", "n1")
+        .unwrap();
+    assert_eq!(
+        fresh,
+        Some(json!({"v": 2})),
+        "entrée périmée servie par le cache"
+    );
+
+    cli.delete_document(&env.space, &env.db, "notes", "n1")
+        .unwrap();
+    assert_eq!(
+        app.read_document(&env.space, &env.db, "notes", "n1")
+            .unwrap(),
+        None
+    );
+    assert_eq!(app.cache_stats().invalidations, 2);
+}
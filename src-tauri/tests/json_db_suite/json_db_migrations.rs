@@ -0,0 +1,71 @@
+// FICHIER : src-tauri/tests/json_db_suite/json_db_migrations.rs
+
+use crate::{init_test_env, TEST_DB, TEST_SPACE};
+use raise::json_db::changes;
+use raise::json_db::collections::manager::CollectionsManager;
+use raise::json_db::migrations::catalog;
+use raise::json_db::migrations::migrator::{MigrationState, Migrator};
+use serde_json::json;
+
+#[test]
+fn test_migrations_from_dir_up_and_down() {
+    let env = init_test_env();
+    let mgr = CollectionsManager::new(&env.storage, TEST_SPACE, TEST_DB);
+    mgr.create_collection("users", None).unwrap();
+    for id in ["u1", "u2"] {
+        mgr.insert_raw("users", &json!({ "id": id, "login": id }))
+            .unwrap();
+    }
+
+    // Migrations écrites en JSON, comme pour `jsondb_cli migrate --dir`
+    let dir = tempfile::tempdir().unwrap();
+    let files = [
+        json!({
+            "id": "001_users_active", "version": "1.0.0", "description": "Flag actif",
+            "up": [{ "AddField": { "collection": "users", "field": "active", "default": true } }],
+            "down": [{ "RemoveField": { "collection": "users", "field": "active" } }]
+        }),
+        json!({
+            "id": "002_users_name", "version": "1.1.0", "description": "login -> name",
+            "up": [{ "RenameField": { "collection": "users", "old_name": "login", "new_name": "name" } }],
+            "down": [{ "RenameField": { "collection": "users", "old_name": "name", "new_name": "login" } }]
+        }),
+    ];
+    for (i, file) in files.iter().enumerate() {
+        std::fs::write(dir.path().join(format!("{:03}.json", i)), file.to_string()).unwrap();
+    }
+    let declared = catalog::load_dir(dir.path()).unwrap();
+    let migrator = Migrator::new(&env.storage, TEST_SPACE, TEST_DB);
+
+    let preview = migrator.plan_up(&declared).unwrap();
+    assert_eq!(preview.iter().map(|p| p.documents()).sum::<usize>(), 4);
+    let seq = changes::last_seq(&env.cfg, TEST_SPACE, TEST_DB).unwrap();
+
+    migrator.migrate_up(&declared).unwrap();
+    let doc = mgr.get_document("users", "u1").unwrap().unwrap();
+    assert_eq!(
+        (doc["active"].clone(), doc["name"].clone()),
+        (json!(true), json!("u1"))
+    );
+    assert!(migrator
+        .status(&declared)
+        .unwrap()
+        .iter()
+        .all(|s| s.state == MigrationState::Applied));
+
+    // Chaque migration est une transaction : documents et historique ensemble
+    let events = changes::read_since(&env.cfg, TEST_SPACE, TEST_DB, seq, usize::MAX).unwrap();
+    assert_eq!(events.len(), 6);
+    assert!(events.iter().all(|e| e.transaction.is_some()));
+    assert_ne!(events[0].transaction, events[5].transaction);
+
+    migrator.migrate_down(&declared, "0.0.0").unwrap();
+    let doc = mgr.get_document("users", "u2").unwrap().unwrap();
+    assert_eq!(doc["login"], "u2");
+    assert!(doc.get("active").is_none() && doc.get("name").is_none());
+    assert!(migrator
+        .status(&declared)
+        .unwrap()
+        .iter()
+        .all(|s| s.state == MigrationState::Pending));
+}
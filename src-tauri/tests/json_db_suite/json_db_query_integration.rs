@@ -8,8 +8,8 @@ use crate::{ensure_db_exists, get_dataset_file, init_test_env, TEST_DB, TEST_SPA
 use raise::json_db::{
     collections::manager::CollectionsManager,
     query::{
-        ComparisonOperator, Condition, FilterOperator, Query, QueryEngine, QueryFilter, SortField,
-        SortOrder,
+        parser::parse_filter_from_json, ComparisonOperator, Condition, FilterOperator, Query,
+        QueryEngine, QueryFilter, SortField, SortOrder,
     },
     storage::JsonDbConfig,
 };
@@ -91,6 +91,7 @@ async fn query_find_one_article_by_handle() {
             operator: ComparisonOperator::Eq,
             value: json!(handle),
         }],
+        filters: Vec::new(),
     };
     let query = Query {
         collection: "articles".to_string(),
@@ -99,6 +100,9 @@ async fn query_find_one_article_by_handle() {
         limit: Some(1),
         offset: None,
         projection: None,
+        explain: None,
+        aggregation: None,
+        joins: Vec::new(),
     };
 
     let result = engine.execute_query(query).await.expect("query failed");
@@ -134,6 +138,9 @@ async fn query_find_many_with_sort_and_limit() {
         offset: Some(0),
         limit: Some(3),
         projection: None,
+        explain: None,
+        aggregation: None,
+        joins: Vec::new(),
     };
 
     let result = engine.execute_query(q).await.expect("query failed");
@@ -146,3 +153,310 @@ async fn query_find_many_with_sort_and_limit() {
         Some("sort-9")
     );
 }
+
+#[tokio::test]
+async fn query_uses_declared_indexes() {
+    let test_env = init_test_env();
+    ensure_db_exists(&test_env.cfg, TEST_SPACE, TEST_DB);
+
+    let mgr = CollectionsManager::new(&test_env.storage, TEST_SPACE, TEST_DB);
+    mgr.create_collection("components", None)
+        .expect("create collection");
+
+    for (i, layer) in ["sa", "la", "la", "pa", "la"].iter().enumerate() {
+        let doc = json!({ "id": format!("c{}", i), "layer": layer, "level": i * 10 });
+        mgr.insert_raw("components", &doc).expect("insert failed");
+    }
+    mgr.create_index("components", "layer", "hash")
+        .expect("hash index");
+    mgr.create_index("components", "level", "btree")
+        .expect("btree index");
+
+    // Document écrit hors moteur : absent des index, il ne doit pas remonter via un plan indexé
+    let ghost_path = test_env
+        .cfg
+        .db_collection_path(TEST_SPACE, TEST_DB, "components")
+        .join("ghost.json");
+    fs::write(
+        &ghost_path,
+        json!({ "id": "ghost", "layer": "la", "level": 25 }).to_string(),
+    )
+    .unwrap();
+
+    let engine = QueryEngine::new(&mgr);
+
+    // 1. Eq -> lookup sur l'index hash
+    let by_layer = Query {
+        filter: Some(QueryFilter {
+            operator: FilterOperator::And,
+            conditions: vec![Condition::eq("layer", json!("la"))],
+            filters: Vec::new(),
+        }),
+        ..Query::new("components")
+    };
+    let result = engine.execute_query(by_layer).await.expect("query failed");
+    let mut ids: Vec<&str> = result
+        .documents
+        .iter()
+        .map(|d| d["id"].as_str().unwrap())
+        .collect();
+    ids.sort();
+    assert_eq!(ids, vec!["c1", "c2", "c4"]);
+
+    // 2. Gte + Lt -> parcours de plage sur l'index B-Tree (ordre numérique)
+    let by_level = Query {
+        filter: Some(QueryFilter {
+            operator: FilterOperator::And,
+            conditions: vec![
+                Condition {
+                    field: "level".to_string(),
+                    operator: ComparisonOperator::Gte,
+                    value: json!(20),
+                },
+                Condition {
+                    field: "level".to_string(),
+                    operator: ComparisonOperator::Lt,
+                    value: json!(40),
+                },
+            ],
+            filters: Vec::new(),
+        }),
+        sort: Some(vec![SortField {
+            field: "level".to_string(),
+            order: SortOrder::Asc,
+        }]),
+        ..Query::new("components")
+    };
+    let result = engine.execute_query(by_level).await.expect("query failed");
+    let ids: Vec<&str> = result
+        .documents
+        .iter()
+        .map(|d| d["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, vec!["c2", "c3"]);
+}
+
+#[tokio::test]
+async fn indexed_query_on_snapshot_ignores_later_writes() {
+    let test_env = init_test_env();
+    ensure_db_exists(&test_env.cfg, TEST_SPACE, TEST_DB);
+
+    let mgr = CollectionsManager::new(&test_env.storage, TEST_SPACE, TEST_DB);
+    mgr.create_collection("components", None)
+        .expect("create collection");
+    mgr.create_index("components", "layer", "hash")
+        .expect("hash index");
+    for (id, layer) in [("c1", "la"), ("c2", "la"), ("c3", "pa")] {
+        mgr.insert_raw("components", &json!({ "id": id, "layer": layer }))
+            .expect("insert failed");
+    }
+
+    let snap = mgr.snapshot().expect("snapshot");
+
+    // Après le snapshot : c1 change de couche, c4 arrive, c2 disparaît
+    mgr.update_document("components", "c1", json!({ "layer": "pa" }))
+        .unwrap();
+    mgr.insert_raw("components", &json!({ "id": "c4", "layer": "la" }))
+        .unwrap();
+    mgr.delete_document("components", "c2").unwrap();
+
+    let layer = |value: &str| Query {
+        filter: Some(QueryFilter {
+            operator: FilterOperator::And,
+            conditions: vec![Condition::eq("layer", json!(value))],
+            filters: Vec::new(),
+        }),
+        ..Query::new("components")
+    };
+    let ids = |result: raise::json_db::query::QueryResult| {
+        let mut ids: Vec<String> = result
+            .documents
+            .iter()
+            .map(|d| d["id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        ids
+    };
+
+    // Index courant interrogé, documents vus tels qu'au snapshot
+    let engine = QueryEngine::new(&snap);
+    let result = engine.execute_query(layer("la")).await.unwrap();
+    assert_eq!(ids(result), vec!["c1", "c2"]);
+    let result = engine.execute_query(layer("pa")).await.unwrap();
+    assert_eq!(ids(result), vec!["c3"]);
+
+    // Sans snapshot explicite : état courant
+    let engine = QueryEngine::new(&mgr);
+    let result = engine.execute_query(layer("la")).await.unwrap();
+    assert_eq!(ids(result), vec!["c4"]);
+}
+
+#[tokio::test]
+async fn query_matches_uses_text_index_with_ranking() {
+    let test_env = init_test_env();
+    ensure_db_exists(&test_env.cfg, TEST_SPACE, TEST_DB);
+
+    let mgr = CollectionsManager::new(&test_env.storage, TEST_SPACE, TEST_DB);
+    mgr.create_collection("requirements", None)
+        .expect("create collection");
+
+    let corpus = [
+        ("r1", "La station de sol reçoit la télémesure du satellite"),
+        ("r2", "Exigence de performance du radar de veille aérienne"),
+        (
+            "r3",
+            "Exigences radar : le radar détecte, le radar suit les pistes",
+        ),
+        ("r4", "Le sol de la station est isolé"),
+    ];
+    for (id, title) in corpus {
+        mgr.insert_raw("requirements", &json!({ "id": id, "title": title }))
+            .expect("insert failed");
+    }
+    mgr.create_index("requirements", "title", "text")
+        .expect("text index");
+
+    let engine = QueryEngine::new(&mgr);
+    let matches = |text: &str| Query {
+        filter: Some(QueryFilter {
+            operator: FilterOperator::And,
+            conditions: vec![Condition {
+                field: "title".to_string(),
+                operator: ComparisonOperator::Matches,
+                value: json!(text),
+            }],
+            filters: Vec::new(),
+        }),
+        ..Query::new("requirements")
+    };
+    let ids = |docs: &[Value]| -> Vec<String> {
+        docs.iter()
+            .map(|d| d["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // 1. Racines françaises et classement BM25 (r3 cite trois fois le radar)
+    let result = engine
+        .execute_query(matches("exigence radar"))
+        .await
+        .expect("query failed");
+    assert_eq!(ids(&result.documents), vec!["r3", "r2"]);
+    let scores = result.scores.expect("scores BM25");
+    assert!(scores[0] > scores[1]);
+
+    // 2. Phrase (mots vides compris dans l'écart) et préfixe
+    let result = engine
+        .execute_query(matches("\"station de sol\""))
+        .await
+        .unwrap();
+    assert_eq!(ids(&result.documents), vec!["r1"]);
+    let result = engine.execute_query(matches("télé*")).await.unwrap();
+    assert_eq!(ids(&result.documents), vec!["r1"]);
+
+    // 3. Sans index, Matches filtre en mémoire avec l'analyseur français
+    mgr.drop_index("requirements", "title").expect("drop index");
+    let result = engine
+        .execute_query(matches("exigences radar"))
+        .await
+        .unwrap();
+    let mut found = ids(&result.documents);
+    found.sort();
+    assert_eq!(found, vec!["r2", "r3"]);
+    assert!(result.scores.is_none());
+}
+
+#[tokio::test]
+async fn query_array_operators_on_link_properties() {
+    let test_env = init_test_env();
+    ensure_db_exists(&test_env.cfg, TEST_SPACE, TEST_DB);
+
+    let mgr = CollectionsManager::new(&test_env.storage, TEST_SPACE, TEST_DB);
+    mgr.create_collection("components", None)
+        .expect("create collection");
+
+    let components = [
+        json!({ "id": "c1", "allocatedFunctions": ["f1", "f2"],
+                "ports": [{ "name": "p1", "direction": "out", "rate": 10 }] }),
+        json!({ "id": "c2", "allocatedFunctions": ["f2"],
+                "ports": [{ "name": "p2", "direction": "in", "rate": 50 },
+                          { "name": "p3", "direction": "out", "rate": 5 }] }),
+        json!({ "id": "c3", "allocatedFunctions": [], "ports": [], "owner": null }),
+    ];
+    for doc in &components {
+        mgr.insert_raw("components", doc).expect("insert failed");
+    }
+
+    let engine = QueryEngine::new(&mgr);
+    let run = |filter: Value| {
+        let engine = &engine;
+        async move {
+            let query = Query {
+                filter: Some(parse_filter_from_json(&filter).expect("filter")),
+                ..Query::new("components")
+            };
+            let result = engine.execute_query(query).await.expect("query failed");
+            let mut ids: Vec<String> = result
+                .documents
+                .iter()
+                .map(|d| d["id"].as_str().unwrap().to_string())
+                .collect();
+            ids.sort();
+            ids
+        }
+    };
+    let single = |field: &str, operator: &str, value: Value| json!({ "conditions": [{ "field": field, "operator": operator, "value": value }] });
+
+    // 1. Appartenance à un tableau de liens
+    assert_eq!(
+        run(single("allocatedFunctions", "contains", json!("f2"))).await,
+        vec!["c1", "c2"]
+    );
+    assert_eq!(
+        run(single(
+            "allocatedFunctions",
+            "contains_all",
+            json!(["f1", "f2"])
+        ))
+        .await,
+        vec!["c1"]
+    );
+    assert_eq!(
+        run(single(
+            "allocatedFunctions",
+            "contains_any",
+            json!(["f1", "f9"])
+        ))
+        .await,
+        vec!["c1"]
+    );
+    assert_eq!(
+        run(single("allocatedFunctions", "size", json!(0))).await,
+        vec!["c3"]
+    );
+
+    // 2. Sous-filtre sur un même élément : c2 a un port sortant, mais pas à 50
+    let elem = single(
+        "ports",
+        "elem_match",
+        json!({ "conditions": [
+            { "field": "direction", "operator": "eq", "value": "out" },
+            { "field": "rate", "operator": "gte", "value": 10 }
+        ]}),
+    );
+    assert_eq!(run(elem).await, vec!["c1"]);
+
+    // 3. Présence, nullité et type
+    assert_eq!(
+        run(single("owner", "exists", json!(true))).await,
+        vec!["c3"]
+    );
+    assert_eq!(
+        run(single("owner", "is_null", Value::Null)).await,
+        vec!["c1", "c2", "c3"]
+    );
+    assert_eq!(
+        run(single("ports", "type", json!("array"))).await,
+        vec!["c1", "c2", "c3"]
+    );
+    assert!(parse_filter_from_json(&single("ports", "sorta", json!(1))).is_err());
+}
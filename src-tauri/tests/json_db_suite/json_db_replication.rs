@@ -0,0 +1,127 @@
+// FICHIER : src-tauri/tests/json_db_suite/json_db_replication.rs
+
+use crate::{init_test_env, TEST_DB, TEST_SPACE};
+use raise::json_db::collections::manager::CollectionsManager;
+use raise::json_db::replication::{self, ConflictChoice, MergeStrategy, SyncOptions};
+use raise::json_db::transactions::manager::TransactionManager;
+use raise::json_db::transactions::TransactionRequest;
+use serde_json::json;
+
+fn update(field: &str, value: &str) -> TransactionRequest {
+    TransactionRequest::Update {
+        collection: "exchange-items".to_string(),
+        id: Some("ei-1".to_string()),
+        handle: None,
+        document: json!({ field: value }),
+    }
+}
+
+#[tokio::test]
+async fn test_bundle_exchange_between_two_replicas() {
+    let laptop = init_test_env();
+    let desk = init_test_env();
+    let schema_uri = format!(
+        "db://{}/{}/schemas/v1/arcadia/data/exchange-item.schema.json",
+        TEST_SPACE, TEST_DB
+    );
+    let mgr = CollectionsManager::new(&laptop.storage, TEST_SPACE, TEST_DB);
+    mgr.create_collection("exchange-items", Some(schema_uri.clone()))
+        .unwrap();
+    let created = mgr
+        .insert_with_schema(
+            "exchange-items",
+            json!({ "id": "ei-1", "name": "GPS", "exchangeMechanism": "Flow" }),
+        )
+        .unwrap();
+
+    // Premier échange par fichier : collection créée avec son schéma, document intact
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("laptop.bundle.zst");
+    let bundle = replication::export_bundle(&laptop.cfg, TEST_SPACE, TEST_DB).unwrap();
+    replication::write_bundle(&bundle, &file).unwrap();
+    let received = replication::read_bundle(&file).unwrap();
+    let report = replication::merge_bundle(
+        &desk.cfg,
+        TEST_SPACE,
+        TEST_DB,
+        &received,
+        &SyncOptions::new(),
+    )
+    .unwrap();
+    assert!(report.applied >= 1);
+
+    let desk_mgr = CollectionsManager::new(&desk.storage, TEST_SPACE, TEST_DB);
+    let copy = desk_mgr
+        .get_document("exchange-items", "ei-1")
+        .unwrap()
+        .unwrap();
+    assert_eq!(copy["updatedAt"], created["updatedAt"]);
+    let meta = std::fs::read_to_string(
+        desk.cfg
+            .db_collection_path(TEST_SPACE, TEST_DB, "exchange-items")
+            .join("_meta.json"),
+    )
+    .unwrap();
+    assert!(meta.contains("exchange-item.schema.json"));
+
+    // Modifications concurrentes par transaction (x_compute, validation)
+    TransactionManager::new(&laptop.cfg, TEST_SPACE, TEST_DB)
+        .execute_smart(vec![update("name", "GPS v2")])
+        .await
+        .unwrap();
+    TransactionManager::new(&desk.cfg, TEST_SPACE, TEST_DB)
+        .execute_smart(vec![update("exchangeMechanism", "Event")])
+        .await
+        .unwrap();
+
+    // Fusion champ par champ pour cette collection, dans les deux sens
+    let options =
+        SyncOptions::new().with_collection_strategy("exchange-items", MergeStrategy::ThreeWay);
+    let report = replication::sync(&laptop.cfg, &desk.cfg, TEST_SPACE, TEST_DB, &options).unwrap();
+    assert_eq!(report.pulled.conflicts, 1);
+    assert_eq!(report.pulled.open, 0);
+    for env in [&laptop, &desk] {
+        let doc = CollectionsManager::new(&env.storage, TEST_SPACE, TEST_DB)
+            .get_document("exchange-items", "ei-1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(doc["name"], "GPS v2");
+        assert_eq!(doc["exchangeMechanism"], "Event");
+    }
+
+    // Conflit manuel : chaque côté garde sa version jusqu'à la résolution
+    TransactionManager::new(&laptop.cfg, TEST_SPACE, TEST_DB)
+        .execute_smart(vec![update("name", "GPS laptop")])
+        .await
+        .unwrap();
+    TransactionManager::new(&desk.cfg, TEST_SPACE, TEST_DB)
+        .execute_smart(vec![update("name", "GPS desk")])
+        .await
+        .unwrap();
+    let manual = SyncOptions::new().with_strategy(MergeStrategy::Manual);
+    let report = replication::sync(&laptop.cfg, &desk.cfg, TEST_SPACE, TEST_DB, &manual).unwrap();
+    assert_eq!((report.pulled.open, report.pushed.open), (1, 1));
+
+    let conflicts = mgr.list_all(replication::CONFLICTS_COLLECTION).unwrap();
+    let open = conflicts
+        .iter()
+        .find(|c| c["status"] == "open")
+        .expect("conflit ouvert");
+    assert_eq!(open["remote"]["name"], "GPS desk");
+    let conflict_id = open["id"].as_str().unwrap().to_string();
+
+    replication::resolve_conflict(
+        &laptop.cfg,
+        TEST_SPACE,
+        TEST_DB,
+        &conflict_id,
+        ConflictChoice::Local,
+    )
+    .unwrap();
+    replication::sync(&laptop.cfg, &desk.cfg, TEST_SPACE, TEST_DB, &manual).unwrap();
+    let doc = desk_mgr
+        .get_document("exchange-items", "ei-1")
+        .unwrap()
+        .unwrap();
+    assert_eq!(doc["name"], "GPS laptop");
+}
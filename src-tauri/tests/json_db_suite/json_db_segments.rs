@@ -0,0 +1,73 @@
+// FICHIER : src-tauri/tests/json_db_suite/json_db_segments.rs
+
+use crate::{ensure_db_exists, init_test_env};
+use raise::json_db::collections::manager::CollectionsManager;
+use raise::json_db::query::{Condition, FilterOperator, Query, QueryEngine, QueryFilter};
+use raise::json_db::storage::segment::{self, StorageLayout};
+use raise::json_db::storage::StorageEngine;
+use serde_json::json;
+use std::fs;
+
+#[tokio::test]
+async fn segment_layout_is_transparent_for_collections_and_queries() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, &env.space, &env.db);
+    let cfg = env.cfg.clone().with_collection_layout(
+        &env.space,
+        &env.db,
+        "parts",
+        StorageLayout::Segments,
+    );
+    let storage = StorageEngine::new(cfg.clone());
+    let mgr = CollectionsManager::new(&storage, &env.space, &env.db);
+    mgr.create_collection("parts", None).unwrap();
+
+    for (i, kind) in ["bolt", "nut", "bolt"].iter().enumerate() {
+        let doc = json!({ "id": format!("p{}", i), "kind": kind });
+        mgr.insert_raw("parts", &doc).unwrap();
+    }
+    mgr.create_index("parts", "kind", "hash").unwrap();
+    mgr.delete_document("parts", "p1").unwrap();
+
+    // Aucun fichier par document : tout est dans le segment
+    let col_path = cfg.db_collection_path(&env.space, &env.db, "parts");
+    let seg_path = segment::segment_path(&cfg, &env.space, &env.db, "parts");
+    assert!(seg_path.exists());
+    assert!(!col_path.join("p0.json").exists());
+
+    assert_eq!(mgr.list_all("parts").unwrap().len(), 2);
+    assert_eq!(mgr.get_document("parts", "p1").unwrap(), None);
+
+    let query = Query {
+        filter: Some(QueryFilter {
+            operator: FilterOperator::And,
+            conditions: vec![Condition::eq("kind", json!("bolt"))],
+            filters: Vec::new(),
+        }),
+        ..Query::new("parts")
+    };
+    let result = QueryEngine::new(&mgr).execute_query(query).await.unwrap();
+    assert_eq!(result.documents.len(), 2);
+
+    // Compaction : le tombstone et l'ancienne version disparaissent
+    let stats = storage
+        .compact_collection(&env.space, &env.db, "parts")
+        .unwrap();
+    assert_eq!(stats.live_records, 2);
+    assert_eq!(stats.dead_bytes, 0);
+    assert_eq!(
+        mgr.get_document("parts", "p2").unwrap().unwrap()["kind"],
+        "bolt"
+    );
+
+    // Retour au format fichiers : lisible par un moteur configuré par défaut
+    let report =
+        segment::migrate_layout(&cfg, &env.space, &env.db, "parts", StorageLayout::Files).unwrap();
+    assert_eq!(report.moved, 2);
+    assert!(!seg_path.exists());
+    assert!(fs::metadata(col_path.join("p0.json")).is_ok());
+
+    let plain = StorageEngine::new(env.cfg.clone());
+    let plain_mgr = CollectionsManager::new(&plain, &env.space, &env.db);
+    assert_eq!(plain_mgr.list_all("parts").unwrap().len(), 2);
+}
@@ -2,9 +2,11 @@
 
 use crate::{ensure_db_exists, get_dataset_file, init_test_env, TEST_DB, TEST_SPACE};
 use raise::json_db::collections::manager::CollectionsManager;
-use raise::json_db::query::sql::parse_sql;
+use raise::json_db::query::sql::{parse_sql, parse_sql_statement, SqlStatement};
 use raise::json_db::query::QueryEngine;
 use raise::json_db::storage::JsonDbConfig;
+use raise::json_db::transactions::manager::TransactionManager;
+use raise::json_db::transactions::revision::RevisionConflict;
 use serde_json::{json, Value};
 use std::fs;
 
@@ -152,3 +154,445 @@ async fn test_sql_json_array_contains() {
 
     assert_eq!(result.documents.len(), 2);
 }
+
+#[tokio::test]
+async fn test_sql_array_null_and_subquery_operators() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, TEST_SPACE, TEST_DB);
+    let mgr = CollectionsManager::new(&env.storage, TEST_SPACE, TEST_DB);
+    seed_actors_from_dataset(&mgr, &env.cfg);
+    let engine = QueryEngine::new(&mgr);
+
+    let handles = |sql: &str| {
+        let query = parse_sql(sql).expect("Parsing SQL");
+        let engine = &engine;
+        async move {
+            let result = engine.execute_query(query).await.expect("Exec");
+            let mut found: Vec<String> = result
+                .documents
+                .iter()
+                .map(|d| d["handle"].as_str().unwrap().to_string())
+                .collect();
+            found.sort();
+            found
+        }
+    };
+
+    assert_eq!(
+        handles("SELECT * FROM actors WHERE 'admin' = ANY(roles)").await,
+        vec!["alice", "eve"]
+    );
+    assert_eq!(
+        handles("SELECT * FROM actors WHERE x_city NOT IN ('Paris', 'Lyon')").await,
+        vec!["bot-build", "frank"]
+    );
+    assert_eq!(
+        handles("SELECT * FROM actors WHERE kind = 'bot' AND x_team IS NULL").await,
+        vec!["bot-build"]
+    );
+    assert_eq!(
+        handles("SELECT * FROM actors WHERE displayName LIKE '%Bot'").await,
+        vec!["bot-build"]
+    );
+    assert_eq!(
+        handles(
+            "SELECT * FROM actors WHERE handle IN \
+             (SELECT handle FROM actors WHERE x_active = false)"
+        )
+        .await,
+        vec!["charlie", "eve"]
+    );
+}
+
+#[tokio::test]
+async fn test_sql_nested_boolean_filters() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, TEST_SPACE, TEST_DB);
+    let mgr = CollectionsManager::new(&env.storage, TEST_SPACE, TEST_DB);
+    seed_actors_from_dataset(&mgr, &env.cfg);
+    let engine = QueryEngine::new(&mgr);
+
+    let handles = |sql: &str| {
+        let query = parse_sql(sql).expect("Parsing SQL");
+        let engine = &engine;
+        async move {
+            let result = engine.execute_query(query).await.expect("Exec");
+            let mut found: Vec<String> = result
+                .documents
+                .iter()
+                .map(|d| d["handle"].as_str().unwrap().to_string())
+                .collect();
+            found.sort();
+            found
+        }
+    };
+
+    // Le OR reste groupé sous le AND (et n'est plus aplati)
+    assert_eq!(
+        handles("SELECT * FROM actors WHERE kind = 'human' AND (x_city = 'Lyon' OR x_age >= 35)")
+            .await,
+        vec!["bob", "charlie", "eve"]
+    );
+    assert_eq!(
+        handles("SELECT * FROM actors WHERE NOT (x_city = 'Paris' OR x_city = 'Lyon')").await,
+        vec!["bot-build", "frank"]
+    );
+    assert_eq!(
+        handles(
+            "SELECT * FROM actors WHERE (x_active = true AND x_age < 30) \
+             OR (x_active = false AND x_city = 'Paris')"
+        )
+        .await,
+        vec!["bob", "bot-build", "charlie"]
+    );
+
+    // Opérateur non supporté : erreur explicite
+    assert!(parse_sql("SELECT * FROM actors WHERE x_age % 2 = 0").is_err());
+}
+
+#[tokio::test]
+async fn test_sql_group_by_and_aggregates() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, TEST_SPACE, TEST_DB);
+    let mgr = CollectionsManager::new(&env.storage, TEST_SPACE, TEST_DB);
+    seed_actors_from_dataset(&mgr, &env.cfg);
+    let engine = QueryEngine::new(&mgr);
+
+    // 1. Regroupement, alias et HAVING sur un agrégat
+    let sql = "SELECT x_city, COUNT(*) AS n, AVG(x_age) AS avg_age FROM actors \
+               GROUP BY x_city HAVING COUNT(*) > 1 ORDER BY x_city";
+    let result = engine
+        .execute_query(parse_sql(sql).expect("Parsing SQL"))
+        .await
+        .expect("Exec");
+    assert!(result.documents.is_empty());
+    assert_eq!(result.total_count, 2);
+    let agg = result.aggregation.expect("lignes agrégées");
+    assert_eq!(agg.columns, vec!["x_city", "n", "avg_age"]);
+    assert_eq!(
+        agg.rows,
+        vec![
+            json!({ "x_city": "Lyon", "n": 2, "avg_age": 32.5 }),
+            json!({ "x_city": "Paris", "n": 2, "avg_age": 32.5 }),
+        ]
+    );
+
+    // 2. Noms de colonnes par défaut et tri sur un agrégat
+    let sql = "SELECT kind, COUNT(*), MIN(x_age), MAX(x_age), SUM(x_age) FROM actors \
+               GROUP BY kind ORDER BY COUNT(*) DESC";
+    let result = engine.execute_query(parse_sql(sql).unwrap()).await.unwrap();
+    let rows = result.aggregation.unwrap().rows;
+    assert_eq!(
+        rows[0],
+        json!({ "kind": "human", "COUNT(*)": 5, "MIN(x_age)": 25, "MAX(x_age)": 40, "SUM(x_age)": 160 })
+    );
+    assert_eq!(rows[1]["COUNT(*)"], 1);
+
+    // 3. DISTINCT et COUNT(DISTINCT ...)
+    let sql = "SELECT DISTINCT x_city FROM actors ORDER BY x_city";
+    let result = engine.execute_query(parse_sql(sql).unwrap()).await.unwrap();
+    let cities: Vec<Value> = result
+        .aggregation
+        .unwrap()
+        .rows
+        .iter()
+        .map(|r| r["x_city"].clone())
+        .collect();
+    assert_eq!(cities, vec!["Bordeaux", "Cloud", "Lyon", "Paris"]);
+
+    let sql = "SELECT COUNT(DISTINCT x_city) AS cities FROM actors WHERE x_active = true";
+    let result = engine.execute_query(parse_sql(sql).unwrap()).await.unwrap();
+    assert_eq!(
+        result.aggregation.unwrap().rows,
+        vec![json!({ "cities": 4 })]
+    );
+
+    // 4. Colonne ni regroupée ni agrégée
+    assert!(parse_sql("SELECT handle, COUNT(*) FROM actors GROUP BY kind").is_err());
+}
+
+#[tokio::test]
+async fn test_sql_join_on_link_arrays() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, TEST_SPACE, TEST_DB);
+    let mgr = CollectionsManager::new(&env.storage, TEST_SPACE, TEST_DB);
+    mgr.create_collection("functions", None).unwrap();
+    mgr.create_collection("components", None).unwrap();
+    for doc in [
+        json!({ "id": "lf1", "name": "Acquire", "layer": "la" }),
+        json!({ "id": "lf2", "name": "Track", "layer": "la" }),
+        json!({ "id": "lf3", "name": "Orphan", "layer": "la" }),
+        json!({ "id": "sf1", "name": "Detect", "layer": "sa" }),
+    ] {
+        mgr.insert_raw("functions", &doc).unwrap();
+    }
+    for doc in [
+        json!({ "id": "lc1", "name": "Radar", "allocatedFunctions": ["lf1", "lf2"] }),
+        json!({ "id": "lc2", "name": "Backup", "allocatedFunctions": ["lf2"] }),
+    ] {
+        mgr.insert_raw("components", &doc).unwrap();
+    }
+    let engine = QueryEngine::new(&mgr);
+
+    // Fonction logique + nom du composant qui l'alloue (une ligne par allocation)
+    let sql = "SELECT f.name, c.name FROM functions f \
+               JOIN components c ON f.id = ANY(c.allocatedFunctions) \
+               WHERE f.layer = 'la' ORDER BY f.name, c.name";
+    let result = engine
+        .execute_query(parse_sql(sql).expect("Parsing SQL"))
+        .await
+        .expect("Exec");
+    assert_eq!(
+        result.documents,
+        vec![
+            json!({ "name": "Acquire", "c.name": "Radar" }),
+            json!({ "name": "Track", "c.name": "Backup" }),
+            json!({ "name": "Track", "c.name": "Radar" }),
+        ]
+    );
+
+    // LEFT JOIN : les fonctions non allouées sont conservées
+    let sql = "SELECT f.name FROM functions f \
+               LEFT JOIN components c ON f.id = ANY(c.allocatedFunctions) \
+               WHERE c.id IS NULL ORDER BY f.name";
+    let result = engine.execute_query(parse_sql(sql).unwrap()).await.unwrap();
+    assert_eq!(
+        result.documents,
+        vec![json!({ "name": "Detect" }), json!({ "name": "Orphan" })]
+    );
+
+    // Jointure puis agrégation : nombre de fonctions par composant
+    let sql = "SELECT c.name, COUNT(*) AS n FROM functions f \
+               JOIN components c ON f.id = ANY(c.allocatedFunctions) \
+               GROUP BY c.name ORDER BY c.name";
+    let result = engine.execute_query(parse_sql(sql).unwrap()).await.unwrap();
+    assert_eq!(
+        result.aggregation.unwrap().rows,
+        vec![
+            json!({ "c.name": "Backup", "n": 1 }),
+            json!({ "c.name": "Radar", "n": 2 }),
+        ]
+    );
+}
+
+async fn handles(engine: &QueryEngine<'_>, sql: &str) -> Vec<String> {
+    let result = engine.execute_query(parse_sql(sql).unwrap()).await.unwrap();
+    result
+        .documents
+        .iter()
+        .map(|d| d["handle"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_sql_insert_update_delete() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, TEST_SPACE, TEST_DB);
+    let mgr = CollectionsManager::new(&env.storage, TEST_SPACE, TEST_DB);
+    seed_actors_from_dataset(&mgr, &env.cfg);
+    let engine = QueryEngine::new(&mgr);
+
+    // INSERT ... VALUES : plusieurs lignes, schéma appliqué (id généré)
+    let sql = "INSERT INTO actors (handle, displayName, kind, x_age) \
+               VALUES ('gina', 'Gina Ops', 'human', 28), ('bot-deploy', 'Deploy Bot', 'bot', 2)";
+    let statement = parse_sql_statement(sql).expect("Parsing SQL");
+    let result = statement.execute(&mgr).await.expect("INSERT");
+    assert_eq!(result.affected, Some(2));
+    assert_eq!(
+        handles(
+            &engine,
+            "SELECT * FROM actors WHERE kind = 'bot' ORDER BY handle"
+        )
+        .await,
+        vec!["bot-build", "bot-deploy"]
+    );
+    let sql = "SELECT * FROM actors WHERE handle = 'gina'";
+    let gina = engine.execute_query(parse_sql(sql).unwrap()).await.unwrap();
+    assert!(gina.documents[0]["id"].is_string());
+
+    // UPDATE : champs imbriqués fusionnés dans chaque document visé
+    let sql = "UPDATE actors SET x_active = false, x_review.done = true WHERE x_city = 'Lyon'";
+    let result = parse_sql_statement(sql)
+        .unwrap()
+        .execute(&mgr)
+        .await
+        .expect("UPDATE");
+    assert_eq!(result.affected, Some(2));
+    let sql = "SELECT * FROM actors WHERE x_review.done = true ORDER BY handle";
+    let updated = engine.execute_query(parse_sql(sql).unwrap()).await.unwrap();
+    assert_eq!(updated.documents.len(), 2);
+    assert_eq!(updated.documents[0]["handle"], "bob");
+    assert_eq!(updated.documents[0]["x_active"], false);
+    assert_eq!(updated.documents[0]["displayName"], "Bob User");
+
+    // Violation de schéma : tout le lot est rejeté
+    let sql = "UPDATE actors SET kind = 'alien' WHERE x_age >= 30";
+    assert!(parse_sql_statement(sql)
+        .unwrap()
+        .execute(&mgr)
+        .await
+        .is_err());
+    assert!(
+        handles(&engine, "SELECT * FROM actors WHERE kind = 'alien'")
+            .await
+            .is_empty()
+    );
+
+    // INSERT ... SELECT vers une autre collection, colonnes renommées
+    mgr.create_collection("archive", None).unwrap();
+    let sql = "INSERT INTO archive (handle, city) \
+               SELECT handle, x_city FROM actors WHERE x_active = false";
+    let result = parse_sql_statement(sql)
+        .unwrap()
+        .execute(&mgr)
+        .await
+        .expect("INSERT SELECT");
+    assert_eq!(result.affected, Some(3));
+    assert_eq!(
+        handles(
+            &engine,
+            "SELECT * FROM archive WHERE city = 'Lyon' ORDER BY handle"
+        )
+        .await,
+        vec!["bob", "eve"]
+    );
+
+    // DELETE ; sans document visé, aucune transaction n'est ouverte
+    let sql = "DELETE FROM actors WHERE kind = 'bot'";
+    let result = parse_sql_statement(sql).unwrap().execute(&mgr).await;
+    assert_eq!(result.expect("DELETE").affected, Some(2));
+    assert!(handles(&engine, "SELECT * FROM actors WHERE kind = 'bot'")
+        .await
+        .is_empty());
+    let result = parse_sql_statement(sql).unwrap().execute(&mgr).await;
+    assert_eq!(result.unwrap().affected, Some(0));
+
+    // Les écritures ne passent pas par parse_sql
+    assert!(parse_sql("DELETE FROM actors").is_err());
+}
+
+#[tokio::test]
+async fn test_sql_writes_are_guarded_by_revision() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, TEST_SPACE, TEST_DB);
+    let mgr = CollectionsManager::new(&env.storage, TEST_SPACE, TEST_DB);
+    seed_actors_from_dataset(&mgr, &env.cfg);
+    let engine = QueryEngine::new(&mgr);
+    let tm = TransactionManager::new(&env.cfg, TEST_SPACE, TEST_DB);
+
+    let write_of = |sql: &str| {
+        let SqlStatement::Write(write) = parse_sql_statement(sql).unwrap() else {
+            panic!("écriture attendue : {}", sql);
+        };
+        write
+    };
+    let sql = "SELECT * FROM actors WHERE handle = 'bob'";
+    let bob = engine.execute_query(parse_sql(sql).unwrap()).await.unwrap();
+    let mut bob = bob.documents[0].clone();
+    let id = bob["id"].as_str().unwrap().to_string();
+
+    // Sélection faite, puis document modifié avant la transaction : refus
+    let update = write_of("UPDATE actors SET x_active = false WHERE handle = 'bob'");
+    let stale_update = update.plan(&engine).await.unwrap();
+    let delete = write_of("DELETE FROM actors WHERE handle = 'bob'");
+    let stale_delete = delete.plan(&engine).await.unwrap();
+    bob["x_city"] = json!("Nantes");
+    mgr.update_document("actors", &id, bob).unwrap();
+
+    let err = tm.execute_smart(stale_update).await.unwrap_err();
+    assert!(err.is::<RevisionConflict>());
+    let err = tm.execute_smart(stale_delete).await.unwrap_err();
+    assert!(err.is::<RevisionConflict>());
+    let stored = mgr.get_document("actors", &id).unwrap().unwrap();
+    assert_eq!(stored["x_city"], "Nantes");
+    assert_eq!(stored["x_active"], true);
+
+    // execute() resélectionne : la modification intercalée est conservée
+    let sql = "UPDATE actors SET x_active = false WHERE handle = 'bob'";
+    let result = parse_sql_statement(sql).unwrap().execute(&mgr).await;
+    assert_eq!(result.expect("UPDATE").affected, Some(1));
+    let stored = mgr.get_document("actors", &id).unwrap().unwrap();
+    assert_eq!(
+        (&stored["x_city"], &stored["x_active"]),
+        (&json!("Nantes"), &json!(false))
+    );
+
+    let sql = "DELETE FROM actors WHERE handle = 'bob'";
+    let result = parse_sql_statement(sql).unwrap().execute(&mgr).await;
+    assert_eq!(result.expect("DELETE").affected, Some(1));
+    assert!(mgr.get_document("actors", &id).unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_sql_insert_never_replaces_existing_documents() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, TEST_SPACE, TEST_DB);
+    let mgr = CollectionsManager::new(&env.storage, TEST_SPACE, TEST_DB);
+    seed_actors_from_dataset(&mgr, &env.cfg);
+    let engine = QueryEngine::new(&mgr);
+
+    let sql = "SELECT * FROM actors WHERE handle = 'bob'";
+    let found = engine.execute_query(parse_sql(sql).unwrap()).await.unwrap();
+    let id = found.documents[0]["id"].as_str().unwrap();
+    let bob = mgr.get_document("actors", id).unwrap().unwrap();
+    let total = handles(&engine, "SELECT * FROM actors").await.len();
+
+    // Identifiant déjà présent : refus, document intact
+    let sql = format!(
+        "INSERT INTO actors (id, handle, displayName, kind) VALUES ('{}', 'mallory', 'Mallory', 'human')",
+        id
+    );
+    assert!(parse_sql_statement(&sql)
+        .unwrap()
+        .execute(&mgr)
+        .await
+        .is_err());
+    assert_eq!(mgr.get_document("actors", id).unwrap().unwrap(), bob);
+
+    // Copie d'une collection sur elle-même : tout le lot est refusé
+    let sql = "INSERT INTO actors SELECT * FROM actors";
+    assert!(parse_sql_statement(sql)
+        .unwrap()
+        .execute(&mgr)
+        .await
+        .is_err());
+    assert_eq!(handles(&engine, "SELECT * FROM actors").await.len(), total);
+    assert_eq!(mgr.get_document("actors", id).unwrap().unwrap(), bob);
+}
+
+#[tokio::test]
+async fn test_sql_explain_and_analyze() {
+    let env = init_test_env();
+    ensure_db_exists(&env.cfg, TEST_SPACE, TEST_DB);
+    let mgr = CollectionsManager::new(&env.storage, TEST_SPACE, TEST_DB);
+    seed_actors_from_dataset(&mgr, &env.cfg);
+    let engine = QueryEngine::new(&mgr);
+
+    // EXPLAIN : plan seul, aucune lecture de documents
+    let sql =
+        "EXPLAIN SELECT * FROM actors WHERE x_age >= 30 AND kind = 'human' ORDER BY x_age DESC";
+    let query = parse_sql(sql).expect("Parsing SQL");
+    let result = engine.execute_query(query).await.expect("Exec");
+
+    assert!(result.documents.is_empty());
+    let plan = result.plan.expect("Plan attendu");
+    // L'égalité (plus sélective) est évaluée avant la plage
+    assert_eq!(plan.conditions[0].condition.field, "kind");
+    assert_eq!(plan.conditions[1].condition.field, "x_age");
+    assert!(plan.index.is_none(), "Aucun index déclaré sur actors");
+    assert!(plan.execution.is_none());
+
+    // EXPLAIN ANALYZE : plan + mesures réelles
+    let sql = "EXPLAIN ANALYZE SELECT * FROM actors WHERE x_age >= 30";
+    let query = parse_sql(sql).expect("Parsing SQL");
+    let result = engine.execute_query(query).await.expect("Exec");
+
+    assert!(result.documents.is_empty());
+    let stats = result
+        .plan
+        .and_then(|p| p.execution)
+        .expect("Mesures attendues");
+    assert_eq!(stats.documents_scanned, 6);
+    assert_eq!(stats.documents_matched, 4);
+    assert_eq!(stats.documents_returned, 4);
+}
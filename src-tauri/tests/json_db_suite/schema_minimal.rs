@@ -30,7 +30,8 @@ fn schema_instantiate_validate_minimal() {
         SchemaValidator::compile_with_registry(&root_uri, &reg).expect("compile failed");
 
     // 3) Document minimal avec TOUS les champs requis (Id, Dates, $schema)
-    // CORRECTION : On fournit un document "complet" car le Validator ne fait plus d'injection auto.
+    // Les champs système fournis sont conservés par les `x_compute` (update: if_missing),
+    // sauf updatedAt (update: always).
     let mut doc = json!({
       "$schema": root_uri, // Requis
       "id": uuid::Uuid::new_v4().to_string(), // Requis
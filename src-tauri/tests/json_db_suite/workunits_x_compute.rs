@@ -1,7 +1,9 @@
 use crate::{ensure_db_exists, init_test_env, TEST_DB, TEST_SPACE};
 use raise::json_db::collections::manager; // On a besoin du manager pour le nouveau moteur
+use raise::json_db::collections::manager::CollectionsManager;
 use raise::json_db::schema::{SchemaRegistry, SchemaValidator};
 use serde_json::json;
+use std::fs;
 use uuid::Uuid;
 
 #[test]
@@ -26,8 +28,8 @@ fn workunit_compute_then_validate_minimal() {
     // Donnée conforme au workunit.schema.json (qui inclut finance)
     let doc = json!({
         "id": Uuid::new_v4().to_string(),
-        "code": "WU-DEVOPS-01",
-        "name": { "fr": "DevOps pipeline" },
+        "code": "wu-devops-01",
+        "name": { "fr": "Pipeline DevOps", "en": "DevOps pipeline" },
         "status": "draft",
         "version": "1.0.0",
         "createdAt": "2024-01-01T00:00:00Z",
@@ -45,8 +47,8 @@ fn workunit_compute_then_validate_minimal() {
     // Ce test valide surtout la structure globale (validation de schéma).
     validator.validate(&doc).expect("validate workunit failed");
 
-    // Le calcul de l'ID ou createdAt est géré par le validateur/compute legacy ou le code d'insertion.
-    // Ici on vérifie juste que ça valide.
+    // Le calcul de l'ID ou createdAt est porté par les `x_compute` du schéma
+    // (voir x_compute_drives_inserts_and_updates). Ici on vérifie juste que ça valide.
 }
 
 #[test]
@@ -144,3 +146,145 @@ fn finance_compute_minimal() {
         "Référence générée incorrecte"
     );
 }
+
+#[test]
+fn x_compute_drives_inserts_and_updates() {
+    let env = init_test_env();
+    let mgr = CollectionsManager::new(&env.storage, &env.space, &env.db);
+
+    // Schéma de test : défaut, concaténation et lookup, plus id/dates hérités de base.schema.json
+    let schema_path = env
+        .cfg
+        .db_schemas_root(&env.space, &env.db)
+        .join("v1/tests/ticket.schema.json");
+    fs::create_dir_all(schema_path.parent().unwrap()).unwrap();
+    let schema = json!({
+        "type": "object",
+        "allOf": [{ "$ref": "../common/types/base.schema.json" }],
+        "properties": {
+            "title": { "type": "string", "minLength": 1 },
+            "status": {
+                "enum": ["open", "closed"],
+                "x_compute": { "expr": { "val": "open" } }
+            },
+            "reference": {
+                "type": "string",
+                "x_compute": {
+                    "update": "always",
+                    "expr": { "concat": [{ "upper": { "var": "status" } }, { "val": "-" }, { "var": "title" }] }
+                }
+            },
+            "owner": { "type": "string" },
+            "ownerEmail": {
+                "type": "string",
+                "format": "email",
+                "x_compute": {
+                    "expr": { "lookup": { "collection": "users", "id": { "var": "owner" }, "field": "email" } }
+                }
+            }
+        },
+        "required": ["title", "status", "reference"]
+    });
+    fs::write(&schema_path, schema.to_string()).unwrap();
+
+    mgr.insert_raw("users", &json!({ "id": "u1", "email": "alice@raise.io" }))
+        .unwrap();
+    let schema_uri = format!(
+        "db://{}/{}/schemas/v1/tests/ticket.schema.json",
+        TEST_SPACE, TEST_DB
+    );
+    mgr.create_collection("tickets", Some(schema_uri)).unwrap();
+
+    let doc = mgr
+        .insert_with_schema("tickets", json!({ "title": "Panne", "owner": "u1" }))
+        .expect("insert");
+    let id = doc["id"].as_str().expect("id calculé").to_string();
+    assert_eq!(
+        Uuid::parse_str(&id).ok().map(|u| u.get_version_num()),
+        Some(4)
+    );
+    assert!(doc["createdAt"].is_string());
+    assert!(doc["updatedAt"].is_string());
+    assert_eq!(doc["status"], "open");
+    assert_eq!(doc["reference"], "OPEN-Panne");
+    assert_eq!(doc["ownerEmail"], "alice@raise.io");
+
+    // `always` recalcule, `if_missing` conserve les valeurs fournies
+    let updated = mgr
+        .update_document(
+            "tickets",
+            &id,
+            json!({
+                "title": "Panne",
+                "status": "closed",
+                "reference": "manuel",
+                "createdAt": "2024-01-01T00:00:00Z"
+            }),
+        )
+        .expect("update");
+    assert_eq!(updated["reference"], "CLOSED-Panne");
+    assert_eq!(updated["createdAt"], "2024-01-01T00:00:00Z");
+    assert!(updated.get("ownerEmail").is_none());
+
+    // Les valeurs calculées restent validées par le schéma
+    assert!(mgr
+        .insert_with_schema("tickets", json!({ "title": "" }))
+        .is_err());
+}
+
+#[tokio::test]
+async fn x_rules_apply_to_transactional_writes() {
+    use raise::json_db::transactions::manager::TransactionManager;
+    use raise::json_db::transactions::TransactionRequest;
+
+    let env = init_test_env();
+    let mgr = CollectionsManager::new(&env.storage, &env.space, &env.db);
+
+    let schema_path = env
+        .cfg
+        .db_schemas_root(&env.space, &env.db)
+        .join("v1/tests/order.schema.json");
+    fs::create_dir_all(schema_path.parent().unwrap()).unwrap();
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "qty": { "type": "number" },
+            "price": { "type": "number" },
+            "total": { "type": "number" }
+        },
+        "x_rules": [{
+            "id": "calc_total",
+            "target": "total",
+            "expr": { "mul": [{ "var": "qty" }, { "var": "price" }] }
+        }]
+    });
+    fs::write(&schema_path, schema.to_string()).unwrap();
+    let schema_uri = format!(
+        "db://{}/{}/schemas/v1/tests/order.schema.json",
+        TEST_SPACE, TEST_DB
+    );
+    mgr.create_collection("orders", Some(schema_uri)).unwrap();
+
+    let tm = TransactionManager::new(&env.cfg, &env.space, &env.db);
+    tm.execute_smart(vec![TransactionRequest::Insert {
+        collection: "orders".to_string(),
+        id: Some("o1".to_string()),
+        document: json!({ "qty": 2, "price": 10 }),
+    }])
+    .await
+    .expect("insertion transactionnelle");
+    let doc = mgr.get_document("orders", "o1").unwrap().unwrap();
+    assert_eq!(doc["total"].as_f64(), Some(20.0));
+
+    // La règle est relancée par le champ modifié
+    tm.execute_smart(vec![TransactionRequest::Update {
+        collection: "orders".to_string(),
+        id: Some("o1".to_string()),
+        handle: None,
+        document: json!({ "qty": 3 }),
+    }])
+    .await
+    .expect("mise à jour transactionnelle");
+    let doc = mgr.get_document("orders", "o1").unwrap().unwrap();
+    assert_eq!(doc["total"].as_f64(), Some(30.0));
+}
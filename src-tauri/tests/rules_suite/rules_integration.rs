@@ -10,9 +10,7 @@ fn test_end_to_end_rules_execution() {
     let dir = tempdir().unwrap();
 
     // CORRECTION ICI : 'data_root' au lieu de 'root_dir'
-    let config = JsonDbConfig {
-        data_root: dir.path().to_path_buf(),
-    };
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
 
     let space = "test_space";
     let db = "test_db";
@@ -80,9 +78,7 @@ fn test_end_to_end_rules_execution() {
 fn test_advanced_business_rules() {
     // 1. SETUP
     let dir = tempdir().unwrap();
-    let config = JsonDbConfig {
-        data_root: dir.path().to_path_buf(),
-    };
+    let config = JsonDbConfig::new(dir.path().to_path_buf());
     let space = "biz_space";
     let db = "biz_db";
     let storage = StorageEngine::new(config.clone());
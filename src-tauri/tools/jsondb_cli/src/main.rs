@@ -9,11 +9,19 @@ use std::fs;
 use std::path::PathBuf;
 
 // Imports RAISE
-use raise::json_db::collections::manager::CollectionsManager;
-use raise::json_db::query::{Query, QueryEngine};
+use raise::json_db::backup::{self, RestoreOptions};
+use raise::json_db::changes;
+use raise::json_db::collections::{self, manager::CollectionsManager};
+use raise::json_db::migrations::migrator::{MigrationState, Migrator};
+use raise::json_db::migrations::plan::MigrationPlan;
+use raise::json_db::migrations::{catalog, Direction};
+use raise::json_db::query::{ExplainMode, Query, QueryEngine, QueryResult};
+use raise::json_db::replication::{self, ConflictChoice, MergeStrategy, SyncOptions};
 use raise::json_db::storage::{
+    compression::{self, Compression},
     file_storage::{self},
-    JsonDbConfig, StorageEngine,
+    segment::{self, StorageLayout},
+    JsonDbConfig, StorageEngine, DEFAULT_CACHE_BYTES, DEFAULT_CACHE_ENTRIES,
 };
 use raise::json_db::transactions::manager::TransactionManager;
 use raise::json_db::transactions::TransactionRequest;
@@ -35,6 +43,14 @@ struct Cli {
     #[arg(long, env = "RAISE_DATA_DIR")]
     root: Option<PathBuf>,
 
+    /// Codec des documents écrits : "none", "zstd" ou "zstd:<niveau>"
+    #[arg(long, env = "RAISE_DB_COMPRESSION")]
+    compression: Option<String>,
+
+    /// Format des collections : "files" ou "segments"
+    #[arg(long, env = "RAISE_DB_LAYOUT")]
+    layout: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -63,11 +79,15 @@ enum Commands {
     CreateIndex {
         #[arg(long)]
         collection: String,
+        /// Champ indexé, ou liste séparée par des virgules pour un index composite
         #[arg(long)]
         field: String,
         /// Type d'index : "unique", "hash", "text", "btree"
         #[arg(long, default_value = "hash")]
         kind: String,
+        /// Contrainte d'unicité (refus des doublons à l'insertion et à la mise à jour)
+        #[arg(long)]
+        unique: bool,
     },
     DropIndex {
         #[arg(long)]
@@ -100,7 +120,11 @@ enum Commands {
         limit: Option<usize>,
         #[arg(long)]
         offset: Option<usize>,
+        /// Affiche le plan d'exécution au lieu des documents
+        #[arg(long)]
+        explain: bool,
     },
+    /// Requête SQL : SELECT, ou INSERT / UPDATE / DELETE exécutés en une transaction
     Sql {
         #[arg(long)]
         query: String,
@@ -115,6 +139,133 @@ enum Commands {
         #[arg(long)]
         file: PathBuf,
     },
+
+    // --- STOCKAGE ---
+    /// Convertit les documents existants vers un codec (migration progressive)
+    Compress {
+        /// Collection à convertir (toutes si absent)
+        #[arg(long)]
+        collection: Option<String>,
+        /// "none", "zstd" ou "zstd:<niveau>"
+        #[arg(long, default_value = "zstd")]
+        codec: String,
+    },
+
+    /// Déplace les documents vers un format ("files" ou "segments")
+    Pack {
+        #[arg(long)]
+        collection: Option<String>,
+        #[arg(long, default_value = "segments")]
+        layout: String,
+    },
+    /// Compacte les segments (à lancer application fermée)
+    Compact {
+        #[arg(long)]
+        collection: Option<String>,
+    },
+
+    // --- SAUVEGARDE ---
+    /// Sauvegarde à chaud de la base dans une archive .tar.zst (avec manifeste)
+    Backup {
+        #[arg(long, short = 'o')]
+        output: PathBuf,
+    },
+    /// Restaure une archive dans --space/--db (base qui ne doit pas exister)
+    Restore {
+        archive: PathBuf,
+        /// Journal des changements archivé, rejoué après la sauvegarde
+        #[arg(long)]
+        changes: Option<PathBuf>,
+        /// Date limite du rejeu, RFC 3339 (ex. 2026-01-31T12:00:00Z)
+        #[arg(long, requires = "changes")]
+        until: Option<String>,
+    },
+    /// Copie les nouveaux événements du journal des changements (à lancer périodiquement)
+    ArchiveChanges {
+        #[arg(long, short = 'o')]
+        output: PathBuf,
+        /// Retire ensuite du journal de la base les événements archivés
+        #[arg(long)]
+        purge: bool,
+    },
+
+    // --- RÉPLICATION ---
+    /// Synchronise la base avec la même base sous une autre racine (dans les deux sens)
+    Sync {
+        /// Racine de données distante (clé USB, dossier partagé...)
+        remote: PathBuf,
+        /// Conflits : "lww", "three-way" ou "manual"
+        #[arg(long, default_value = "lww")]
+        strategy: String,
+        /// Stratégie propre à une collection : <collection>=<stratégie> (répétable)
+        #[arg(long = "collection-strategy")]
+        collection_strategies: Vec<String>,
+    },
+    /// Exporte l'état de la base dans un lot .zst à transmettre hors ligne
+    ExportBundle {
+        #[arg(long, short = 'o')]
+        output: PathBuf,
+    },
+    /// Fusionne un lot reçu dans la base
+    ImportBundle {
+        bundle: PathBuf,
+        #[arg(long, default_value = "lww")]
+        strategy: String,
+        #[arg(long = "collection-strategy")]
+        collection_strategies: Vec<String>,
+    },
+    /// Règle un conflit ouvert de la collection _conflicts
+    ResolveConflict {
+        /// Identifiant du conflit (<collection>--<id>)
+        id: String,
+        /// Version retenue : "local" ou "remote"
+        #[arg(long = "use", conflicts_with = "file")]
+        choice: Option<String>,
+        /// Contenu retenu, lu dans un fichier JSON
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+
+    // --- MIGRATIONS ---
+    /// Migrations de schéma (par défaut celles de l'application)
+    Migrate {
+        /// Dossier de migrations JSON (un fichier par migration)
+        #[arg(long, global = true)]
+        dir: Option<PathBuf>,
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+
+    // --- CACHE ---
+    /// Lit les documents deux fois pour mesurer le comportement du cache
+    CacheStats {
+        #[arg(long)]
+        collection: Option<String>,
+        /// Budget mémoire du cache simulé (en Mo)
+        #[arg(long)]
+        max_mb: Option<usize>,
+        /// Nombre maximal de documents en cache
+        #[arg(long)]
+        max_entries: Option<usize>,
+    },
+}
+
+#[derive(Subcommand)]
+enum MigrateAction {
+    /// État de chaque migration : appliquée, en attente, modifiée, inconnue
+    Status,
+    /// Applique les migrations en attente (une transaction par migration)
+    Up {
+        /// Affiche les documents touchés sans rien écrire
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Annule les migrations de version supérieure à <version>
+    Down {
+        version: String,
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[tokio::main]
@@ -134,13 +285,22 @@ async fn main() -> Result<()> {
         home.join("raise_domain")
     };
 
-    let config = JsonDbConfig {
-        data_root: root_path,
-    };
+    let mut config = JsonDbConfig::new(root_path);
+    if let Some(codec) = &cli.compression {
+        config = config.with_compression(Compression::parse(codec)?);
+    }
+    if let Some(layout) = &cli.layout {
+        config = config.with_layout(StorageLayout::parse(layout)?);
+    }
 
     // Auto-bootstrap
-    if !matches!(cli.command, Commands::CreateDb | Commands::DropDb { .. })
-        && !config.db_root(&cli.space, &cli.db).exists()
+    if !matches!(
+        cli.command,
+        Commands::CreateDb
+            | Commands::DropDb { .. }
+            | Commands::Backup { .. }
+            | Commands::Restore { .. }
+    ) && !config.db_root(&cli.space, &cli.db).exists()
     {
         let storage = StorageEngine::new(config.clone());
         let mgr = CollectionsManager::new(&storage, &cli.space, &cli.db);
@@ -195,13 +355,14 @@ async fn main() -> Result<()> {
             collection,
             field,
             kind,
+            unique,
         } => {
             println!(
                 "🏗️  Création de l'index '{}' sur {}.{}...",
                 kind, collection, field
             );
-            // Note: create_index doit être publique dans CollectionsManager
-            mgr.create_index(&collection, &field, &kind)?;
+            let fields: Vec<&str> = field.split(',').map(str::trim).collect();
+            mgr.create_index_on(&collection, &fields, &kind, unique)?;
             println!("✅ Index créé.");
         }
 
@@ -261,6 +422,7 @@ async fn main() -> Result<()> {
             filter: _,
             limit,
             offset,
+            explain,
         } => {
             let query = Query {
                 collection: collection.clone(),
@@ -269,8 +431,14 @@ async fn main() -> Result<()> {
                 limit,
                 offset,
                 projection: None,
+                explain: explain.then_some(ExplainMode::Plan),
+                aggregation: None,
+                joins: Vec::new(),
             };
             let result = QueryEngine::new(&mgr).execute_query(query).await?;
+            if print_plan(&result)? {
+                return Ok(());
+            }
             println!("🔎 Résultat : {} documents", result.documents.len());
             for doc in result.documents {
                 println!("{}", doc);
@@ -278,8 +446,25 @@ async fn main() -> Result<()> {
         }
 
         Commands::Sql { query } => {
-            let q = raise::json_db::query::sql::parse_sql(&query)?;
-            let result = QueryEngine::new(&mgr).execute_query(q).await?;
+            let statement = raise::json_db::query::sql::parse_sql_statement(&query)?;
+            let result = statement.execute(&mgr).await?;
+            if let Some(affected) = result.affected {
+                println!("✅ SQL : {} documents modifiés", affected);
+                return Ok(());
+            }
+            if print_plan(&result)? {
+                return Ok(());
+            }
+            if let Some(agg) = &result.aggregation {
+                println!("⚡ SQL Result : {} lignes", agg.rows.len());
+                println!("{}", agg.columns.join(" | "));
+                for row in &agg.rows {
+                    let cells: Vec<String> =
+                        agg.columns.iter().map(|c| row[c].to_string()).collect();
+                    println!("{}", cells.join(" | "));
+                }
+                return Ok(());
+            }
             println!("⚡ SQL Result : {} documents", result.documents.len());
             for doc in result.documents {
                 println!("{}", doc);
@@ -325,7 +510,315 @@ async fn main() -> Result<()> {
             tm.execute_smart(reqs).await?;
             println!("✅ Transaction exécutée avec succès.");
         }
+
+        Commands::Compress { collection, codec } => {
+            let target = Compression::parse(&codec)?;
+            let targets = match collection {
+                Some(c) => vec![c],
+                None => mgr.list_collections()?,
+            };
+            println!("🗜️  Conversion vers {:?}...", target);
+            for col in targets {
+                let report =
+                    compression::migrate_collection(&config, &cli.space, &cli.db, &col, target)?;
+                println!(
+                    "  - {} : {} convertis, {} inchangés ({} -> {} octets)",
+                    report.collection,
+                    report.converted,
+                    report.unchanged,
+                    report.bytes_before,
+                    report.bytes_after
+                );
+            }
+            println!("✅ Conversion terminée. Pensez à aligner le réglage de compression de l'application.");
+        }
+
+        Commands::Pack { collection, layout } => {
+            let target = StorageLayout::parse(&layout)?;
+            let targets = match collection {
+                Some(c) => vec![c],
+                None => mgr.list_collections()?,
+            };
+            println!("📦 Passage au format {:?}...", target);
+            for col in targets {
+                let report = segment::migrate_layout(&config, &cli.space, &cli.db, &col, target)?;
+                println!(
+                    "  - {} : {} documents déplacés",
+                    report.collection, report.moved
+                );
+            }
+            println!("✅ Terminé. Pensez à aligner le réglage de format de l'application.");
+        }
+
+        Commands::Compact { collection } => {
+            let targets = match collection {
+                Some(c) => vec![c],
+                None => mgr.list_collections()?,
+            };
+            for col in targets {
+                let stats = storage.compact_collection(&cli.space, &cli.db, &col)?;
+                println!(
+                    "🧹 {} : {} documents, {} octets",
+                    col, stats.live_records, stats.file_bytes
+                );
+            }
+        }
+
+        Commands::Backup { output } => {
+            println!("💾 Sauvegarde de '{}/{}'...", cli.space, cli.db);
+            let manifest = backup::backup(&config, &cli.space, &cli.db, &output)?;
+            println!(
+                "✅ {} fichiers sauvegardés dans {:?} (flux de changements : {})",
+                manifest.files.len(),
+                output,
+                manifest.change_seq
+            );
+        }
+
+        Commands::Restore {
+            archive,
+            changes,
+            until,
+        } => {
+            let mut options = RestoreOptions::new();
+            if let Some(journal) = changes {
+                options = options.with_changes(journal);
+            }
+            if let Some(date) = until {
+                let date = chrono::DateTime::parse_from_rfc3339(&date)?;
+                options = options.with_until(date.timestamp_millis());
+            }
+            println!("♻️ Restauration dans '{}/{}'...", cli.space, cli.db);
+            let report = backup::restore(&config, &archive, &cli.space, &cli.db, &options)?;
+            println!(
+                "✅ {} fichiers restaurés, {} changements rejoués (flux de changements : {})",
+                report.files, report.replayed, report.change_seq
+            );
+        }
+
+        Commands::ArchiveChanges { output, purge } => {
+            let copied = changes::archive(&config, &cli.space, &cli.db, &output)?;
+            println!("🗄️ {} événements archivés dans {:?}", copied, output);
+            if purge {
+                let archived = changes::read_journal(&output)?.last().map_or(0, |e| e.seq);
+                let removed = changes::purge(&config, &cli.space, &cli.db, archived)?;
+                println!("🧹 {} événements retirés du journal", removed);
+            }
+        }
+
+        Commands::Sync {
+            remote,
+            strategy,
+            collection_strategies,
+        } => {
+            let options = sync_options(&strategy, &collection_strategies)?;
+            let mut remote_config = JsonDbConfig::new(remote);
+            if let Some(codec) = &cli.compression {
+                remote_config = remote_config.with_compression(Compression::parse(codec)?);
+            }
+            if let Some(layout) = &cli.layout {
+                remote_config = remote_config.with_layout(StorageLayout::parse(layout)?);
+            }
+            println!("🔁 Synchronisation de '{}/{}'...", cli.space, cli.db);
+            let report = replication::sync(&config, &remote_config, &cli.space, &cli.db, &options)?;
+            println!(
+                "⬇️  Reçus  : {} appliqués, {} conflits ({} ouverts)",
+                report.pulled.applied, report.pulled.conflicts, report.pulled.open
+            );
+            println!(
+                "⬆️  Envoyés : {} appliqués, {} conflits ({} ouverts)",
+                report.pushed.applied, report.pushed.conflicts, report.pushed.open
+            );
+        }
+
+        Commands::ExportBundle { output } => {
+            let bundle = replication::export_bundle(&config, &cli.space, &cli.db)?;
+            replication::write_bundle(&bundle, &output)?;
+            println!(
+                "📦 {} documents exportés dans {:?} (réplique {})",
+                bundle.documents.len(),
+                output,
+                bundle.replica
+            );
+        }
+
+        Commands::ImportBundle {
+            bundle,
+            strategy,
+            collection_strategies,
+        } => {
+            let options = sync_options(&strategy, &collection_strategies)?;
+            let received = replication::read_bundle(&bundle)?;
+            let report =
+                replication::merge_bundle(&config, &cli.space, &cli.db, &received, &options)?;
+            println!(
+                "✅ {} appliqués, {} conflits ({} ouverts)",
+                report.applied, report.conflicts, report.open
+            );
+        }
+
+        Commands::ResolveConflict { id, choice, file } => {
+            let choice = match (choice, file) {
+                (_, Some(path)) => ConflictChoice::Document(Some(serde_json::from_str(
+                    &fs::read_to_string(path)?,
+                )?)),
+                (Some(c), None) => ConflictChoice::parse(&c)?,
+                (None, None) => anyhow::bail!("--use ou --file requis"),
+            };
+            replication::resolve_conflict(&config, &cli.space, &cli.db, &id, choice)?;
+            println!("✅ Conflit {} résolu.", id);
+        }
+
+        Commands::Migrate { dir, action } => {
+            let declared = match dir {
+                Some(path) => catalog::load_dir(&path)?,
+                None => catalog::app_migrations(),
+            };
+            let migrator = Migrator::new(&storage, &cli.space, &cli.db);
+            match action {
+                MigrateAction::Status => {
+                    println!("📜 Migrations de '{}/{}' :", cli.space, cli.db);
+                    for status in migrator.status(&declared)? {
+                        let state = match status.state {
+                            MigrationState::Applied => "✅ appliquée",
+                            MigrationState::Pending => "⏳ en attente",
+                            MigrationState::Modified => "⚠️ modifiée",
+                            MigrationState::Missing => "❓ non déclarée",
+                        };
+                        println!(
+                            "  {:<10} {:<32} {:<18} {}",
+                            status.version,
+                            status.id,
+                            state,
+                            status.applied_at.unwrap_or_default()
+                        );
+                    }
+                }
+                MigrateAction::Up { dry_run } => {
+                    let plans = if dry_run {
+                        migrator.plan_up(&declared)?
+                    } else {
+                        migrator.migrate_up(&declared)?
+                    };
+                    print_migrations(&plans, dry_run);
+                }
+                MigrateAction::Down { version, dry_run } => {
+                    let plans = if dry_run {
+                        migrator.plan_down(&declared, &version)?
+                    } else {
+                        migrator.migrate_down(&declared, &version)?
+                    };
+                    print_migrations(&plans, dry_run);
+                }
+            }
+        }
+
+        Commands::CacheStats {
+            collection,
+            max_mb,
+            max_entries,
+        } => {
+            let engine = StorageEngine::with_cache_limits(
+                config.clone(),
+                max_entries.unwrap_or(DEFAULT_CACHE_ENTRIES),
+                max_mb.map_or(DEFAULT_CACHE_BYTES, |mb| mb * 1024 * 1024),
+            );
+            let cache_mgr = CollectionsManager::new(&engine, &cli.space, &cli.db);
+            let targets = match collection {
+                Some(c) => vec![c],
+                None => cache_mgr.list_collections()?,
+            };
+
+            // Deux passes : la première remplit le cache, la seconde mesure les hits
+            for _ in 0..2 {
+                for col in &targets {
+                    for id in collections::list_ids(&config, &cli.space, &cli.db, col)? {
+                        engine.read_document(&cli.space, &cli.db, col, &id)?;
+                    }
+                }
+            }
+
+            let stats = engine.cache_stats();
+            println!("🧠 Cache de documents ({}/{}) :", cli.space, cli.db);
+            println!("  - Entrées   : {} / {}", stats.entries, stats.capacity);
+            println!(
+                "  - Mémoire   : {} / {} octets",
+                stats.bytes,
+                stats.max_bytes.unwrap_or(0)
+            );
+            println!("  - Hits      : {}", stats.hits);
+            println!("  - Misses    : {}", stats.misses);
+            println!("  - Évictions : {}", stats.evictions);
+            println!("  - Hit ratio : {:.1}%", stats.hit_ratio() * 100.0);
+        }
     }
 
     Ok(())
 }
+
+/// Options de réplication : stratégie par défaut + surcharges `<collection>=<stratégie>`
+fn sync_options(strategy: &str, overrides: &[String]) -> Result<SyncOptions> {
+    let mut options = SyncOptions::new().with_strategy(MergeStrategy::parse(strategy)?);
+    for spec in overrides {
+        let (collection, strategy) = spec.split_once('=').ok_or_else(|| {
+            anyhow::anyhow!("Format attendu : <collection>=<stratégie> ({})", spec)
+        })?;
+        options = options.with_collection_strategy(collection, MergeStrategy::parse(strategy)?);
+    }
+    Ok(options)
+}
+
+/// Affiche l'effet de chaque migration, collection par collection
+fn print_migrations(plans: &[MigrationPlan], dry_run: bool) {
+    if plans.is_empty() {
+        println!("✅ Rien à faire.");
+        return;
+    }
+    for plan in plans {
+        let arrow = match plan.direction {
+            Direction::Up => "⬆️",
+            Direction::Down => "⬇️",
+        };
+        println!(
+            "{} {} {} ({}) : {} document(s)",
+            arrow,
+            plan.version,
+            plan.id,
+            plan.description,
+            plan.documents()
+        );
+        for diff in &plan.collections {
+            println!("  📁 {} : {}", diff.collection, diff.operations.join(", "));
+            for doc in &diff.documents {
+                println!("    ~ {}", doc.id);
+                for change in &doc.changes {
+                    let show =
+                        |v: &Option<Value>| v.as_ref().map_or("∅".to_string(), |v| v.to_string());
+                    println!(
+                        "        {} : {} -> {}",
+                        change.field,
+                        show(&change.before),
+                        show(&change.after)
+                    );
+                }
+            }
+        }
+    }
+    if dry_run {
+        println!("🔎 Simulation : aucune écriture.");
+    } else {
+        println!("✅ {} migration(s) exécutée(s).", plans.len());
+    }
+}
+
+/// Affiche le plan d'un résultat EXPLAIN. Retourne false s'il n'y en a pas.
+fn print_plan(result: &QueryResult) -> Result<bool> {
+    match &result.plan {
+        Some(plan) => {
+            println!("🧭 Plan d'exécution :");
+            println!("{}", serde_json::to_string_pretty(plan)?);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
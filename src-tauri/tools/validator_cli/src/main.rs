@@ -7,7 +7,7 @@ use std::fs;
 use std::path::PathBuf;
 
 // Imports internes
-use raise::json_db::schema::{SchemaRegistry, SchemaValidator};
+use raise::json_db::schema::{SchemaRegistry, SchemaValidator, ValidationReport};
 use raise::json_db::storage::JsonDbConfig;
 
 #[derive(Parser, Debug)]
@@ -22,6 +22,11 @@ struct Args {
     /// ex: dapps/dapp.schema.json
     #[arg(short, long)]
     schema: String,
+
+    /// Écrit le rapport de validation complet (JSON) dans ce fichier
+    /// ex: target/report.json
+    #[arg(short, long)]
+    report: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
@@ -112,22 +117,49 @@ fn main() -> Result<()> {
 
     // 7. COMPUTE & VALIDATE
     println!("🚀 Validation stricte (compute_then_validate)...");
-    match validator.compute_then_validate(&mut doc) {
-        Ok(_) => {
-            println!("\n✅ SUCCÈS : Document VALIDE !");
-            // Affiche les champs calculés pour preuve
-            if let Some(id) = doc.get("id") {
-                println!("   ID généré     : {}", id);
+    let result = validator.compute_then_validate(&mut doc);
+    let report = match &result {
+        Ok(_) => ValidationReport {
+            schema_uri: full_uri.clone(),
+            errors: Vec::new(),
+        },
+        Err(e) => match e.downcast_ref::<ValidationReport>() {
+            Some(report) => report.clone(),
+            None => return Err(anyhow::anyhow!("{:#}", e)),
+        },
+    };
+
+    if let Some(path) = &args.report {
+        fs::write(path, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Écriture du rapport impossible : {:?}", path))?;
+        println!("📝 Rapport écrit dans {:?}", path);
+    }
+
+    if report.is_valid() {
+        println!("\n✅ SUCCÈS : Document VALIDE !");
+        // Affiche les champs calculés pour preuve
+        if let Some(id) = doc.get("id") {
+            println!("   ID généré     : {}", id);
+        }
+        if let Some(at) = doc.get("createdAt") {
+            println!("   Créé le       : {}", at);
+        }
+    } else {
+        println!(
+            "\n❌ ÉCHEC DE VALIDATION : {} erreur(s)",
+            report.errors.len()
+        );
+        for issue in &report.errors {
+            println!("\n  • {}", issue);
+            println!("    mot-clé : {}", issue.keyword_path);
+            if let Some(expected) = &issue.expected {
+                println!("    attendu : {}", expected);
             }
-            if let Some(at) = doc.get("createdAt") {
-                println!("   Créé le       : {}", at);
+            if let Some(actual) = &issue.actual {
+                println!("    reçu    : {}", actual);
             }
         }
-        Err(e) => {
-            println!("\n❌ ÉCHEC DE VALIDATION :");
-            println!("{}", e);
-            std::process::exit(1);
-        }
+        std::process::exit(1);
     }
 
     Ok(())
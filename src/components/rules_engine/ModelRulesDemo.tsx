@@ -11,7 +11,7 @@ interface ModelRuleData {
 }
 
 export default function ModelRulesDemo() {
-  const { doc, handleChange, isCalculating } = useRulesEngine({
+  const { doc, handleChange, isCalculating, validationErrors, fieldErrors } = useRulesEngine({
     space: 'demo',
     db: 'architecture',
     collection: 'components',
@@ -48,6 +48,11 @@ export default function ModelRulesDemo() {
               onChange={(e) => handleChange('parent_pkg', e.target.value)}
             />
             <p className="text-xs text-gray-400 mt-1">Doit commencer par 'com.company'</p>
+            {fieldErrors('parent_pkg').map((e) => (
+              <p key={e.keyword_path} className="text-xs text-red-600 mt-1">
+                {e.message}
+              </p>
+            ))}
           </div>
 
           <div>
@@ -59,6 +64,11 @@ export default function ModelRulesDemo() {
               onChange={(e) => handleChange('name', e.target.value)}
             />
             <p className="text-xs text-gray-400 mt-1">Doit être en PascalCase</p>
+            {fieldErrors('name').map((e) => (
+              <p key={e.keyword_path} className="text-xs text-red-600 mt-1">
+                {e.message}
+              </p>
+            ))}
           </div>
 
           <div>
@@ -95,6 +105,21 @@ export default function ModelRulesDemo() {
                 </code>
               </div>
             </div>
+
+            {validationErrors.length > 0 && (
+              <div className="mt-4">
+                <h4 className="font-semibold text-gray-500 uppercase text-xs mb-2">
+                  Schéma : {validationErrors.length} erreur(s)
+                </h4>
+                <ul className="space-y-1 text-xs text-red-700">
+                  {validationErrors.map((e) => (
+                    <li key={`${e.instance_path}${e.keyword_path}`}>
+                      <code className="font-mono">{e.instance_path || '/'}</code> : {e.message}
+                    </li>
+                  ))}
+                </ul>
+              </div>
+            )}
           </div>
         </div>
       </div>
@@ -1,5 +1,6 @@
 import { useState, useEffect, useRef, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import type { ValidationIssue, ValidationReport } from '@/types/json-db.types';
 
 interface RulesEngineOptions {
   space: string;
@@ -21,6 +22,7 @@ export function useRulesEngine({
   const [doc, setDoc] = useState<Record<string, unknown>>(initialDoc);
   const [isCalculating, setIsCalculating] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const [validationErrors, setValidationErrors] = useState<ValidationIssue[]>([]);
 
   // Ref pour éviter les boucles infinies si le backend renvoie la même chose
   const lastEvaluatedDoc = useRef<string>(JSON.stringify(initialDoc));
@@ -43,6 +45,15 @@ export function useRulesEngine({
           doc,
         });
 
+        // Validation du document calculé : toutes les violations, par champ
+        const report = await invoke<ValidationReport>('jsondb_validate_document', {
+          space,
+          db,
+          collection,
+          document: updatedDoc,
+        });
+        setValidationErrors(report.errors);
+
         const updatedDocStr = JSON.stringify(updatedDoc);
         if (updatedDocStr !== currentDocStr) {
           lastEvaluatedDoc.current = updatedDocStr;
@@ -67,5 +78,14 @@ export function useRulesEngine({
     setDoc((prev) => ({ ...prev, [field]: value }));
   }, []);
 
-  return { doc, setDoc, handleChange, isCalculating, error };
+  // Violations portant sur un champ de premier niveau (ou ses sous-éléments)
+  const fieldErrors = useCallback(
+    (field: string) =>
+      validationErrors.filter(
+        (e) => e.instance_path === `/${field}` || e.instance_path.startsWith(`/${field}/`),
+      ),
+    [validationErrors],
+  );
+
+  return { doc, setDoc, handleChange, isCalculating, error, validationErrors, fieldErrors };
 }
@@ -0,0 +1,56 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { useSettingsStore } from '@/store/settings-store';
+import type { ChangeEvent, ChangeNotification } from '@/types/json-db.types';
+
+/** Événement Tauri émis par `jsondb_subscribe_changes` */
+export const CHANGE_EVENT = 'jsondb:change';
+
+export class ChangeFeedService {
+  private getConfig() {
+    const { jsonDbSpace, jsonDbDatabase } = useSettingsStore.getState();
+    return { space: jsonDbSpace, db: jsonDbDatabase };
+  }
+
+  // Changements postérieurs au jeton `after` (le `seq` du dernier événement traité)
+  async readChanges(after = 0, limit?: number): Promise<ChangeEvent[]> {
+    const { space, db } = this.getConfig();
+    return invoke<ChangeEvent[]>('jsondb_read_changes', { space, db, after, limit });
+  }
+
+  // Reçoit chaque changement postérieur à `after` (par défaut : ceux à venir).
+  // Retourne la fonction de désabonnement.
+  async subscribe(
+    handler: (event: ChangeEvent) => void,
+    after?: number,
+  ): Promise<() => Promise<void>> {
+    const { space, db } = this.getConfig();
+    // Écoute ouverte avant l'abonnement : les premiers événements sont gardés
+    // jusqu'à connaître l'identifiant de l'abonnement
+    let subscription: string | undefined;
+    const early: ChangeNotification[] = [];
+    const unlisten = await listen<ChangeNotification>(CHANGE_EVENT, ({ payload }) => {
+      if (subscription === undefined) {
+        early.push(payload);
+      } else if (payload.subscription === subscription) {
+        handler(payload.event);
+      }
+    });
+
+    try {
+      subscription = await invoke<string>('jsondb_subscribe_changes', { space, db, after });
+    } catch (e: unknown) {
+      unlisten();
+      console.error('[ChangeFeedService] Subscribe Failed:', e);
+      throw e;
+    }
+    early.filter((n) => n.subscription === subscription).forEach((n) => handler(n.event));
+
+    return async () => {
+      unlisten();
+      await invoke('jsondb_unsubscribe_changes', { subscription });
+    };
+  }
+}
+
+export const changeFeedService = new ChangeFeedService();
@@ -1,7 +1,13 @@
 import { invoke } from '@tauri-apps/api/core';
 import { queryService } from './query-service';
 import { useSettingsStore } from '@/store/settings-store';
-import type { Query, Document } from '@/types/json-db.types';
+import type {
+  BackupManifest,
+  Document,
+  Query,
+  RestoreReport,
+  ValidationReport,
+} from '@/types/json-db.types';
 
 export class CollectionService {
   /**
@@ -24,6 +30,30 @@ export class CollectionService {
     await invoke('jsondb_drop_db', { space, db });
   }
 
+  /** Sauvegarde à chaud de la base active dans une archive `.tar.zst` */
+  async backupDb(path: string): Promise<BackupManifest> {
+    const { space, db } = this.getConfig();
+    return await invoke<BackupManifest>('jsondb_backup_db', { space, db, path });
+  }
+
+  /**
+   * Restaure une archive dans une nouvelle base. Avec un journal des changements
+   * archivé, rejoue les écritures postérieures à la sauvegarde jusqu'à `until`.
+   */
+  async restoreDb(
+    path: string,
+    target: { space: string; db: string },
+    options: { changes?: string; until?: Date } = {},
+  ): Promise<RestoreReport> {
+    return await invoke<RestoreReport>('jsondb_restore_db', {
+      path,
+      space: target.space,
+      db: target.db,
+      changes: options.changes ?? null,
+      until: options.until?.getTime() ?? null,
+    });
+  }
+
   // --- COLLECTION MANAGEMENT ---
 
   async createCollection(name: string, schemaUri?: string): Promise<void> {
@@ -52,18 +82,24 @@ export class CollectionService {
 
   // --- INDEX MANAGEMENT ---
 
+  /**
+   * Crée un index. Plusieurs champs = index composite (ex: ['layer', 'name']).
+   * Avec `unique`, les doublons sont refusés à l'insertion et à la mise à jour.
+   */
   async createIndex(
     collection: string,
-    field: string,
-    kind: 'hash' | 'btree' | 'text' = 'hash',
+    field: string | string[],
+    kind: 'hash' | 'btree' | 'text' | 'text:fr' | 'text:en' | 'text:simple' = 'hash',
+    unique = false,
   ): Promise<void> {
     const { space, db } = this.getConfig();
     await invoke('jsondb_create_index', {
       space,
       db,
       collection,
-      field,
+      field: Array.isArray(field) ? field.join(',') : field,
       kind,
+      unique,
     });
   }
 
@@ -126,6 +162,23 @@ export class CollectionService {
     });
   }
 
+  /**
+   * Valide un brouillon contre le schéma de la collection sans l'enregistrer.
+   * Renvoie toutes les violations (vide si le document est valide).
+   */
+  async validateDocument(
+    collection: string,
+    doc: Record<string, unknown>,
+  ): Promise<ValidationReport> {
+    const { space, db } = this.getConfig();
+    return await invoke<ValidationReport>('jsondb_validate_document', {
+      space,
+      db,
+      collection,
+      document: doc,
+    });
+  }
+
   async deleteDocument(collection: string, id: string): Promise<boolean> {
     const { space, db } = this.getConfig();
     return await invoke('jsondb_delete_document', {
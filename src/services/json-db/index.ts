@@ -3,3 +3,5 @@ export * from './schema-service';
 export * from './query-service';
 export * from './jsonld-service';
 export * from './transaction-service';
+export * from './change-service';
+export * from './replication-service';
@@ -7,6 +7,7 @@ import type {
   ComparisonOperator,
   QueryResponse,
   Document,
+  AggregationResult,
 } from '@/types/json-db.types';
 
 export class QueryBuilder {
@@ -105,6 +106,38 @@ export class JsonDbQueryService {
       throw e;
     }
   }
+
+  // Requête SQL agrégée (GROUP BY, COUNT, SUM ...) : une ligne par groupe
+  async aggregateSql(sql: string): Promise<AggregationResult> {
+    try {
+      const { space, db } = this.getConfig();
+      const res = await invoke<QueryResponse>('jsondb_execute_sql', {
+        space,
+        db,
+        sql,
+      });
+      return res.aggregation ?? { columns: [], rows: [] };
+    } catch (e: unknown) {
+      console.error('[QueryService] SQL Failed:', e);
+      throw e;
+    }
+  }
+
+  // INSERT / UPDATE / DELETE exécutés en une transaction : nombre de documents écrits
+  async writeSql(sql: string): Promise<number> {
+    try {
+      const { space, db } = this.getConfig();
+      const res = await invoke<QueryResponse>('jsondb_execute_sql', {
+        space,
+        db,
+        sql,
+      });
+      return res.affected ?? 0;
+    } catch (e: unknown) {
+      console.error('[QueryService] SQL Failed:', e);
+      throw e;
+    }
+  }
 }
 
 export const queryService = new JsonDbQueryService();
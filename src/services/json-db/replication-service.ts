@@ -0,0 +1,84 @@
+import { invoke } from '@tauri-apps/api/core';
+import { useSettingsStore } from '@/store/settings-store';
+import type {
+  Conflict,
+  Document,
+  MergeStrategy,
+  ReplicationReport,
+  SyncReport,
+} from '@/types/json-db.types';
+
+/** Collection des conflits détectés par la réplication */
+export const CONFLICTS_COLLECTION = '_conflicts';
+
+export interface SyncOptions {
+  /** Stratégie par défaut (`last-writer-wins` si absente) */
+  strategy?: MergeStrategy;
+  /** Stratégie propre à certaines collections */
+  collections?: Record<string, MergeStrategy>;
+}
+
+export class ReplicationService {
+  private getConfig() {
+    const { jsonDbSpace, jsonDbDatabase } = useSettingsStore.getState();
+    return { space: jsonDbSpace, db: jsonDbDatabase };
+  }
+
+  // Synchronise la base active avec la même base sous une autre racine (dans les deux sens)
+  async sync(remoteRoot: string, options: SyncOptions = {}): Promise<ReplicationReport> {
+    const { space, db } = this.getConfig();
+    return invoke<ReplicationReport>('jsondb_sync_db', {
+      space,
+      db,
+      remoteRoot,
+      strategy: options.strategy ?? null,
+      collections: options.collections ?? null,
+    });
+  }
+
+  // Exporte l'état de la base dans un lot à transmettre hors ligne (nombre de documents)
+  async exportBundle(path: string): Promise<number> {
+    const { space, db } = this.getConfig();
+    return invoke<number>('jsondb_export_bundle', { space, db, path });
+  }
+
+  // Fusionne un lot reçu dans la base active
+  async importBundle(path: string, options: SyncOptions = {}): Promise<SyncReport> {
+    const { space, db } = this.getConfig();
+    return invoke<SyncReport>('jsondb_import_bundle', {
+      space,
+      db,
+      path,
+      strategy: options.strategy ?? null,
+      collections: options.collections ?? null,
+    });
+  }
+
+  async listConflicts(): Promise<Conflict[]> {
+    const { space, db } = this.getConfig();
+    const docs = await invoke<Conflict[]>('jsondb_list_all', {
+      space,
+      db,
+      collection: CONFLICTS_COLLECTION,
+    });
+    return docs.filter((c) => c.status === 'open');
+  }
+
+  // Règle un conflit ouvert : version locale, distante, ou contenu fourni (null : suppression)
+  async resolveConflict(
+    conflictId: string,
+    choice: 'local' | 'remote' | { document: Document | null },
+  ): Promise<Document | null> {
+    const { space, db } = this.getConfig();
+    const custom = typeof choice === 'object';
+    return invoke<Document | null>('jsondb_resolve_conflict', {
+      space,
+      db,
+      conflictId,
+      choice: custom ? 'custom' : choice,
+      document: custom ? choice.document : null,
+    });
+  }
+}
+
+export const replicationService = new ReplicationService();
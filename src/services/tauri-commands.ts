@@ -14,6 +14,8 @@ export const CMDS = {
   DB_INSERT: 'jsondb_insert_document',
   DB_QUERY: 'jsondb_execute_query',
   DB_SQL: 'jsondb_execute_sql',
+  DB_CACHE_STATS: 'jsondb_cache_stats',
+  DB_CLEAR_CACHE: 'jsondb_clear_cache',
 
   // Module Model
   MODEL_LOAD: 'load_project_model',
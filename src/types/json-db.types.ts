@@ -19,10 +19,22 @@ export type ComparisonOperator =
   | 'Lt'
   | 'Lte'
   | 'In'
+  | 'NotIn'
   | 'Contains'
+  | 'Like'
   | 'StartsWith'
   | 'EndsWith'
-  | 'Matches';
+  | 'Matches'
+  | 'ContainsAll'
+  | 'ContainsAny'
+  | 'Size'
+  | 'ElemMatch'
+  | 'Exists'
+  | 'IsNull'
+  | 'IsNotNull'
+  | 'IsType'
+  | 'InSubquery'
+  | 'NotInSubquery';
 
 export interface Condition {
   field: string;
@@ -34,6 +46,8 @@ export interface Condition {
 export interface QueryFilter {
   operator: FilterOperator;
   conditions: Condition[];
+  // Sous-filtres combinés avec les conditions par `operator`
+  filters?: QueryFilter[];
 }
 
 export interface Query {
@@ -43,12 +57,52 @@ export interface Query {
   limit?: number;
   offset?: number;
   projection?: string[];
+  aggregation?: Aggregation;
+  joins?: Join[];
+}
+
+export interface Join {
+  collection: string;
+  /** Le document joint est attaché sous cette clé (`alias.champ`) */
+  alias: string;
+  kind: 'Inner' | 'Left';
+  local_field: string;
+  foreign_field: string;
+}
+
+export type AggregateFunction = 'Count' | 'Sum' | 'Avg' | 'Min' | 'Max';
+
+export interface Aggregate {
+  function: AggregateFunction;
+  /** Absent pour COUNT(*) */
+  field?: string;
+  alias: string;
+  distinct?: boolean;
+}
+
+export interface Aggregation {
+  group_by: string[];
+  aggregates: Aggregate[];
+  having?: QueryFilter;
+}
+
+export interface AggregationResult {
+  /** Champs de regroupement puis alias des agrégats */
+  columns: string[];
+  /** Une ligne par groupe */
+  rows: Record<string, unknown>[];
 }
 
 export interface QueryResponse {
   // CORRECTION : Typage fort avec l'interface Document définie plus bas
   documents: Document[];
   total: number;
+  /** Scores BM25 alignés sur `documents` (recherche `Matches` servie par un index texte) */
+  scores?: number[];
+  /** Lignes agrégées (GROUP BY / COUNT / SUM ...) ; `documents` est alors vide */
+  aggregation?: AggregationResult;
+  /** Documents écrits par une instruction INSERT / UPDATE / DELETE */
+  affected?: number;
 }
 
 // --- Transactions ---
@@ -63,6 +117,101 @@ export interface TransactionRequest {
   operations: OperationRequest[];
 }
 
+// --- Flux de changements ---
+
+export type ChangeKind = 'insert' | 'update' | 'delete';
+
+// Miroir de `json_db::changes::ChangeEvent`
+export interface ChangeEvent {
+  /** Numéro de séquence dans la base : jeton de reprise */
+  seq: number;
+  /** Millisecondes UTC */
+  timestamp: number;
+  collection: string;
+  id: string;
+  kind: ChangeKind;
+  before: Document | null;
+  after: Document | null;
+  /** Transaction d'origine (absente pour une écriture directe) */
+  transaction?: string;
+}
+
+/** Charge utile de l'événement Tauri `jsondb:change` */
+export interface ChangeNotification {
+  subscription: string;
+  space: string;
+  db: string;
+  event: ChangeEvent;
+}
+
+// --- Sauvegarde / restauration ---
+
+// Miroir de `json_db::backup::BackupManifest`
+export interface BackupManifest {
+  format: number;
+  space: string;
+  db: string;
+  /** Millisecondes UTC */
+  created_at: number;
+  /** Dernier événement du flux de changements contenu dans la sauvegarde */
+  change_seq: number;
+  files: { path: string; size: number; sha256: string }[];
+}
+
+// Miroir de `json_db::backup::RestoreReport`
+export interface RestoreReport {
+  space: string;
+  db: string;
+  files: number;
+  /** Événements du journal rejoués après la sauvegarde */
+  replayed: number;
+  change_seq: number;
+}
+
+// --- Réplication ---
+
+// Miroir de `json_db::replication::MergeStrategy`
+export type MergeStrategy = 'last-writer-wins' | 'three-way' | 'manual';
+
+// Miroir de `json_db::replication::SyncReport`
+export interface SyncReport {
+  /** Documents écrits ou supprimés localement */
+  applied: number;
+  /** Modifications concurrentes détectées */
+  conflicts: number;
+  /** Conflits laissés ouverts (stratégie manuelle) */
+  open: number;
+}
+
+// Miroir de `json_db::replication::ReplicationReport`
+export interface ReplicationReport {
+  pulled: SyncReport;
+  pushed: SyncReport;
+}
+
+// Miroir de `json_db::replication::Conflict` (collection `_conflicts`)
+export interface Conflict {
+  /** `<collection>--<id>` */
+  id: string;
+  collection: string;
+  documentId: string;
+  strategy: MergeStrategy;
+  status: 'open' | 'resolved';
+  /** Version retenue : local, remote, merged ou custom */
+  resolution: string | null;
+  local: Document | null;
+  remote: Document | null;
+  base: Document | null;
+  /** Champs modifiés des deux côtés (fusion à trois voies) */
+  fields: string[];
+  localVersions: Record<string, number>;
+  remoteVersions: Record<string, number>;
+  remoteReplica: string;
+  remoteUpdatedAt: string;
+  detectedAt: string;
+  resolvedAt: string | null;
+}
+
 // --- Document Générique ---
 
 // CORRECTION : Interface simplifiée et sécurisée sans 'any'
@@ -72,3 +221,22 @@ export interface Document {
   id: string;
   [key: string]: unknown;
 }
+
+// --- Validation ---
+
+// Miroir de `json_db::schema::ValidationIssue` (champs snake_case côté Rust)
+export interface ValidationIssue {
+  /** JSON Pointer dans le document ("" = racine, "/name", "/items/0") */
+  instance_path: string;
+  /** Chemin du mot-clé dans le schéma ("/properties/name/minLength") */
+  keyword_path: string;
+  keyword: string;
+  message: string;
+  expected?: unknown;
+  actual?: unknown;
+}
+
+export interface ValidationReport {
+  schema_uri: string;
+  errors: ValidationIssue[];
+}